@@ -13,6 +13,9 @@ import idl from '../idl/solana_nft_marketplace.json';
 // L'ID du programme déployé
 const PROGRAM_ID = new PublicKey('4hVp7QQKuowuf1SgPVXcD5YkTrHHiDRPbn4V9HKvYwrT');
 
+// Programme de métadonnées Metaplex
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+
 // Obtenir une connection à Solana
 export const getConnection = (cluster = 'devnet') => {
   return new Connection(
@@ -78,6 +81,115 @@ export const getListingAddress = async (nftMint, seller) => {
   return listingAddress;
 };
 
+// Obtenir l'adresse de la grille de frais (PDA)
+export const getFeeScheduleAddress = async () => {
+  const [feeScheduleAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('fee_schedule')],
+    PROGRAM_ID
+  );
+  return feeScheduleAddress;
+};
+
+// Obtenir l'adresse du compte de volume glissant d'un vendeur (PDA)
+export const getSellerVolumeAddress = async (seller) => {
+  const [sellerVolumeAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('seller_volume'), new PublicKey(seller).toBuffer()],
+    PROGRAM_ID
+  );
+  return sellerVolumeAddress;
+};
+
+// Obtenir l'adresse de l'entrée de liste de blocage d'un mint (PDA)
+export const getMintBlocklistAddress = async (nftMint) => {
+  const [mintBlocklistAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('blocklist'), new PublicKey(nftMint).toBuffer()],
+    PROGRAM_ID
+  );
+  return mintBlocklistAddress;
+};
+
+// Obtenir l'adresse de l'enregistrement du retrait de force du listing d'un vendeur (PDA)
+export const getDelistRecordAddress = async (nftMint, seller) => {
+  const [delistRecordAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('delisted'), new PublicKey(nftMint).toBuffer(), new PublicKey(seller).toBuffer()],
+    PROGRAM_ID
+  );
+  return delistRecordAddress;
+};
+
+// Obtenir l'adresse du compte de statistiques globales (PDA)
+export const getStatsAddress = async () => {
+  const [statsAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('stats')],
+    PROGRAM_ID
+  );
+  return statsAddress;
+};
+
+// Obtenir l'adresse de l'enregistrement d'un vendeur (PDA)
+export const getSellerRecordAddress = async (seller) => {
+  const [sellerRecordAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('seller_record'), new PublicKey(seller).toBuffer()],
+    PROGRAM_ID
+  );
+  return sellerRecordAddress;
+};
+
+// Obtenir l'adresse des statistiques d'une collection (PDA)
+export const getCollectionStatsAddress = async (collection) => {
+  const [collectionStatsAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('collection_stats'), new PublicKey(collection).toBuffer()],
+    PROGRAM_ID
+  );
+  return collectionStatsAddress;
+};
+
+// Obtenir l'adresse de la configuration d'une collection (PDA)
+export const getCollectionConfigAddress = async (collection) => {
+  const [collectionConfigAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('collection'), new PublicKey(collection).toBuffer()],
+    PROGRAM_ID
+  );
+  return collectionConfigAddress;
+};
+
+// Obtenir l'adresse du compte de métadonnées Metaplex d'un NFT (PDA du programme de métadonnées)
+export const getMetadataAddress = async (nftMint) => {
+  const [metadataAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), new PublicKey(nftMint).toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+  return metadataAddress;
+};
+
+// Lire la collection vérifiée d'un NFT dans ses métadonnées (null sans métadonnées ou sans collection vérifiée)
+const getVerifiedCollection = async (connection, nftMint) => {
+  try {
+    const nft = await Metaplex.make(connection).nfts().findByMint({
+      mintAddress: new PublicKey(nftMint),
+      loadJsonMetadata: false,
+    });
+    return nft.collection && nft.collection.verified ? nft.collection.address : null;
+  } catch (error) {
+    return null;
+  }
+};
+
+// Obtenir l'adresse des statistiques de la collection d'un listing (null si le listing n'a pas de collection)
+const getListingCollectionStatsAddress = async (program, listingAddress) => {
+  const listing = await program.account[getAccountName(program)].fetch(new PublicKey(listingAddress));
+  return listing.collection ? getCollectionStatsAddress(listing.collection) : null;
+};
+
+// Obtenir l'adresse du profil de trading d'un portefeuille (PDA)
+export const getTraderProfileAddress = async (wallet) => {
+  const [traderProfileAddress] = await PublicKey.findProgramAddress(
+    [Buffer.from('profile'), new PublicKey(wallet).toBuffer()],
+    PROGRAM_ID
+  );
+  return traderProfileAddress;
+};
+
 // Initialiser la marketplace
 export const initializeMarketplace = async (wallet, connection) => {
   try {
@@ -104,6 +216,18 @@ export const initializeMarketplace = async (wallet, connection) => {
         authority: wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
+      .postInstructions([
+        // Créer le compte de statistiques dans la même transaction
+        await program.methods
+          .initializeStats()
+          .accounts({
+            stats: await getStatsAddress(),
+            marketplace: marketplaceAddress,
+            authority: wallet.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .instruction(),
+      ])
       .rpc();
     
     console.log('Marketplace initialized: ', tx);
@@ -119,7 +243,7 @@ export const initializeMarketplace = async (wallet, connection) => {
 };
 
 // Mettre un NFT en vente avec délégation au programme
-export const listNFT = async (wallet, connection, nftMint, price) => {
+export const listNFT = async (wallet, connection, nftMint, price, quantity = 1) => {
   try {
     const provider = getProvider(connection, wallet);
     const program = getProgram(provider);
@@ -153,11 +277,13 @@ export const listNFT = async (wallet, connection, nftMint, price) => {
       
       // Si nous arrivons ici, le listing existe déjà - utiliser updateListing
       const tx = await program.methods
-        .updateListing(priceInLamports)
+        .updateListing(priceInLamports, new BN(quantity))
         .accounts({
           listing: listingAddress,
           seller: wallet.publicKey,
           nftTokenAccount: sellerTokenAccount,
+          collectionStats: await getListingCollectionStatsAddress(program, listingAddress),
+          stats: await getStatsAddress(),
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -173,15 +299,33 @@ export const listNFT = async (wallet, connection, nftMint, price) => {
         
         console.log('Aucun listing existant trouvé, création d\'un nouveau listing');
         
+        // La collection vérifiée du NFT détermine les comptes de collection à fournir;
+        // la configuration n'est passée que si elle existe (elle est exigée en mode curé)
+        const collection = await getVerifiedCollection(connection, nftMint);
+        let collectionConfig = null;
+        if (collection) {
+          const collectionConfigAddress = await getCollectionConfigAddress(collection);
+          if (await connection.getAccountInfo(collectionConfigAddress)) {
+            collectionConfig = collectionConfigAddress;
+          }
+        }
+        
         // Appel au programme pour lister le NFT
         const tx = await program.methods
-          .listNft(priceInLamports)
+          .listNft(priceInLamports, new BN(quantity), [])
           .accounts({
             listing: listingAddress,
             marketplace: marketplaceAddress,
             seller: wallet.publicKey,
             nftMint: new PublicKey(nftMint),
             nftTokenAccount: sellerTokenAccount,
+            nftMetadata: await getMetadataAddress(nftMint),
+            collectionConfig,
+            mintBlocklist: await getMintBlocklistAddress(nftMint),
+            delistRecord: await getDelistRecordAddress(nftMint, wallet.publicKey),
+            sellerRecord: await getSellerRecordAddress(wallet.publicKey),
+            collectionStats: collection ? await getCollectionStatsAddress(collection) : null,
+            stats: await getStatsAddress(),
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
           })
@@ -202,7 +346,7 @@ export const listNFT = async (wallet, connection, nftMint, price) => {
 };
 
 // Mettre à jour le prix d'un NFT listé
-export const updateListing = async (wallet, connection, listingAddress, nftMint, newPrice) => {
+export const updateListing = async (wallet, connection, listingAddress, nftMint, newPrice, quantity = 1) => {
   try {
     const provider = getProvider(connection, wallet);
     const program = getProgram(provider);
@@ -218,11 +362,13 @@ export const updateListing = async (wallet, connection, listingAddress, nftMint,
     
     // Appel au programme pour mettre à jour le listing
     const tx = await program.methods
-      .updateListing(priceInLamports)
+      .updateListing(priceInLamports, new BN(quantity))
       .accounts({
         listing: new PublicKey(listingAddress),
         seller: wallet.publicKey,
         nftTokenAccount: nftTokenAccount,
+        collectionStats: await getListingCollectionStatsAddress(program, listingAddress),
+        stats: await getStatsAddress(),
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -237,7 +383,7 @@ export const updateListing = async (wallet, connection, listingAddress, nftMint,
 };
 
 // Acheter un NFT avec transfert automatique
-export const buyNFT = async (wallet, connection, listingAddress, sellerAddress, nftMint) => {
+export const buyNFT = async (wallet, connection, listingAddress, sellerAddress, nftMint, quantity = 1) => {
   try {
     console.log("Démarrage de l'achat automatique de NFT...");
     console.log("Paramètres:", { listingAddress, sellerAddress, nftMint });
@@ -254,6 +400,10 @@ export const buyNFT = async (wallet, connection, listingAddress, sellerAddress,
     const marketplaceAddress = await getMarketplaceAddress();
     const marketplace = await program.account.marketplace.fetch(marketplaceAddress);
     
+    // La collection du listing impose de fournir sa configuration et ses statistiques
+    const listing = await program.account[getAccountName(program)].fetch(new PublicKey(listingAddress));
+    const collection = listing.collection;
+    
     // Obtenir l'adresse du compte de token associé pour le vendeur
     const sellerTokenAccount = await getAssociatedTokenAddress(
       new PublicKey(nftMint),
@@ -298,15 +448,26 @@ export const buyNFT = async (wallet, connection, listingAddress, sellerAddress,
     console.log("Exécution de l'instruction buyNft du programme...");
     
     const tx = await program.methods
-      .buyNft()
+      .buyNft(new BN(quantity))
       .accounts({
         marketplace: marketplaceAddress,
         listing: new PublicKey(listingAddress),
         buyer: wallet.publicKey,
         sellerWallet: new PublicKey(sellerAddress),
-        marketplaceAuthority: marketplace.authority,
+        treasury: marketplace.treasury,
         sellerTokenAccount: sellerTokenAccount,
         buyerTokenAccount: buyerTokenAccount,
+        referrer: null,
+        referrerEarnings: null,
+        feeSchedule: await getFeeScheduleAddress(),
+        sellerVolume: await getSellerVolumeAddress(sellerAddress),
+        sellerMembershipToken: null,
+        collectionConfig: collection ? await getCollectionConfigAddress(collection) : null,
+        mintBlocklist: await getMintBlocklistAddress(nftMint),
+        collectionStats: collection ? await getCollectionStatsAddress(collection) : null,
+        buyerProfile: await getTraderProfileAddress(wallet.publicKey),
+        sellerProfile: await getTraderProfileAddress(sellerAddress),
+        stats: await getStatsAddress(),
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -368,6 +529,9 @@ export const cancelListing = async (wallet, connection, listingAddress, nftMint)
         listing: new PublicKey(listingAddress),
         seller: wallet.publicKey,
         nftTokenAccount: nftTokenAccount,
+        collectionStats: await getListingCollectionStatsAddress(program, listingAddress),
+        sellerProfile: await getTraderProfileAddress(wallet.publicKey),
+        stats: await getStatsAddress(),
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
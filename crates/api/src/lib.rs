@@ -0,0 +1,39 @@
+//! API HTTP en lecture seule sur les tables de l'indexeur.
+//!
+//! Expose les listings (filtres et tris), l'historique des ventes par NFT, les statistiques
+//! du marché, les meilleurs vendeurs, les compteurs par wallet et le fil d'activité, avec une
+//! pagination par curseur, pour remplacer les parcours de signatures faits par l'application.
+
+pub mod queries;
+pub mod routes;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use solana_nft_marketplace_indexer::IndexerError;
+
+pub use routes::router;
+
+// Erreurs renvoyées par l'API
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("ressource introuvable")]
+    NotFound,
+    #[error(transparent)]
+    Database(#[from] IndexerError),
+    #[error("requête interrompue")]
+    Aborted,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Database(_) | ApiError::Aborted => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(serde_json::json!({ "error": self.to_string() }))).into_response()
+    }
+}
@@ -0,0 +1,36 @@
+//! Serveur HTTP en lecture seule sur la base de l'indexeur.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use solana_nft_marketplace_indexer::Store;
+
+#[derive(Parser)]
+#[command(name = "marketplace-api", version, about = "API HTTP sur les données indexées de la marketplace NFT")]
+struct Cli {
+    /// Base de données de l'indexeur: chemin ou URL SQLite (sqlite://...), ou URL Postgres (postgres://...)
+    #[arg(short = 'd', long = "database", default_value = "sqlite://marketplace-indexer.db", env = "MARKETPLACE_DATABASE_URL")]
+    database: String,
+
+    /// Adresse d'écoute du serveur
+    #[arg(short = 'b', long = "bind", default_value = "127.0.0.1:8080", env = "MARKETPLACE_API_BIND")]
+    bind: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    
+    // Le client Postgres synchrone ne peut pas être créé depuis un thread asynchrone
+    let database = cli.database.clone();
+    let store = tokio::task::spawn_blocking(move || Store::open(&database))
+        .await?
+        .with_context(|| format!("cannot open database {}", cli.database))?;
+    
+    let listener = tokio::net::TcpListener::bind(&cli.bind)
+        .await
+        .with_context(|| format!("cannot listen on {}", cli.bind))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    
+    axum::serve(listener, solana_nft_marketplace_api::router(store)).await?;
+    Ok(())
+}
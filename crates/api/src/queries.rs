@@ -0,0 +1,517 @@
+//! Requêtes de lecture sur les tables de l'indexeur.
+
+use serde::{Deserialize, Serialize};
+use solana_nft_marketplace_indexer::schema::LISTING_ACTIVE;
+use solana_nft_marketplace_indexer::{Database, Row, Value};
+
+use crate::ApiError;
+
+// Taille de page par défaut et maximale
+pub const DEFAULT_LIMIT: u32 = 20;
+pub const MAX_LIMIT: u32 = 100;
+
+// Durée de la fenêtre des statistiques récentes (24 heures)
+const RECENT_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Page de résultats: `next_cursor` est à repasser dans `cursor` pour obtenir la page suivante.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListingView {
+    pub address: String,
+    pub seller: String,
+    pub nft_mint: String,
+    pub collection: Option<String>,
+    pub price: i64,
+    pub quantity: i64,
+    pub status: String,
+    pub created_slot: i64,
+    pub created_at: Option<i64>,
+    pub updated_slot: i64,
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleView {
+    pub signature: String,
+    pub slot: i64,
+    pub block_time: Option<i64>,
+    pub kind: String,
+    pub listing: String,
+    pub nft_mint: Option<String>,
+    pub seller: Option<String>,
+    pub buyer: String,
+    pub unit_price: i64,
+    pub quantity: i64,
+    pub price: i64,
+    pub marketplace_fee: i64,
+    pub referrer: Option<String>,
+    pub referral_fee: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivityView {
+    pub signature: String,
+    pub slot: i64,
+    pub block_time: Option<i64>,
+    pub kind: String,
+    pub wallet: String,
+    pub counterparty: Option<String>,
+    pub account: String,
+    pub nft_mint: Option<String>,
+    pub price: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsView {
+    pub total_sales: i64,
+    pub total_volume: i64,
+    pub total_fees: i64,
+    pub average_price: i64,
+    pub active_listings: i64,
+    pub floor_price: Option<i64>,
+    pub unique_sellers: i64,
+    pub unique_buyers: i64,
+    pub sales_24h: i64,
+    pub volume_24h: i64,
+    pub indexed_slot: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SellerView {
+    pub wallet: String,
+    pub items_sold: i64,
+    pub volume_sold: i64,
+    pub active_listings: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletView {
+    pub wallet: String,
+    pub listings_created: i64,
+    pub listings_canceled: i64,
+    pub items_bought: i64,
+    pub items_sold: i64,
+    pub volume_bought: i64,
+    pub volume_sold: i64,
+    pub first_activity_at: Option<i64>,
+    pub last_activity_at: Option<i64>,
+}
+
+/// Tri des listings.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingSort {
+    #[default]
+    Recent,
+    PriceAsc,
+    PriceDesc,
+}
+
+/// Filtres des listings (paramètres de la requête HTTP).
+#[derive(Debug, Default, Deserialize)]
+pub struct ListingQuery {
+    pub seller: Option<String>,
+    pub mint: Option<String>,
+    pub status: Option<String>, // Statut exact, "all" pour tous (par défaut: active)
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    #[serde(default)]
+    pub sort: ListingSort,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+/// Filtres du fil d'activité.
+#[derive(Debug, Default, Deserialize)]
+pub struct ActivityQuery {
+    pub wallet: Option<String>, // Acteur ou contrepartie
+    pub mint: Option<String>,
+    pub kind: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+/// Pagination seule.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+/// Classement des vendeurs.
+#[derive(Debug, Default, Deserialize)]
+pub struct TopSellersQuery {
+    pub limit: Option<u32>,
+}
+
+// Clauses WHERE construites dynamiquement, avec leurs paramètres numérotés
+#[derive(Default)]
+struct Filters {
+    clauses: Vec<String>,
+    params: Vec<Value>,
+}
+
+impl Filters {
+    // Ajouter une clause dont les `?` sont numérotés à la suite des paramètres existants
+    fn add(&mut self, clause: &str, values: Vec<Value>) {
+        let mut numbered = String::with_capacity(clause.len() + 4);
+        let mut next = self.params.len();
+        for character in clause.chars() {
+            if character == '?' {
+                next += 1;
+                numbered.push_str(&format!("?{next}"));
+            } else {
+                numbered.push(character);
+            }
+        }
+        self.params.extend(values);
+        self.clauses.push(numbered);
+    }
+    
+    // Clause WHERE complète (vide sans filtre)
+    fn where_sql(&self) -> String {
+        if self.clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.clauses.join(" AND "))
+        }
+    }
+    
+    // Numéro du prochain paramètre
+    fn next_param(&self) -> usize {
+        self.params.len() + 1
+    }
+}
+
+// Curseur opaque: clé de tri de la dernière ligne renvoyée
+fn encode_cursor(parts: &[String]) -> String {
+    parts.join(":")
+}
+
+fn decode_cursor(cursor: &str, expected: usize) -> Result<Vec<&str>, ApiError> {
+    let parts: Vec<&str> = cursor.split(':').collect();
+    if parts.len() != expected || parts.iter().any(|part| part.is_empty()) {
+        return Err(ApiError::BadRequest("curseur invalide".to_string()));
+    }
+    Ok(parts)
+}
+
+fn cursor_int(part: &str) -> Result<i64, ApiError> {
+    part.parse().map_err(|_| ApiError::BadRequest("curseur invalide".to_string()))
+}
+
+// Taille de page demandée, bornée
+fn page_size(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+// Découper une page: une ligne de plus que la taille est lue pour savoir s'il en reste
+fn paginate<T>(mut items: Vec<T>, limit: u32, cursor: impl Fn(&T) -> String) -> Page<T> {
+    let next_cursor = if items.len() > limit as usize {
+        items.truncate(limit as usize);
+        items.last().map(cursor)
+    } else {
+        None
+    };
+    Page { items, next_cursor }
+}
+
+const LISTING_COLUMNS: &str =
+    "address, seller, nft_mint, collection, price, quantity, status, created_slot, created_at, updated_slot, updated_at";
+
+fn listing_view(row: &Row) -> ListingView {
+    ListingView {
+        address: row.text(0),
+        seller: row.text(1),
+        nft_mint: row.text(2),
+        collection: row.opt_text(3),
+        price: row.int(4),
+        quantity: row.int(5),
+        status: row.text(6),
+        created_slot: row.int(7),
+        created_at: row.opt_int(8),
+        updated_slot: row.int(9),
+        updated_at: row.opt_int(10),
+    }
+}
+
+// Listings filtrés, triés par prix ou par date de création
+pub fn listings(db: &mut Database, query: &ListingQuery) -> Result<Page<ListingView>, ApiError> {
+    let limit = page_size(query.limit);
+    let mut filters = Filters::default();
+    
+    match query.status.as_deref() {
+        Some("all") => {}
+        status => filters.add("status = ?", vec![status.unwrap_or(LISTING_ACTIVE).into()]),
+    }
+    if let Some(seller) = &query.seller {
+        filters.add("seller = ?", vec![seller.as_str().into()]);
+    }
+    if let Some(mint) = &query.mint {
+        filters.add("nft_mint = ?", vec![mint.as_str().into()]);
+    }
+    if let Some(min_price) = query.min_price {
+        filters.add("price >= ?", vec![min_price.into()]);
+    }
+    if let Some(max_price) = query.max_price {
+        filters.add("price <= ?", vec![max_price.into()]);
+    }
+    
+    let (key, order, comparison) = match query.sort {
+        ListingSort::Recent => ("created_slot", "DESC", "<"),
+        ListingSort::PriceAsc => ("price", "ASC", ">"),
+        ListingSort::PriceDesc => ("price", "DESC", "<"),
+    };
+    if let Some(cursor) = &query.cursor {
+        let parts = decode_cursor(cursor, 2)?;
+        filters.add(
+            &format!("({key}, address) {comparison} (?, ?)"),
+            vec![cursor_int(parts[0])?.into(), parts[1].into()],
+        );
+    }
+    
+    let sql = format!(
+        "SELECT {LISTING_COLUMNS} FROM listings {} ORDER BY {key} {order}, address {order} LIMIT ?{}",
+        filters.where_sql(),
+        filters.next_param(),
+    );
+    let mut params = filters.params;
+    params.push(i64::from(limit + 1).into());
+    
+    let items = db.query(&sql, &params)?.iter().map(listing_view).collect();
+    Ok(paginate(items, limit, |listing: &ListingView| {
+        let key = match query.sort {
+            ListingSort::Recent => listing.created_slot,
+            ListingSort::PriceAsc | ListingSort::PriceDesc => listing.price,
+        };
+        encode_cursor(&[key.to_string(), listing.address.clone()])
+    }))
+}
+
+// Un listing par adresse
+pub fn listing(db: &mut Database, address: &str) -> Result<Option<ListingView>, ApiError> {
+    let row = db.query_optional(
+        &format!("SELECT {LISTING_COLUMNS} FROM listings WHERE address = ?1"),
+        &[address.into()],
+    )?;
+    Ok(row.as_ref().map(listing_view))
+}
+
+// Historique des ventes d'un NFT, de la plus récente à la plus ancienne
+pub fn mint_sales(db: &mut Database, mint: &str, query: &PageQuery) -> Result<Page<SaleView>, ApiError> {
+    let limit = page_size(query.limit);
+    let mut filters = Filters::default();
+    filters.add("nft_mint = ?", vec![mint.into()]);
+    
+    if let Some(cursor) = &query.cursor {
+        let parts = decode_cursor(cursor, 4)?;
+        filters.add(
+            "(slot, signature, event_index, item_index) < (?, ?, ?, ?)",
+            vec![
+                cursor_int(parts[0])?.into(),
+                parts[1].into(),
+                cursor_int(parts[2])?.into(),
+                cursor_int(parts[3])?.into(),
+            ],
+        );
+    }
+    
+    let sql = format!(
+        "SELECT signature, slot, block_time, kind, listing, nft_mint, seller, buyer, unit_price, quantity, price,
+                marketplace_fee, referrer, referral_fee, event_index, item_index
+         FROM sales {}
+         ORDER BY slot DESC, signature DESC, event_index DESC, item_index DESC
+         LIMIT ?{}",
+        filters.where_sql(),
+        filters.next_param(),
+    );
+    let mut params = filters.params;
+    params.push(i64::from(limit + 1).into());
+    
+    let rows = db.query(&sql, &params)?;
+    let items: Vec<(SaleView, i64, i64)> = rows
+        .iter()
+        .map(|row| {
+            let sale = SaleView {
+                signature: row.text(0),
+                slot: row.int(1),
+                block_time: row.opt_int(2),
+                kind: row.text(3),
+                listing: row.text(4),
+                nft_mint: row.opt_text(5),
+                seller: row.opt_text(6),
+                buyer: row.text(7),
+                unit_price: row.int(8),
+                quantity: row.int(9),
+                price: row.int(10),
+                marketplace_fee: row.int(11),
+                referrer: row.opt_text(12),
+                referral_fee: row.int(13),
+            };
+            (sale, row.int(14), row.int(15))
+        })
+        .collect();
+    
+    let page = paginate(items, limit, |(sale, event_index, item_index)| {
+        encode_cursor(&[
+            sale.slot.to_string(),
+            sale.signature.clone(),
+            event_index.to_string(),
+            item_index.to_string(),
+        ])
+    });
+    Ok(Page {
+        items: page.items.into_iter().map(|(sale, _, _)| sale).collect(),
+        next_cursor: page.next_cursor,
+    })
+}
+
+// Fil d'activité, du plus récent au plus ancien
+pub fn activity(db: &mut Database, query: &ActivityQuery) -> Result<Page<ActivityView>, ApiError> {
+    let limit = page_size(query.limit);
+    let mut filters = Filters::default();
+    
+    if let Some(wallet) = &query.wallet {
+        filters.add("(wallet = ? OR counterparty = ?)", vec![wallet.as_str().into(), wallet.as_str().into()]);
+    }
+    if let Some(mint) = &query.mint {
+        filters.add("nft_mint = ?", vec![mint.as_str().into()]);
+    }
+    if let Some(kind) = &query.kind {
+        filters.add("kind = ?", vec![kind.as_str().into()]);
+    }
+    if let Some(cursor) = &query.cursor {
+        let parts = decode_cursor(cursor, 3)?;
+        filters.add(
+            "(slot, signature, event_index) < (?, ?, ?)",
+            vec![cursor_int(parts[0])?.into(), parts[1].into(), cursor_int(parts[2])?.into()],
+        );
+    }
+    
+    let sql = format!(
+        "SELECT signature, slot, block_time, kind, wallet, counterparty, account, nft_mint, price, event_index
+         FROM activity {}
+         ORDER BY slot DESC, signature DESC, event_index DESC
+         LIMIT ?{}",
+        filters.where_sql(),
+        filters.next_param(),
+    );
+    let mut params = filters.params;
+    params.push(i64::from(limit + 1).into());
+    
+    let rows = db.query(&sql, &params)?;
+    let items: Vec<(ActivityView, i64)> = rows
+        .iter()
+        .map(|row| {
+            let activity = ActivityView {
+                signature: row.text(0),
+                slot: row.int(1),
+                block_time: row.opt_int(2),
+                kind: row.text(3),
+                wallet: row.text(4),
+                counterparty: row.opt_text(5),
+                account: row.text(6),
+                nft_mint: row.opt_text(7),
+                price: row.opt_int(8),
+            };
+            (activity, row.int(9))
+        })
+        .collect();
+    
+    let page = paginate(items, limit, |(activity, event_index)| {
+        encode_cursor(&[activity.slot.to_string(), activity.signature.clone(), event_index.to_string()])
+    });
+    Ok(Page {
+        items: page.items.into_iter().map(|(activity, _)| activity).collect(),
+        next_cursor: page.next_cursor,
+    })
+}
+
+// Statistiques globales du marché (`now` en secondes Unix)
+pub fn stats(db: &mut Database, now: i64) -> Result<StatsView, ApiError> {
+    let sales = db
+        .query_optional(
+            "SELECT COUNT(*),
+                    CAST(COALESCE(SUM(price), 0) AS BIGINT),
+                    CAST(COALESCE(SUM(marketplace_fee + referral_fee), 0) AS BIGINT),
+                    COUNT(DISTINCT seller),
+                    COUNT(DISTINCT buyer),
+                    COUNT(CASE WHEN block_time >= ?1 THEN 1 END),
+                    CAST(COALESCE(SUM(CASE WHEN block_time >= ?1 THEN price ELSE 0 END), 0) AS BIGINT)
+             FROM sales",
+            &[(now - RECENT_WINDOW_SECS).into()],
+        )?
+        .unwrap_or_default();
+    let listings = db
+        .query_optional(
+            "SELECT COUNT(*), MIN(price) FROM listings WHERE status = ?1",
+            &[LISTING_ACTIVE.into()],
+        )?
+        .unwrap_or_default();
+    let checkpoint = db.query_optional("SELECT slot FROM checkpoint WHERE id = 1", &[])?;
+    
+    let (total_sales, total_volume) = (sales.int(0), sales.int(1));
+    Ok(StatsView {
+        total_sales,
+        total_volume,
+        total_fees: sales.int(2),
+        average_price: if total_sales > 0 { total_volume / total_sales } else { 0 },
+        active_listings: listings.int(0),
+        floor_price: listings.opt_int(1),
+        unique_sellers: sales.int(3),
+        unique_buyers: sales.int(4),
+        sales_24h: sales.int(5),
+        volume_24h: sales.int(6),
+        indexed_slot: checkpoint.and_then(|row| row.opt_int(0)),
+    })
+}
+
+// Meilleurs vendeurs par volume vendu
+pub fn top_sellers(db: &mut Database, query: &TopSellersQuery) -> Result<Vec<SellerView>, ApiError> {
+    let rows = db.query(
+        "SELECT wallet, items_sold, volume_sold,
+                (SELECT COUNT(*) FROM listings WHERE listings.seller = wallets.wallet AND listings.status = ?1)
+         FROM wallets
+         WHERE items_sold > 0
+         ORDER BY volume_sold DESC, items_sold DESC, wallet ASC
+         LIMIT ?2",
+        &[LISTING_ACTIVE.into(), i64::from(page_size(query.limit)).into()],
+    )?;
+    
+    Ok(rows
+        .iter()
+        .map(|row| SellerView {
+            wallet: row.text(0),
+            items_sold: row.int(1),
+            volume_sold: row.int(2),
+            active_listings: row.int(3),
+        })
+        .collect())
+}
+
+// Compteurs d'un wallet
+pub fn wallet(db: &mut Database, wallet: &str) -> Result<Option<WalletView>, ApiError> {
+    let row = db.query_optional(
+        "SELECT wallet, listings_created, listings_canceled, items_bought, items_sold, volume_bought, volume_sold,
+                first_activity_at, last_activity_at
+         FROM wallets WHERE wallet = ?1",
+        &[wallet.into()],
+    )?;
+    
+    Ok(row.map(|row| WalletView {
+        wallet: row.text(0),
+        listings_created: row.int(1),
+        listings_canceled: row.int(2),
+        items_bought: row.int(3),
+        items_sold: row.int(4),
+        volume_bought: row.int(5),
+        volume_sold: row.int(6),
+        first_activity_at: row.opt_int(7),
+        last_activity_at: row.opt_int(8),
+    }))
+}
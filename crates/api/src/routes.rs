@@ -0,0 +1,105 @@
+//! Routes HTTP.
+
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Path, Query, State};
+use axum::http::header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN};
+use axum::middleware::map_response;
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+use solana_nft_marketplace_indexer::{Database, Store};
+
+use crate::queries::{self, ActivityQuery, ListingQuery, PageQuery, TopSellersQuery};
+use crate::ApiError;
+
+// La connexion est partagée entre les requêtes (SQLite et le client Postgres synchrone ne sont pas partageables)
+type AppState = Arc<Mutex<Store>>;
+
+// Construire le routeur de l'API
+pub fn router(store: Store) -> Router {
+    Router::new()
+        .route("/listings", get(list_listings))
+        .route("/listings/:address", get(get_listing))
+        .route("/mints/:mint/sales", get(mint_sales))
+        .route("/stats", get(stats))
+        .route("/sellers/top", get(top_sellers))
+        .route("/wallets/:wallet", get(get_wallet))
+        .route("/activity", get(activity))
+        .layer(map_response(allow_any_origin))
+        .with_state(Arc::new(Mutex::new(store)))
+}
+
+// L'API est publique et en lecture seule: elle peut être appelée depuis n'importe quelle origine
+async fn allow_any_origin(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    response
+}
+
+// Exécuter une requête sur la base hors des threads asynchrones
+async fn with_db<T: Send + 'static>(
+    state: AppState,
+    f: impl FnOnce(&mut Database) -> Result<T, ApiError> + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let mut store = state.lock().map_err(|_| ApiError::Aborted)?;
+        f(store.db())
+    })
+    .await
+    .map_err(|_| ApiError::Aborted)?
+}
+
+async fn list_listings(
+    State(state): State<AppState>,
+    Query(query): Query<ListingQuery>,
+) -> Result<Json<queries::Page<queries::ListingView>>, ApiError> {
+    with_db(state, move |db| queries::listings(db, &query)).await.map(Json)
+}
+
+async fn get_listing(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<queries::ListingView>, ApiError> {
+    let listing = with_db(state, move |db| queries::listing(db, &address)).await?;
+    listing.map(Json).ok_or(ApiError::NotFound)
+}
+
+async fn mint_sales(
+    State(state): State<AppState>,
+    Path(mint): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<queries::Page<queries::SaleView>>, ApiError> {
+    with_db(state, move |db| queries::mint_sales(db, &mint, &query)).await.map(Json)
+}
+
+async fn stats(State(state): State<AppState>) -> Result<Json<queries::StatsView>, ApiError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    with_db(state, move |db| queries::stats(db, now)).await.map(Json)
+}
+
+async fn top_sellers(
+    State(state): State<AppState>,
+    Query(query): Query<TopSellersQuery>,
+) -> Result<Json<Vec<queries::SellerView>>, ApiError> {
+    with_db(state, move |db| queries::top_sellers(db, &query)).await.map(Json)
+}
+
+async fn get_wallet(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> Result<Json<queries::WalletView>, ApiError> {
+    let wallet = with_db(state, move |db| queries::wallet(db, &wallet)).await?;
+    wallet.map(Json).ok_or(ApiError::NotFound)
+}
+
+async fn activity(
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<queries::Page<queries::ActivityView>>, ApiError> {
+    with_db(state, move |db| queries::activity(db, &query)).await.map(Json)
+}
@@ -0,0 +1,335 @@
+//! Routes de l'API servies sur un port local, au-dessus d'une base SQLite en mémoire remplie à la main.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use serde_json::Value as Json;
+use solana_nft_marketplace_api::router;
+use solana_nft_marketplace_indexer::schema::*;
+use solana_nft_marketplace_indexer::{Store, Value};
+use tokio::runtime::Runtime;
+
+// Serveur de test: le runtime vit aussi longtemps que le test
+struct Api {
+    address: SocketAddr,
+    _runtime: Runtime,
+}
+
+impl Api {
+    fn serve(store: Store) -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+        let listener = runtime.block_on(tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+        let address = listener.local_addr().unwrap();
+        runtime.spawn(async move { axum::serve(listener, router(store)).await.unwrap() });
+        Api { address, _runtime: runtime }
+    }
+    
+    // Requête GET: statut HTTP et corps JSON
+    fn get(&self, path: &str) -> (u16, Json) {
+        let mut stream = TcpStream::connect(self.address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        
+        let (head, body) = response.split_once("\r\n\r\n").expect("réponse HTTP");
+        let status = head[9..12].parse().unwrap();
+        (status, serde_json::from_str(body).unwrap_or(Json::Null))
+    }
+    
+    // Parcourir toutes les pages d'une liste et renvoyer le champ `field` de chaque élément
+    fn walk(&self, path: &str, field: &str) -> Vec<String> {
+        self.walk_from(path, field, None)
+    }
+    
+    // Idem, à partir du curseur d'une page déjà lue
+    fn walk_from(&self, path: &str, field: &str, mut cursor: Option<String>) -> Vec<String> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut values = Vec::new();
+        loop {
+            let url = match &cursor {
+                Some(cursor) => format!("{path}{separator}cursor={cursor}"),
+                None => path.to_string(),
+            };
+            let (status, body) = self.get(&url);
+            assert_eq!(status, 200, "{url}: {body}");
+            values.extend(strings(&body, field));
+            match body["next_cursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => return values,
+            }
+        }
+    }
+}
+
+fn strings(body: &Json, field: &str) -> Vec<String> {
+    body["items"].as_array().unwrap().iter().map(|item| item[field].as_str().unwrap().to_string()).collect()
+}
+
+fn addresses(api: &Api, path: &str) -> Vec<String> {
+    let (status, body) = api.get(path);
+    assert_eq!(status, 200, "{path}: {body}");
+    assert!(body["next_cursor"].is_null());
+    strings(&body, "address")
+}
+
+fn insert_listing(store: &mut Store, address: &str, seller: &str, mint: &str, price: i64, status: &str, slot: i64) {
+    store
+        .db()
+        .execute(
+            "INSERT INTO listings (address, seller, nft_mint, collection, price, quantity, status, created_slot,
+                                   created_at, updated_slot, updated_at)
+             VALUES (?1, ?2, ?3, NULL, ?4, 1, ?5, ?6, NULL, ?6, NULL)",
+            &[address.into(), seller.into(), mint.into(), price.into(), status.into(), slot.into()],
+        )
+        .unwrap();
+}
+
+// Vente identifiée par son listing, seul champ distinct des éléments de l'historique
+fn insert_sale(store: &mut Store, key: (&str, i64, i64), slot: i64, listing: &str, mint: &str, price: i64) {
+    let (signature, event_index, item_index) = key;
+    store
+        .db()
+        .execute(
+            "INSERT INTO sales (signature, event_index, item_index, slot, block_time, kind, listing, nft_mint, seller,
+                                buyer, unit_price, quantity, price, marketplace_fee, referrer, referral_fee)
+             VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6, ?7, 'alice', 'carol', ?8, 1, ?8, 0, NULL, 0)",
+            &[
+                signature.into(),
+                event_index.into(),
+                item_index.into(),
+                slot.into(),
+                SALE_LISTING.into(),
+                listing.into(),
+                mint.into(),
+                price.into(),
+            ],
+        )
+        .unwrap();
+}
+
+// Entrée du fil d'activité identifiée par son compte
+fn insert_activity(
+    store: &mut Store,
+    key: (&str, i64),
+    slot: i64,
+    kind: &str,
+    actors: (&str, Option<&str>),
+    account: &str,
+) {
+    let (signature, event_index) = key;
+    let (wallet, counterparty) = actors;
+    store
+        .db()
+        .execute(
+            "INSERT INTO activity (signature, event_index, slot, block_time, kind, wallet, counterparty, account,
+                                   nft_mint, price)
+             VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6, ?7, 'mint-a', NULL)",
+            &[
+                signature.into(),
+                event_index.into(),
+                slot.into(),
+                kind.into(),
+                wallet.into(),
+                counterparty.map_or(Value::Null, Value::from),
+                account.into(),
+            ],
+        )
+        .unwrap();
+}
+
+fn insert_wallet(store: &mut Store, wallet: &str, items_sold: i64, volume_sold: i64) {
+    store
+        .db()
+        .execute(
+            "INSERT INTO wallets (wallet, listings_created, listings_canceled, items_bought, items_sold, volume_bought,
+                                  volume_sold, first_activity_at, last_activity_at)
+             VALUES (?1, 0, 0, 0, ?2, 0, ?3, NULL, NULL)",
+            &[wallet.into(), items_sold.into(), volume_sold.into()],
+        )
+        .unwrap();
+}
+
+// Deux listings ex aequo en prix (l2, l3) et en slot de création (l2, l3) pour éprouver le départage par adresse
+fn seed(mut store: Store) -> Store {
+    insert_listing(&mut store, "l1", "alice", "mint-a", 100, LISTING_ACTIVE, 10);
+    insert_listing(&mut store, "l2", "alice", "mint-b", 200, LISTING_ACTIVE, 11);
+    insert_listing(&mut store, "l3", "bob", "mint-c", 200, LISTING_ACTIVE, 11);
+    insert_listing(&mut store, "l4", "bob", "mint-d", 300, LISTING_ACTIVE, 12);
+    insert_listing(&mut store, "l5", "bob", "mint-e", 150, LISTING_SOLD, 9);
+    insert_listing(&mut store, "l6", "alice", "mint-f", 50, LISTING_CANCELED, 13);
+    insert_listing(&mut store, "l7", "bob", "mint-a", 400, LISTING_ACTIVE, 14);
+    
+    insert_sale(&mut store, ("sig-a", 0, 0), 20, "s1", "mint-a", 10);
+    insert_sale(&mut store, ("sig-a", 0, 1), 20, "s2", "mint-a", 20);
+    insert_sale(&mut store, ("sig-b", 0, 0), 21, "s3", "mint-a", 30);
+    insert_sale(&mut store, ("sig-c", 1, 0), 21, "s4", "mint-a", 40);
+    insert_sale(&mut store, ("sig-d", 0, 0), 5, "s5", "mint-a", 50);
+    insert_sale(&mut store, ("sig-e", 0, 0), 30, "s6", "mint-b", 60);
+    
+    insert_activity(&mut store, ("sig-f", 0), 30, "list", ("alice", None), "a1");
+    insert_activity(&mut store, ("sig-f", 1), 30, "sale", ("carol", Some("alice")), "a2");
+    insert_activity(&mut store, ("sig-g", 0), 31, "sale", ("carol", Some("bob")), "a3");
+    insert_activity(&mut store, ("sig-h", 0), 29, "cancel", ("bob", None), "a4");
+    insert_activity(&mut store, ("sig-i", 0), 31, "list", ("bob", None), "a5");
+    
+    insert_wallet(&mut store, "alice", 3, 600);
+    insert_wallet(&mut store, "bob", 1, 900);
+    insert_wallet(&mut store, "carol", 0, 0);
+    store
+}
+
+fn seeded() -> Store {
+    seed(Store::open("sqlite::memory:").unwrap())
+}
+
+#[test]
+fn listing_filters() {
+    let api = Api::serve(seeded());
+    
+    // Par défaut: listings actifs, les plus récents d'abord
+    assert_eq!(addresses(&api, "/listings"), ["l7", "l4", "l3", "l2", "l1"]);
+    assert_eq!(addresses(&api, "/listings?status=all").len(), 7);
+    assert_eq!(addresses(&api, "/listings?status=sold"), ["l5"]);
+    assert_eq!(addresses(&api, "/listings?status=canceled"), ["l6"]);
+    assert_eq!(addresses(&api, "/listings?seller=alice"), ["l2", "l1"]);
+    assert_eq!(addresses(&api, "/listings?seller=alice&status=all"), ["l6", "l2", "l1"]);
+    assert_eq!(addresses(&api, "/listings?mint=mint-a"), ["l7", "l1"]);
+    assert_eq!(addresses(&api, "/listings?min_price=150&max_price=300"), ["l4", "l3", "l2"]);
+    assert_eq!(addresses(&api, "/listings?sort=price_asc"), ["l1", "l2", "l3", "l4", "l7"]);
+    assert_eq!(addresses(&api, "/listings?sort=price_desc"), ["l7", "l4", "l3", "l2", "l1"]);
+    assert_eq!(addresses(&api, "/listings?seller=bob&max_price=350&sort=price_asc"), ["l3", "l4"]);
+    assert!(addresses(&api, "/listings?seller=dave").is_empty());
+    
+    let (status, listing) = api.get("/listings/l4");
+    assert_eq!(status, 200);
+    assert_eq!((listing["seller"].as_str(), listing["price"].as_i64()), (Some("bob"), Some(300)));
+    
+    // Paramètres mal typés ou tri inconnu
+    assert_eq!(api.get("/listings?min_price=cheap").0, 400);
+    assert_eq!(api.get("/listings?sort=cheapest").0, 400);
+}
+
+#[test]
+fn sales_activity_stats_and_sellers() {
+    let api = Api::serve(seeded());
+    
+    // Historique d'un NFT: slot, signature, événement puis élément décroissants
+    assert_eq!(api.walk("/mints/mint-a/sales", "listing"), ["s4", "s3", "s2", "s1", "s5"]);
+    assert!(strings(&api.get("/mints/mint-z/sales").1, "listing").is_empty());
+    
+    assert_eq!(api.walk("/activity", "account"), ["a5", "a3", "a2", "a1", "a4"]);
+    // Un wallet est retrouvé comme acteur ou comme contrepartie
+    assert_eq!(api.walk("/activity?wallet=bob", "account"), ["a5", "a3", "a4"]);
+    assert_eq!(api.walk("/activity?kind=sale", "account"), ["a3", "a2"]);
+    assert_eq!(api.walk("/activity?wallet=alice&kind=list", "account"), ["a1"]);
+    assert!(api.walk("/activity?mint=mint-z", "account").is_empty());
+    
+    let (status, stats) = api.get("/stats");
+    assert_eq!(status, 200);
+    assert_eq!(stats["total_sales"], 6);
+    assert_eq!(stats["total_volume"], 210);
+    assert_eq!(stats["active_listings"], 5);
+    assert_eq!(stats["floor_price"], 100);
+    assert!(stats["indexed_slot"].is_null());
+    
+    let (status, sellers) = api.get("/sellers/top");
+    assert_eq!(status, 200);
+    let ranking: Vec<(&str, i64)> = sellers
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|seller| (seller["wallet"].as_str().unwrap(), seller["active_listings"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(ranking, [("bob", 3), ("alice", 2)]);
+    assert_eq!(api.get("/sellers/top?limit=1").1.as_array().unwrap().len(), 1);
+    
+    let (status, wallet) = api.get("/wallets/alice");
+    assert_eq!((status, wallet["volume_sold"].as_i64()), (200, Some(600)));
+}
+
+#[test]
+fn cursor_pagination_is_stable_without_duplicates_or_gaps() {
+    let api = Api::serve(seeded());
+    
+    for (sort, expected) in [
+        ("recent", ["l7", "l4", "l3", "l2", "l1"]),
+        ("price_asc", ["l1", "l2", "l3", "l4", "l7"]),
+        ("price_desc", ["l7", "l4", "l3", "l2", "l1"]),
+    ] {
+        for limit in 1..=5 {
+            assert_eq!(api.walk(&format!("/listings?sort={sort}&limit={limit}"), "address"), expected);
+        }
+    }
+    for limit in 1..=5 {
+        assert_eq!(api.walk(&format!("/mints/mint-a/sales?limit={limit}"), "listing"), ["s4", "s3", "s2", "s1", "s5"]);
+        assert_eq!(api.walk(&format!("/activity?limit={limit}"), "account"), ["a5", "a3", "a2", "a1", "a4"]);
+    }
+    
+    // Une page redemandée avec le même curseur est identique
+    let (_, first) = api.get("/listings?sort=price_asc&limit=2");
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let (_, second) = api.get(&format!("/listings?sort=price_asc&limit=2&cursor={cursor}"));
+    let (_, again) = api.get(&format!("/listings?sort=price_asc&limit=2&cursor={cursor}"));
+    assert_eq!(second, again);
+    assert_eq!(strings(&second, "address"), ["l3", "l4"]);
+    
+    // La dernière page n'a pas de curseur, même quand elle est pleine
+    let (_, last) = api.get("/listings?limit=5");
+    assert!(last["next_cursor"].is_null());
+    
+    // La taille de page est bornée
+    let (_, clamped) = api.get("/listings?limit=0");
+    assert_eq!(strings(&clamped, "address"), ["l7"]);
+}
+
+#[test]
+fn new_rows_do_not_shift_the_following_pages() {
+    // Base en mémoire partagée: une seconde connexion écrit pendant que l'API lit
+    let url = "sqlite:file:api-routes?mode=memory&cache=shared";
+    let api = Api::serve(seed(Store::open(url).unwrap()));
+    let mut writer = Store::open(url).unwrap();
+    
+    let (_, first) = api.get("/listings?limit=2");
+    assert_eq!(strings(&first, "address"), ["l7", "l4"]);
+    let cursor = first["next_cursor"].as_str().map(str::to_string);
+    
+    // Un listing plus récent et un listing moins cher apparaissent entre deux pages:
+    // la suite reprend après le dernier élément vu, sans doublon
+    insert_listing(&mut writer, "l8", "carol", "mint-g", 10, LISTING_ACTIVE, 99);
+    insert_listing(&mut writer, "l0", "carol", "mint-h", 250, LISTING_ACTIVE, 11);
+    assert_eq!(api.get("/listings/l8").0, 200);
+    assert_eq!(api.walk_from("/listings?limit=2", "address", cursor), ["l3", "l2", "l0", "l1"]);
+}
+
+#[test]
+fn invalid_cursors_are_rejected() {
+    let api = Api::serve(seeded());
+    
+    for path in [
+        "/listings?cursor=abc",
+        "/listings?cursor=10",
+        "/listings?cursor=ten:l1",
+        "/listings?cursor=10:",
+        "/listings?cursor=10:l1:extra",
+        "/mints/mint-a/sales?cursor=20:sig-a:0",
+        "/mints/mint-a/sales?cursor=20:sig-a:zero:0",
+        "/activity?cursor=30:sig-f",
+        "/activity?cursor=30::0",
+    ] {
+        let (status, body) = api.get(path);
+        assert_eq!(status, 400, "{path}");
+        assert_eq!(body["error"], "curseur invalide", "{path}");
+    }
+}
+
+#[test]
+fn unknown_resources_are_not_found() {
+    let api = Api::serve(seeded());
+    
+    for path in ["/listings/missing", "/wallets/dave"] {
+        let (status, body) = api.get(path);
+        assert_eq!(status, 404, "{path}");
+        assert_eq!(body["error"], "ressource introuvable");
+    }
+    assert_eq!(api.get("/offers").0, 404);
+}
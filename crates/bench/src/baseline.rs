@@ -0,0 +1,108 @@
+//! Fichier de référence des unités de calcul et détection des régressions.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::scenario::Measurement;
+
+// Hausse tolérée par défaut, en pourcentage de la référence
+pub const DEFAULT_TOLERANCE_PERCENT: u64 = 2;
+
+/// Unités de calcul de référence par instruction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub tolerance_percent: u64,
+    pub instructions: BTreeMap<String, u64>,
+}
+
+impl Default for Baseline {
+    fn default() -> Self {
+        Baseline {
+            tolerance_percent: DEFAULT_TOLERANCE_PERCENT,
+            instructions: BTreeMap::new(),
+        }
+    }
+}
+
+/// Résultat de la comparaison d'une mesure à la référence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    New,       // Instruction absente de la référence
+    Improved,  // Moins d'unités que la référence
+    Unchanged, // Autant d'unités que la référence
+    Increased, // Hausse dans la tolérance
+    Regressed, // Hausse au-delà de la tolérance
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    pub instruction: String,
+    pub baseline: Option<u64>,
+    pub measured: u64,
+    pub status: Status,
+}
+
+impl Comparison {
+    /// Écart relatif à la référence, en pourcentage.
+    pub fn change_percent(&self) -> Option<f64> {
+        self.baseline
+            .filter(|baseline| *baseline > 0)
+            .map(|baseline| (self.measured as f64 - baseline as f64) * 100.0 / baseline as f64)
+    }
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("invalid baseline file {}", path.display()))
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        std::fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+    }
+    
+    /// Remplacer la référence des instructions mesurées.
+    pub fn record(&mut self, measurements: &[Measurement]) {
+        for measurement in measurements {
+            self.instructions.insert(measurement.instruction.clone(), measurement.units);
+        }
+    }
+    
+    /// Comparer des mesures à la référence, dans l'ordre des mesures.
+    pub fn compare(&self, measurements: &[Measurement]) -> Vec<Comparison> {
+        measurements
+            .iter()
+            .map(|measurement| {
+                let baseline = self.instructions.get(&measurement.instruction).copied();
+                Comparison {
+                    instruction: measurement.instruction.clone(),
+                    baseline,
+                    measured: measurement.units,
+                    status: self.status(baseline, measurement.units),
+                }
+            })
+            .collect()
+    }
+    
+    fn status(&self, baseline: Option<u64>, measured: u64) -> Status {
+        let Some(baseline) = baseline else {
+            return Status::New;
+        };
+        let limit = baseline as u128 + baseline as u128 * self.tolerance_percent as u128 / 100;
+        match measured.cmp(&baseline) {
+            std::cmp::Ordering::Less => Status::Improved,
+            std::cmp::Ordering::Equal => Status::Unchanged,
+            std::cmp::Ordering::Greater if measured as u128 <= limit => Status::Increased,
+            std::cmp::Ordering::Greater => Status::Regressed,
+        }
+    }
+}
+
+pub fn has_regressions(comparisons: &[Comparison]) -> bool {
+    comparisons.iter().any(|comparison| comparison.status == Status::Regressed)
+}
@@ -0,0 +1,12 @@
+//! Mesure des unités de calcul (CU) consommées par les instructions de la marketplace.
+//!
+//! Le scénario est exécuté sur un validateur où le programme est déployé: chaque instruction est
+//! simulée pour relever ses unités de calcul, puis envoyée pour préparer la suivante. Les mesures
+//! sont comparées à un fichier de référence versionné (`compute-units.json`), avec une tolérance
+//! au-delà de laquelle une hausse est une régression.
+
+pub mod baseline;
+pub mod scenario;
+
+pub use baseline::{Baseline, Comparison, Status};
+pub use scenario::Measurement;
@@ -0,0 +1,76 @@
+//! Mesure des unités de calcul des instructions de la marketplace sur un validateur, comparées
+//! au fichier de référence versionné (ou enregistrées comme nouvelle référence avec `--update`).
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace_bench::baseline::has_regressions;
+use solana_nft_marketplace_bench::{scenario, Baseline, Comparison, Status};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Parser)]
+#[command(name = "marketplace-bench", version, about = "Unités de calcul des instructions de la marketplace")]
+struct Cli {
+    /// URL du validateur où le programme est déployé (build sans la feature program-logs)
+    #[arg(short = 'u', long = "url", default_value = "http://127.0.0.1:8899", env = "MARKETPLACE_RPC_URL")]
+    url: String,
+
+    /// Fichier de référence des unités de calcul
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/compute-units.json"))]
+    baseline: PathBuf,
+
+    /// Enregistrer les mesures comme nouvelle référence au lieu de les comparer
+    #[arg(long, visible_alias = "record")]
+    update: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+    
+    let measurements = scenario::run(&rpc)?;
+    if measurements.iter().any(|measurement| measurement.program_logs > 0) {
+        eprintln!("warning: the deployed program emits diagnostic logs (built with `program-logs`), units are not comparable to a production build");
+    }
+    
+    let mut baseline = if cli.update && !cli.baseline.exists() {
+        Baseline::default()
+    } else {
+        Baseline::load(&cli.baseline)?
+    };
+    let comparisons = baseline.compare(&measurements);
+    print_comparisons(&comparisons, baseline.tolerance_percent);
+    
+    if cli.update {
+        baseline.record(&measurements);
+        baseline.save(&cli.baseline)?;
+        println!("baseline written to {}", cli.baseline.display());
+        return Ok(());
+    }
+    
+    if comparisons.iter().any(|comparison| comparison.status == Status::New) {
+        println!("new instructions are not in the baseline, run with --update to record them");
+    }
+    if has_regressions(&comparisons) {
+        bail!("compute units regressed beyond {}% of the baseline", baseline.tolerance_percent);
+    }
+    Ok(())
+}
+
+fn print_comparisons(comparisons: &[Comparison], tolerance_percent: u64) {
+    println!("{:<16} {:>10} {:>10} {:>9}  status (tolerance {}%)", "instruction", "baseline", "measured", "change", tolerance_percent);
+    for comparison in comparisons {
+        let baseline = comparison.baseline.map_or("-".to_string(), |units| units.to_string());
+        let change = comparison.change_percent().map_or("-".to_string(), |change| format!("{:+.2}%", change));
+        let status = match comparison.status {
+            Status::New => "new",
+            Status::Improved => "improved",
+            Status::Unchanged => "unchanged",
+            Status::Increased => "increased",
+            Status::Regressed => "REGRESSED",
+        };
+        println!("{:<16} {:>10} {:>10} {:>9}  {}", comparison.instruction, baseline, comparison.measured, change, status);
+    }
+}
@@ -0,0 +1,182 @@
+//! Scénario mesuré: listing, mise à jour, annulation et achat d'un NFT créé pour l'occasion,
+//! par des portefeuilles neufs alimentés par airdrop (validateur local ou devnet).
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace_sdk::{accounts, instructions, pda, Marketplace};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+
+// Solde des portefeuilles du scénario
+const WALLET_LAMPORTS: u64 = 2 * LAMPORTS_PER_SOL;
+
+// Prix du NFT mis en vente
+const PRICE: u64 = LAMPORTS_PER_SOL / 10;
+
+// Frais de la marketplace si le scénario doit l'initialiser
+const FEE_BPS: u16 = 250;
+
+// Attente maximale de la confirmation d'un airdrop
+const AIRDROP_ATTEMPTS: usize = 60;
+
+/// Unités de calcul consommées par une instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    pub instruction: String,
+    pub units: u64,
+    pub program_logs: usize, // Logs de diagnostic émis (0 sans la feature `program-logs`)
+}
+
+impl Measurement {
+    pub fn new(instruction: &str, units: u64) -> Self {
+        Measurement {
+            instruction: instruction.to_string(),
+            units,
+            program_logs: 0,
+        }
+    }
+}
+
+struct Scenario<'a> {
+    rpc: &'a RpcClient,
+    measurements: Vec<Measurement>,
+}
+
+/// Exécuter le scénario et retourner les unités de calcul de chaque instruction mesurée.
+pub fn run(rpc: &RpcClient) -> Result<Vec<Measurement>> {
+    let mut scenario = Scenario { rpc, measurements: Vec::new() };
+    let seller = Keypair::new();
+    let buyer = Keypair::new();
+    scenario.fund(&seller.pubkey())?;
+    scenario.fund(&buyer.pubkey())?;
+    
+    // La marketplace peut déjà exister (déploiement partagé): elle n'est initialisée qu'une fois
+    if accounts::fetch_optional::<Marketplace>(rpc, &pda::marketplace_address().0)?.is_none() {
+        scenario.send(
+            &seller,
+            &[],
+            &[
+                instructions::initialize_marketplace(seller.pubkey(), FEE_BPS),
+                instructions::initialize_stats(seller.pubkey()),
+            ],
+        )?;
+    }
+    
+    let mint = scenario.create_nft(&seller, &buyer.pubkey())?;
+    let listing = pda::listing_address(&mint, &seller.pubkey()).0;
+    
+    scenario.measure("list_nft", &seller, instructions::ListNft::new(seller.pubkey(), mint, PRICE).instruction())?;
+    
+    let state = accounts::fetch_listing(rpc, &listing)?;
+    let update = instructions::UpdateListing::new(listing, &state, PRICE * 2, 1);
+    scenario.measure("update_listing", &seller, update.instruction())?;
+    
+    let state = accounts::fetch_listing(rpc, &listing)?;
+    scenario.measure("cancel_listing", &seller, instructions::CancelListing::new(listing, &state).instruction())?;
+    
+    // Remettre le NFT en vente (non mesuré) pour l'acheter
+    let state = accounts::fetch_listing(rpc, &listing)?;
+    let relist = instructions::UpdateListing::new(listing, &state, PRICE, 1);
+    scenario.send(&seller, &[], &[relist.instruction()])?;
+    
+    let state = accounts::fetch_listing(rpc, &listing)?;
+    let marketplace = accounts::fetch_marketplace(rpc)?;
+    let buy = instructions::BuyNft::new(buyer.pubkey(), listing, &state, &marketplace);
+    scenario.measure("buy_nft", &buyer, buy.instruction())?;
+    
+    Ok(scenario.measurements)
+}
+
+impl Scenario<'_> {
+    fn fund(&self, wallet: &Pubkey) -> Result<()> {
+        let signature = self
+            .rpc
+            .request_airdrop(wallet, WALLET_LAMPORTS)
+            .context("airdrop failed (the bench needs a local validator or devnet)")?;
+        for _ in 0..AIRDROP_ATTEMPTS {
+            if self.rpc.confirm_transaction(&signature)? {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(500));
+        }
+        bail!("airdrop {} was not confirmed", signature)
+    }
+    
+    // Créer un mint (0 décimale), le NFT du vendeur et le compte de token vide de l'acheteur
+    fn create_nft(&self, seller: &Keypair, buyer: &Pubkey) -> Result<Pubkey> {
+        let mint = Keypair::new();
+        let rent = self.rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+        let seller_token_account = get_associated_token_address(&seller.pubkey(), &mint.pubkey());
+        let instructions = [
+            system_instruction::create_account(
+                &seller.pubkey(),
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &seller.pubkey(), None, 0)?,
+            create_associated_token_account(&seller.pubkey(), &seller.pubkey(), &mint.pubkey(), &spl_token::ID),
+            create_associated_token_account(&seller.pubkey(), buyer, &mint.pubkey(), &spl_token::ID),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &seller_token_account,
+                &seller.pubkey(),
+                &[],
+                1,
+            )?,
+        ];
+        self.send(seller, &[&mint], &instructions)?;
+        Ok(mint.pubkey())
+    }
+    
+    // Simuler l'instruction seule pour relever ses unités de calcul, puis l'envoyer
+    fn measure(&mut self, name: &str, payer: &Keypair, instruction: Instruction) -> Result<()> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+        
+        let result = self.rpc.simulate_transaction(&transaction)?.value;
+        let logs = result.logs.unwrap_or_default();
+        if let Some(error) = result.err {
+            bail!("{} failed in simulation: {}\n{}", name, error, logs.join("\n"));
+        }
+        let units = result
+            .units_consumed
+            .ok_or_else(|| anyhow!("the RPC node did not report compute units for {}", name))?;
+        
+        self.measurements.push(Measurement {
+            program_logs: count_program_logs(&logs),
+            ..Measurement::new(name, units)
+        });
+        self.rpc
+            .send_and_confirm_transaction(&transaction)
+            .with_context(|| format!("{} failed", name))?;
+        Ok(())
+    }
+    
+    fn send(&self, payer: &Keypair, signers: &[&Keypair], instructions: &[Instruction]) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+}
+
+// Logs de diagnostic (hors nom d'instruction d'Anchor et de SPL Token et hors événements)
+fn count_program_logs(logs: &[String]) -> usize {
+    logs.iter()
+        .filter(|log| log.starts_with("Program log: ") && !log.starts_with("Program log: Instruction: "))
+        .count()
+}
@@ -0,0 +1,64 @@
+//! Comparaison des mesures au fichier de référence.
+
+use solana_nft_marketplace_bench::baseline::has_regressions;
+use solana_nft_marketplace_bench::{Baseline, Measurement, Status};
+
+fn baseline(entries: &[(&str, u64)]) -> Baseline {
+    Baseline {
+        tolerance_percent: 2,
+        instructions: entries.iter().map(|(name, units)| (name.to_string(), *units)).collect(),
+    }
+}
+
+#[test]
+fn statuses_follow_the_tolerance() {
+    let baseline = baseline(&[("list_nft", 10_000), ("update_listing", 10_000), ("buy_nft", 10_000), ("cancel_listing", 10_000)]);
+    let measurements = [
+        Measurement::new("list_nft", 9_000),
+        Measurement::new("update_listing", 10_000),
+        Measurement::new("buy_nft", 10_200),
+        Measurement::new("cancel_listing", 10_201),
+        Measurement::new("buy_many", 50_000),
+    ];
+    
+    let statuses: Vec<Status> = baseline.compare(&measurements).iter().map(|comparison| comparison.status).collect();
+    assert_eq!(
+        statuses,
+        [Status::Improved, Status::Unchanged, Status::Increased, Status::Regressed, Status::New]
+    );
+    assert!(has_regressions(&baseline.compare(&measurements)));
+    assert!(!has_regressions(&baseline.compare(&measurements[..3])));
+}
+
+#[test]
+fn change_is_relative_to_the_baseline() {
+    let comparisons = baseline(&[("buy_nft", 20_000)]).compare(&[Measurement::new("buy_nft", 21_000), Measurement::new("list_nft", 1)]);
+    assert_eq!(comparisons[0].change_percent(), Some(5.0));
+    assert_eq!(comparisons[1].change_percent(), None);
+}
+
+#[test]
+fn record_updates_measured_instructions_only() {
+    let mut baseline = baseline(&[("list_nft", 10_000), ("buy_nft", 30_000)]);
+    baseline.record(&[Measurement::new("buy_nft", 25_000), Measurement::new("cancel_listing", 8_000)]);
+    
+    assert_eq!(baseline.instructions["list_nft"], 10_000);
+    assert_eq!(baseline.instructions["buy_nft"], 25_000);
+    assert_eq!(baseline.instructions["cancel_listing"], 8_000);
+}
+
+#[test]
+fn baseline_round_trips_through_the_file() {
+    let path = std::env::temp_dir().join(format!("marketplace-bench-{}.json", std::process::id()));
+    let baseline = baseline(&[("buy_nft", 30_000)]);
+    baseline.save(&path).unwrap();
+    
+    assert_eq!(Baseline::load(&path).unwrap(), baseline);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn committed_baseline_is_valid() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/compute-units.json");
+    Baseline::load(path.as_ref()).unwrap();
+}
@@ -0,0 +1,184 @@
+//! Arguments de la ligne de commande et construction des instructions de chaque commande.
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use solana_nft_marketplace_sdk::{instructions, Marketplace, NFTListing, SellerVolume};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::output::ListingView;
+
+#[derive(Parser)]
+#[command(name = "marketplace-cli", version, about = "Administration et utilisation de la marketplace NFT")]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: Config,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Args)]
+pub struct Config {
+    /// URL du cluster ou raccourci (localhost, devnet, testnet, mainnet-beta)
+    #[arg(short = 'u', long = "url", global = true, default_value = "localhost", env = "MARKETPLACE_RPC_URL")]
+    pub url: String,
+
+    /// Fichier de la paire de clés qui signe les transactions
+    #[arg(short = 'k', long = "keypair", global = true, env = "MARKETPLACE_KEYPAIR")]
+    pub keypair: Option<PathBuf>,
+
+    /// Afficher le résultat en JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Simuler la transaction sans l'envoyer
+    #[arg(long, global = true)]
+    pub simulate: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Initialiser la marketplace et son compte de statistiques
+    Init {
+        /// Frais en points de base (250 = 2,5%)
+        #[arg(long)]
+        fee: u16,
+    },
+    /// Mettre un NFT en vente
+    List(ListArgs),
+    /// Modifier le prix et la quantité d'un listing
+    Update(UpdateArgs),
+    /// Acheter un NFT listé
+    Buy(BuyArgs),
+    /// Annuler un listing
+    Cancel {
+        /// Adresse du listing
+        #[arg(long)]
+        listing: Pubkey,
+    },
+    /// Afficher le compte de la marketplace
+    ShowMarketplace,
+    /// Afficher un listing
+    ShowListing {
+        /// Adresse du listing
+        listing: Pubkey,
+    },
+    /// Lister les listings de la marketplace
+    ListListings(ListingFilter),
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Mint du NFT
+    #[arg(long)]
+    pub mint: Pubkey,
+    /// Prix unitaire en lamports
+    #[arg(long)]
+    pub price: u64,
+    /// Quantité mise en vente
+    #[arg(long, default_value_t = 1)]
+    pub quantity: u64,
+    /// Collection vérifiée du NFT (fournit les métadonnées au programme)
+    #[arg(long)]
+    pub collection: Option<Pubkey>,
+    /// Fournir la configuration de la collection (mode curé)
+    #[arg(long, requires = "collection")]
+    pub collection_config: bool,
+}
+
+impl ListArgs {
+    pub fn instruction(&self, seller: Pubkey) -> Instruction {
+        let mut list = instructions::ListNft::new(seller, self.mint, self.price);
+        list.quantity = self.quantity;
+        list.collection = self.collection;
+        list.with_collection_config = self.collection_config;
+        list.instruction()
+    }
+}
+
+#[derive(Args)]
+pub struct UpdateArgs {
+    /// Adresse du listing
+    #[arg(long)]
+    pub listing: Pubkey,
+    /// Nouveau prix unitaire en lamports
+    #[arg(long)]
+    pub price: u64,
+    /// Nouvelle quantité
+    #[arg(long, default_value_t = 1)]
+    pub quantity: u64,
+}
+
+impl UpdateArgs {
+    pub fn instruction(&self, listing: &NFTListing) -> Instruction {
+        instructions::UpdateListing::new(self.listing, listing, self.price, self.quantity).instruction()
+    }
+}
+
+#[derive(Args)]
+pub struct BuyArgs {
+    /// Adresse du listing
+    #[arg(long)]
+    pub listing: Pubkey,
+    /// Quantité achetée
+    #[arg(long, default_value_t = 1)]
+    pub quantity: u64,
+    /// Parrain de l'achat (son compte de gains doit exister)
+    #[arg(long)]
+    pub referrer: Option<Pubkey>,
+}
+
+impl BuyArgs {
+    // Le compte de token du NFT de membre enregistré par le vendeur est obligatoire
+    pub fn instruction(
+        &self,
+        buyer: Pubkey,
+        listing: &NFTListing,
+        marketplace: &Marketplace,
+        seller_volume: Option<&SellerVolume>,
+    ) -> Instruction {
+        let mut buy = instructions::BuyNft::new(buyer, self.listing, listing, marketplace);
+        buy.quantity = self.quantity;
+        buy.referrer = self.referrer;
+        buy.membership_token = seller_volume.and_then(|volume| volume.membership_token);
+        buy.instruction()
+    }
+}
+
+#[derive(Args)]
+pub struct ListingFilter {
+    /// Uniquement les listings d'un vendeur
+    #[arg(long)]
+    pub seller: Option<Pubkey>,
+    /// Uniquement les listings d'un mint
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+    /// Inclure les listings inactifs
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl ListingFilter {
+    // Garder les listings correspondant au filtre, triés par prix croissant
+    pub fn select(&self, listings: &[(Pubkey, NFTListing)]) -> Vec<ListingView> {
+        let mut selected: Vec<ListingView> = listings
+            .iter()
+            .filter(|(_, listing)| self.all || listing.active)
+            .filter(|(_, listing)| self.seller.is_none_or(|seller| listing.seller == seller))
+            .filter(|(_, listing)| self.mint.is_none_or(|mint| listing.nft_mint == mint))
+            .map(|(address, listing)| ListingView::new(*address, listing))
+            .collect();
+        selected.sort_by_key(|listing| listing.price);
+        selected
+    }
+}
+
+// Initialiser la marketplace et son compte de statistiques dans la même transaction
+pub fn init_instructions(authority: Pubkey, fee: u16) -> Vec<Instruction> {
+    vec![
+        instructions::initialize_marketplace(authority, fee),
+        instructions::initialize_stats(authority),
+    ]
+}
@@ -0,0 +1,10 @@
+//! Outil en ligne de commande pour initialiser, inspecter et utiliser la marketplace
+//! sans passer par l'application React.
+//!
+//! Les arguments et la construction des instructions sont dans `command`, la mise en forme
+//! des résultats dans `output`; le binaire se charge des lectures RPC et de l'envoi.
+
+pub mod command;
+pub mod output;
+
+pub use command::{Cli, Command, Config};
@@ -0,0 +1,142 @@
+//! Binaire `marketplace-cli`: lecture des comptes, signature et envoi des transactions.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace_cli::command::init_instructions;
+use solana_nft_marketplace_cli::output::{ListingView, MarketplaceView, Output, QuoteView, TransactionView};
+use solana_nft_marketplace_cli::{Cli, Command, Config};
+use solana_nft_marketplace_sdk::fees::{quote_buy, BuyQuoteInputs};
+use solana_nft_marketplace_sdk::{accounts, cluster_url, instructions, pda, CollectionConfig, FeeSchedule, SellerVolume};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cluster_url(&cli.config.url), CommitmentConfig::confirmed());
+    
+    let output = run(&cli, &rpc)?;
+    output.print(cli.config.json)
+}
+
+fn run(cli: &Cli, rpc: &RpcClient) -> Result<Output> {
+    let config = &cli.config;
+    
+    match &cli.command {
+        Command::Init { fee } => {
+            let payer = load_keypair(config)?;
+            let instructions = init_instructions(payer.pubkey(), *fee);
+            Ok(Output::Transaction(send(rpc, config, &payer, &instructions)?))
+        }
+        Command::List(list) => {
+            let payer = load_keypair(config)?;
+            Ok(Output::Transaction(send(rpc, config, &payer, &[list.instruction(payer.pubkey())])?))
+        }
+        Command::Update(update) => {
+            let payer = load_keypair(config)?;
+            let account = accounts::fetch_listing(rpc, &update.listing)?;
+            Ok(Output::Transaction(send(rpc, config, &payer, &[update.instruction(&account)])?))
+        }
+        Command::Buy(buy) => {
+            let payer = load_keypair(config)?;
+            let marketplace = accounts::fetch_marketplace(rpc)?;
+            let account = accounts::fetch_listing(rpc, &buy.listing)?;
+            
+            // La configuration de la collection, si elle existe, peut modifier les frais
+            let collection_config = match account.collection {
+                Some(collection) => accounts::fetch_optional::<CollectionConfig>(
+                    rpc,
+                    &pda::collection_config_address(&collection).0,
+                )?,
+                None => None,
+            };
+            
+            let fee_schedule = accounts::fetch_optional::<FeeSchedule>(rpc, &pda::fee_schedule_address().0)?;
+            let seller_volume =
+                accounts::fetch_optional::<SellerVolume>(rpc, &pda::seller_volume_address(&account.seller).0)?;
+            let now = rpc.get_block_time(rpc.get_slot()?)?;
+            let seller_is_holder = accounts::fetch_seller_is_holder(rpc, fee_schedule.as_ref(), seller_volume.as_ref())?;
+            
+            let quote = quote_buy(&BuyQuoteInputs {
+                marketplace: &marketplace,
+                listing: &account,
+                quantity: buy.quantity,
+                fee_schedule: fee_schedule.as_ref(),
+                seller_volume: seller_volume.as_ref(),
+                seller_is_holder,
+                collection_config: collection_config.as_ref(),
+                has_referrer: buy.referrer.is_some(),
+                now,
+            })?;
+            
+            let instruction = buy.instruction(payer.pubkey(), &account, &marketplace, seller_volume.as_ref());
+            Ok(Output::Purchase {
+                quote: QuoteView::from(&quote),
+                transaction: send(rpc, config, &payer, &[instruction])?,
+            })
+        }
+        Command::Cancel { listing } => {
+            let payer = load_keypair(config)?;
+            let account = accounts::fetch_listing(rpc, listing)?;
+            let cancel = instructions::CancelListing::new(*listing, &account);
+            Ok(Output::Transaction(send(rpc, config, &payer, &[cancel.instruction()])?))
+        }
+        Command::ShowMarketplace => {
+            let address = pda::marketplace_address().0;
+            let marketplace = accounts::fetch_marketplace(rpc)?;
+            Ok(Output::Marketplace(MarketplaceView::new(address, &marketplace)))
+        }
+        Command::ShowListing { listing } => {
+            let account = accounts::fetch_listing(rpc, listing)?;
+            Ok(Output::Listing(ListingView::new(*listing, &account)))
+        }
+        Command::ListListings(filter) => Ok(Output::Listings(filter.select(&accounts::fetch_listings(rpc)?))),
+    }
+}
+
+// Charger la paire de clés (par défaut celle de la CLI Solana)
+fn load_keypair(config: &Config) -> Result<Keypair> {
+    let path = match &config.keypair {
+        Some(path) => path.clone(),
+        None => {
+            let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME is not set, use --keypair"))?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+    
+    read_keypair_file(&path).map_err(|error| anyhow!("failed to read keypair {}: {}", path.display(), error))
+}
+
+// Signer puis envoyer (ou simuler) une transaction
+fn send(rpc: &RpcClient, config: &Config, payer: &Keypair, instructions: &[Instruction]) -> Result<TransactionView> {
+    let blockhash = rpc.get_latest_blockhash().context("failed to fetch a recent blockhash")?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+    
+    if config.simulate {
+        let result = rpc.simulate_transaction(&transaction)?.value;
+        return Ok(TransactionView {
+            signature: transaction.signatures[0].to_string(),
+            simulated: true,
+            error: result.err.map(|error| error.to_string()),
+            units_consumed: result.units_consumed,
+            logs: result.logs.unwrap_or_default(),
+        });
+    }
+    
+    let signature = match rpc.send_and_confirm_transaction(&transaction) {
+        Ok(signature) => signature,
+        Err(error) => bail!("transaction failed: {}", error),
+    };
+    
+    Ok(TransactionView {
+        signature: signature.to_string(),
+        simulated: false,
+        error: None,
+        units_consumed: None,
+        logs: Vec::new(),
+    })
+}
@@ -0,0 +1,212 @@
+//! Mise en forme des résultats (texte ou JSON).
+
+use std::fmt::{self, Write};
+
+use anyhow::Result;
+use serde::Serialize;
+use solana_nft_marketplace_sdk::fees::BuyQuote;
+use solana_nft_marketplace_sdk::{Marketplace, NFTListing};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Output {
+    Transaction(TransactionView),
+    Purchase { quote: QuoteView, transaction: TransactionView },
+    Marketplace(MarketplaceView),
+    Listing(ListingView),
+    Listings(Vec<ListingView>),
+}
+
+#[derive(Serialize)]
+pub struct TransactionView {
+    pub signature: String,
+    pub simulated: bool,
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MarketplaceView {
+    pub address: String,
+    pub version: u8,
+    pub authority: String,
+    pub treasury: String,
+    pub fee_bps: u16,
+    pub referral_fee_bps: u16,
+    pub curated: bool,
+    pub moderators: Vec<String>,
+}
+
+impl MarketplaceView {
+    pub fn new(address: Pubkey, marketplace: &Marketplace) -> Self {
+        Self {
+            address: address.to_string(),
+            version: marketplace.version,
+            authority: marketplace.authority.to_string(),
+            treasury: marketplace.treasury.to_string(),
+            fee_bps: marketplace.fee,
+            referral_fee_bps: marketplace.referral_fee_bps,
+            curated: marketplace.curated,
+            moderators: marketplace.moderators.iter().map(Pubkey::to_string).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListingView {
+    pub address: String,
+    pub version: u8,
+    pub seller: String,
+    pub nft_mint: String,
+    pub price: u64,
+    pub quantity: u64,
+    pub active: bool,
+    pub collection: Option<String>,
+    pub payout_splits: Vec<PayoutSplitView>,
+}
+
+#[derive(Serialize)]
+pub struct PayoutSplitView {
+    pub recipient: String,
+    pub bps: u16,
+}
+
+impl ListingView {
+    pub fn new(address: Pubkey, listing: &NFTListing) -> Self {
+        Self {
+            address: address.to_string(),
+            version: listing.version,
+            seller: listing.seller.to_string(),
+            nft_mint: listing.nft_mint.to_string(),
+            price: listing.price,
+            quantity: listing.quantity,
+            active: listing.active,
+            collection: listing.collection.map(|collection| collection.to_string()),
+            payout_splits: listing
+                .payout_splits
+                .iter()
+                .map(|split| PayoutSplitView {
+                    recipient: split.recipient.to_string(),
+                    bps: split.bps,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct QuoteView {
+    pub total_price: u64,
+    pub fee_bps: u16,
+    pub fee_tier: Option<u8>,
+    pub fee_amount: u64,
+    pub referral_amount: u64,
+    pub treasury_amount: u64,
+    pub seller_amount: u64,
+}
+
+impl From<&BuyQuote> for QuoteView {
+    fn from(quote: &BuyQuote) -> Self {
+        Self {
+            total_price: quote.total_price,
+            fee_bps: quote.fee_bps,
+            fee_tier: quote.fee_tier,
+            fee_amount: quote.breakdown.fee_amount,
+            referral_amount: quote.breakdown.referral_amount,
+            treasury_amount: quote.breakdown.treasury_amount,
+            seller_amount: quote.breakdown.seller_amount,
+        }
+    }
+}
+
+impl Output {
+    pub fn print(&self, json: bool) -> Result<()> {
+        print!("{}", self.render(json)?);
+        Ok(())
+    }
+    
+    // Texte affiché par la commande (JSON indenté si demandé)
+    pub fn render(&self, json: bool) -> Result<String> {
+        if json {
+            return Ok(format!("{}\n", serde_json::to_string_pretty(self)?));
+        }
+        
+        let mut out = String::new();
+        match self {
+            Output::Transaction(transaction) => write_transaction(&mut out, transaction)?,
+            Output::Purchase { quote, transaction } => {
+                writeln!(out, "Total price:     {} lamports", quote.total_price)?;
+                writeln!(out, "Fee:             {} bps (tier {:?})", quote.fee_bps, quote.fee_tier)?;
+                writeln!(out, "Treasury amount: {} lamports", quote.treasury_amount)?;
+                writeln!(out, "Referral amount: {} lamports", quote.referral_amount)?;
+                writeln!(out, "Seller amount:   {} lamports", quote.seller_amount)?;
+                write_transaction(&mut out, transaction)?;
+            }
+            Output::Marketplace(marketplace) => {
+                writeln!(out, "Marketplace:  {}", marketplace.address)?;
+                writeln!(out, "Version:      {}", marketplace.version)?;
+                writeln!(out, "Authority:    {}", marketplace.authority)?;
+                writeln!(out, "Treasury:     {}", marketplace.treasury)?;
+                writeln!(out, "Fee:          {} bps", marketplace.fee_bps)?;
+                writeln!(out, "Referral fee: {} bps of the fee", marketplace.referral_fee_bps)?;
+                writeln!(out, "Curated:      {}", marketplace.curated)?;
+                writeln!(out, "Moderators:   {}", marketplace.moderators.join(", "))?;
+            }
+            Output::Listing(listing) => write_listing(&mut out, listing)?,
+            Output::Listings(listings) => {
+                for listing in listings {
+                    writeln!(
+                        out,
+                        "{}  mint {}  seller {}  {} x {} lamports{}",
+                        listing.address,
+                        listing.nft_mint,
+                        listing.seller,
+                        listing.quantity,
+                        listing.price,
+                        if listing.active { "" } else { "  (inactive)" },
+                    )?;
+                }
+                writeln!(out, "{} listing(s)", listings.len())?;
+            }
+        }
+        
+        Ok(out)
+    }
+}
+
+fn write_transaction(out: &mut String, transaction: &TransactionView) -> fmt::Result {
+    if !transaction.simulated {
+        return writeln!(out, "Signature: {}", transaction.signature);
+    }
+    
+    match &transaction.error {
+        Some(error) => writeln!(out, "Simulation failed: {}", error)?,
+        None => writeln!(out, "Simulation succeeded")?,
+    }
+    if let Some(units) = transaction.units_consumed {
+        writeln!(out, "Compute units: {}", units)?;
+    }
+    for log in &transaction.logs {
+        writeln!(out, "  {}", log)?;
+    }
+    Ok(())
+}
+
+fn write_listing(out: &mut String, listing: &ListingView) -> fmt::Result {
+    writeln!(out, "Listing:    {}", listing.address)?;
+    writeln!(out, "Version:    {}", listing.version)?;
+    writeln!(out, "Seller:     {}", listing.seller)?;
+    writeln!(out, "NFT mint:   {}", listing.nft_mint)?;
+    writeln!(out, "Price:      {} lamports", listing.price)?;
+    writeln!(out, "Quantity:   {}", listing.quantity)?;
+    writeln!(out, "Active:     {}", listing.active)?;
+    if let Some(collection) = &listing.collection {
+        writeln!(out, "Collection: {}", collection)?;
+    }
+    for split in &listing.payout_splits {
+        writeln!(out, "Payout:     {} ({} bps)", split.recipient, split.bps)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,302 @@
+//! Analyse des arguments, instructions construites par chaque commande et mise en forme des résultats.
+
+use clap::Parser;
+use solana_nft_marketplace_cli::command::{init_instructions, ListingFilter};
+use solana_nft_marketplace_cli::output::{ListingView, MarketplaceView, Output, QuoteView, TransactionView};
+use solana_nft_marketplace_cli::{Cli, Command};
+use solana_nft_marketplace_sdk::fees::BuyQuote;
+use solana_nft_marketplace_sdk::{
+    instructions, pda, FeeBreakdown, Marketplace, NFTListing, PayoutSplit, SellerVolume, PROGRAM_ID,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn parse(args: &[&str]) -> Cli {
+    Cli::try_parse_from([&["marketplace-cli"], args].concat()).unwrap()
+}
+
+fn listing(seller: Pubkey, price: u64, active: bool) -> NFTListing {
+    NFTListing {
+        version: 1,
+        seller,
+        nft_mint: Pubkey::new_unique(),
+        price,
+        active,
+        bump: 255,
+        quantity: 1,
+        payout_splits: Vec::new(),
+        collection: None,
+        stats_tracked: false,
+        reserved: [0; 63],
+    }
+}
+
+fn marketplace() -> Marketplace {
+    Marketplace {
+        version: 1,
+        authority: Pubkey::new_unique(),
+        fee: 250,
+        bump: 255,
+        referral_fee_bps: 1_000,
+        curated: true,
+        moderators: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        treasury: Pubkey::new_unique(),
+        reserved: [0; 64],
+    }
+}
+
+#[test]
+fn global_options_and_defaults() {
+    let cli = parse(&["show-marketplace"]);
+    assert_eq!(cli.config.url, "localhost");
+    assert_eq!(cli.config.keypair, None);
+    assert!(!cli.config.json && !cli.config.simulate);
+    assert!(matches!(cli.command, Command::ShowMarketplace));
+    
+    // Les options globales sont acceptées après la sous-commande
+    let cli = parse(&["init", "--fee", "250", "-u", "devnet", "--json", "--simulate", "-k", "/tmp/id.json"]);
+    assert_eq!(cli.config.url, "devnet");
+    assert_eq!(cli.config.keypair.as_deref(), Some(std::path::Path::new("/tmp/id.json")));
+    assert!(cli.config.json && cli.config.simulate);
+    assert!(matches!(cli.command, Command::Init { fee: 250 }));
+}
+
+#[test]
+fn trading_commands_parse_their_arguments() {
+    let mint = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+    let listing = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    
+    let cli = parse(&["list", "--mint", &mint.to_string(), "--price", "5000"]);
+    let Command::List(list) = cli.command else { panic!("commande list attendue") };
+    assert_eq!((list.mint, list.price, list.quantity), (mint, 5_000, 1));
+    assert_eq!((list.collection, list.collection_config), (None, false));
+    
+    let cli = parse(&[
+        "list",
+        "--mint",
+        &mint.to_string(),
+        "--price",
+        "5000",
+        "--quantity",
+        "3",
+        "--collection",
+        &collection.to_string(),
+        "--collection-config",
+    ]);
+    let Command::List(list) = cli.command else { panic!("commande list attendue") };
+    assert_eq!((list.quantity, list.collection, list.collection_config), (3, Some(collection), true));
+    
+    let cli = parse(&["buy", "--listing", &listing.to_string(), "--referrer", &referrer.to_string()]);
+    let Command::Buy(buy) = cli.command else { panic!("commande buy attendue") };
+    assert_eq!((buy.listing, buy.quantity, buy.referrer), (listing, 1, Some(referrer)));
+    
+    let cli = parse(&["update", "--listing", &listing.to_string(), "--price", "7", "--quantity", "2"]);
+    let Command::Update(update) = cli.command else { panic!("commande update attendue") };
+    assert_eq!((update.listing, update.price, update.quantity), (listing, 7, 2));
+    
+    let cli = parse(&["show-listing", &listing.to_string()]);
+    assert!(matches!(cli.command, Command::ShowListing { listing: address } if address == listing));
+    
+    let cli = parse(&["list-listings", "--all", "--seller", &referrer.to_string()]);
+    let Command::ListListings(filter) = cli.command else { panic!("commande list-listings attendue") };
+    assert_eq!((filter.seller, filter.mint, filter.all), (Some(referrer), None, true));
+}
+
+#[test]
+fn invalid_arguments_are_rejected() {
+    let mint = Pubkey::new_unique().to_string();
+    let invalid: &[&[&str]] = &[
+        &["list", "--mint", "not-a-pubkey", "--price", "1"],
+        &["list", "--mint", &mint],
+        &["list", "--mint", &mint, "--price", "-1"],
+        &["list", "--mint", &mint, "--price", "1", "--collection-config"],
+        &["init", "--fee", "70000"],
+        &["cancel"],
+        &["unknown"],
+    ];
+    for args in invalid {
+        assert!(Cli::try_parse_from([&["marketplace-cli"], *args].concat()).is_err(), "{:?} accepté", args);
+    }
+}
+
+#[test]
+fn commands_build_the_sdk_instructions() {
+    let payer = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+    
+    let init = init_instructions(payer, 250);
+    assert_eq!(init, vec![instructions::initialize_marketplace(payer, 250), instructions::initialize_stats(payer)]);
+    
+    let cli = parse(&[
+        "list",
+        "--mint",
+        &mint.to_string(),
+        "--price",
+        "42",
+        "--quantity",
+        "3",
+        "--collection",
+        &collection.to_string(),
+        "--collection-config",
+    ]);
+    let Command::List(list) = cli.command else { panic!("commande list attendue") };
+    let mut expected = instructions::ListNft::new(payer, mint, 42);
+    expected.quantity = 3;
+    expected.collection = Some(collection);
+    expected.with_collection_config = true;
+    assert_eq!(list.instruction(payer), expected.instruction());
+    
+    let listing_address = Pubkey::new_unique();
+    let account = listing(Pubkey::new_unique(), 10, true);
+    let cli = parse(&["update", "--listing", &listing_address.to_string(), "--price", "7", "--quantity", "2"]);
+    let Command::Update(update) = cli.command else { panic!("commande update attendue") };
+    assert_eq!(
+        update.instruction(&account),
+        instructions::UpdateListing::new(listing_address, &account, 7, 2).instruction()
+    );
+}
+
+#[test]
+fn buy_passes_the_referrer_and_the_registered_membership_token() {
+    let buyer = Pubkey::new_unique();
+    let listing_address = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let membership_token = Pubkey::new_unique();
+    let account = listing(Pubkey::new_unique(), 10, true);
+    let marketplace = marketplace();
+    
+    let cli = parse(&[
+        "buy",
+        "--listing",
+        &listing_address.to_string(),
+        "--quantity",
+        "1",
+        "--referrer",
+        &referrer.to_string(),
+    ]);
+    let Command::Buy(buy) = cli.command else { panic!("commande buy attendue") };
+    let volume = SellerVolume {
+        seller: account.seller,
+        window_start: 0,
+        current_volume: 0,
+        previous_volume: 0,
+        membership_token: Some(membership_token),
+        membership_collection: Some(Pubkey::new_unique()),
+        bump: 255,
+    };
+    
+    let mut expected = instructions::BuyNft::new(buyer, listing_address, &account, &marketplace);
+    expected.referrer = Some(referrer);
+    expected.membership_token = Some(membership_token);
+    assert_eq!(buy.instruction(buyer, &account, &marketplace, Some(&volume)), expected.instruction());
+    
+    // Sans compte de volume, le NFT de membre est laissé au programme
+    let instruction = buy.instruction(buyer, &account, &marketplace, None);
+    assert_eq!(instruction.accounts[11].pubkey, PROGRAM_ID);
+    assert_eq!(instruction.accounts[7].pubkey, referrer);
+    assert_eq!(instruction.accounts[8].pubkey, pda::referrer_earnings_address(&referrer).0);
+}
+
+#[test]
+fn listing_filter_selects_and_sorts_by_price() {
+    let seller = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let listings: Vec<(Pubkey, NFTListing)> = vec![
+        (Pubkey::new_unique(), listing(seller, 30, true)),
+        (Pubkey::new_unique(), listing(other, 10, true)),
+        (Pubkey::new_unique(), listing(seller, 20, false)),
+        (Pubkey::new_unique(), listing(seller, 5, true)),
+    ];
+    let prices = |filter: ListingFilter| filter.select(&listings).iter().map(|view| view.price).collect::<Vec<_>>();
+    
+    assert_eq!(prices(ListingFilter { seller: None, mint: None, all: false }), vec![5, 10, 30]);
+    assert_eq!(prices(ListingFilter { seller: None, mint: None, all: true }), vec![5, 10, 20, 30]);
+    assert_eq!(prices(ListingFilter { seller: Some(seller), mint: None, all: true }), vec![5, 20, 30]);
+    let mint = listings[0].1.nft_mint;
+    assert_eq!(prices(ListingFilter { seller: None, mint: Some(mint), all: false }), vec![30]);
+    assert!(prices(ListingFilter { seller: Some(other), mint: Some(mint), all: true }).is_empty());
+}
+
+#[test]
+fn text_output_formats_each_result() {
+    let seller = Pubkey::new_unique();
+    let address = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let mut account = listing(seller, 1_500, true);
+    account.quantity = 4;
+    account.collection = Some(Pubkey::new_unique());
+    account.payout_splits = vec![PayoutSplit { recipient, bps: 10_000 }];
+    
+    let text = Output::Listing(ListingView::new(address, &account)).render(false).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "Listing:    {}\nVersion:    1\nSeller:     {}\nNFT mint:   {}\nPrice:      1500 lamports\n\
+             Quantity:   4\nActive:     true\nCollection: {}\nPayout:     {} (10000 bps)\n",
+            address,
+            seller,
+            account.nft_mint,
+            account.collection.unwrap(),
+            recipient,
+        )
+    );
+    
+    let inactive = listing(seller, 20, false);
+    let text = Output::Listings(vec![ListingView::new(address, &inactive)]).render(false).unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "{}  mint {}  seller {}  1 x 20 lamports  (inactive)\n1 listing(s)\n",
+            address, inactive.nft_mint, seller
+        )
+    );
+    
+    let marketplace = marketplace();
+    let text = Output::Marketplace(MarketplaceView::new(address, &marketplace)).render(false).unwrap();
+    assert!(text.contains("Fee:          250 bps\n"));
+    assert!(text.contains("Referral fee: 1000 bps of the fee\n"));
+    assert!(text.contains(&format!("Moderators:   {}, {}\n", marketplace.moderators[0], marketplace.moderators[1])));
+}
+
+#[test]
+fn transaction_output_shows_signature_or_simulation() {
+    let sent = TransactionView {
+        signature: "5sig".to_string(),
+        simulated: false,
+        error: None,
+        units_consumed: None,
+        logs: Vec::new(),
+    };
+    assert_eq!(Output::Transaction(sent).render(false).unwrap(), "Signature: 5sig\n");
+    
+    let simulated = TransactionView {
+        signature: "5sig".to_string(),
+        simulated: true,
+        error: Some("custom program error: 0x1770".to_string()),
+        units_consumed: Some(42_000),
+        logs: vec!["Program log: Instruction: BuyNft".to_string()],
+    };
+    let quote = BuyQuote {
+        total_price: 1_000,
+        fee_bps: 250,
+        fee_tier: Some(1),
+        breakdown: FeeBreakdown { fee_amount: 25, referral_amount: 5, treasury_amount: 20, seller_amount: 975 },
+    };
+    let purchase = Output::Purchase { quote: QuoteView::from(&quote), transaction: simulated };
+    assert_eq!(
+        purchase.render(false).unwrap(),
+        "Total price:     1000 lamports\nFee:             250 bps (tier Some(1))\nTreasury amount: 20 lamports\n\
+         Referral amount: 5 lamports\nSeller amount:   975 lamports\nSimulation failed: custom program error: 0x1770\n\
+         Compute units: 42000\n  Program log: Instruction: BuyNft\n"
+    );
+    
+    // Le JSON garde la structure des vues, sans étiquette de variante
+    let json: serde_json::Value = serde_json::from_str(&purchase.render(true).unwrap()).unwrap();
+    assert_eq!(json["quote"]["fee_tier"], 1);
+    assert_eq!(json["quote"]["seller_amount"], 975);
+    assert_eq!(json["transaction"]["simulated"], true);
+    assert_eq!(json["transaction"]["units_consumed"], 42_000);
+    assert_eq!(json["transaction"]["logs"][0], "Program log: Instruction: BuyNft");
+}
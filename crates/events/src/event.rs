@@ -0,0 +1,90 @@
+//! Énumération typée des événements émis par le programme.
+
+use anchor_lang::{AnchorDeserialize, AnchorSerialize, Discriminator};
+use ::solana_nft_marketplace::*;
+
+use crate::EventError;
+
+// Générer l'énumération et son décodeur à partir de la liste des événements du programme
+macro_rules! marketplace_events {
+    ($($event:ident),* $(,)?) => {
+        /// Événement émis par le programme (via `emit!` ou `emit_cpi!`).
+        #[derive(Clone, Debug)]
+        pub enum MarketplaceEvent {
+            $($event($event),)*
+        }
+
+        impl MarketplaceEvent {
+            /// Décoder un événement sérialisé (discriminateur de 8 octets suivi des données Borsh).
+            /// Retourne `Ok(None)` si le discriminateur ne correspond à aucun événement connu.
+            pub fn decode(data: &[u8]) -> Result<Option<Self>, EventError> {
+                if data.len() < 8 {
+                    return Err(EventError::TooShort(data.len()));
+                }
+                let (discriminator, mut payload) = data.split_at(8);
+                
+                $(
+                    if discriminator == $event::DISCRIMINATOR {
+                        let event = $event::deserialize(&mut payload)
+                            .map_err(|error| EventError::Deserialize(stringify!($event), error))?;
+                        return Ok(Some(MarketplaceEvent::$event(event)));
+                    }
+                )*
+                
+                Ok(None)
+            }
+
+            /// Sérialiser l'événement au format émis par le programme (inverse de `decode`).
+            pub fn data(&self) -> Vec<u8> {
+                match self {
+                    $(MarketplaceEvent::$event(event) => {
+                        let mut data = $event::DISCRIMINATOR.to_vec();
+                        // L'écriture dans un Vec ne peut pas échouer
+                        event.serialize(&mut data).expect("event serialization");
+                        data
+                    })*
+                }
+            }
+
+            /// Nom de l'événement, tel que déclaré dans le programme.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(MarketplaceEvent::$event(_) => stringify!($event),)*
+                }
+            }
+        }
+    };
+}
+
+marketplace_events!(
+    MarketplaceCreated,
+    NFTListed,
+    NFTSold,
+    NFTListingCanceled,
+    NFTListingUpdated,
+    BundleListed,
+    BundleUpdated,
+    BundleSold,
+    BundleCanceled,
+    SweepCompleted,
+    SwapCreated,
+    SwapAccepted,
+    SwapCanceled,
+    ReferralFeeUpdated,
+    ReferrerRegistered,
+    MembershipRegistered,
+    FeeScheduleUpdated,
+    FeeTierApplied,
+    CuratedModeUpdated,
+    CollectionConfigUpdated,
+    ModeratorAdded,
+    ModeratorRemoved,
+    ListingForceDelisted,
+    RelistApproved,
+    MintBlocked,
+    MintUnblocked,
+    ChangeQueued,
+    ChangeExecuted,
+    ChangeCanceled,
+    AccountMigrated,
+);
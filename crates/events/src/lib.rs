@@ -0,0 +1,184 @@
+//! Décodage typé des événements du programme `solana_nft_marketplace`.
+//!
+//! Les événements sont lus dans les logs (`Program data: ...`, écrits par `emit!`) et dans
+//! les instructions internes (`emit_cpi!`), puis associés au slot, à la signature et à
+//! l'index de l'instruction de la transaction qui les a émis.
+
+mod event;
+mod logs;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use solana_nft_marketplace::ID;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, EncodedTransactionWithStatusMeta, UiInstruction,
+    UiMessage, UiParsedInstruction,
+};
+
+pub use event::MarketplaceEvent;
+pub use logs::{parse_logs, LogEvent, ParsedLogs};
+
+// Erreurs de décodage
+#[derive(Debug, thiserror::Error)]
+pub enum EventError {
+    #[error("données d'événement trop courtes ({0} octets)")]
+    TooShort(usize),
+    #[error("impossible de désérialiser l'événement {0}: {1}")]
+    Deserialize(&'static str, std::io::Error),
+    #[error("la transaction n'a pas de métadonnées")]
+    MissingMeta,
+}
+
+/// Origine d'un événement dans la transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventSource {
+    Log,
+    InnerInstruction,
+}
+
+/// Événement décodé et son contexte.
+#[derive(Clone, Debug)]
+pub struct DecodedEvent {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: Signature,
+    pub instruction_index: usize,
+    pub source: EventSource,
+    pub event: MarketplaceEvent,
+}
+
+/// Événements d'une transaction.
+#[derive(Clone, Debug)]
+pub struct DecodedTransaction {
+    pub slot: u64,
+    pub signature: Signature,
+    pub failed: bool,          // Transaction échouée: aucun événement n'est retenu
+    pub truncated: bool,       // Logs tronqués: des événements émis via `emit!` peuvent manquer
+    pub undecodable: usize,    // Données du programme impossibles à décoder
+    pub events: Vec<DecodedEvent>,
+}
+
+// Décoder un événement émis via `emit_cpi!` (données d'une instruction interne du programme)
+pub fn decode_inner_instruction(data: &[u8]) -> Option<Result<MarketplaceEvent, EventError>> {
+    let payload = data.strip_prefix(&EVENT_IX_TAG_LE[..])?;
+    MarketplaceEvent::decode(payload).transpose()
+}
+
+// Décoder tous les événements d'une transaction récupérée par `getTransaction`
+// Les événements d'une transaction échouée sont ignorés: son état a été annulé
+pub fn decode_transaction(
+    signature: Signature,
+    transaction: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<DecodedTransaction, EventError> {
+    let slot = transaction.slot;
+    let block_time = transaction.block_time;
+    let meta = transaction.transaction.meta.as_ref().ok_or(EventError::MissingMeta)?;
+    
+    let mut decoded = DecodedTransaction {
+        slot,
+        signature,
+        failed: meta.err.is_some(),
+        truncated: false,
+        undecodable: 0,
+        events: Vec::new(),
+    };
+    if decoded.failed {
+        return Ok(decoded);
+    }
+    
+    let new_event = |instruction_index: usize, source: EventSource, event: MarketplaceEvent| DecodedEvent {
+        slot,
+        block_time,
+        signature,
+        instruction_index,
+        source,
+        event,
+    };
+    
+    // Événements écrits dans les logs
+    if let Some(logs) = Option::<&Vec<String>>::from(meta.log_messages.as_ref()) {
+        let parsed = parse_logs(logs);
+        decoded.truncated = parsed.truncated;
+        decoded.undecodable = parsed.undecodable;
+        decoded.events.extend(
+            parsed
+                .events
+                .into_iter()
+                .map(|log_event| new_event(log_event.instruction_index, EventSource::Log, log_event.event)),
+        );
+    }
+    
+    // Événements émis par auto-invocation du programme (instructions internes)
+    let inner_instructions = Option::<&Vec<_>>::from(meta.inner_instructions.as_ref());
+    if let Some(inner_instructions) = inner_instructions {
+        let account_keys = account_keys(&transaction.transaction);
+        
+        for inner in inner_instructions {
+            for instruction in &inner.instructions {
+                let Some((program_id, data)) = instruction_program_and_data(instruction, &account_keys) else {
+                    continue;
+                };
+                if program_id != ID {
+                    continue;
+                }
+                
+                match decode_inner_instruction(&data) {
+                    Some(Ok(event)) => {
+                        decoded.events.push(new_event(inner.index as usize, EventSource::InnerInstruction, event))
+                    }
+                    Some(Err(_)) => decoded.undecodable += 1,
+                    None => {}
+                }
+            }
+        }
+    }
+    
+    Ok(decoded)
+}
+
+// Comptes de la transaction dans l'ordre utilisé par les index des instructions compilées
+// (comptes statiques puis adresses chargées depuis les tables d'adresses)
+fn account_keys(transaction: &EncodedTransactionWithStatusMeta) -> Vec<Pubkey> {
+    let parse = |key: &String| key.parse::<Pubkey>().unwrap_or_default();
+    
+    let mut keys: Vec<Pubkey> = match &transaction.transaction {
+        EncodedTransaction::Json(ui) => match &ui.message {
+            // Les messages "parsed" incluent déjà les adresses chargées
+            UiMessage::Parsed(message) => {
+                return message.account_keys.iter().map(|account| parse(&account.pubkey)).collect();
+            }
+            UiMessage::Raw(message) => message.account_keys.iter().map(parse).collect(),
+        },
+        encoded => encoded
+            .decode()
+            .map(|decoded| decoded.message.static_account_keys().to_vec())
+            .unwrap_or_default(),
+    };
+    
+    let loaded = transaction
+        .meta
+        .as_ref()
+        .and_then(|meta| Option::<&_>::from(meta.loaded_addresses.as_ref()));
+    if let Some(loaded) = loaded {
+        keys.extend(loaded.writable.iter().chain(loaded.readonly.iter()).map(parse));
+    }
+    
+    keys
+}
+
+// Programme appelé et données d'une instruction interne
+fn instruction_program_and_data(instruction: &UiInstruction, account_keys: &[Pubkey]) -> Option<(Pubkey, Vec<u8>)> {
+    let (program_id, data) = match instruction {
+        UiInstruction::Compiled(compiled) => (
+            *account_keys.get(compiled.program_id_index as usize)?,
+            &compiled.data,
+        ),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(partial)) => {
+            (partial.program_id.parse().ok()?, &partial.data)
+        }
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => return None,
+    };
+    
+    Some((program_id, bs58::decode(data).into_vec().ok()?))
+}
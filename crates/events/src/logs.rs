@@ -0,0 +1,95 @@
+//! Lecture des événements dans les messages de log d'une transaction.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_nft_marketplace::ID;
+
+use crate::MarketplaceEvent;
+
+// Préfixe des logs écrits par `emit!` (sol_log_data)
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+// Préfixe des messages écrits par les programmes (msg!, sol_log)
+const PROGRAM_LOG_PREFIX: &str = "Program log:";
+// Message écrit par le runtime quand la limite de taille des logs est atteinte
+const LOG_TRUNCATED: &str = "Log truncated";
+
+/// Événement trouvé dans les logs, avec l'index de l'instruction de premier niveau qui l'a émis.
+#[derive(Clone, Debug)]
+pub struct LogEvent {
+    pub instruction_index: usize,
+    pub event: MarketplaceEvent,
+}
+
+/// Résultat de la lecture des logs d'une transaction.
+#[derive(Clone, Debug, Default)]
+pub struct ParsedLogs {
+    pub events: Vec<LogEvent>,
+    pub truncated: bool,      // Les logs ont été tronqués: des événements peuvent manquer
+    pub undecodable: usize,   // Données du programme impossibles à décoder (ignorées)
+}
+
+// Lire les événements du programme dans les logs d'une transaction
+// Les lignes inattendues sont ignorées; la lecture s'arrête proprement sur des logs tronqués
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> ParsedLogs {
+    let program_id = ID.to_string();
+    let mut parsed = ParsedLogs::default();
+    let mut stack: Vec<String> = Vec::new();
+    let mut instruction_index: Option<usize> = None;
+    
+    for line in logs.iter().map(AsRef::as_ref) {
+        if line.starts_with(LOG_TRUNCATED) {
+            parsed.truncated = true;
+            break;
+        }
+        
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA_PREFIX) {
+            // Seules les données écrites directement par notre programme sont des événements
+            let (Some(current), Some(index)) = (stack.last(), instruction_index) else {
+                continue;
+            };
+            if *current != program_id {
+                continue;
+            }
+            
+            match STANDARD.decode(data).ok().map(|bytes| MarketplaceEvent::decode(&bytes)) {
+                Some(Ok(Some(event))) => parsed.events.push(LogEvent {
+                    instruction_index: index,
+                    event,
+                }),
+                Some(Ok(None)) => {}
+                _ => parsed.undecodable += 1,
+            }
+            continue;
+        }
+        
+        // Le contenu d'un message est libre: un programme ne doit pas pouvoir simuler un appel ou un retour
+        if line.starts_with(PROGRAM_LOG_PREFIX) {
+            continue;
+        }
+        
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        let mut words = rest.split_whitespace();
+        let (Some(program), Some(action)) = (words.next(), words.next()) else {
+            continue;
+        };
+        
+        match action {
+            "invoke" => {
+                // "Program <id> invoke [1]" marque le début d'une instruction de premier niveau
+                if words.next() == Some("[1]") {
+                    stack.clear();
+                    instruction_index = Some(instruction_index.map_or(0, |index| index + 1));
+                }
+                stack.push(program.to_string());
+            }
+            "success" | "failed:" => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    
+    parsed
+}
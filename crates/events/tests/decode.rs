@@ -0,0 +1,344 @@
+//! Lecture des événements dans les logs (piles d'appels imbriquées, logs tronqués, instructions
+//! échouées) et dans les instructions internes `emit_cpi!` des transactions encodées par le RPC.
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::Discriminator;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_nft_marketplace::{NFTListed, NFTListingCanceled, NFTSold, ID};
+use solana_nft_marketplace_events::{
+    decode_inner_instruction, decode_transaction, parse_logs, EventError, EventSource, MarketplaceEvent,
+};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{CompiledInstruction, InstructionError};
+use solana_sdk::message::v0::{self, LoadedAddresses, MessageAddressTableLookup};
+use solana_sdk::message::{Message, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+use solana_transaction_status::{
+    ConfirmedTransactionWithStatusMeta, EncodedConfirmedTransactionWithStatusMeta, InnerInstruction,
+    InnerInstructions, TransactionStatusMeta, TransactionWithStatusMeta, UiTransactionEncoding,
+    VersionedTransactionWithStatusMeta,
+};
+
+const SLOT: u64 = 4_242;
+const BLOCK_TIME: i64 = 1_700_000_000;
+
+fn listed(price: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTListed(NFTListed {
+        listing: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        price,
+        quantity: 1,
+    })
+}
+
+fn sold(price: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTSold(NFTSold {
+        listing: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        price,
+        quantity: 1,
+        marketplace_fee: price / 40,
+        referrer: None,
+        referral_fee: 0,
+    })
+}
+
+fn canceled() -> MarketplaceEvent {
+    MarketplaceEvent::NFTListingCanceled(NFTListingCanceled {
+        listing: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+    })
+}
+
+// Ligne écrite par `emit!`
+fn data_log(event: &MarketplaceEvent) -> String {
+    format!("Program data: {}", STANDARD.encode(event.data()))
+}
+
+// Données de l'instruction interne écrite par `emit_cpi!`
+fn cpi_data(event: &MarketplaceEvent) -> Vec<u8> {
+    [&EVENT_IX_TAG_LE[..], &event.data()].concat()
+}
+
+fn invoke(program: &Pubkey, depth: usize) -> String {
+    format!("Program {} invoke [{}]", program, depth)
+}
+
+fn success(program: &Pubkey) -> String {
+    format!("Program {} success", program)
+}
+
+fn datas(events: &[MarketplaceEvent]) -> Vec<Vec<u8>> {
+    events.iter().map(MarketplaceEvent::data).collect()
+}
+
+fn inner(index: u8, instructions: Vec<(u8, Vec<u8>)>) -> InnerInstructions {
+    InnerInstructions {
+        index,
+        instructions: instructions
+            .into_iter()
+            .map(|(program_id_index, data)| InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(program_id_index, data, vec![]),
+                stack_height: Some(2),
+            })
+            .collect(),
+    }
+}
+
+// Transaction confirmée telle que retournée par `getTransaction` dans l'encodage demandé
+fn encoded(
+    transaction: VersionedTransaction,
+    meta: Option<TransactionStatusMeta>,
+    encoding: UiTransactionEncoding,
+) -> EncodedConfirmedTransactionWithStatusMeta {
+    let tx_with_meta = match meta {
+        Some(meta) => TransactionWithStatusMeta::Complete(VersionedTransactionWithStatusMeta { transaction, meta }),
+        None => TransactionWithStatusMeta::MissingMetadata(transaction.into_legacy_transaction().unwrap()),
+    };
+    ConfirmedTransactionWithStatusMeta { slot: SLOT, tx_with_meta, block_time: Some(BLOCK_TIME) }
+        .encode(encoding, Some(0))
+        .unwrap()
+}
+
+// Transaction à deux instructions: le programme (index de compte 1) puis un transfert système (index 2)
+fn legacy_transaction() -> VersionedTransaction {
+    let payer = Pubkey::new_unique();
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        2,
+        vec![payer, ID, system_program::ID],
+        Hash::default(),
+        vec![
+            CompiledInstruction::new_from_raw_parts(1, vec![1], vec![0]),
+            CompiledInstruction::new_from_raw_parts(2, vec![2], vec![0]),
+        ],
+    );
+    VersionedTransaction::from(Transaction::new_unsigned(message))
+}
+
+#[test]
+fn nested_invocations_keep_only_data_written_by_the_program() {
+    let token = Pubkey::new_unique();
+    let router = Pubkey::new_unique();
+    let (first, second, third) = (listed(1), sold(2), canceled());
+    let logs = vec![
+        // Instruction 0: le programme appelle un autre programme qui écrit ses propres données
+        invoke(&ID, 1),
+        "Program log: Instruction: BuyNft".to_string(),
+        invoke(&token, 2),
+        data_log(&listed(99)),
+        success(&token),
+        data_log(&first),
+        format!("Program {} consumed 12000 of 200000 compute units", ID),
+        format!("Program return: {} AQ==", ID),
+        success(&ID),
+        // Instruction 1: un autre programme appelle le programme, qui appelle à son tour un tiers
+        invoke(&router, 1),
+        data_log(&sold(98)),
+        invoke(&ID, 2),
+        data_log(&second),
+        invoke(&token, 3),
+        data_log(&listed(97)),
+        success(&token),
+        success(&ID),
+        success(&router),
+        // Instruction 2: appel direct
+        invoke(&ID, 1),
+        data_log(&third),
+        success(&ID),
+    ];
+    
+    let parsed = parse_logs(&logs);
+    assert!(!parsed.truncated);
+    assert_eq!(parsed.undecodable, 0);
+    let found: Vec<_> = parsed.events.iter().map(|event| (event.instruction_index, event.event.data())).collect();
+    assert_eq!(found, vec![(0, first.data()), (1, second.data()), (2, third.data())]);
+}
+
+#[test]
+fn truncated_logs_stop_the_parsing() {
+    let kept = listed(1);
+    let logs = vec![
+        invoke(&ID, 1),
+        data_log(&kept),
+        "Log truncated".to_string(),
+        data_log(&sold(2)),
+        success(&ID),
+    ];
+    
+    let parsed = parse_logs(&logs);
+    assert!(parsed.truncated);
+    assert_eq!(datas(&parsed.events.into_iter().map(|event| event.event).collect::<Vec<_>>()), vec![kept.data()]);
+}
+
+#[test]
+fn failed_instructions_pop_the_stack_and_undecodable_data_is_counted() {
+    let other = Pubkey::new_unique();
+    let emitted = sold(5);
+    let logs = vec![
+        // Données écrites hors de toute instruction ou par un autre programme: ignorées
+        data_log(&listed(1)),
+        invoke(&ID, 1),
+        invoke(&other, 2),
+        format!("Program {} failed: custom program error: 0x1", other),
+        // De retour dans le programme après l'échec de l'appel interne
+        data_log(&emitted),
+        "Program data: pas du base64!".to_string(),
+        format!("Program data: {}", STANDARD.encode([NFTSold::DISCRIMINATOR.as_slice(), &[1, 2]].concat())),
+        format!("Program data: {}", STANDARD.encode([0u8; 16])),
+        format!("Program {} failed: custom program error: 0x1770", ID),
+    ];
+    
+    // Les logs d'une instruction échouée restent lisibles; decode_transaction les écarte
+    let parsed = parse_logs(&logs);
+    assert_eq!(parsed.events.len(), 1);
+    assert_eq!((parsed.events[0].instruction_index, parsed.events[0].event.data()), (0, emitted.data()));
+    assert_eq!(parsed.undecodable, 2);
+}
+
+#[test]
+fn program_messages_cannot_spoof_invocations() {
+    let other = Pubkey::new_unique();
+    let (emitted, second) = (listed(3), canceled());
+    let logs = vec![
+        invoke(&ID, 1),
+        // Messages d'un programme imitant le début d'une instruction puis la fin de l'appel interne
+        "Program log: invoke [1]".to_string(),
+        invoke(&other, 2),
+        "Program log: success".to_string(),
+        format!("Program log: Program {} success", ID),
+        success(&other),
+        data_log(&emitted),
+        success(&ID),
+        invoke(&ID, 1),
+        data_log(&second),
+        success(&ID),
+    ];
+    
+    let parsed = parse_logs(&logs);
+    let found: Vec<_> = parsed.events.iter().map(|event| (event.instruction_index, event.event.data())).collect();
+    assert_eq!(found, vec![(0, emitted.data()), (1, second.data())]);
+}
+
+#[test]
+fn emit_cpi_events_round_trip_through_every_encoding() {
+    let (from_cpi, second_cpi, from_log) = (sold(10), canceled(), listed(20));
+    let mut corrupted = cpi_data(&sold(1));
+    corrupted.truncate(EVENT_IX_TAG_LE.len() + 8 + 4);
+    
+    let meta = TransactionStatusMeta {
+        log_messages: Some(vec![invoke(&ID, 1), data_log(&from_log), success(&ID)]),
+        inner_instructions: Some(vec![
+            inner(0, vec![(1, cpi_data(&from_cpi)), (1, vec![7, 7, 7]), (1, corrupted)]),
+            // Des données d'événement passées à un autre programme ne sont pas des événements
+            inner(1, vec![(2, cpi_data(&sold(3))), (1, cpi_data(&second_cpi))]),
+        ]),
+        ..TransactionStatusMeta::default()
+    };
+    
+    for encoding in [UiTransactionEncoding::Json, UiTransactionEncoding::Base64, UiTransactionEncoding::JsonParsed] {
+        let signature = Signature::new_unique();
+        let transaction = encoded(legacy_transaction(), Some(meta.clone()), encoding);
+        let decoded = decode_transaction(signature, &transaction).unwrap();
+        
+        assert_eq!((decoded.slot, decoded.signature, decoded.failed), (SLOT, signature, false));
+        assert_eq!(decoded.undecodable, 1, "{:?}", encoding);
+        let found: Vec<_> = decoded
+            .events
+            .iter()
+            .map(|event| (event.instruction_index, event.source, event.event.data()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (0, EventSource::Log, from_log.data()),
+                (0, EventSource::InnerInstruction, from_cpi.data()),
+                (1, EventSource::InnerInstruction, second_cpi.data()),
+            ],
+            "{:?}",
+            encoding
+        );
+        assert!(decoded.events.iter().all(|event| event.slot == SLOT && event.block_time == Some(BLOCK_TIME)));
+        assert!(decoded.events.iter().all(|event| event.signature == signature));
+    }
+}
+
+#[test]
+fn emit_cpi_events_resolve_programs_loaded_from_lookup_tables() {
+    let payer = Pubkey::new_unique();
+    let router = Pubkey::new_unique();
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 1,
+        },
+        account_keys: vec![payer, router],
+        recent_blockhash: Hash::default(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![0, 2])],
+        address_table_lookups: vec![MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![],
+            readonly_indexes: vec![0],
+        }],
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::V0(message),
+    };
+    
+    // Le programme, chargé depuis la table d'adresses, occupe l'index 2
+    let event = sold(30);
+    let meta = TransactionStatusMeta {
+        inner_instructions: Some(vec![inner(0, vec![(2, cpi_data(&event))])]),
+        loaded_addresses: LoadedAddresses { writable: vec![], readonly: vec![ID] },
+        ..TransactionStatusMeta::default()
+    };
+    
+    for encoding in [UiTransactionEncoding::Json, UiTransactionEncoding::Base64, UiTransactionEncoding::JsonParsed] {
+        let transaction = encoded(transaction.clone(), Some(meta.clone()), encoding);
+        let decoded = decode_transaction(Signature::default(), &transaction).unwrap();
+        assert_eq!(datas(&decoded.events.into_iter().map(|event| event.event).collect::<Vec<_>>()), vec![event.data()]);
+    }
+}
+
+#[test]
+fn failed_transactions_and_missing_metadata_yield_no_events() {
+    let meta = TransactionStatusMeta {
+        status: Err(TransactionError::InstructionError(0, InstructionError::Custom(6000))),
+        log_messages: Some(vec![invoke(&ID, 1), data_log(&listed(1)), format!("Program {} failed: custom", ID)]),
+        inner_instructions: Some(vec![inner(0, vec![(1, cpi_data(&sold(1)))])]),
+        ..TransactionStatusMeta::default()
+    };
+    let transaction = encoded(legacy_transaction(), Some(meta), UiTransactionEncoding::Json);
+    let decoded = decode_transaction(Signature::default(), &transaction).unwrap();
+    assert!(decoded.failed);
+    assert!(decoded.events.is_empty());
+    
+    let transaction = encoded(legacy_transaction(), None, UiTransactionEncoding::Json);
+    assert!(matches!(decode_transaction(Signature::default(), &transaction), Err(EventError::MissingMeta)));
+}
+
+#[test]
+fn inner_instruction_decoding_requires_the_event_tag() {
+    let event = canceled();
+    let decoded = decode_inner_instruction(&cpi_data(&event)).unwrap().unwrap();
+    assert_eq!(decoded.data(), event.data());
+    assert_eq!(decoded.name(), "NFTListingCanceled");
+    
+    // Instruction ordinaire du programme ou événement inconnu: pas d'événement
+    assert!(decode_inner_instruction(&event.data()).is_none());
+    assert!(decode_inner_instruction(&[&EVENT_IX_TAG_LE[..], &[0; 8]].concat()).is_none());
+    assert!(decode_inner_instruction(&EVENT_IX_TAG_LE[..4]).is_none());
+    assert!(matches!(
+        decode_inner_instruction(&[&EVENT_IX_TAG_LE[..], &[1, 2]].concat()),
+        Some(Err(EventError::TooShort(2)))
+    ));
+}
@@ -0,0 +1,38 @@
+//! Configuration du plugin (fichier JSON passé au validateur via `--geyser-plugin-config`).
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Destination des messages.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    File { path: PathBuf },       // Fichier JSON lines (ajout en fin de fichier)
+    UnixSocket { path: PathBuf }, // Socket Unix: chaque client connecté reçoit les messages en JSON lines
+}
+
+/// Configuration du plugin. Le champ `libpath` attendu par le validateur est ignoré.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PluginConfig {
+    pub sink: Option<SinkConfig>,   // Optionnel si une destination est fournie par le code (MarketplacePlugin::with_sink)
+    pub accounts: bool,             // Diffuser les écritures de comptes du programme
+    pub startup_accounts: bool,     // Diffuser aussi les comptes chargés depuis le snapshot au démarrage
+    pub transactions: bool,         // Diffuser les transactions qui invoquent le programme
+    pub failed_transactions: bool,  // Diffuser aussi les transactions échouées (sans événements)
+    pub slots: bool,                // Diffuser les changements de statut des slots
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        PluginConfig {
+            sink: None,
+            accounts: true,
+            startup_accounts: true,
+            transactions: true,
+            failed_transactions: false,
+            slots: true,
+        }
+    }
+}
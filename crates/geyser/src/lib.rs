@@ -0,0 +1,31 @@
+//! Plugin Geyser pour le programme `solana_nft_marketplace`.
+//!
+//! Chargé par un validateur (`solana-test-validator --geyser-plugin-config <fichier>`), il filtre
+//! les écritures des comptes du programme et les transactions qui l'invoquent, décode les comptes
+//! `Marketplace`/`NFTListing` et les événements, puis les envoie en JSON lines vers un fichier,
+//! un socket Unix ou une destination fournie par le code (trait `Sink`), pour alimenter
+//! l'indexeur en temps réel.
+
+pub mod config;
+pub mod message;
+pub mod plugin;
+pub mod sink;
+
+use solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin;
+
+pub use config::{PluginConfig, SinkConfig};
+pub use message::StreamMessage;
+pub use plugin::MarketplacePlugin;
+pub use sink::{FileSink, Sink, UnixSocketSink};
+
+/// Point d'entrée appelé par le validateur au chargement de la bibliothèque.
+///
+/// # Safety
+///
+/// Le pointeur retourné doit être repris par le validateur (`Box::from_raw`), qui en devient propriétaire.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::new(MarketplacePlugin::default());
+    Box::into_raw(plugin)
+}
@@ -0,0 +1,199 @@
+//! Messages diffusés par le plugin (un objet JSON par ligne).
+
+use std::str::FromStr;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_nft_marketplace::{Marketplace, NFTListing};
+use solana_nft_marketplace_events::{
+    DecodedEvent, DecodedTransaction, EventError, EventSource, MarketplaceEvent,
+};
+use solana_sdk::signature::Signature;
+
+/// Message diffusé.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Account(AccountUpdate),
+    Transaction(TransactionUpdate),
+    Slot(SlotUpdate),
+    EndOfStartup,
+}
+
+/// Écriture d'un compte du programme (ou fermeture d'un compte par le programme).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub pubkey: String,
+    pub lamports: u64,
+    pub write_version: u64,
+    pub is_startup: bool,
+    pub closed: bool,                    // Compte fermé (rendu au System Program)
+    pub txn_signature: Option<String>,   // Transaction à l'origine de l'écriture, si connue
+    pub data: String,                    // Données brutes en base64
+    pub decoded: Option<DecodedAccount>, // Contenu décodé pour les comptes connus
+}
+
+/// Contenu décodé d'un compte.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecodedAccount {
+    Marketplace(MarketplaceAccount),
+    Listing(ListingAccount),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarketplaceAccount {
+    pub version: u8,
+    pub authority: String,
+    pub treasury: String,
+    pub fee: u16,
+    pub referral_fee_bps: u16,
+    pub curated: bool,
+    pub moderators: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingAccount {
+    pub version: u8,
+    pub seller: String,
+    pub nft_mint: String,
+    pub price: u64,
+    pub quantity: u64,
+    pub active: bool,
+    pub collection: Option<String>,
+}
+
+/// Transaction qui invoque le programme, avec ses événements.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionUpdate {
+    pub slot: u64,
+    pub signature: String,
+    pub index: Option<usize>, // Position de la transaction dans le bloc
+    pub failed: bool,
+    pub truncated: bool,      // Logs tronqués: des événements peuvent manquer
+    pub events: Vec<StreamEvent>,
+}
+
+/// Événement d'une transaction.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamEvent {
+    pub instruction_index: usize,
+    pub source: StreamEventSource,
+    pub name: String,
+    pub data: String, // Événement sérialisé (discriminateur compris) en base64
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamEventSource {
+    Log,
+    InnerInstruction,
+}
+
+/// Changement de statut d'un slot.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlotUpdate {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: String, // processed, confirmed ou rooted
+}
+
+// Erreurs de conversion d'un message reçu
+#[derive(Debug, thiserror::Error)]
+pub enum MessageError {
+    #[error("signature invalide: {0}")]
+    InvalidSignature(String),
+    #[error("données base64 invalides")]
+    InvalidData(#[from] base64::DecodeError),
+    #[error(transparent)]
+    Event(#[from] EventError),
+    #[error("événement inconnu: {0}")]
+    UnknownEvent(String),
+}
+
+impl DecodedAccount {
+    // Décoder les comptes connus (format actuel uniquement, les comptes à migrer sont ignorés)
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        use anchor_lang::AccountDeserialize;
+        
+        if let Ok(marketplace) = Marketplace::try_deserialize(&mut &data[..]) {
+            return Some(DecodedAccount::Marketplace(MarketplaceAccount {
+                version: marketplace.version,
+                authority: marketplace.authority.to_string(),
+                treasury: marketplace.treasury.to_string(),
+                fee: marketplace.fee,
+                referral_fee_bps: marketplace.referral_fee_bps,
+                curated: marketplace.curated,
+                moderators: marketplace.moderators.iter().map(ToString::to_string).collect(),
+            }));
+        }
+        
+        NFTListing::try_deserialize(&mut &data[..]).ok().map(|listing| {
+            DecodedAccount::Listing(ListingAccount {
+                version: listing.version,
+                seller: listing.seller.to_string(),
+                nft_mint: listing.nft_mint.to_string(),
+                price: listing.price,
+                quantity: listing.quantity,
+                active: listing.active,
+                collection: listing.collection.map(|collection| collection.to_string()),
+            })
+        })
+    }
+}
+
+impl StreamEvent {
+    pub fn new(instruction_index: usize, source: EventSource, event: &MarketplaceEvent) -> Self {
+        StreamEvent {
+            instruction_index,
+            source: match source {
+                EventSource::Log => StreamEventSource::Log,
+                EventSource::InnerInstruction => StreamEventSource::InnerInstruction,
+            },
+            name: event.name().to_string(),
+            data: STANDARD.encode(event.data()),
+        }
+    }
+}
+
+impl TransactionUpdate {
+    // Convertir en transaction décodée, au format appliqué par l'indexeur
+    // (l'heure du bloc n'est pas connue du plugin)
+    pub fn to_decoded(&self) -> Result<DecodedTransaction, MessageError> {
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|_| MessageError::InvalidSignature(self.signature.clone()))?;
+        
+        let events = self
+            .events
+            .iter()
+            .map(|event| {
+                let data = STANDARD.decode(&event.data)?;
+                let decoded = MarketplaceEvent::decode(&data)?
+                    .ok_or_else(|| MessageError::UnknownEvent(event.name.clone()))?;
+                
+                Ok(DecodedEvent {
+                    slot: self.slot,
+                    block_time: None,
+                    signature,
+                    instruction_index: event.instruction_index,
+                    source: match event.source {
+                        StreamEventSource::Log => EventSource::Log,
+                        StreamEventSource::InnerInstruction => EventSource::InnerInstruction,
+                    },
+                    event: decoded,
+                })
+            })
+            .collect::<Result<Vec<_>, MessageError>>()?;
+        
+        Ok(DecodedTransaction {
+            slot: self.slot,
+            signature,
+            failed: self.failed,
+            truncated: self.truncated,
+            undecodable: 0,
+            events,
+        })
+    }
+}
@@ -0,0 +1,230 @@
+//! Implémentation de l'interface Geyser.
+
+use std::fmt;
+use std::fs;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, ReplicaTransactionInfoVersions, Result, SlotStatus,
+};
+use solana_nft_marketplace::ID;
+use solana_nft_marketplace_events::{decode_inner_instruction, parse_logs, EventSource};
+use solana_sdk::clock::Slot;
+use solana_sdk::transaction::SanitizedTransaction;
+
+use crate::config::PluginConfig;
+use crate::message::{AccountUpdate, DecodedAccount, SlotUpdate, StreamEvent, StreamMessage, TransactionUpdate};
+use crate::sink::{open_sink, Sink};
+
+/// Plugin qui diffuse les comptes, transactions et événements du programme.
+#[derive(Default)]
+pub struct MarketplacePlugin {
+    config: PluginConfig,
+    sink: Option<Box<dyn Sink>>,
+}
+
+impl fmt::Debug for MarketplacePlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarketplacePlugin")
+            .field("config", &self.config)
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl MarketplacePlugin {
+    // Plugin qui envoie ses messages vers une destination fournie par l'appelant
+    pub fn with_sink(config: PluginConfig, sink: Box<dyn Sink>) -> Self {
+        MarketplacePlugin {
+            config,
+            sink: Some(sink),
+        }
+    }
+    
+    fn send(&self, message: StreamMessage) -> Result<()> {
+        let Some(sink) = &self.sink else {
+            return Ok(());
+        };
+        sink.send(&message).map_err(|error| GeyserPluginError::Custom(Box::new(error)))
+    }
+}
+
+// Indiquer si une transaction référence le programme
+fn invokes_program(transaction: &SanitizedTransaction) -> bool {
+    transaction.message().account_keys().iter().any(|key| *key == ID)
+}
+
+impl GeyserPlugin for MarketplacePlugin {
+    fn name(&self) -> &'static str {
+        "solana_nft_marketplace_geyser"
+    }
+    
+    fn setup_logger(&self, logger: &'static dyn log::Log, level: log::LevelFilter) -> Result<()> {
+        log::set_max_level(level);
+        log::set_logger(logger).map_err(|error| GeyserPluginError::Custom(Box::new(error)))
+    }
+    
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let contents = fs::read_to_string(config_file)?;
+        self.config = serde_json::from_str(&contents)
+            .map_err(|error| GeyserPluginError::ConfigFileReadError { msg: error.to_string() })?;
+        
+        if self.sink.is_none() {
+            let sink = self.config.sink.as_ref().ok_or_else(|| GeyserPluginError::ConfigFileReadError {
+                msg: "missing \"sink\" entry".to_string(),
+            })?;
+            self.sink = Some(open_sink(sink)?);
+        }
+        
+        log::info!("Marketplace plugin loaded: {:?}", self.config);
+        Ok(())
+    }
+    
+    fn on_unload(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            if let Err(error) = sink.flush() {
+                log::warn!("Marketplace plugin sink flush failed: {error}");
+            }
+        }
+    }
+    
+    fn update_account(&self, account: ReplicaAccountInfoVersions, slot: Slot, is_startup: bool) -> Result<()> {
+        if is_startup && !self.config.startup_accounts {
+            return Ok(());
+        }
+        
+        let (pubkey, lamports, owner, data, write_version, txn_signature, txn) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(info) => {
+                (info.pubkey, info.lamports, info.owner, info.data, info.write_version, None, None)
+            }
+            ReplicaAccountInfoVersions::V0_0_2(info) => (
+                info.pubkey,
+                info.lamports,
+                info.owner,
+                info.data,
+                info.write_version,
+                info.txn_signature.copied(),
+                None,
+            ),
+            ReplicaAccountInfoVersions::V0_0_3(info) => (
+                info.pubkey,
+                info.lamports,
+                info.owner,
+                info.data,
+                info.write_version,
+                info.txn.map(|txn| *txn.signature()),
+                info.txn,
+            ),
+        };
+        
+        // Les comptes fermés par le programme ne lui appartiennent plus: ils sont reconnus
+        // à leur solde nul dans une transaction qui invoque le programme
+        let owned = owner == ID.as_ref();
+        let closed = !owned && lamports == 0 && txn.is_some_and(invokes_program);
+        if !owned && !closed {
+            return Ok(());
+        }
+        
+        self.send(StreamMessage::Account(AccountUpdate {
+            slot,
+            pubkey: bs58_pubkey(pubkey),
+            lamports,
+            write_version,
+            is_startup,
+            closed,
+            txn_signature: txn_signature.map(|signature| signature.to_string()),
+            data: STANDARD.encode(data),
+            decoded: if owned { DecodedAccount::decode(data) } else { None },
+        }))
+    }
+    
+    fn notify_end_of_startup(&self) -> Result<()> {
+        self.send(StreamMessage::EndOfStartup)
+    }
+    
+    fn update_slot_status(&self, slot: Slot, parent: Option<u64>, status: SlotStatus) -> Result<()> {
+        if !self.config.slots {
+            return Ok(());
+        }
+        
+        self.send(StreamMessage::Slot(SlotUpdate {
+            slot,
+            parent,
+            status: status.as_str().to_string(),
+        }))
+    }
+    
+    fn notify_transaction(&self, transaction: ReplicaTransactionInfoVersions, slot: Slot) -> Result<()> {
+        let (signature, is_vote, sanitized, meta, index) = match transaction {
+            ReplicaTransactionInfoVersions::V0_0_1(info) => {
+                (info.signature, info.is_vote, info.transaction, info.transaction_status_meta, None)
+            }
+            ReplicaTransactionInfoVersions::V0_0_2(info) => (
+                info.signature,
+                info.is_vote,
+                info.transaction,
+                info.transaction_status_meta,
+                Some(info.index),
+            ),
+        };
+        if is_vote || !invokes_program(sanitized) {
+            return Ok(());
+        }
+        
+        let failed = meta.status.is_err();
+        if failed && !self.config.failed_transactions {
+            return Ok(());
+        }
+        
+        // Les événements d'une transaction échouée ne sont pas retenus: son état a été annulé
+        let mut update = TransactionUpdate {
+            slot,
+            signature: signature.to_string(),
+            index,
+            failed,
+            truncated: false,
+            events: Vec::new(),
+        };
+        if !failed {
+            if let Some(logs) = &meta.log_messages {
+                let parsed = parse_logs(logs);
+                update.truncated = parsed.truncated;
+                for log_event in &parsed.events {
+                    let event = StreamEvent::new(log_event.instruction_index, EventSource::Log, &log_event.event);
+                    update.events.push(event);
+                }
+            }
+            
+            let account_keys = sanitized.message().account_keys();
+            for inner in meta.inner_instructions.iter().flatten() {
+                for instruction in &inner.instructions {
+                    let program_id = account_keys.get(instruction.instruction.program_id_index as usize);
+                    if program_id != Some(&ID) {
+                        continue;
+                    }
+                    if let Some(Ok(event)) = decode_inner_instruction(&instruction.instruction.data) {
+                        update
+                            .events
+                            .push(StreamEvent::new(inner.index as usize, EventSource::InnerInstruction, &event));
+                    }
+                }
+            }
+        }
+        
+        self.send(StreamMessage::Transaction(update))
+    }
+    
+    fn account_data_notifications_enabled(&self) -> bool {
+        self.config.accounts
+    }
+    
+    fn transaction_notifications_enabled(&self) -> bool {
+        self.config.transactions
+    }
+}
+
+// Adresse en base58 à partir de ses octets
+fn bs58_pubkey(bytes: &[u8]) -> String {
+    solana_sdk::pubkey::Pubkey::try_from(bytes).map_or_else(|_| String::new(), |pubkey| pubkey.to_string())
+}
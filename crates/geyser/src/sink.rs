@@ -0,0 +1,140 @@
+//! Destinations des messages: fichier, socket Unix ou implémentation fournie par l'appelant.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::SinkConfig;
+use crate::message::StreamMessage;
+
+// Délai d'écriture maximal vers un client: un client trop lent est déconnecté plutôt que de bloquer le validateur
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+// Intervalle de vérification des nouvelles connexions
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Destination des messages du plugin.
+pub trait Sink: Send + Sync {
+    fn send(&self, message: &StreamMessage) -> io::Result<()>;
+    
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Ouvrir la destination décrite par la configuration
+pub fn open_sink(config: &SinkConfig) -> io::Result<Box<dyn Sink>> {
+    Ok(match config {
+        SinkConfig::File { path } => Box::new(FileSink::open(path)?),
+        SinkConfig::UnixSocket { path } => Box::new(UnixSocketSink::bind(path)?),
+    })
+}
+
+// Encoder un message en une ligne JSON
+fn encode_line(message: &StreamMessage) -> io::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Fichier JSON lines, un message par ligne.
+pub struct FileSink {
+    writer: Mutex<LineWriter<File>>,
+}
+
+impl FileSink {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileSink {
+            writer: Mutex::new(LineWriter::new(file)),
+        })
+    }
+}
+
+impl Sink for FileSink {
+    fn send(&self, message: &StreamMessage) -> io::Result<()> {
+        let line = encode_line(message)?;
+        let mut writer = self.writer.lock().map_err(|_| io::Error::other("file sink poisoned"))?;
+        writer.write_all(&line)
+    }
+    
+    fn flush(&self) -> io::Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| io::Error::other("file sink poisoned"))?;
+        writer.flush()
+    }
+}
+
+/// Socket Unix en écoute: chaque client connecté reçoit les messages suivants en JSON lines.
+pub struct UnixSocketSink {
+    path: PathBuf,
+    clients: Arc<Mutex<Vec<UnixStream>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl UnixSocketSink {
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        // Supprimer le socket laissé par une exécution précédente
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        
+        // Accepter les connexions jusqu'à la fermeture de la destination (déchargement du plugin)
+        let (accepted, stop) = (Arc::clone(&clients), Arc::clone(&stopped));
+        thread::Builder::new()
+            .name("marketplace-geyser-accept".to_string())
+            .spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let stream = match listener.accept() {
+                        Ok((stream, _)) => stream,
+                        Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(ACCEPT_INTERVAL);
+                            continue;
+                        }
+                        Err(_) => continue,
+                    };
+                    let configured = stream
+                        .set_nonblocking(false)
+                        .and_then(|_| stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)));
+                    if configured.is_err() {
+                        continue;
+                    }
+                    match accepted.lock() {
+                        Ok(mut clients) => clients.push(stream),
+                        Err(_) => break,
+                    }
+                }
+            })?;
+        
+        Ok(UnixSocketSink {
+            path: path.to_path_buf(),
+            clients,
+            stopped,
+        })
+    }
+}
+
+impl Sink for UnixSocketSink {
+    fn send(&self, message: &StreamMessage) -> io::Result<()> {
+        let line = encode_line(message)?;
+        let mut clients = self.clients.lock().map_err(|_| io::Error::other("socket sink poisoned"))?;
+        
+        // Les clients déconnectés ou trop lents sont retirés
+        clients.retain_mut(|client| client.write_all(&line).is_ok());
+        Ok(())
+    }
+}
+
+impl Drop for UnixSocketSink {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = fs::remove_file(&self.path);
+    }
+}
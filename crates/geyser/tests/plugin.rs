@@ -0,0 +1,313 @@
+//! Filtrage des comptes et des transactions par le plugin et extraction des événements,
+//! avec une destination en mémoire.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::AccountSerialize;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, ReplicaAccountInfoV3, ReplicaAccountInfoVersions, ReplicaTransactionInfoV2,
+    ReplicaTransactionInfoVersions,
+};
+use solana_nft_marketplace::{NFTListed, NFTListing, NFTSold, ID};
+use solana_nft_marketplace_events::MarketplaceEvent;
+use solana_nft_marketplace_geyser::message::{DecodedAccount, StreamEventSource};
+use solana_nft_marketplace_geyser::{MarketplacePlugin, PluginConfig, Sink, StreamMessage};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{CompiledInstruction, InstructionError};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{SanitizedTransaction, Transaction, TransactionError};
+use solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
+
+const SLOT: u64 = 77;
+
+// Destination qui garde les messages reçus
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<StreamMessage>>>);
+
+impl Sink for Recorder {
+    fn send(&self, message: &StreamMessage) -> io::Result<()> {
+        self.0.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<StreamMessage> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+fn recorded(config: PluginConfig) -> (MarketplacePlugin, Recorder) {
+    let recorder = Recorder::default();
+    (MarketplacePlugin::with_sink(config, Box::new(recorder.clone())), recorder)
+}
+
+fn listed(price: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTListed(NFTListed {
+        listing: Pubkey::new_unique(),
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        price,
+        quantity: 1,
+    })
+}
+
+fn sold(price: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTSold(NFTSold {
+        listing: Pubkey::new_unique(),
+        buyer: Pubkey::new_unique(),
+        price,
+        quantity: 1,
+        marketplace_fee: price / 40,
+        referrer: None,
+        referral_fee: 0,
+    })
+}
+
+// Transaction signée par un payeur, dont l'unique instruction appelle `program`
+// (comptes: payeur, `program`, System Program)
+fn transaction(program: Pubkey) -> SanitizedTransaction {
+    let message = Message::new_with_compiled_instructions(
+        1,
+        0,
+        2,
+        vec![Pubkey::new_unique(), program, system_program::ID],
+        Hash::default(),
+        vec![CompiledInstruction::new_from_raw_parts(1, vec![1], vec![0])],
+    );
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction.signatures = vec![Signature::new_unique()];
+    SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap()
+}
+
+fn listing_data(price: u64) -> Vec<u8> {
+    let listing = NFTListing {
+        version: 1,
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        price,
+        active: true,
+        bump: 255,
+        quantity: 2,
+        payout_splits: Vec::new(),
+        collection: None,
+        stats_tracked: false,
+        reserved: [0; 63],
+    };
+    let mut data = Vec::new();
+    listing.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn update_account(
+    plugin: &MarketplacePlugin,
+    owner: &Pubkey,
+    lamports: u64,
+    data: &[u8],
+    txn: Option<&SanitizedTransaction>,
+    is_startup: bool,
+) {
+    let pubkey = Pubkey::new_unique();
+    let info = ReplicaAccountInfoV3 {
+        pubkey: pubkey.as_ref(),
+        lamports,
+        owner: owner.as_ref(),
+        executable: false,
+        rent_epoch: 0,
+        data,
+        write_version: 9,
+        txn,
+    };
+    plugin.update_account(ReplicaAccountInfoVersions::V0_0_3(&info), SLOT, is_startup).unwrap();
+}
+
+fn notify_transaction(plugin: &MarketplacePlugin, transaction: &SanitizedTransaction, meta: &TransactionStatusMeta) {
+    let info = ReplicaTransactionInfoV2 {
+        signature: transaction.signature(),
+        is_vote: false,
+        transaction,
+        transaction_status_meta: meta,
+        index: 3,
+    };
+    plugin.notify_transaction(ReplicaTransactionInfoVersions::V0_0_2(&info), SLOT).unwrap();
+}
+
+fn data_log(event: &MarketplaceEvent) -> String {
+    format!("Program data: {}", STANDARD.encode(event.data()))
+}
+
+fn cpi(program_id_index: u8, event: &MarketplaceEvent) -> InnerInstruction {
+    InnerInstruction {
+        instruction: CompiledInstruction::new_from_raw_parts(
+            program_id_index,
+            [&EVENT_IX_TAG_LE[..], &event.data()].concat(),
+            vec![],
+        ),
+        stack_height: Some(2),
+    }
+}
+
+#[test]
+fn accounts_owned_or_closed_by_the_program_are_streamed() {
+    let (plugin, recorder) = recorded(PluginConfig::default());
+    let invoking = transaction(ID);
+    let foreign = transaction(Pubkey::new_unique());
+    
+    // Compte du programme: données brutes et contenu décodé
+    let data = listing_data(42);
+    update_account(&plugin, &ID, 1_000, &data, Some(&invoking), false);
+    // Compte du programme au format inconnu: transmis sans contenu décodé
+    update_account(&plugin, &ID, 1_000, &[1, 2, 3], None, false);
+    // Compte fermé dans une transaction du programme
+    update_account(&plugin, &system_program::ID, 0, &[], Some(&invoking), false);
+    
+    let messages = recorder.take();
+    assert_eq!(messages.len(), 3);
+    let StreamMessage::Account(owned) = &messages[0] else { panic!("compte attendu") };
+    assert_eq!((owned.slot, owned.lamports, owned.write_version, owned.closed), (SLOT, 1_000, 9, false));
+    assert_eq!(owned.txn_signature, Some(invoking.signature().to_string()));
+    assert_eq!(STANDARD.decode(&owned.data).unwrap(), data);
+    match &owned.decoded {
+        Some(DecodedAccount::Listing(listing)) => assert_eq!((listing.price, listing.quantity), (42, 2)),
+        decoded => panic!("listing attendu: {decoded:?}"),
+    }
+    let StreamMessage::Account(unknown) = &messages[1] else { panic!("compte attendu") };
+    assert!(unknown.decoded.is_none() && unknown.txn_signature.is_none());
+    let StreamMessage::Account(closed) = &messages[2] else { panic!("compte attendu") };
+    assert!(closed.closed && closed.decoded.is_none());
+    
+    // Comptes d'autres programmes, y compris fermés hors d'une transaction du programme
+    update_account(&plugin, &system_program::ID, 5, &[], Some(&invoking), false);
+    update_account(&plugin, &system_program::ID, 0, &[], Some(&foreign), false);
+    update_account(&plugin, &system_program::ID, 0, &[], None, false);
+    update_account(&plugin, &Pubkey::new_unique(), 1_000, &data, Some(&invoking), false);
+    assert!(recorder.take().is_empty());
+}
+
+#[test]
+fn startup_accounts_follow_the_configuration() {
+    let (plugin, recorder) = recorded(PluginConfig::default());
+    update_account(&plugin, &ID, 1_000, &listing_data(1), None, true);
+    let messages = recorder.take();
+    assert!(matches!(&messages[..], [StreamMessage::Account(account)] if account.is_startup));
+    
+    let (plugin, recorder) = recorded(PluginConfig { startup_accounts: false, ..PluginConfig::default() });
+    update_account(&plugin, &ID, 1_000, &listing_data(1), None, true);
+    assert!(recorder.take().is_empty());
+    update_account(&plugin, &ID, 1_000, &listing_data(1), None, false);
+    assert_eq!(recorder.take().len(), 1);
+}
+
+#[test]
+fn events_come_from_logs_and_program_inner_instructions() {
+    let (plugin, recorder) = recorded(PluginConfig::default());
+    let transaction = transaction(ID);
+    let (from_log, from_cpi, second_cpi) = (listed(10), sold(20), sold(30));
+    let meta = TransactionStatusMeta {
+        log_messages: Some(vec![
+            format!("Program {} invoke [1]", ID),
+            data_log(&from_log),
+            format!("Program {} success", ID),
+        ]),
+        inner_instructions: Some(vec![
+            // Index de compte 1: le programme; 2: le System Program, dont les données ne sont pas des événements
+            InnerInstructions { index: 0, instructions: vec![cpi(1, &from_cpi), cpi(2, &listed(99))] },
+            InnerInstructions {
+                index: 2,
+                instructions: vec![
+                    cpi(1, &second_cpi),
+                    InnerInstruction {
+                        instruction: CompiledInstruction::new_from_raw_parts(1, vec![7, 7, 7], vec![]),
+                        stack_height: Some(2),
+                    },
+                ],
+            },
+        ]),
+        ..TransactionStatusMeta::default()
+    };
+    
+    notify_transaction(&plugin, &transaction, &meta);
+    let messages = recorder.take();
+    let [StreamMessage::Transaction(update)] = &messages[..] else { panic!("transaction attendue: {messages:?}") };
+    assert_eq!((update.slot, update.index, update.failed, update.truncated), (SLOT, Some(3), false, false));
+    assert_eq!(update.signature, transaction.signature().to_string());
+    
+    let found: Vec<_> = update
+        .events
+        .iter()
+        .map(|event| {
+            let data = STANDARD.decode(&event.data).unwrap();
+            (event.instruction_index, event.source, event.name.as_str(), data)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0, StreamEventSource::Log, "NFTListed", from_log.data()),
+            (0, StreamEventSource::InnerInstruction, "NFTSold", from_cpi.data()),
+            (2, StreamEventSource::InnerInstruction, "NFTSold", second_cpi.data()),
+        ]
+    );
+    
+    // Le message se relit au format appliqué par l'indexeur
+    let decoded = update.to_decoded().unwrap();
+    assert_eq!(decoded.signature, *transaction.signature());
+    let events: Vec<_> = decoded.events.iter().map(|event| event.event.data()).collect();
+    assert_eq!(events, vec![from_log.data(), from_cpi.data(), second_cpi.data()]);
+    
+    // Logs tronqués: signalés dans le message
+    let truncated = TransactionStatusMeta {
+        log_messages: Some(vec![format!("Program {} invoke [1]", ID), "Log truncated".to_string()]),
+        ..TransactionStatusMeta::default()
+    };
+    notify_transaction(&plugin, &transaction, &truncated);
+    let messages = recorder.take();
+    let [StreamMessage::Transaction(update)] = &messages[..] else { panic!("transaction attendue: {messages:?}") };
+    assert!(update.truncated && update.events.is_empty());
+}
+
+#[test]
+fn failed_foreign_and_vote_transactions_are_filtered() {
+    let failed_meta = TransactionStatusMeta {
+        status: Err(TransactionError::InstructionError(0, InstructionError::Custom(6000))),
+        log_messages: Some(vec![
+            format!("Program {} invoke [1]", ID),
+            data_log(&listed(1)),
+            format!("Program {} failed: custom program error: 0x1770", ID),
+        ]),
+        inner_instructions: Some(vec![InnerInstructions { index: 0, instructions: vec![cpi(1, &sold(1))] }]),
+        ..TransactionStatusMeta::default()
+    };
+    let invoking = transaction(ID);
+    
+    // Transactions échouées ignorées par défaut
+    let (plugin, recorder) = recorded(PluginConfig::default());
+    notify_transaction(&plugin, &invoking, &failed_meta);
+    assert!(recorder.take().is_empty());
+    
+    // Diffusées sur demande, sans leurs événements annulés
+    let (plugin, recorder) = recorded(PluginConfig { failed_transactions: true, ..PluginConfig::default() });
+    notify_transaction(&plugin, &invoking, &failed_meta);
+    let messages = recorder.take();
+    assert!(matches!(&messages[..], [StreamMessage::Transaction(update)] if update.failed && update.events.is_empty()));
+    
+    // Transactions qui n'invoquent pas le programme et votes
+    let meta = TransactionStatusMeta::default();
+    notify_transaction(&plugin, &transaction(Pubkey::new_unique()), &meta);
+    let vote = ReplicaTransactionInfoV2 {
+        signature: invoking.signature(),
+        is_vote: true,
+        transaction: &invoking,
+        transaction_status_meta: &meta,
+        index: 0,
+    };
+    plugin.notify_transaction(ReplicaTransactionInfoVersions::V0_0_2(&vote), SLOT).unwrap();
+    assert!(recorder.take().is_empty());
+}
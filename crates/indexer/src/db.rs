@@ -0,0 +1,265 @@
+//! Accès à la base de données: SQLite (par défaut) ou Postgres.
+//!
+//! Les requêtes sont écrites une seule fois avec des paramètres numérotés `?1`, `?2`...
+//! et réécrites en `$1`, `$2`... pour Postgres. Le schéma n'utilise que des colonnes
+//! `BIGINT` et `TEXT` pour rester compatible avec les deux moteurs.
+
+use std::error::Error;
+
+use anchor_lang::prelude::Pubkey;
+use postgres::types::{private::BytesMut, IsNull, ToSql, Type};
+
+use crate::IndexerError;
+
+/// Valeur d'un paramètre ou d'une colonne.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+// Les montants en lamports et les slots sont stockés en BIGINT: une valeur au-delà de i64::MAX
+// est refusée plutôt que tronquée
+impl TryFrom<u64> for Value {
+    type Error = IndexerError;
+    
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        i64::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| IndexerError::ValueOutOfRange(value))
+    }
+}
+
+impl TryFrom<Option<u64>> for Value {
+    type Error = IndexerError;
+    
+    fn try_from(value: Option<u64>) -> Result<Self, Self::Error> {
+        value.map_or(Ok(Value::Null), Value::try_from)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<Pubkey> for Value {
+    fn from(value: Pubkey) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl rusqlite::ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::{ToSqlOutput, ValueRef};
+        
+        Ok(match self {
+            Value::Null => ToSqlOutput::Borrowed(ValueRef::Null),
+            Value::Integer(value) => ToSqlOutput::Borrowed(ValueRef::Integer(*value)),
+            Value::Text(value) => ToSqlOutput::Borrowed(ValueRef::Text(value.as_bytes())),
+        })
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Integer(value) => value.to_sql(ty, out),
+            Value::Text(value) => value.as_str().to_sql(ty, out),
+        }
+    }
+    
+    fn accepts(ty: &Type) -> bool {
+        <i64 as ToSql>::accepts(ty) || <&str as ToSql>::accepts(ty)
+    }
+    
+    postgres::types::to_sql_checked!();
+}
+
+/// Ligne renvoyée par une requête.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Row(pub Vec<Value>);
+
+impl Row {
+    // Colonne entière (0 si NULL)
+    pub fn int(&self, index: usize) -> i64 {
+        self.opt_int(index).unwrap_or_default()
+    }
+    
+    // Colonne entière pouvant être NULL
+    pub fn opt_int(&self, index: usize) -> Option<i64> {
+        match self.0.get(index) {
+            Some(Value::Integer(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    
+    // Colonne texte (vide si NULL)
+    pub fn text(&self, index: usize) -> String {
+        self.opt_text(index).unwrap_or_default()
+    }
+    
+    // Colonne texte pouvant être NULL
+    pub fn opt_text(&self, index: usize) -> Option<String> {
+        match self.0.get(index) {
+            Some(Value::Text(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Connexion à l'un des moteurs supportés.
+pub enum Database {
+    Sqlite(rusqlite::Connection),
+    Postgres(Box<postgres::Client>),
+}
+
+impl Database {
+    // Ouvrir une base à partir de son URL:
+    // "postgres://..." / "postgresql://..." pour Postgres, "sqlite://chemin", "sqlite::memory:" ou un chemin pour SQLite
+    pub fn connect(url: &str) -> Result<Self, IndexerError> {
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            let client = postgres::Client::connect(url, postgres::NoTls)?;
+            return Ok(Database::Postgres(Box::new(client)));
+        }
+        
+        let path = url.strip_prefix("sqlite://").or_else(|| url.strip_prefix("sqlite:")).unwrap_or(url);
+        let connection = if path.is_empty() || path == ":memory:" {
+            rusqlite::Connection::open_in_memory()?
+        } else {
+            rusqlite::Connection::open(path)?
+        };
+        connection.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        
+        Ok(Database::Sqlite(connection))
+    }
+    
+    // Exécuter plusieurs instructions SQL sans paramètres
+    pub fn batch(&mut self, sql: &str) -> Result<(), IndexerError> {
+        match self {
+            Database::Sqlite(connection) => connection.execute_batch(sql)?,
+            Database::Postgres(client) => client.batch_execute(sql)?,
+        }
+        Ok(())
+    }
+    
+    // Exécuter une instruction et retourner le nombre de lignes modifiées
+    pub fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64, IndexerError> {
+        match self {
+            Database::Sqlite(connection) => {
+                let changed = connection.execute(sql, rusqlite::params_from_iter(params.iter()))?;
+                Ok(changed as u64)
+            }
+            Database::Postgres(client) => Ok(client.execute(postgres_sql(sql).as_str(), &postgres_params(params))?),
+        }
+    }
+    
+    // Exécuter une requête et retourner toutes les lignes
+    pub fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>, IndexerError> {
+        match self {
+            Database::Sqlite(connection) => {
+                let mut statement = connection.prepare_cached(sql)?;
+                let columns = statement.column_count();
+                let mut rows = statement.query(rusqlite::params_from_iter(params.iter()))?;
+                
+                let mut result = Vec::new();
+                while let Some(row) = rows.next()? {
+                    let values = (0..columns)
+                        .map(|index| {
+                            Ok(match row.get_ref(index)? {
+                                rusqlite::types::ValueRef::Integer(value) => Value::Integer(value),
+                                rusqlite::types::ValueRef::Text(value) => {
+                                    Value::Text(String::from_utf8_lossy(value).into_owned())
+                                }
+                                rusqlite::types::ValueRef::Real(value) => Value::Integer(value as i64),
+                                _ => Value::Null,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+                    result.push(Row(values));
+                }
+                Ok(result)
+            }
+            Database::Postgres(client) => client
+                .query(postgres_sql(sql).as_str(), &postgres_params(params))?
+                .iter()
+                .map(postgres_row)
+                .collect(),
+        }
+    }
+    
+    // Exécuter une requête qui retourne au plus une ligne
+    pub fn query_optional(&mut self, sql: &str, params: &[Value]) -> Result<Option<Row>, IndexerError> {
+        Ok(self.query(sql, params)?.into_iter().next())
+    }
+    
+    // Exécuter `f` dans une transaction, annulée en cas d'erreur
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Database) -> Result<T, IndexerError>,
+    ) -> Result<T, IndexerError> {
+        self.batch("BEGIN")?;
+        match f(self) {
+            Ok(value) => {
+                self.batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(error) => {
+                // L'erreur d'origine est plus utile que celle d'un éventuel échec du rollback
+                let _ = self.batch("ROLLBACK");
+                Err(error)
+            }
+        }
+    }
+}
+
+// Réécrire les paramètres `?N` au format Postgres `$N`
+fn postgres_sql(sql: &str) -> String {
+    sql.replace('?', "$")
+}
+
+fn postgres_params(params: &[Value]) -> Vec<&(dyn ToSql + Sync)> {
+    params.iter().map(|param| param as &(dyn ToSql + Sync)).collect()
+}
+
+// Convertir une ligne Postgres (seuls les types entiers et texte sont utilisés par le schéma)
+fn postgres_row(row: &postgres::Row) -> Result<Row, IndexerError> {
+    let values = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let value = match *column.type_() {
+                Type::INT8 => row.try_get::<_, Option<i64>>(index)?.map(Value::Integer),
+                Type::INT4 => row.try_get::<_, Option<i32>>(index)?.map(|value| Value::Integer(value.into())),
+                Type::INT2 => row.try_get::<_, Option<i16>>(index)?.map(|value| Value::Integer(value.into())),
+                Type::TEXT | Type::VARCHAR => row.try_get::<_, Option<String>>(index)?.map(Value::Text),
+                ref other => return Err(IndexerError::UnsupportedColumn(column.name().to_string(), other.to_string())),
+            };
+            Ok(value.unwrap_or(Value::Null))
+        })
+        .collect::<Result<Vec<_>, IndexerError>>()?;
+    
+    Ok(Row(values))
+}
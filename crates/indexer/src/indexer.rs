@@ -0,0 +1,172 @@
+//! Synchronisation de la base avec la chaîne: rattrapage depuis les signatures puis suivi des nouveaux slots.
+
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_nft_marketplace::ID;
+use solana_nft_marketplace_events::{decode_transaction, DecodedTransaction};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::store::Store;
+use crate::IndexerError;
+
+// Nombre maximal de signatures par appel à getSignaturesForAddress
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// Paramètres de l'indexeur.
+#[derive(Clone, Debug)]
+pub struct IndexerConfig {
+    pub commitment: CommitmentConfig,
+    pub poll_interval: Duration, // Délai entre deux vérifications du slot courant
+    pub reconcile_every: u32,    // Rapprochement des listings avec l'état on-chain toutes les N synchronisations
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        IndexerConfig {
+            commitment: CommitmentConfig::confirmed(),
+            poll_interval: Duration::from_secs(2),
+            reconcile_every: 30,
+        }
+    }
+}
+
+/// Résultat d'une synchronisation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub transactions: usize, // Transactions appliquées
+    pub failed: usize,       // Transactions échouées (enregistrées sans événement)
+    pub events: usize,       // Événements appliqués
+    pub truncated: usize,    // Transactions dont les logs étaient tronqués
+}
+
+/// Indexeur du programme.
+pub struct Indexer {
+    rpc: RpcClient,
+    store: Store,
+    config: IndexerConfig,
+}
+
+impl Indexer {
+    pub fn new(rpc: RpcClient, store: Store, config: IndexerConfig) -> Self {
+        Indexer { rpc, store, config }
+    }
+    
+    pub fn store(&mut self) -> &mut Store {
+        &mut self.store
+    }
+    
+    // Appliquer toutes les transactions du programme plus récentes que le point de reprise
+    // (toutes depuis le déploiement lors du premier lancement), de la plus ancienne à la plus récente
+    pub fn sync(&mut self) -> Result<SyncReport, IndexerError> {
+        let until = self
+            .store
+            .checkpoint()?
+            .map(|checkpoint| Signature::from_str(&checkpoint.signature))
+            .transpose()
+            .map_err(|_| IndexerError::InvalidCheckpoint)?;
+        
+        // Parcourir les signatures de la plus récente à la plus ancienne, page par page
+        let mut pending = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.rpc.get_signatures_for_address_with_config(
+                &ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(SIGNATURES_PAGE_SIZE),
+                    commitment: Some(self.config.commitment),
+                },
+            )?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(Signature::from_str(&last.signature).map_err(|_| IndexerError::InvalidSignature)?);
+            
+            let full = page.len() == SIGNATURES_PAGE_SIZE;
+            pending.extend(page);
+            if !full {
+                break;
+            }
+        }
+        
+        let mut report = SyncReport::default();
+        for status in pending.into_iter().rev() {
+            let signature = Signature::from_str(&status.signature).map_err(|_| IndexerError::InvalidSignature)?;
+            
+            // Inutile de récupérer une transaction échouée: son état a été annulé
+            let transaction = if status.err.is_some() {
+                DecodedTransaction {
+                    slot: status.slot,
+                    signature,
+                    failed: true,
+                    truncated: false,
+                    undecodable: 0,
+                    events: Vec::new(),
+                }
+            } else {
+                self.fetch_transaction(&signature)?
+            };
+            
+            if self.store.apply_transaction(&transaction)? {
+                report.transactions += 1;
+                report.failed += usize::from(transaction.failed);
+                report.events += transaction.events.len();
+                report.truncated += usize::from(transaction.truncated);
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    // Lire et décoder une transaction du programme
+    pub fn fetch_transaction(&self, signature: &Signature) -> Result<DecodedTransaction, IndexerError> {
+        let transaction = self.rpc.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(self.config.commitment),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        
+        Ok(decode_transaction(*signature, &transaction)?)
+    }
+    
+    // Rapprocher les listings de leur état on-chain (getProgramAccounts)
+    pub fn reconcile(&mut self) -> Result<usize, IndexerError> {
+        let slot = self.rpc.get_slot_with_commitment(self.config.commitment)?;
+        let listings = solana_nft_marketplace_sdk::accounts::fetch_listings(&self.rpc)?;
+        
+        self.store.reconcile_listings(&listings, slot)
+    }
+    
+    // Rattraper l'historique puis suivre les nouveaux slots indéfiniment
+    pub fn run(&mut self, mut on_sync: impl FnMut(&SyncReport)) -> Result<(), IndexerError> {
+        let mut last_slot = 0;
+        let mut until_reconcile = 0u32;
+        
+        loop {
+            let slot = self.rpc.get_slot_with_commitment(self.config.commitment)?;
+            if slot > last_slot {
+                let report = self.sync()?;
+                on_sync(&report);
+                
+                if until_reconcile == 0 {
+                    self.reconcile()?;
+                    until_reconcile = self.config.reconcile_every.max(1);
+                }
+                until_reconcile -= 1;
+                last_slot = slot;
+            }
+            
+            thread::sleep(self.config.poll_interval);
+        }
+    }
+}
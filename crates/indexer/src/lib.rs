@@ -0,0 +1,49 @@
+//! Indexeur du programme `solana_nft_marketplace`.
+//!
+//! Rattrape l'historique du programme à partir de ses signatures, suit les nouveaux slots,
+//! décode les événements des transactions et matérialise les listings, ventes, offres
+//! d'échange, compteurs par wallet et le fil d'activité dans SQLite (par défaut) ou Postgres.
+//! Le traitement reprend au dernier point de reprise enregistré et chaque transaction est
+//! appliquée de façon atomique et idempotente.
+
+pub mod db;
+pub mod indexer;
+pub mod schema;
+pub mod store;
+
+use solana_client::client_error::ClientError;
+use solana_nft_marketplace_events::EventError;
+use solana_nft_marketplace_sdk::SdkError;
+
+pub use db::{Database, Row, Value};
+pub use indexer::{Indexer, IndexerConfig, SyncReport};
+pub use store::{Checkpoint, Store};
+
+// Erreurs de l'indexeur
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("erreur SQLite: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("erreur Postgres: {0}")]
+    Postgres(#[from] postgres::Error),
+    #[error("type de colonne non supporté pour {0}: {1}")]
+    UnsupportedColumn(String, String),
+    #[error("erreur RPC: {0}")]
+    Rpc(Box<ClientError>),
+    #[error(transparent)]
+    Sdk(#[from] SdkError),
+    #[error(transparent)]
+    Event(#[from] EventError),
+    #[error("signature invalide renvoyée par le RPC")]
+    InvalidSignature,
+    #[error("point de reprise invalide en base")]
+    InvalidCheckpoint,
+    #[error("valeur {0} trop grande pour une colonne BIGINT")]
+    ValueOutOfRange(u64),
+}
+
+impl From<ClientError> for IndexerError {
+    fn from(error: ClientError) -> Self {
+        IndexerError::Rpc(Box::new(error))
+    }
+}
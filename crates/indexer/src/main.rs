@@ -0,0 +1,94 @@
+//! Service d'indexation: rattrape l'historique du programme puis suit les nouveaux slots.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace_indexer::{Indexer, IndexerConfig, Store, SyncReport};
+use solana_nft_marketplace_sdk::cluster_url;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+#[derive(Parser)]
+#[command(name = "marketplace-indexer", version, about = "Indexeur des listings, ventes et offres de la marketplace NFT")]
+struct Cli {
+    /// URL du cluster ou raccourci (localhost, devnet, testnet, mainnet-beta)
+    #[arg(short = 'u', long = "url", default_value = "localhost", env = "MARKETPLACE_RPC_URL")]
+    url: String,
+
+    /// Base de données: chemin ou URL SQLite (sqlite://...), ou URL Postgres (postgres://...)
+    #[arg(short = 'd', long = "database", default_value = "sqlite://marketplace-indexer.db", env = "MARKETPLACE_DATABASE_URL")]
+    database: String,
+
+    /// Niveau de confirmation des transactions indexées
+    #[arg(long, value_enum, default_value_t = Commitment::Confirmed)]
+    commitment: Commitment,
+
+    /// Délai entre deux vérifications du slot courant, en millisecondes
+    #[arg(long, default_value_t = 2000)]
+    poll_interval_ms: u64,
+
+    /// Rapprocher les listings de l'état on-chain toutes les N synchronisations
+    #[arg(long, default_value_t = 30)]
+    reconcile_every: u32,
+
+    /// Rattraper l'historique puis quitter au lieu de suivre les nouveaux slots
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Commitment {
+    Confirmed,
+    Finalized,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    
+    let commitment = match cli.commitment {
+        Commitment::Confirmed => CommitmentConfig::confirmed(),
+        Commitment::Finalized => CommitmentConfig::finalized(),
+    };
+    let config = IndexerConfig {
+        commitment,
+        poll_interval: Duration::from_millis(cli.poll_interval_ms),
+        reconcile_every: cli.reconcile_every,
+    };
+    
+    let store = Store::open(&cli.database).with_context(|| format!("cannot open database {}", cli.database))?;
+    let rpc = RpcClient::new_with_commitment(cluster_url(&cli.url), commitment);
+    let mut indexer = Indexer::new(rpc, store, config);
+    
+    if let Some(checkpoint) = indexer.store().checkpoint()? {
+        println!("Resuming from slot {} ({})", checkpoint.slot, checkpoint.signature);
+    }
+    
+    if cli.once {
+        let report = indexer.sync()?;
+        print_report(&report);
+        let changed = indexer.reconcile()?;
+        println!("Reconciled listings: {changed} updated");
+        return Ok(());
+    }
+    
+    indexer.run(print_report)?;
+    Ok(())
+}
+
+fn print_report(report: &SyncReport) {
+    if report.transactions == 0 {
+        return;
+    }
+    println!(
+        "Indexed {} transactions ({} failed), {} events{}",
+        report.transactions,
+        report.failed,
+        report.events,
+        if report.truncated > 0 {
+            format!(", {} with truncated logs", report.truncated)
+        } else {
+            String::new()
+        }
+    );
+}
@@ -0,0 +1,117 @@
+//! Schéma des tables matérialisées par l'indexeur.
+
+// Tables créées au démarrage (idempotent, compatible SQLite et Postgres)
+pub const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id BIGINT PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    signature TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS processed_transactions (
+    signature TEXT PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    failed BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS listings (
+    address TEXT PRIMARY KEY,
+    seller TEXT NOT NULL,
+    nft_mint TEXT NOT NULL,
+    collection TEXT,
+    price BIGINT NOT NULL,
+    quantity BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    created_slot BIGINT NOT NULL,
+    created_at BIGINT,
+    updated_slot BIGINT NOT NULL,
+    updated_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS listings_seller ON listings (seller);
+CREATE INDEX IF NOT EXISTS listings_nft_mint ON listings (nft_mint);
+CREATE INDEX IF NOT EXISTS listings_status_price ON listings (status, price);
+
+CREATE TABLE IF NOT EXISTS sales (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    item_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    kind TEXT NOT NULL,
+    listing TEXT NOT NULL,
+    nft_mint TEXT,
+    seller TEXT,
+    buyer TEXT NOT NULL,
+    unit_price BIGINT NOT NULL,
+    quantity BIGINT NOT NULL,
+    price BIGINT NOT NULL,
+    marketplace_fee BIGINT NOT NULL,
+    referrer TEXT,
+    referral_fee BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index, item_index)
+);
+CREATE INDEX IF NOT EXISTS sales_nft_mint ON sales (nft_mint, slot);
+CREATE INDEX IF NOT EXISTS sales_seller ON sales (seller);
+CREATE INDEX IF NOT EXISTS sales_buyer ON sales (buyer);
+
+CREATE TABLE IF NOT EXISTS offers (
+    address TEXT PRIMARY KEY,
+    maker TEXT NOT NULL,
+    taker TEXT,
+    offered_mints TEXT NOT NULL,
+    offered_lamports BIGINT NOT NULL,
+    requested_mints TEXT NOT NULL,
+    requested_collection TEXT,
+    requested_count BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    created_slot BIGINT NOT NULL,
+    created_at BIGINT,
+    updated_slot BIGINT NOT NULL,
+    updated_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS offers_maker ON offers (maker);
+
+CREATE TABLE IF NOT EXISTS wallets (
+    wallet TEXT PRIMARY KEY,
+    listings_created BIGINT NOT NULL,
+    listings_canceled BIGINT NOT NULL,
+    items_bought BIGINT NOT NULL,
+    items_sold BIGINT NOT NULL,
+    volume_bought BIGINT NOT NULL,
+    volume_sold BIGINT NOT NULL,
+    first_activity_at BIGINT,
+    last_activity_at BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS activity (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    kind TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    counterparty TEXT,
+    account TEXT NOT NULL,
+    nft_mint TEXT,
+    price BIGINT,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS activity_slot ON activity (slot);
+CREATE INDEX IF NOT EXISTS activity_wallet ON activity (wallet, slot);
+";
+
+// Statuts d'un listing
+pub const LISTING_ACTIVE: &str = "active";
+pub const LISTING_SOLD: &str = "sold";
+pub const LISTING_CANCELED: &str = "canceled";
+pub const LISTING_DELISTED: &str = "delisted";
+pub const LISTING_INACTIVE: &str = "inactive";
+
+// Statuts d'une offre d'échange
+pub const OFFER_OPEN: &str = "open";
+pub const OFFER_ACCEPTED: &str = "accepted";
+pub const OFFER_CANCELED: &str = "canceled";
+
+// Types de vente
+pub const SALE_LISTING: &str = "listing";
+pub const SALE_BUNDLE: &str = "bundle";
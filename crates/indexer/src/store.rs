@@ -0,0 +1,594 @@
+//! Matérialisation des événements et de l'état des comptes dans la base.
+
+use std::collections::HashSet;
+
+use anchor_lang::prelude::Pubkey;
+use solana_nft_marketplace::NFTListing;
+use solana_nft_marketplace_events::{DecodedEvent, DecodedTransaction, MarketplaceEvent};
+
+use crate::db::{Database, Value};
+use crate::schema::*;
+use crate::IndexerError;
+
+/// Dernière transaction traitée, point de reprise de l'indexeur.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub signature: String,
+}
+
+// Variation des compteurs d'un wallet
+#[derive(Default)]
+struct WalletDelta {
+    listings_created: i64,
+    listings_canceled: i64,
+    items_bought: i64,
+    items_sold: i64,
+    volume_bought: i64,
+    volume_sold: i64,
+}
+
+// Ligne du fil d'activité
+struct Activity<'a> {
+    kind: &'a str,
+    wallet: Pubkey,
+    counterparty: Option<Pubkey>,
+    account: Pubkey,
+    nft_mint: Option<String>,
+    price: Option<u64>,
+}
+
+// Ligne de vente
+struct Sale<'a> {
+    kind: &'a str,
+    listing: Pubkey,
+    nft_mint: Option<String>,
+    seller: Option<String>,
+    buyer: Pubkey,
+    unit_price: u64,
+    quantity: u64,
+    marketplace_fee: u64,
+    referrer: Option<Pubkey>,
+    referral_fee: u64,
+}
+
+/// Base de l'indexeur.
+pub struct Store {
+    db: Database,
+}
+
+impl Store {
+    // Ouvrir la base et créer les tables manquantes
+    pub fn open(url: &str) -> Result<Self, IndexerError> {
+        let mut db = Database::connect(url)?;
+        db.batch(SCHEMA)?;
+        Ok(Store { db })
+    }
+    
+    // Accès direct à la base (requêtes de lecture)
+    pub fn db(&mut self) -> &mut Database {
+        &mut self.db
+    }
+    
+    // Point de reprise, None si rien n'a encore été indexé
+    pub fn checkpoint(&mut self) -> Result<Option<Checkpoint>, IndexerError> {
+        let row = self
+            .db
+            .query_optional("SELECT slot, signature FROM checkpoint WHERE id = 1", &[])?;
+        
+        Ok(row.map(|row| Checkpoint {
+            slot: row.int(0) as u64,
+            signature: row.text(1),
+        }))
+    }
+    
+    // Indiquer si une transaction a déjà été appliquée
+    pub fn is_processed(&mut self, signature: &str) -> Result<bool, IndexerError> {
+        let row = self.db.query_optional(
+            "SELECT 1 FROM processed_transactions WHERE signature = ?1",
+            &[signature.into()],
+        )?;
+        Ok(row.is_some())
+    }
+    
+    // Appliquer les événements d'une transaction et avancer le point de reprise, de façon atomique
+    // Une transaction déjà appliquée est ignorée (le traitement est idempotent)
+    pub fn apply_transaction(&mut self, transaction: &DecodedTransaction) -> Result<bool, IndexerError> {
+        let signature = transaction.signature.to_string();
+        
+        self.db.transaction(|db| {
+            let inserted = db.execute(
+                "INSERT INTO processed_transactions (signature, slot, failed) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (signature) DO NOTHING",
+                &[
+                    signature.as_str().into(),
+                    Value::try_from(transaction.slot)?,
+                    i64::from(transaction.failed).into(),
+                ],
+            )?;
+            if inserted == 0 {
+                return Ok(false);
+            }
+            
+            for (event_index, event) in transaction.events.iter().enumerate() {
+                apply_event(db, event_index as i64, event)?;
+            }
+            
+            db.execute(
+                "INSERT INTO checkpoint (id, slot, signature) VALUES (1, ?1, ?2)
+                 ON CONFLICT (id) DO UPDATE SET slot = excluded.slot, signature = excluded.signature",
+                &[Value::try_from(transaction.slot)?, signature.as_str().into()],
+            )?;
+            Ok(true)
+        })
+    }
+    
+    // Rapprocher la table des listings de l'état on-chain (lu via getProgramAccounts au slot donné)
+    // Les listings actifs en base dont le compte a disparu sont marqués comme annulés
+    pub fn reconcile_listings(&mut self, listings: &[(Pubkey, NFTListing)], slot: u64) -> Result<usize, IndexerError> {
+        self.db.transaction(|db| {
+            let mut changed = 0;
+            
+            for (address, listing) in listings {
+                let status = if listing.active {
+                    LISTING_ACTIVE
+                } else if listing.quantity == 0 {
+                    LISTING_SOLD
+                } else {
+                    LISTING_INACTIVE
+                };
+                
+                changed += db.execute(
+                    "INSERT INTO listings
+                        (address, seller, nft_mint, collection, price, quantity, status, created_slot, updated_slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+                     ON CONFLICT (address) DO UPDATE SET
+                        collection = excluded.collection,
+                        price = excluded.price,
+                        quantity = excluded.quantity,
+                        status = CASE
+                            WHEN excluded.status = ?9 THEN listings.status
+                            ELSE excluded.status
+                        END,
+                        updated_slot = excluded.updated_slot
+                     WHERE listings.collection IS DISTINCT FROM excluded.collection
+                        OR listings.price <> excluded.price
+                        OR listings.quantity <> excluded.quantity
+                        OR (listings.status <> excluded.status AND excluded.status <> ?9)",
+                    &[
+                        (*address).into(),
+                        listing.seller.into(),
+                        listing.nft_mint.into(),
+                        listing.collection.into(),
+                        Value::try_from(listing.price)?,
+                        Value::try_from(listing.quantity)?,
+                        status.into(),
+                        Value::try_from(slot)?,
+                        LISTING_INACTIVE.into(),
+                    ],
+                )?;
+            }
+            
+            // Comptes fermés (annulation ou retrait) non observés dans les événements
+            let live: HashSet<String> = listings.iter().map(|(address, _)| address.to_string()).collect();
+            let active = db.query("SELECT address FROM listings WHERE status = ?1", &[LISTING_ACTIVE.into()])?;
+            for row in active {
+                let address = row.text(0);
+                if !live.contains(&address) {
+                    changed += db.execute(
+                        "UPDATE listings SET status = ?1, updated_slot = ?2 WHERE address = ?3",
+                        &[LISTING_CANCELED.into(), Value::try_from(slot)?, address.into()],
+                    )?;
+                }
+            }
+            
+            Ok(changed as usize)
+        })
+    }
+}
+
+// Appliquer un événement aux tables matérialisées
+fn apply_event(db: &mut Database, event_index: i64, decoded: &DecodedEvent) -> Result<(), IndexerError> {
+    let slot = decoded.slot;
+    let time = decoded.block_time;
+    
+    match &decoded.event {
+        MarketplaceEvent::NFTListed(event) => {
+            db.execute(
+                "INSERT INTO listings
+                    (address, seller, nft_mint, price, quantity, status, created_slot, created_at, updated_slot, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?7, ?8)
+                 ON CONFLICT (address) DO UPDATE SET
+                    seller = excluded.seller,
+                    nft_mint = excluded.nft_mint,
+                    price = excluded.price,
+                    quantity = excluded.quantity,
+                    status = excluded.status,
+                    created_slot = excluded.created_slot,
+                    created_at = excluded.created_at,
+                    updated_slot = excluded.updated_slot,
+                    updated_at = excluded.updated_at",
+                &[
+                    event.listing.into(),
+                    event.seller.into(),
+                    event.nft_mint.into(),
+                    Value::try_from(event.price)?,
+                    Value::try_from(event.quantity)?,
+                    LISTING_ACTIVE.into(),
+                    Value::try_from(slot)?,
+                    time.into(),
+                ],
+            )?;
+            update_wallet(db, event.seller, time, WalletDelta {
+                listings_created: 1,
+                ..WalletDelta::default()
+            })?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "list",
+                wallet: event.seller,
+                counterparty: None,
+                account: event.listing,
+                nft_mint: Some(event.nft_mint.to_string()),
+                price: Some(event.price),
+            })?;
+        }
+        MarketplaceEvent::NFTListingUpdated(event) => {
+            db.execute(
+                "UPDATE listings SET price = ?1, quantity = ?2, status = ?3, updated_slot = ?4, updated_at = ?5
+                 WHERE address = ?6",
+                &[
+                    Value::try_from(event.price)?,
+                    Value::try_from(event.quantity)?,
+                    LISTING_ACTIVE.into(),
+                    Value::try_from(slot)?,
+                    time.into(),
+                    event.listing.into(),
+                ],
+            )?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "update",
+                wallet: event.seller,
+                counterparty: None,
+                account: event.listing,
+                nft_mint: Some(event.nft_mint.to_string()),
+                price: Some(event.price),
+            })?;
+        }
+        MarketplaceEvent::NFTSold(event) => {
+            // L'événement ne contient ni le vendeur ni le mint: ils sont lus dans le listing indexé
+            let listing = db.query_optional(
+                "SELECT seller, nft_mint FROM listings WHERE address = ?1",
+                &[event.listing.into()],
+            )?;
+            let (seller, nft_mint) = listing.map_or((None, None), |row| (row.opt_text(0), row.opt_text(1)));
+            
+            db.execute(
+                "UPDATE listings SET
+                    quantity = CASE WHEN quantity > ?1 THEN quantity - ?1 ELSE 0 END,
+                    status = CASE WHEN quantity > ?1 THEN status ELSE ?2 END,
+                    updated_slot = ?3,
+                    updated_at = ?4
+                 WHERE address = ?5",
+                &[
+                    Value::try_from(event.quantity)?,
+                    LISTING_SOLD.into(),
+                    Value::try_from(slot)?,
+                    time.into(),
+                    event.listing.into(),
+                ],
+            )?;
+            
+            let sale = Sale {
+                kind: SALE_LISTING,
+                listing: event.listing,
+                nft_mint: nft_mint.clone(),
+                seller: seller.clone(),
+                buyer: event.buyer,
+                unit_price: event.price,
+                quantity: event.quantity,
+                marketplace_fee: event.marketplace_fee,
+                referrer: event.referrer,
+                referral_fee: event.referral_fee,
+            };
+            record_sale(db, decoded, event_index, 0, sale)?;
+            
+            let seller = seller.and_then(|seller| seller.parse::<Pubkey>().ok());
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "sale",
+                wallet: event.buyer,
+                counterparty: seller,
+                account: event.listing,
+                nft_mint,
+                price: Some(event.price.saturating_mul(event.quantity)),
+            })?;
+        }
+        MarketplaceEvent::NFTListingCanceled(event) => {
+            set_listing_status(db, &event.listing, LISTING_CANCELED, slot, time)?;
+            update_wallet(db, event.seller, time, WalletDelta {
+                listings_canceled: 1,
+                ..WalletDelta::default()
+            })?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "cancel",
+                wallet: event.seller,
+                counterparty: None,
+                account: event.listing,
+                nft_mint: Some(event.nft_mint.to_string()),
+                price: None,
+            })?;
+        }
+        MarketplaceEvent::ListingForceDelisted(event) => {
+            set_listing_status(db, &event.listing, LISTING_DELISTED, slot, time)?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "delist",
+                wallet: event.seller,
+                counterparty: Some(event.moderator),
+                account: event.listing,
+                nft_mint: Some(event.nft_mint.to_string()),
+                price: None,
+            })?;
+        }
+        MarketplaceEvent::BundleListed(event) => {
+            update_wallet(db, event.seller, time, WalletDelta {
+                listings_created: 1,
+                ..WalletDelta::default()
+            })?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "bundle_list",
+                wallet: event.seller,
+                counterparty: None,
+                account: event.bundle,
+                nft_mint: None,
+                price: Some(event.price),
+            })?;
+        }
+        MarketplaceEvent::BundleSold(event) => {
+            // Une ligne de vente par NFT du lot, le prix du lot étant réparti entre eux
+            // (le reste de la division est attribué au premier NFT)
+            let count = event.nft_mints.len().max(1) as u64;
+            for (position, mint) in event.nft_mints.iter().enumerate() {
+                let share = event.price / count + if position == 0 { event.price % count } else { 0 };
+                let sale = Sale {
+                    kind: SALE_BUNDLE,
+                    listing: event.bundle,
+                    nft_mint: Some(mint.to_string()),
+                    seller: Some(event.seller.to_string()),
+                    buyer: event.buyer,
+                    unit_price: share,
+                    quantity: 1,
+                    marketplace_fee: 0,
+                    referrer: None,
+                    referral_fee: 0,
+                };
+                record_sale(db, decoded, event_index, position as i64, sale)?;
+            }
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "bundle_sale",
+                wallet: event.buyer,
+                counterparty: Some(event.seller),
+                account: event.bundle,
+                nft_mint: None,
+                price: Some(event.price),
+            })?;
+        }
+        MarketplaceEvent::BundleCanceled(event) => {
+            update_wallet(db, event.seller, time, WalletDelta {
+                listings_canceled: 1,
+                ..WalletDelta::default()
+            })?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "bundle_cancel",
+                wallet: event.seller,
+                counterparty: None,
+                account: event.bundle,
+                nft_mint: None,
+                price: None,
+            })?;
+        }
+        MarketplaceEvent::SwapCreated(event) => {
+            db.execute(
+                "INSERT INTO offers
+                    (address, maker, offered_mints, offered_lamports, requested_mints, requested_collection,
+                     requested_count, status, created_slot, created_at, updated_slot, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?9, ?10)
+                 ON CONFLICT (address) DO UPDATE SET
+                    maker = excluded.maker,
+                    taker = NULL,
+                    offered_mints = excluded.offered_mints,
+                    offered_lamports = excluded.offered_lamports,
+                    requested_mints = excluded.requested_mints,
+                    requested_collection = excluded.requested_collection,
+                    requested_count = excluded.requested_count,
+                    status = excluded.status,
+                    created_slot = excluded.created_slot,
+                    created_at = excluded.created_at,
+                    updated_slot = excluded.updated_slot,
+                    updated_at = excluded.updated_at",
+                &[
+                    event.swap.into(),
+                    event.maker.into(),
+                    join_mints(&event.offered_mints).into(),
+                    Value::try_from(event.offered_lamports)?,
+                    join_mints(&event.requested_mints).into(),
+                    event.requested_collection.into(),
+                    i64::from(event.requested_count).into(),
+                    OFFER_OPEN.into(),
+                    Value::try_from(slot)?,
+                    time.into(),
+                ],
+            )?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "offer",
+                wallet: event.maker,
+                counterparty: None,
+                account: event.swap,
+                nft_mint: event.offered_mints.first().map(Pubkey::to_string),
+                price: Some(event.offered_lamports),
+            })?;
+        }
+        MarketplaceEvent::SwapAccepted(event) => {
+            db.execute(
+                "UPDATE offers SET status = ?1, taker = ?2, updated_slot = ?3, updated_at = ?4 WHERE address = ?5",
+                &[OFFER_ACCEPTED.into(), event.taker.into(), Value::try_from(slot)?, time.into(), event.swap.into()],
+            )?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "offer_accept",
+                wallet: event.taker,
+                counterparty: Some(event.maker),
+                account: event.swap,
+                nft_mint: event.received_mints.first().map(Pubkey::to_string),
+                price: Some(event.offered_lamports),
+            })?;
+        }
+        MarketplaceEvent::SwapCanceled(event) => {
+            db.execute(
+                "UPDATE offers SET status = ?1, updated_slot = ?2, updated_at = ?3 WHERE address = ?4",
+                &[OFFER_CANCELED.into(), Value::try_from(slot)?, time.into(), event.swap.into()],
+            )?;
+            insert_activity(db, decoded, event_index, Activity {
+                kind: "offer_cancel",
+                wallet: event.maker,
+                counterparty: None,
+                account: event.swap,
+                nft_mint: None,
+                price: None,
+            })?;
+        }
+        // Les événements d'administration ne sont pas matérialisés
+        _ => {}
+    }
+    
+    Ok(())
+}
+
+// Enregistrer une vente et mettre à jour les compteurs de l'acheteur et du vendeur
+fn record_sale(
+    db: &mut Database,
+    decoded: &DecodedEvent,
+    event_index: i64,
+    item_index: i64,
+    sale: Sale,
+) -> Result<(), IndexerError> {
+    let price = sale.unit_price.saturating_mul(sale.quantity);
+    
+    db.execute(
+        "INSERT INTO sales
+            (signature, event_index, item_index, slot, block_time, kind, listing, nft_mint, seller, buyer,
+             unit_price, quantity, price, marketplace_fee, referrer, referral_fee)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT (signature, event_index, item_index) DO NOTHING",
+        &[
+            decoded.signature.to_string().into(),
+            event_index.into(),
+            item_index.into(),
+            Value::try_from(decoded.slot)?,
+            decoded.block_time.into(),
+            sale.kind.into(),
+            sale.listing.into(),
+            sale.nft_mint.into(),
+            sale.seller.clone().into(),
+            sale.buyer.into(),
+            Value::try_from(sale.unit_price)?,
+            Value::try_from(sale.quantity)?,
+            Value::try_from(price)?,
+            Value::try_from(sale.marketplace_fee)?,
+            sale.referrer.into(),
+            Value::try_from(sale.referral_fee)?,
+        ],
+    )?;
+    
+    let quantity = i64::try_from(sale.quantity).map_err(|_| IndexerError::ValueOutOfRange(sale.quantity))?;
+    let volume = i64::try_from(price).map_err(|_| IndexerError::ValueOutOfRange(price))?;
+    update_wallet(db, sale.buyer, decoded.block_time, WalletDelta {
+        items_bought: quantity,
+        volume_bought: volume,
+        ..WalletDelta::default()
+    })?;
+    if let Some(seller) = sale.seller.and_then(|seller| seller.parse().ok()) {
+        update_wallet(db, seller, decoded.block_time, WalletDelta {
+            items_sold: quantity,
+            volume_sold: volume,
+            ..WalletDelta::default()
+        })?;
+    }
+    
+    Ok(())
+}
+
+// Incrémenter les compteurs d'un wallet (créé à sa première activité)
+fn update_wallet(db: &mut Database, wallet: Pubkey, time: Option<i64>, delta: WalletDelta) -> Result<(), IndexerError> {
+    db.execute(
+        "INSERT INTO wallets
+            (wallet, listings_created, listings_canceled, items_bought, items_sold, volume_bought, volume_sold,
+             first_activity_at, last_activity_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+         ON CONFLICT (wallet) DO UPDATE SET
+            listings_created = wallets.listings_created + excluded.listings_created,
+            listings_canceled = wallets.listings_canceled + excluded.listings_canceled,
+            items_bought = wallets.items_bought + excluded.items_bought,
+            items_sold = wallets.items_sold + excluded.items_sold,
+            volume_bought = wallets.volume_bought + excluded.volume_bought,
+            volume_sold = wallets.volume_sold + excluded.volume_sold,
+            first_activity_at = COALESCE(wallets.first_activity_at, excluded.first_activity_at),
+            last_activity_at = COALESCE(excluded.last_activity_at, wallets.last_activity_at)",
+        &[
+            wallet.into(),
+            delta.listings_created.into(),
+            delta.listings_canceled.into(),
+            delta.items_bought.into(),
+            delta.items_sold.into(),
+            delta.volume_bought.into(),
+            delta.volume_sold.into(),
+            time.into(),
+        ],
+    )?;
+    Ok(())
+}
+
+// Ajouter une ligne au fil d'activité
+fn insert_activity(
+    db: &mut Database,
+    decoded: &DecodedEvent,
+    event_index: i64,
+    activity: Activity,
+) -> Result<(), IndexerError> {
+    db.execute(
+        "INSERT INTO activity
+            (signature, event_index, slot, block_time, kind, wallet, counterparty, account, nft_mint, price)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT (signature, event_index) DO NOTHING",
+        &[
+            decoded.signature.to_string().into(),
+            event_index.into(),
+            Value::try_from(decoded.slot)?,
+            decoded.block_time.into(),
+            activity.kind.into(),
+            activity.wallet.into(),
+            activity.counterparty.into(),
+            activity.account.into(),
+            activity.nft_mint.into(),
+            Value::try_from(activity.price)?,
+        ],
+    )?;
+    Ok(())
+}
+
+// Changer le statut d'un listing
+fn set_listing_status(
+    db: &mut Database,
+    listing: &Pubkey,
+    status: &str,
+    slot: u64,
+    time: Option<i64>,
+) -> Result<(), IndexerError> {
+    db.execute(
+        "UPDATE listings SET status = ?1, updated_slot = ?2, updated_at = ?3 WHERE address = ?4",
+        &[status.into(), Value::try_from(slot)?, time.into(), (*listing).into()],
+    )?;
+    Ok(())
+}
+
+// Liste de mints stockée sous forme de texte séparé par des virgules
+fn join_mints(mints: &[Pubkey]) -> String {
+    mints.iter().map(Pubkey::to_string).collect::<Vec<_>>().join(",")
+}
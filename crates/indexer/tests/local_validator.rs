@@ -0,0 +1,68 @@
+//! Indexation d'un validateur local (`solana-test-validator`) sur lequel le programme est déployé.
+//!
+//! Lancer avec `cargo test -p solana_nft_marketplace_indexer -- --ignored`
+//! (URL du validateur configurable via `MARKETPLACE_RPC_URL`).
+
+use std::collections::HashMap;
+
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace_indexer::schema::LISTING_ACTIVE;
+use solana_nft_marketplace_indexer::{Indexer, IndexerConfig, Store};
+use solana_nft_marketplace_sdk::accounts::fetch_listings;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+fn rpc() -> RpcClient {
+    let url = std::env::var("MARKETPLACE_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8899".to_string());
+    RpcClient::new_with_commitment(url, CommitmentConfig::confirmed())
+}
+
+#[test]
+#[ignore = "nécessite un validateur local avec le programme déployé"]
+fn backfill_matches_onchain_listings() {
+    let store = Store::open("sqlite::memory:").unwrap();
+    let mut indexer = Indexer::new(rpc(), store, IndexerConfig::default());
+    
+    // Rattrapage complet puis reprise depuis le point de reprise
+    let first = indexer.sync().unwrap();
+    let checkpoint = indexer.store().checkpoint().unwrap();
+    assert_eq!(checkpoint.is_some(), first.transactions > 0);
+    
+    let second = indexer.sync().unwrap();
+    let resumed = indexer.store().checkpoint().unwrap();
+    assert!(second.transactions == 0 || resumed.unwrap().slot >= checkpoint.unwrap().slot);
+    
+    // Après rapprochement, les listings actifs en base sont exactement les listings actifs on-chain
+    indexer.reconcile().unwrap();
+    let onchain: HashMap<String, _> = fetch_listings(&rpc())
+        .unwrap()
+        .into_iter()
+        .filter(|(_, listing)| listing.active)
+        .map(|(address, listing)| (address.to_string(), listing))
+        .collect();
+    
+    let rows = indexer
+        .store()
+        .db()
+        .query(
+            "SELECT address, price, quantity FROM listings WHERE status = ?1",
+            &[LISTING_ACTIVE.into()],
+        )
+        .unwrap();
+    assert_eq!(rows.len(), onchain.len());
+    for row in rows {
+        let listing = &onchain[&row.text(0)];
+        assert_eq!(row.int(1) as u64, listing.price);
+        assert_eq!(row.int(2) as u64, listing.quantity);
+    }
+    
+    // Chaque vente d'un listing référence un listing indexé
+    let orphans = indexer
+        .store()
+        .db()
+        .query(
+            "SELECT COUNT(*) FROM sales WHERE kind = 'listing' AND listing NOT IN (SELECT address FROM listings)",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(orphans[0].int(0), 0);
+}
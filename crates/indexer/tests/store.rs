@@ -0,0 +1,253 @@
+//! Application des transactions décodées et rapprochement des listings sur une base SQLite en mémoire.
+
+use anchor_lang::prelude::Pubkey;
+use solana_nft_marketplace::{NFTListed, NFTListing, NFTListingCanceled, NFTSold};
+use solana_nft_marketplace_events::{DecodedEvent, DecodedTransaction, EventSource, MarketplaceEvent};
+use solana_nft_marketplace_indexer::schema::*;
+use solana_nft_marketplace_indexer::{Checkpoint, IndexerError, Store};
+use solana_sdk::signature::Signature;
+
+const BLOCK_TIME: i64 = 1_700_000_000;
+
+// Transaction réussie contenant les événements donnés
+fn transaction(slot: u64, events: Vec<MarketplaceEvent>) -> DecodedTransaction {
+    let signature = Signature::new_unique();
+    DecodedTransaction {
+        slot,
+        signature,
+        failed: false,
+        truncated: false,
+        undecodable: 0,
+        events: events
+            .into_iter()
+            .map(|event| DecodedEvent {
+                slot,
+                block_time: Some(BLOCK_TIME + slot as i64),
+                signature,
+                instruction_index: 0,
+                source: EventSource::Log,
+                event,
+            })
+            .collect(),
+    }
+}
+
+fn listed(listing: Pubkey, seller: Pubkey, price: u64, quantity: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTListed(NFTListed {
+        listing,
+        seller,
+        nft_mint: Pubkey::new_unique(),
+        price,
+        quantity,
+    })
+}
+
+fn sold(listing: Pubkey, buyer: Pubkey, price: u64, quantity: u64) -> MarketplaceEvent {
+    MarketplaceEvent::NFTSold(NFTSold {
+        listing,
+        buyer,
+        price,
+        quantity,
+        marketplace_fee: price * quantity / 40,
+        referrer: None,
+        referral_fee: 0,
+    })
+}
+
+fn canceled(listing: Pubkey, seller: Pubkey) -> MarketplaceEvent {
+    MarketplaceEvent::NFTListingCanceled(NFTListingCanceled {
+        listing,
+        seller,
+        nft_mint: Pubkey::new_unique(),
+    })
+}
+
+fn onchain(seller: Pubkey, price: u64, quantity: u64, active: bool) -> NFTListing {
+    NFTListing {
+        version: 1,
+        seller,
+        nft_mint: Pubkey::new_unique(),
+        price,
+        active,
+        bump: 255,
+        quantity,
+        payout_splits: Vec::new(),
+        collection: None,
+        stats_tracked: false,
+        reserved: [0; 63],
+    }
+}
+
+// (statut, prix, quantité) d'un listing indexé
+fn listing_row(store: &mut Store, listing: &Pubkey) -> (String, i64, i64) {
+    let row = store
+        .db()
+        .query_optional("SELECT status, price, quantity FROM listings WHERE address = ?1", &[(*listing).into()])
+        .unwrap()
+        .expect("listing indexé");
+    (row.text(0), row.int(1), row.int(2))
+}
+
+fn count(store: &mut Store, table: &str) -> i64 {
+    store.db().query(&format!("SELECT COUNT(*) FROM {}", table), &[]).unwrap()[0].int(0)
+}
+
+// (créés, annulés, achetés, vendus, volume acheté, volume vendu) d'un wallet
+fn wallet(store: &mut Store, wallet: &Pubkey) -> [i64; 6] {
+    let row = store
+        .db()
+        .query_optional(
+            "SELECT listings_created, listings_canceled, items_bought, items_sold, volume_bought, volume_sold
+             FROM wallets WHERE wallet = ?1",
+            &[(*wallet).into()],
+        )
+        .unwrap()
+        .unwrap_or_default();
+    [row.int(0), row.int(1), row.int(2), row.int(3), row.int(4), row.int(5)]
+}
+
+#[test]
+fn list_buy_and_cancel_are_materialized() {
+    let mut store = Store::open("sqlite::memory:").unwrap();
+    assert_eq!(store.checkpoint().unwrap(), None);
+    
+    let (seller, buyer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (edition, single) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let listing = transaction(10, vec![listed(edition, seller, 100, 3), listed(single, seller, 50, 1)]);
+    assert!(store.apply_transaction(&listing).unwrap());
+    assert_eq!(listing_row(&mut store, &edition), (LISTING_ACTIVE.to_string(), 100, 3));
+    assert_eq!(wallet(&mut store, &seller), [2, 0, 0, 0, 0, 0]);
+    
+    // Achat partiel: le listing reste actif avec la quantité restante
+    let partial = transaction(11, vec![sold(edition, buyer, 100, 2)]);
+    assert!(store.apply_transaction(&partial).unwrap());
+    assert_eq!(listing_row(&mut store, &edition), (LISTING_ACTIVE.to_string(), 100, 1));
+    
+    let rows = store
+        .db()
+        .query("SELECT seller, buyer, unit_price, quantity, price, marketplace_fee, kind FROM sales", &[])
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!((rows[0].text(0), rows[0].text(1)), (seller.to_string(), buyer.to_string()));
+    assert_eq!((rows[0].int(2), rows[0].int(3), rows[0].int(4), rows[0].int(5)), (100, 2, 200, 5));
+    assert_eq!(rows[0].text(6), SALE_LISTING);
+    
+    // Achat du reste et annulation de l'autre listing
+    let last = transaction(12, vec![sold(edition, buyer, 100, 1), canceled(single, seller)]);
+    assert!(store.apply_transaction(&last).unwrap());
+    assert_eq!(listing_row(&mut store, &edition), (LISTING_SOLD.to_string(), 100, 0));
+    assert_eq!(listing_row(&mut store, &single).0, LISTING_CANCELED);
+    
+    assert_eq!(wallet(&mut store, &seller), [2, 1, 0, 3, 0, 300]);
+    assert_eq!(wallet(&mut store, &buyer), [0, 0, 3, 0, 300, 0]);
+    assert_eq!(count(&mut store, "sales"), 2);
+    assert_eq!(count(&mut store, "activity"), 5);
+    assert_eq!(
+        store.checkpoint().unwrap(),
+        Some(Checkpoint { slot: 12, signature: last.signature.to_string() })
+    );
+}
+
+#[test]
+fn replayed_transactions_are_ignored() {
+    let mut store = Store::open("sqlite::memory:").unwrap();
+    let (seller, buyer, listing) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let first = transaction(20, vec![listed(listing, seller, 70, 2)]);
+    let second = transaction(21, vec![sold(listing, buyer, 70, 1)]);
+    
+    assert!(store.apply_transaction(&first).unwrap());
+    assert!(store.apply_transaction(&second).unwrap());
+    assert!(store.is_processed(&first.signature.to_string()).unwrap());
+    
+    // Rejouer une transaction (reprise après interruption) ne compte rien deux fois
+    // et ne fait pas reculer le point de reprise
+    assert!(!store.apply_transaction(&first).unwrap());
+    assert!(!store.apply_transaction(&second).unwrap());
+    assert_eq!(listing_row(&mut store, &listing), (LISTING_ACTIVE.to_string(), 70, 1));
+    assert_eq!(wallet(&mut store, &seller), [1, 0, 0, 1, 0, 70]);
+    assert_eq!(count(&mut store, "sales"), 1);
+    assert_eq!(count(&mut store, "activity"), 2);
+    assert_eq!(store.checkpoint().unwrap().unwrap().signature, second.signature.to_string());
+}
+
+#[test]
+fn failed_transactions_advance_the_checkpoint_without_events() {
+    let mut store = Store::open("sqlite::memory:").unwrap();
+    let mut failed = transaction(30, Vec::new());
+    failed.failed = true;
+    
+    assert!(store.apply_transaction(&failed).unwrap());
+    assert_eq!(store.checkpoint().unwrap(), Some(Checkpoint { slot: 30, signature: failed.signature.to_string() }));
+    let rows = store.db().query("SELECT slot, failed FROM processed_transactions", &[]).unwrap();
+    assert_eq!((rows[0].int(0), rows[0].int(1)), (30, 1));
+    assert_eq!(count(&mut store, "activity"), 0);
+}
+
+#[test]
+fn amounts_beyond_bigint_roll_the_transaction_back() {
+    let mut store = Store::open("sqlite::memory:").unwrap();
+    let seller = Pubkey::new_unique();
+    let applied = transaction(40, vec![listed(Pubkey::new_unique(), seller, 10, 1)]);
+    store.apply_transaction(&applied).unwrap();
+    
+    // Le premier événement est valide, le second dépasse i64::MAX: rien n'est enregistré
+    let overflow = transaction(
+        41,
+        vec![listed(Pubkey::new_unique(), seller, 5, 1), listed(Pubkey::new_unique(), seller, u64::MAX, 1)],
+    );
+    assert!(matches!(
+        store.apply_transaction(&overflow),
+        Err(IndexerError::ValueOutOfRange(value)) if value == u64::MAX
+    ));
+    assert!(!store.is_processed(&overflow.signature.to_string()).unwrap());
+    assert_eq!(count(&mut store, "listings"), 1);
+    assert_eq!(wallet(&mut store, &seller), [1, 0, 0, 0, 0, 0]);
+    assert_eq!(store.checkpoint().unwrap().unwrap().slot, 40);
+}
+
+#[test]
+fn reconcile_listings_follows_the_onchain_state() {
+    let mut store = Store::open("sqlite::memory:").unwrap();
+    let seller = Pubkey::new_unique();
+    let [repriced, closed, withdrawn, created, sold_out] = [(); 5].map(|_| Pubkey::new_unique());
+    store
+        .apply_transaction(&transaction(50, vec![
+            listed(repriced, seller, 100, 1),
+            listed(closed, seller, 200, 1),
+            listed(withdrawn, seller, 300, 1),
+            canceled(withdrawn, seller),
+        ]))
+        .unwrap();
+    
+    let listings = vec![
+        (repriced, onchain(seller, 150, 1, true)),
+        (created, onchain(seller, 400, 2, true)),
+        (sold_out, onchain(seller, 500, 0, false)),
+        // Un listing inactif on-chain garde le statut connu par les événements
+        (withdrawn, onchain(seller, 300, 1, false)),
+    ];
+    assert_eq!(store.reconcile_listings(&listings, 60).unwrap(), 4);
+    
+    assert_eq!(listing_row(&mut store, &repriced), (LISTING_ACTIVE.to_string(), 150, 1));
+    assert_eq!(listing_row(&mut store, &created), (LISTING_ACTIVE.to_string(), 400, 2));
+    assert_eq!(listing_row(&mut store, &sold_out), (LISTING_SOLD.to_string(), 500, 0));
+    assert_eq!(listing_row(&mut store, &withdrawn).0, LISTING_CANCELED);
+    // Le compte fermé sans événement observé est considéré comme annulé
+    assert_eq!(listing_row(&mut store, &closed).0, LISTING_CANCELED);
+    
+    let updated = store
+        .db()
+        .query_optional("SELECT updated_slot FROM listings WHERE address = ?1", &[closed.into()])
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.int(0), 60);
+    
+    // Un second rapprochement avec le même état ne change rien
+    assert_eq!(store.reconcile_listings(&listings, 61).unwrap(), 0);
+    
+    let mut too_expensive = onchain(seller, u64::MAX, 1, true);
+    too_expensive.collection = Some(Pubkey::new_unique());
+    let result = store.reconcile_listings(&[(Pubkey::new_unique(), too_expensive)], 62);
+    assert!(matches!(result, Err(IndexerError::ValueOutOfRange(_))));
+    assert_eq!(count(&mut store, "listings"), 5);
+}
@@ -0,0 +1,88 @@
+//! Lecture et décodage des comptes du programme.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator, Space};
+use anchor_spl::token::{self, TokenAccount};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_nft_marketplace::{FeeSchedule, Marketplace, NFTListing, SellerVolume, ID};
+
+use crate::{pda, SdkError};
+
+// Décoder les données brutes d'un compte (discriminateur Anchor compris)
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T, SdkError> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+// Lire et décoder un compte, None s'il n'existe pas
+pub fn fetch_optional<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<Option<T>, SdkError> {
+    let account = rpc
+        .get_multiple_accounts(&[*address])?
+        .pop()
+        .flatten();
+    
+    account.map(|account| decode(&account.data)).transpose()
+}
+
+// Lire et décoder un compte qui doit exister
+pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T, SdkError> {
+    fetch_optional(rpc, address)?.ok_or(SdkError::AccountNotFound(*address))
+}
+
+// Lire le compte global de la marketplace
+pub fn fetch_marketplace(rpc: &RpcClient) -> Result<Marketplace, SdkError> {
+    fetch(rpc, &pda::marketplace_address().0)
+}
+
+// Lire un listing
+pub fn fetch_listing(rpc: &RpcClient, address: &Pubkey) -> Result<NFTListing, SdkError> {
+    fetch(rpc, address)
+}
+
+// Vérifier comme buy_nft si le vendeur détient encore le NFT de membre qu'il a enregistré
+pub fn fetch_seller_is_holder(
+    rpc: &RpcClient,
+    fee_schedule: Option<&FeeSchedule>,
+    seller_volume: Option<&SellerVolume>,
+) -> Result<bool, SdkError> {
+    let (Some(schedule), Some(volume)) = (fee_schedule, seller_volume) else {
+        return Ok(false);
+    };
+    let Some(membership_token) = volume.membership_token else {
+        return Ok(false);
+    };
+    if schedule.membership_collection != volume.membership_collection {
+        return Ok(false);
+    }
+    
+    let account = rpc.get_multiple_accounts(&[membership_token])?.pop().flatten();
+    match account {
+        Some(account) if account.owner == token::ID => {
+            let token_account: TokenAccount = decode(&account.data)?;
+            Ok(token_account.owner == volume.seller && token_account.amount > 0)
+        }
+        _ => Ok(false),
+    }
+}
+
+// Lire tous les listings au format actuel (les listings à migrer sont ignorés)
+pub fn fetch_listings(rpc: &RpcClient) -> Result<Vec<(Pubkey, NFTListing)>, SdkError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize((8 + NFTListing::INIT_SPACE) as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &NFTListing::DISCRIMINATOR)),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    
+    rpc.get_program_accounts_with_config(&ID, config)?
+        .into_iter()
+        .map(|(address, account)| Ok((address, decode(&account.data)?)))
+        .collect()
+}
@@ -0,0 +1,13 @@
+//! Résolution des raccourcis de cluster, partagée par la CLI, l'indexeur et le service de webhooks.
+
+// Résoudre les raccourcis de cluster de la CLI Solana
+pub fn cluster_url(url: &str) -> String {
+    match url {
+        "localhost" | "l" => "http://127.0.0.1:8899",
+        "devnet" | "d" => "https://api.devnet.solana.com",
+        "testnet" | "t" => "https://api.testnet.solana.com",
+        "mainnet-beta" | "m" => "https://api.mainnet-beta.solana.com",
+        url => url,
+    }
+    .to_string()
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::Pubkey;
+use solana_client::client_error::ClientError;
+
+// Erreurs du client
+#[derive(Debug, thiserror::Error)]
+pub enum SdkError {
+    #[error("erreur RPC: {0}")]
+    Rpc(Box<ClientError>),
+    #[error("compte {0} introuvable")]
+    AccountNotFound(Pubkey),
+    #[error("erreur du programme: {0}")]
+    Program(Box<anchor_lang::error::Error>),
+    #[error("quantité invalide: {requested} demandée(s), {available} disponible(s)")]
+    InvalidQuantity { requested: u64, available: u64 },
+}
+
+impl From<ClientError> for SdkError {
+    fn from(error: ClientError) -> Self {
+        SdkError::Rpc(Box::new(error))
+    }
+}
+
+impl From<anchor_lang::error::Error> for SdkError {
+    fn from(error: anchor_lang::error::Error) -> Self {
+        SdkError::Program(Box::new(error))
+    }
+}
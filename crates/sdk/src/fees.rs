@@ -0,0 +1,76 @@
+//! Devis d'achat: mêmes fonctions de calcul que l'instruction `buy_nft`.
+
+use solana_nft_marketplace::{
+    calculate_fee_breakdown, roll_seller_volume, select_fee_bps, CollectionConfig, ErrorCode, FeeBreakdown,
+    FeeSchedule, Marketplace, NFTListing, SellerVolume,
+};
+
+use crate::SdkError;
+
+/// Données nécessaires pour calculer le devis d'un achat.
+#[derive(Clone, Copy)]
+pub struct BuyQuoteInputs<'a> {
+    pub marketplace: &'a Marketplace,
+    pub listing: &'a NFTListing,
+    pub quantity: u64,
+    pub fee_schedule: Option<&'a FeeSchedule>,         // Grille de frais, si elle existe
+    pub seller_volume: Option<&'a SellerVolume>,       // Volume glissant du vendeur, s'il existe
+    pub seller_is_holder: bool,                        // Le vendeur prouve détenir un NFT membre
+    pub collection_config: Option<&'a CollectionConfig>, // Configuration de la collection du listing, si elle existe
+    pub has_referrer: bool,                            // Un parrain est fourni à l'achat
+    pub now: i64,                                      // Horodatage utilisé pour le volume glissant
+}
+
+/// Devis d'un achat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuyQuote {
+    pub total_price: u64,        // Prix total payé par l'acheteur
+    pub fee_bps: u16,            // Taux de frais effectif
+    pub fee_tier: Option<u8>,    // Palier de la grille de frais appliqué
+    pub breakdown: FeeBreakdown, // Répartition entre trésorerie, parrain et vendeur
+}
+
+// Calculer le devis d'un achat comme le ferait buy_nft
+pub fn quote_buy(inputs: &BuyQuoteInputs) -> Result<BuyQuote, SdkError> {
+    let listing = inputs.listing;
+    if inputs.quantity == 0 || inputs.quantity > listing.quantity {
+        return Err(SdkError::InvalidQuantity {
+            requested: inputs.quantity,
+            available: listing.quantity,
+        });
+    }
+    
+    let total_price = listing
+        .price
+        .checked_mul(inputs.quantity)
+        .ok_or_else(|| anchor_lang::error::Error::from(ErrorCode::MathOverflow))?;
+    
+    let trailing_volume = inputs.seller_volume.map_or(0, |volume| {
+        let mut volume = volume.clone();
+        roll_seller_volume(&mut volume, inputs.now);
+        volume.previous_volume.saturating_add(volume.current_volume)
+    });
+    
+    // La configuration de collection n'est prise en compte que si elle correspond au listing
+    let collection_config = inputs
+        .collection_config
+        .filter(|config| listing.collection == Some(config.collection_mint));
+    
+    let (fee_bps, fee_tier) = select_fee_bps(
+        inputs.marketplace.fee,
+        inputs.fee_schedule,
+        trailing_volume,
+        inputs.fee_schedule.is_some() && inputs.seller_is_holder,
+        collection_config,
+    );
+    
+    let referral_fee_bps = inputs.has_referrer.then_some(inputs.marketplace.referral_fee_bps);
+    let breakdown = calculate_fee_breakdown(total_price, fee_bps, referral_fee_bps)?;
+    
+    Ok(BuyQuote {
+        total_price,
+        fee_bps,
+        fee_tier,
+        breakdown,
+    })
+}
@@ -0,0 +1,253 @@
+//! Construction des instructions principales avec leurs comptes.
+//!
+//! Les comptes et les données sont générés à partir des types Anchor du programme
+//! (`accounts::*` et `instruction::*`), les comptes optionnels absents étant remplacés
+//! par l'identifiant du programme comme le fait Anchor.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use solana_nft_marketplace::{accounts, instruction, Marketplace, NFTListing, PayoutSplit, ID};
+
+use crate::pda;
+
+// Initialiser la marketplace
+pub fn initialize_marketplace(authority: Pubkey, marketplace_fee: u16) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializeMarketplace {
+            marketplace: pda::marketplace_address().0,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeMarketplace { marketplace_fee }.data(),
+    }
+}
+
+// Créer le compte de statistiques globales (les instructions l'ignorent tant qu'il n'est pas créé)
+pub fn initialize_stats(authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializeStats {
+            stats: pda::stats_address().0,
+            marketplace: pda::marketplace_address().0,
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeStats {}.data(),
+    }
+}
+
+/// Mise en vente d'un NFT.
+#[derive(Clone)]
+pub struct ListNft {
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,                       // Prix unitaire en lamports
+    pub quantity: u64,                    // Quantité mise en vente
+    pub payout_splits: Vec<PayoutSplit>,  // Répartition des recettes (vide = tout au vendeur)
+    pub collection: Option<Pubkey>,       // Collection vérifiée: fournit les métadonnées et les statistiques de collection
+    pub with_collection_config: bool,     // Fournir la configuration de la collection (obligatoire en mode curé)
+}
+
+impl ListNft {
+    pub fn new(seller: Pubkey, nft_mint: Pubkey, price: u64) -> Self {
+        Self {
+            seller,
+            nft_mint,
+            price,
+            quantity: 1,
+            payout_splits: Vec::new(),
+            collection: None,
+            with_collection_config: false,
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::ListNFT {
+                listing: pda::listing_address(&self.nft_mint, &self.seller).0,
+                marketplace: pda::marketplace_address().0,
+                seller: self.seller,
+                nft_mint: self.nft_mint,
+                nft_token_account: get_associated_token_address(&self.seller, &self.nft_mint),
+                nft_metadata: pda::metadata_address(&self.nft_mint).0,
+                collection_config: self
+                    .collection
+                    .filter(|_| self.with_collection_config)
+                    .map(|collection| pda::collection_config_address(&collection).0),
+                mint_blocklist: pda::mint_blocklist_address(&self.nft_mint).0,
+                delist_record: pda::delist_record_address(&self.nft_mint, &self.seller).0,
+                collection_stats: self.collection.map(|collection| pda::collection_stats_address(&collection).0),
+                stats: pda::stats_address().0,
+                seller_record: pda::seller_record_address(&self.seller).0,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ListNft {
+                price: self.price,
+                quantity: self.quantity,
+                payout_splits: self.payout_splits.clone(),
+            }
+            .data(),
+        }
+    }
+}
+
+/// Mise à jour du prix et de la quantité d'un listing existant.
+#[derive(Clone)]
+pub struct UpdateListing {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub collection: Option<Pubkey>,
+    pub price: u64,
+    pub quantity: u64,
+}
+
+impl UpdateListing {
+    pub fn new(listing_address: Pubkey, listing: &NFTListing, price: u64, quantity: u64) -> Self {
+        Self {
+            listing: listing_address,
+            seller: listing.seller,
+            nft_mint: listing.nft_mint,
+            collection: listing.collection,
+            price,
+            quantity,
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::UpdateListing {
+                listing: self.listing,
+                seller: self.seller,
+                nft_token_account: get_associated_token_address(&self.seller, &self.nft_mint),
+                collection_stats: self.collection.map(|collection| pda::collection_stats_address(&collection).0),
+                stats: pda::stats_address().0,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::UpdateListing {
+                price: self.price,
+                quantity: self.quantity,
+            }
+            .data(),
+        }
+    }
+}
+
+/// Achat d'un NFT listé.
+#[derive(Clone)]
+pub struct BuyNft {
+    pub buyer: Pubkey,
+    pub listing_address: Pubkey,
+    pub listing: NFTListing,
+    pub treasury: Pubkey,
+    pub quantity: u64,
+    pub referrer: Option<Pubkey>,                // Parrain (son compte de gains doit exister)
+    pub membership_token: Option<Pubkey>,        // NFT de membre enregistré par le vendeur (obligatoire s'il existe)
+}
+
+impl BuyNft {
+    pub fn new(buyer: Pubkey, listing_address: Pubkey, listing: &NFTListing, marketplace: &Marketplace) -> Self {
+        Self {
+            buyer,
+            listing_address,
+            listing: listing.clone(),
+            treasury: marketplace.treasury,
+            quantity: 1,
+            referrer: None,
+            membership_token: None,
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        let listing = &self.listing;
+        let mut account_metas = accounts::BuyNFT {
+            marketplace: pda::marketplace_address().0,
+            listing: self.listing_address,
+            buyer: self.buyer,
+            seller_wallet: listing.seller,
+            treasury: self.treasury,
+            seller_token_account: get_associated_token_address(&listing.seller, &listing.nft_mint),
+            buyer_token_account: get_associated_token_address(&self.buyer, &listing.nft_mint),
+            referrer: self.referrer,
+            referrer_earnings: self.referrer.map(|referrer| pda::referrer_earnings_address(&referrer).0),
+            fee_schedule: pda::fee_schedule_address().0,
+            seller_volume: pda::seller_volume_address(&listing.seller).0,
+            seller_membership_token: self.membership_token,
+            collection_config: listing.collection.map(|collection| pda::collection_config_address(&collection).0),
+            mint_blocklist: pda::mint_blocklist_address(&listing.nft_mint).0,
+            collection_stats: listing.collection.map(|collection| pda::collection_stats_address(&collection).0),
+            buyer_profile: pda::trader_profile_address(&self.buyer).0,
+            seller_profile: pda::trader_profile_address(&listing.seller).0,
+            stats: pda::stats_address().0,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        
+        // remaining_accounts: destinataires de la répartition des recettes, dans l'ordre
+        account_metas.extend(
+            listing
+                .payout_splits
+                .iter()
+                .map(|split| AccountMeta::new(split.recipient, false)),
+        );
+        
+        Instruction {
+            program_id: ID,
+            accounts: account_metas,
+            data: instruction::BuyNft { quantity: self.quantity }.data(),
+        }
+    }
+}
+
+/// Annulation d'un listing par son vendeur.
+#[derive(Clone)]
+pub struct CancelListing {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub collection: Option<Pubkey>,
+}
+
+impl CancelListing {
+    pub fn new(listing_address: Pubkey, listing: &NFTListing) -> Self {
+        Self {
+            listing: listing_address,
+            seller: listing.seller,
+            nft_mint: listing.nft_mint,
+            collection: listing.collection,
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::CancelListing {
+                listing: self.listing,
+                seller: self.seller,
+                nft_token_account: get_associated_token_address(&self.seller, &self.nft_mint),
+                collection_stats: self.collection.map(|collection| pda::collection_stats_address(&collection).0),
+                seller_profile: pda::trader_profile_address(&self.seller).0,
+                stats: pda::stats_address().0,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CancelListing {}.data(),
+        }
+    }
+}
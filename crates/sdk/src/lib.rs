@@ -0,0 +1,20 @@
+//! Client Rust pour le programme `solana_nft_marketplace`.
+//!
+//! Regroupe la dérivation des PDAs, la construction des instructions principales
+//! (avec les bons comptes), la lecture des comptes on-chain et le calcul des frais
+//! d'un achat, qui réutilise exactement les fonctions appelées par `buy_nft`, ainsi que la
+//! résolution des raccourcis de cluster.
+
+pub mod accounts;
+pub mod cluster;
+pub mod error;
+pub mod fees;
+pub mod instructions;
+pub mod pda;
+
+pub use cluster::cluster_url;
+pub use error::SdkError;
+pub use solana_nft_marketplace::{
+    CollectionConfig, FeeBreakdown, FeeSchedule, Marketplace, MarketplaceStats, NFTListing, PayoutSplit,
+    SellerVolume, TraderProfile, ID as PROGRAM_ID,
+};
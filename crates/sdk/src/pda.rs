@@ -0,0 +1,73 @@
+//! Dérivation des adresses (PDAs) utilisées par le programme.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::metadata::mpl_token_metadata;
+use solana_nft_marketplace::ID;
+
+// Compte global de la marketplace
+pub fn marketplace_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"marketplace"], &ID)
+}
+
+// Listing d'un NFT par un vendeur
+pub fn listing_address(nft_mint: &Pubkey, seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"listing", nft_mint.as_ref(), seller.as_ref()], &ID)
+}
+
+// Statistiques globales de la marketplace
+pub fn stats_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stats"], &ID)
+}
+
+// Enregistrement d'un vendeur (créé lors de son premier listing)
+pub fn seller_record_address(seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"seller_record", seller.as_ref()], &ID)
+}
+
+// Entrée de la liste de blocage d'un mint
+pub fn mint_blocklist_address(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"blocklist", nft_mint.as_ref()], &ID)
+}
+
+// Enregistrement du retrait de force du listing d'un vendeur pour un mint
+pub fn delist_record_address(nft_mint: &Pubkey, seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"delisted", nft_mint.as_ref(), seller.as_ref()], &ID)
+}
+
+// Grille de frais de la marketplace
+pub fn fee_schedule_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_schedule"], &ID)
+}
+
+// Volume glissant d'un vendeur
+pub fn seller_volume_address(seller: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"seller_volume", seller.as_ref()], &ID)
+}
+
+// Configuration d'une collection
+pub fn collection_config_address(collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection", collection.as_ref()], &ID)
+}
+
+// Statistiques d'une collection
+pub fn collection_stats_address(collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection_stats", collection.as_ref()], &ID)
+}
+
+// Profil de trading d'un portefeuille
+pub fn trader_profile_address(wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"profile", wallet.as_ref()], &ID)
+}
+
+// Compte de gains d'un parrain
+pub fn referrer_earnings_address(referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"referrer", referrer.as_ref()], &ID)
+}
+
+// Compte de métadonnées Metaplex d'un mint
+pub fn metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+}
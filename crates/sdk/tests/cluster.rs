@@ -0,0 +1,13 @@
+//! Raccourcis de cluster de la CLI Solana.
+
+use solana_nft_marketplace_sdk::cluster_url;
+
+#[test]
+fn cluster_shortcuts_resolve_to_rpc_urls() {
+    assert_eq!(cluster_url("localhost"), "http://127.0.0.1:8899");
+    assert_eq!(cluster_url("l"), "http://127.0.0.1:8899");
+    assert_eq!(cluster_url("d"), "https://api.devnet.solana.com");
+    assert_eq!(cluster_url("testnet"), "https://api.testnet.solana.com");
+    assert_eq!(cluster_url("m"), "https://api.mainnet-beta.solana.com");
+    assert_eq!(cluster_url("http://rpc.example:8899"), "http://rpc.example:8899");
+}
@@ -0,0 +1,271 @@
+//! Adresses dérivées et comptes des instructions construites par le client, comparés aux seeds
+//! et à l'ordre des comptes déclarés par le programme.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token;
+use solana_nft_marketplace::{
+    instruction, Marketplace, NFTListing, PayoutSplit, ID, LISTING_VERSION, MARKETPLACE_VERSION,
+};
+use solana_nft_marketplace_sdk::{instructions, pda};
+
+fn listing(collection: Option<Pubkey>, payout_splits: Vec<PayoutSplit>) -> NFTListing {
+    NFTListing {
+        version: LISTING_VERSION,
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        price: 1_000_000_000,
+        active: true,
+        bump: 255,
+        quantity: 3,
+        payout_splits,
+        collection,
+        stats_tracked: false,
+        reserved: [0; 63],
+    }
+}
+
+fn marketplace() -> Marketplace {
+    Marketplace {
+        version: MARKETPLACE_VERSION,
+        authority: Pubkey::new_unique(),
+        fee: 250,
+        bump: 255,
+        referral_fee_bps: 0,
+        curated: false,
+        moderators: Vec::new(),
+        treasury: Pubkey::new_unique(),
+        reserved: [0; 64],
+    }
+}
+
+fn keys(instruction: &Instruction) -> Vec<Pubkey> {
+    instruction.accounts.iter().map(|meta| meta.pubkey).collect()
+}
+
+fn signers(instruction: &Instruction) -> Vec<Pubkey> {
+    instruction.accounts.iter().filter(|meta| meta.is_signer).map(|meta| meta.pubkey).collect()
+}
+
+// Les comptes optionnels absents sont remplacés par l'identifiant du programme, en lecture seule
+fn assert_placeholder(meta: &AccountMeta) {
+    assert_eq!(*meta, AccountMeta::new_readonly(ID, false));
+}
+
+fn program_address(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ID).0
+}
+
+#[test]
+fn pdas_use_the_program_seeds() {
+    let mint = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+    
+    assert_eq!(pda::marketplace_address().0, program_address(&[b"marketplace"]));
+    assert_eq!(pda::stats_address().0, program_address(&[b"stats"]));
+    assert_eq!(pda::fee_schedule_address().0, program_address(&[b"fee_schedule"]));
+    assert_eq!(pda::listing_address(&mint, &wallet).0, program_address(&[b"listing", mint.as_ref(), wallet.as_ref()]));
+    assert_eq!(pda::seller_record_address(&wallet).0, program_address(&[b"seller_record", wallet.as_ref()]));
+    assert_eq!(pda::mint_blocklist_address(&mint).0, program_address(&[b"blocklist", mint.as_ref()]));
+    assert_eq!(
+        pda::delist_record_address(&mint, &wallet).0,
+        program_address(&[b"delisted", mint.as_ref(), wallet.as_ref()])
+    );
+    assert_eq!(pda::seller_volume_address(&wallet).0, program_address(&[b"seller_volume", wallet.as_ref()]));
+    assert_eq!(pda::collection_config_address(&mint).0, program_address(&[b"collection", mint.as_ref()]));
+    assert_eq!(pda::collection_stats_address(&mint).0, program_address(&[b"collection_stats", mint.as_ref()]));
+    assert_eq!(pda::trader_profile_address(&wallet).0, program_address(&[b"profile", wallet.as_ref()]));
+    assert_eq!(pda::referrer_earnings_address(&wallet).0, program_address(&[b"referrer", wallet.as_ref()]));
+    assert_eq!(
+        pda::metadata_address(&mint).0,
+        Pubkey::find_program_address(
+            &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    );
+    
+    // Le bump retourné est celui de l'adresse
+    let (address, bump) = pda::listing_address(&mint, &wallet);
+    let seeds: &[&[u8]] = &[b"listing", mint.as_ref(), wallet.as_ref(), &[bump]];
+    assert_eq!(Pubkey::create_program_address(seeds, &ID).unwrap(), address);
+}
+
+#[test]
+fn list_nft_accounts_follow_the_program_order() {
+    let seller = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+    let mut list = instructions::ListNft::new(seller, mint, 42);
+    list.quantity = 5;
+    
+    let instruction = list.instruction();
+    assert_eq!(instruction.program_id, ID);
+    assert_eq!(
+        keys(&instruction),
+        vec![
+            pda::listing_address(&mint, &seller).0,
+            pda::marketplace_address().0,
+            seller,
+            mint,
+            get_associated_token_address(&seller, &mint),
+            pda::metadata_address(&mint).0,
+            ID,
+            pda::mint_blocklist_address(&mint).0,
+            pda::delist_record_address(&mint, &seller).0,
+            ID,
+            pda::stats_address().0,
+            pda::seller_record_address(&seller).0,
+            token::ID,
+            system_program::ID,
+        ]
+    );
+    assert_eq!(signers(&instruction), vec![seller]);
+    assert_placeholder(&instruction.accounts[6]);
+    assert_placeholder(&instruction.accounts[9]);
+    
+    assert_eq!(&instruction.data[..8], &instruction::ListNft::DISCRIMINATOR[..]);
+    let data = instruction::ListNft::deserialize(&mut &instruction.data[8..]).unwrap();
+    assert_eq!((data.price, data.quantity, data.payout_splits.len()), (42, 5, 0));
+    
+    // La collection fournit ses statistiques, et sa configuration seulement si elle est demandée
+    list.collection = Some(collection);
+    let instruction = list.instruction();
+    assert_placeholder(&instruction.accounts[6]);
+    assert_eq!(instruction.accounts[9], AccountMeta::new(pda::collection_stats_address(&collection).0, false));
+    
+    list.with_collection_config = true;
+    let instruction = list.instruction();
+    assert_eq!(instruction.accounts[6].pubkey, pda::collection_config_address(&collection).0);
+    assert_eq!(instruction.accounts.len(), 14);
+}
+
+#[test]
+fn buy_nft_accounts_follow_the_program_order() {
+    let buyer = Pubkey::new_unique();
+    let listing_address = Pubkey::new_unique();
+    let marketplace = marketplace();
+    let listing = listing(None, Vec::new());
+    
+    let buy = instructions::BuyNft::new(buyer, listing_address, &listing, &marketplace);
+    let instruction = buy.instruction();
+    assert_eq!(
+        keys(&instruction),
+        vec![
+            pda::marketplace_address().0,
+            listing_address,
+            buyer,
+            listing.seller,
+            marketplace.treasury,
+            get_associated_token_address(&listing.seller, &listing.nft_mint),
+            get_associated_token_address(&buyer, &listing.nft_mint),
+            ID,
+            ID,
+            pda::fee_schedule_address().0,
+            pda::seller_volume_address(&listing.seller).0,
+            ID,
+            ID,
+            pda::mint_blocklist_address(&listing.nft_mint).0,
+            ID,
+            pda::trader_profile_address(&buyer).0,
+            pda::trader_profile_address(&listing.seller).0,
+            pda::stats_address().0,
+            token::ID,
+            associated_token::ID,
+            system_program::ID,
+        ]
+    );
+    assert_eq!(signers(&instruction), vec![buyer]);
+    for index in [7, 8, 11, 12, 14] {
+        assert_placeholder(&instruction.accounts[index]);
+    }
+    
+    assert_eq!(&instruction.data[..8], &instruction::BuyNft::DISCRIMINATOR[..]);
+    let data = instruction::BuyNft::deserialize(&mut &instruction.data[8..]).unwrap();
+    assert_eq!(data.quantity, 1);
+}
+
+#[test]
+fn buy_nft_fills_optional_accounts_and_appends_payout_recipients() {
+    let buyer = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let membership_token = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+    let splits = vec![
+        PayoutSplit { recipient: Pubkey::new_unique(), bps: 7_000 },
+        PayoutSplit { recipient: Pubkey::new_unique(), bps: 3_000 },
+    ];
+    let listing = listing(Some(collection), splits.clone());
+    
+    let mut buy = instructions::BuyNft::new(buyer, Pubkey::new_unique(), &listing, &marketplace());
+    buy.quantity = 2;
+    buy.referrer = Some(referrer);
+    buy.membership_token = Some(membership_token);
+    let instruction = buy.instruction();
+    
+    let keys = keys(&instruction);
+    assert_eq!(keys[7], referrer);
+    assert_eq!(keys[8], pda::referrer_earnings_address(&referrer).0);
+    assert_eq!(keys[11], membership_token);
+    assert_eq!(keys[12], pda::collection_config_address(&collection).0);
+    assert_eq!(keys[14], pda::collection_stats_address(&collection).0);
+    assert!(instruction.accounts[7].is_writable && instruction.accounts[8].is_writable);
+    
+    // remaining_accounts: destinataires de la répartition, dans l'ordre du listing et modifiables
+    assert_eq!(
+        instruction.accounts[21..],
+        [AccountMeta::new(splits[0].recipient, false), AccountMeta::new(splits[1].recipient, false)]
+    );
+    let data = instruction::BuyNft::deserialize(&mut &instruction.data[8..]).unwrap();
+    assert_eq!(data.quantity, 2);
+}
+
+#[test]
+fn update_and_cancel_accounts_follow_the_program_order() {
+    let listing_address = Pubkey::new_unique();
+    let collection = Pubkey::new_unique();
+    let listing = listing(Some(collection), Vec::new());
+    let token_account = get_associated_token_address(&listing.seller, &listing.nft_mint);
+    
+    let update = instructions::UpdateListing::new(listing_address, &listing, 7, 2).instruction();
+    assert_eq!(
+        keys(&update),
+        vec![
+            listing_address,
+            listing.seller,
+            token_account,
+            pda::collection_stats_address(&collection).0,
+            pda::stats_address().0,
+            token::ID,
+            system_program::ID,
+        ]
+    );
+    assert_eq!(signers(&update), vec![listing.seller]);
+    let data = instruction::UpdateListing::deserialize(&mut &update.data[8..]).unwrap();
+    assert_eq!((data.price, data.quantity), (7, 2));
+    
+    let cancel = instructions::CancelListing::new(listing_address, &listing).instruction();
+    assert_eq!(
+        keys(&cancel),
+        vec![
+            listing_address,
+            listing.seller,
+            token_account,
+            pda::collection_stats_address(&collection).0,
+            pda::trader_profile_address(&listing.seller).0,
+            pda::stats_address().0,
+            token::ID,
+            system_program::ID,
+        ]
+    );
+    assert_eq!(&cancel.data[..], &instruction::CancelListing::DISCRIMINATOR[..]);
+    
+    // Un listing hors collection laisse la place des statistiques de collection au programme
+    let listing = NFTListing { collection: None, ..listing };
+    assert_placeholder(&instructions::UpdateListing::new(listing_address, &listing, 7, 2).instruction().accounts[3]);
+    assert_placeholder(&instructions::CancelListing::new(listing_address, &listing).instruction().accounts[3]);
+}
@@ -0,0 +1,238 @@
+//! Devis d'achat: paliers de la grille de frais, remise membre, frais de collection et parrainage,
+//! comparés aux montants calculés à la main avec les arrondis de `buy_nft`.
+
+use anchor_lang::prelude::Pubkey;
+use solana_nft_marketplace::{
+    CollectionConfig, FeeBreakdown, FeeSchedule, FeeTier, Marketplace, NFTListing, RoyaltyPolicy, SellerVolume,
+    FEE_VOLUME_WINDOW, LISTING_VERSION, MARKETPLACE_VERSION,
+};
+use solana_nft_marketplace_sdk::fees::{quote_buy, BuyQuote, BuyQuoteInputs};
+use solana_nft_marketplace_sdk::SdkError;
+
+const SOL: u64 = 1_000_000_000;
+const NOW: i64 = 1_700_000_000;
+
+fn marketplace(fee: u16, referral_fee_bps: u16) -> Marketplace {
+    Marketplace {
+        version: MARKETPLACE_VERSION,
+        authority: Pubkey::new_unique(),
+        fee,
+        bump: 255,
+        referral_fee_bps,
+        curated: false,
+        moderators: Vec::new(),
+        treasury: Pubkey::new_unique(),
+        reserved: [0; 64],
+    }
+}
+
+fn listing(price: u64, quantity: u64, collection: Option<Pubkey>) -> NFTListing {
+    NFTListing {
+        version: LISTING_VERSION,
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        price,
+        active: true,
+        bump: 255,
+        quantity,
+        payout_splits: Vec::new(),
+        collection,
+        stats_tracked: false,
+        reserved: [0; 63],
+    }
+}
+
+// Grille à deux paliers (10 SOL: 3%, 50 SOL: 1,5%) avec 0,5% de remise membre
+fn schedule() -> FeeSchedule {
+    FeeSchedule {
+        tiers: vec![
+            FeeTier { min_volume: 10 * SOL, fee_bps: 300 },
+            FeeTier { min_volume: 50 * SOL, fee_bps: 150 },
+        ],
+        membership_collection: Some(Pubkey::new_unique()),
+        holder_discount_bps: 50,
+        bump: 255,
+    }
+}
+
+fn volume(window_start: i64, previous_volume: u64, current_volume: u64) -> SellerVolume {
+    SellerVolume {
+        seller: Pubkey::new_unique(),
+        window_start,
+        current_volume,
+        previous_volume,
+        membership_token: None,
+        membership_collection: None,
+        bump: 255,
+    }
+}
+
+fn config(collection: Pubkey, enabled: bool, fee_override: Option<u16>) -> CollectionConfig {
+    CollectionConfig {
+        collection_mint: collection,
+        enabled,
+        fee_override,
+        royalty_policy: RoyaltyPolicy::Optional,
+        bump: 255,
+    }
+}
+
+fn inputs<'a>(marketplace: &'a Marketplace, listing: &'a NFTListing, quantity: u64) -> BuyQuoteInputs<'a> {
+    BuyQuoteInputs {
+        marketplace,
+        listing,
+        quantity,
+        fee_schedule: None,
+        seller_volume: None,
+        seller_is_holder: false,
+        collection_config: None,
+        has_referrer: false,
+        now: NOW,
+    }
+}
+
+fn breakdown(fee_amount: u64, referral_amount: u64, seller_amount: u64) -> FeeBreakdown {
+    FeeBreakdown {
+        fee_amount,
+        referral_amount,
+        treasury_amount: fee_amount - referral_amount,
+        seller_amount,
+    }
+}
+
+#[test]
+fn base_fee_and_referral_share_are_rounded_down() {
+    let marketplace = marketplace(250, 2_000);
+    let listing = listing(123_456_789, 1, None);
+    
+    let quote = quote_buy(&inputs(&marketplace, &listing, 1)).unwrap();
+    assert_eq!(
+        quote,
+        BuyQuote {
+            total_price: 123_456_789,
+            fee_bps: 250,
+            fee_tier: None,
+            breakdown: breakdown(3_086_419, 0, 120_370_370),
+        }
+    );
+    
+    // Le parrain reçoit sa part des frais, prélevée sur la trésorerie et non sur le vendeur
+    let quote = quote_buy(&BuyQuoteInputs { has_referrer: true, ..inputs(&marketplace, &listing, 1) }).unwrap();
+    assert_eq!(quote.breakdown, breakdown(3_086_419, 617_283, 120_370_370));
+    assert_eq!(quote.breakdown.treasury_amount, 2_469_136);
+}
+
+#[test]
+fn fee_tier_follows_the_trailing_volume() {
+    let marketplace = marketplace(500, 2_000);
+    let listing = listing(SOL, 4, None);
+    let schedule = schedule();
+    let quote = |volume: &SellerVolume, now: i64| {
+        quote_buy(&BuyQuoteInputs {
+            fee_schedule: Some(&schedule),
+            seller_volume: Some(volume),
+            now,
+            ..inputs(&marketplace, &listing, 3)
+        })
+        .unwrap()
+    };
+    
+    // 8 SOL sur la fenêtre précédente et 4 SOL sur la courante: premier palier
+    let twelve_sol = volume(NOW - 10, 8 * SOL, 4 * SOL);
+    let quote_tier_0 = quote(&twelve_sol, NOW);
+    assert_eq!((quote_tier_0.fee_bps, quote_tier_0.fee_tier), (300, Some(0)));
+    assert_eq!(quote_tier_0.breakdown, breakdown(90_000_000, 0, 2_910_000_000));
+    
+    let quote_tier_1 = quote(&volume(NOW - 10, 0, 60 * SOL), NOW);
+    assert_eq!((quote_tier_1.fee_bps, quote_tier_1.fee_tier), (150, Some(1)));
+    
+    // Une fenêtre écoulée ne garde que le volume courant, deux fenêtres remettent le volume à zéro
+    let quote_rolled = quote(&twelve_sol, NOW - 10 + FEE_VOLUME_WINDOW);
+    assert_eq!((quote_rolled.fee_bps, quote_rolled.fee_tier), (500, None));
+    let quote_reset = quote(&volume(NOW - 10, 0, 60 * SOL), NOW - 10 + 2 * FEE_VOLUME_WINDOW);
+    assert_eq!((quote_reset.fee_bps, quote_reset.fee_tier), (500, None));
+    
+    // Sans compte de volume, le vendeur n'atteint aucun palier
+    let quote_without_volume = quote_buy(&BuyQuoteInputs {
+        fee_schedule: Some(&schedule),
+        ..inputs(&marketplace, &listing, 3)
+    })
+    .unwrap();
+    assert_eq!((quote_without_volume.fee_bps, quote_without_volume.fee_tier), (500, None));
+}
+
+#[test]
+fn holder_discount_applies_only_with_a_fee_schedule() {
+    let marketplace = marketplace(500, 0);
+    let listing = listing(SOL, 1, None);
+    let schedule = schedule();
+    let seller_volume = volume(NOW, 0, 10 * SOL);
+    
+    let quote = quote_buy(&BuyQuoteInputs {
+        fee_schedule: Some(&schedule),
+        seller_volume: Some(&seller_volume),
+        seller_is_holder: true,
+        ..inputs(&marketplace, &listing, 1)
+    })
+    .unwrap();
+    assert_eq!((quote.fee_bps, quote.fee_tier), (250, Some(0)));
+    assert_eq!(quote.breakdown, breakdown(25_000_000, 0, 975_000_000));
+    
+    let quote = quote_buy(&BuyQuoteInputs { seller_is_holder: true, ..inputs(&marketplace, &listing, 1) }).unwrap();
+    assert_eq!((quote.fee_bps, quote.fee_tier), (500, None));
+}
+
+#[test]
+fn collection_fee_override_replaces_the_schedule() {
+    let marketplace = marketplace(500, 1_000);
+    let collection = Pubkey::new_unique();
+    let listing = listing(2 * SOL, 1, Some(collection));
+    let schedule = schedule();
+    let seller_volume = volume(NOW, 0, 60 * SOL);
+    let quote = |config: &CollectionConfig| {
+        quote_buy(&BuyQuoteInputs {
+            fee_schedule: Some(&schedule),
+            seller_volume: Some(&seller_volume),
+            seller_is_holder: true,
+            collection_config: Some(config),
+            has_referrer: true,
+            ..inputs(&marketplace, &listing, 1)
+        })
+        .unwrap()
+    };
+    
+    // Les frais de collection remplacent palier et remise membre
+    let overridden = quote(&config(collection, true, Some(25)));
+    assert_eq!((overridden.fee_bps, overridden.fee_tier), (25, None));
+    assert_eq!(overridden.breakdown, breakdown(5_000_000, 500_000, 1_995_000_000));
+    
+    // Une configuration désactivée, sans frais ou d'une autre collection est ignorée
+    for ignored in [
+        config(collection, false, Some(25)),
+        config(collection, true, None),
+        config(Pubkey::new_unique(), true, Some(25)),
+    ] {
+        let quote = quote(&ignored);
+        assert_eq!((quote.fee_bps, quote.fee_tier), (100, Some(1)));
+        assert_eq!(quote.breakdown, breakdown(20_000_000, 2_000_000, 1_980_000_000));
+    }
+}
+
+#[test]
+fn invalid_quantities_and_overflows_are_rejected() {
+    let marketplace = marketplace(250, 0);
+    let listing = listing(SOL, 2, None);
+    
+    for quantity in [0, 3] {
+        match quote_buy(&inputs(&marketplace, &listing, quantity)) {
+            Err(SdkError::InvalidQuantity { requested, available }) => {
+                assert_eq!((requested, available), (quantity, 2));
+            }
+            other => panic!("quantité {} acceptée: {:?}", quantity, other.map(|quote| quote.total_price)),
+        }
+    }
+    
+    let expensive = NFTListing { price: u64::MAX, ..listing.clone() };
+    assert!(matches!(quote_buy(&inputs(&marketplace, &expensive, 2)), Err(SdkError::Program(_))));
+    assert_eq!(quote_buy(&inputs(&marketplace, &expensive, 1)).unwrap().total_price, u64::MAX);
+}
@@ -0,0 +1,148 @@
+//! Mise en file des notifications par abonnement et envoi avec reprises.
+
+use serde_json::json;
+
+use crate::notification::Notification;
+use crate::queue::{Delivery, DeliveryQueue};
+use crate::signature::{sign, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER};
+use crate::subscription::Subscription;
+use crate::transport::Transport;
+use crate::WebhookError;
+
+// Nombre maximal d'envois tentés par passage
+const DISPATCH_BATCH: u32 = 100;
+
+/// Politique de reprise: délai exponentiel à partir de `base_delay_ms`, plafonné à `max_delay_ms`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: i64,
+    pub max_delay_ms: i64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 8,
+            base_delay_ms: 1_000,
+            max_delay_ms: 5 * 60 * 1_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Délai avant la tentative suivant l'échec numéro `attempts` (1 pour le premier échec)
+    pub fn delay(&self, attempts: u32) -> i64 {
+        let factor = 1i64.checked_shl(attempts.saturating_sub(1)).unwrap_or(i64::MAX);
+        self.base_delay_ms.saturating_mul(factor).min(self.max_delay_ms)
+    }
+}
+
+/// Résultat d'un passage d'envoi.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DispatchReport {
+    pub delivered: usize,
+    pub retried: usize,
+    pub dead: usize,
+}
+
+/// Distributeur de webhooks.
+pub struct Dispatcher<T: Transport> {
+    subscriptions: Vec<Subscription>,
+    queue: DeliveryQueue,
+    transport: T,
+    policy: RetryPolicy,
+}
+
+impl<T: Transport> Dispatcher<T> {
+    pub fn new(subscriptions: Vec<Subscription>, queue: DeliveryQueue, transport: T, policy: RetryPolicy) -> Self {
+        Dispatcher {
+            subscriptions,
+            queue,
+            transport,
+            policy,
+        }
+    }
+    
+    pub fn queue(&mut self) -> &mut DeliveryQueue {
+        &mut self.queue
+    }
+    
+    // Mettre en file un envoi par abonnement correspondant (`now` en millisecondes Unix)
+    pub fn enqueue(&mut self, notification: &Notification, now: i64) -> Result<usize, WebhookError> {
+        let mut queued = 0;
+        
+        for subscription in self.subscriptions.iter().filter(|subscription| subscription.matches(notification)) {
+            let id = format!("{}:{}", notification.id, subscription.id);
+            let payload = json!({
+                "id": id,
+                "type": notification.event_type,
+                "subscription": subscription.id,
+                "signature": notification.signature,
+                "slot": notification.slot,
+                "data": notification.data,
+            });
+            
+            let delivery = Delivery {
+                id,
+                subscription_id: subscription.id.clone(),
+                event_type: notification.event_type.to_string(),
+                payload: payload.to_string(),
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+                created_at: now,
+            };
+            if self.queue.enqueue(&delivery)? {
+                queued += 1;
+            }
+        }
+        
+        Ok(queued)
+    }
+    
+    // Tenter les envois arrivés à échéance; un échec est reprogrammé ou abandonné après `max_attempts`
+    pub fn process_due(&mut self, now: i64) -> Result<DispatchReport, WebhookError> {
+        let mut report = DispatchReport::default();
+        
+        for delivery in self.queue.due(now, DISPATCH_BATCH)? {
+            let attempts = delivery.attempts + 1;
+            
+            let result = match self.subscriptions.iter().find(|subscription| subscription.id == delivery.subscription_id) {
+                Some(subscription) => {
+                    let body = delivery.payload.as_bytes();
+                    let headers = [
+                        (SIGNATURE_HEADER, sign(&subscription.secret, now / 1_000, body)),
+                        (EVENT_HEADER, delivery.event_type.clone()),
+                        (DELIVERY_HEADER, delivery.id.clone()),
+                    ];
+                    self.transport.post(&subscription.url, &headers, body)
+                }
+                // Abonnement retiré de la configuration depuis la mise en file
+                None => {
+                    self.queue.dead_letter(&delivery.id, attempts, "subscription removed", now)?;
+                    report.dead += 1;
+                    continue;
+                }
+            };
+            
+            match result {
+                Ok(()) => {
+                    self.queue.delivered(&delivery.id)?;
+                    report.delivered += 1;
+                }
+                Err(error) if attempts >= self.policy.max_attempts => {
+                    self.queue.dead_letter(&delivery.id, attempts, &error, now)?;
+                    report.dead += 1;
+                }
+                Err(error) => {
+                    let next_attempt_at = now.saturating_add(self.policy.delay(attempts));
+                    self.queue.reschedule(&delivery.id, attempts, next_attempt_at, &error)?;
+                    report.retried += 1;
+                }
+            }
+        }
+        
+        Ok(report)
+    }
+}
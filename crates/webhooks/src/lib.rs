@@ -0,0 +1,35 @@
+//! Envoi de webhooks pour les événements du programme `solana_nft_marketplace`.
+//!
+//! Les événements décodés sont convertis en notifications, filtrées par abonnement (vendeur,
+//! mint, collection, type d'événement), puis envoyées en JSON signé (HMAC-SHA256). Les envois
+//! échoués sont repris avec un délai exponentiel et abandonnés dans une table de dead letters
+//! après le nombre maximal de tentatives; la file est conservée dans SQLite ou Postgres.
+
+pub mod dispatcher;
+pub mod notification;
+pub mod queue;
+pub mod resolver;
+pub mod signature;
+pub mod subscription;
+pub mod transport;
+
+use solana_nft_marketplace_indexer::IndexerError;
+
+pub use dispatcher::{DispatchReport, Dispatcher, RetryPolicy};
+pub use notification::{notification, ListingInfo, Notification, Resolver};
+pub use queue::{Delivery, DeliveryQueue};
+pub use subscription::{load_subscriptions, Subscription};
+pub use transport::{HttpTransport, Transport};
+
+// Erreurs du service
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error(transparent)]
+    Database(#[from] IndexerError),
+    #[error("lecture des abonnements impossible: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("abonnements invalides: {0}")]
+    InvalidSubscriptions(#[from] serde_json::Error),
+    #[error("abonnement {0} défini plusieurs fois")]
+    DuplicateSubscription(String),
+}
@@ -0,0 +1,238 @@
+//! Service d'envoi des webhooks: suit les logs du programme et envoie les notifications aux abonnés.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_nft_marketplace::ID;
+use solana_nft_marketplace_events::{parse_logs, DecodedEvent, EventSource};
+use solana_nft_marketplace_sdk::cluster_url;
+use solana_nft_marketplace_webhooks::resolver::RpcResolver;
+use solana_nft_marketplace_webhooks::{
+    load_subscriptions, notification, DeliveryQueue, Dispatcher, HttpTransport, RetryPolicy,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+
+// Attente maximale d'un nouvel événement avant de traiter les reprises
+const TICK: Duration = Duration::from_millis(250);
+// Délai avant une nouvelle souscription après une déconnexion
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Parser)]
+#[command(name = "marketplace-webhooks", version, about = "Webhooks signés pour les événements de la marketplace NFT")]
+struct Cli {
+    /// File des envois: chemin ou URL SQLite (sqlite://...), ou URL Postgres (postgres://...)
+    #[arg(short = 'd', long = "database", default_value = "sqlite://marketplace-webhooks.db", env = "MARKETPLACE_WEBHOOKS_DATABASE_URL")]
+    database: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Suivre les événements du programme et envoyer les webhooks
+    Run {
+        /// URL du cluster ou raccourci (localhost, devnet, testnet, mainnet-beta)
+        #[arg(short = 'u', long = "url", default_value = "localhost", env = "MARKETPLACE_RPC_URL")]
+        url: String,
+        /// URL websocket du cluster (déduite de --url par défaut)
+        #[arg(long)]
+        ws_url: Option<String>,
+        /// Fichier JSON des abonnements
+        #[arg(short = 's', long, env = "MARKETPLACE_WEBHOOKS_SUBSCRIPTIONS")]
+        subscriptions: PathBuf,
+        /// Nombre maximal de tentatives avant abandon (dead letter)
+        #[arg(long, default_value_t = 8)]
+        max_attempts: u32,
+        /// Délai avant la première reprise, en millisecondes (doublé à chaque échec)
+        #[arg(long, default_value_t = 1_000)]
+        backoff_ms: i64,
+        /// Délai maximal entre deux reprises, en millisecondes
+        #[arg(long, default_value_t = 300_000)]
+        max_backoff_ms: i64,
+        /// Délai maximal d'une requête HTTP, en millisecondes
+        #[arg(long, default_value_t = 10_000)]
+        timeout_ms: u64,
+    },
+    /// Lister les envois en attente
+    Pending,
+    /// Lister les envois abandonnés
+    DeadLetters,
+    /// Remettre un envoi abandonné en file
+    Replay {
+        /// Identifiant de l'envoi
+        id: String,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut queue = DeliveryQueue::open(&cli.database).with_context(|| format!("cannot open database {}", cli.database))?;
+    
+    match cli.command {
+        Command::Run {
+            url,
+            ws_url,
+            subscriptions,
+            max_attempts,
+            backoff_ms,
+            max_backoff_ms,
+            timeout_ms,
+        } => {
+            let subscriptions = load_subscriptions(&subscriptions)
+                .with_context(|| format!("cannot load subscriptions from {}", subscriptions.display()))?;
+            println!("Loaded {} subscriptions", subscriptions.len());
+            
+            let policy = RetryPolicy {
+                max_attempts,
+                base_delay_ms: backoff_ms,
+                max_delay_ms: max_backoff_ms,
+            };
+            let transport = HttpTransport::new(Duration::from_millis(timeout_ms))?;
+            let mut dispatcher = Dispatcher::new(subscriptions, queue, transport, policy);
+            
+            let url = cluster_url(&url);
+            let ws_url = ws_url.unwrap_or_else(|| websocket_url(&url));
+            let resolver = RpcResolver::new(RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()));
+            run(&mut dispatcher, &resolver, &ws_url)
+        }
+        Command::Pending => {
+            for delivery in queue.pending()? {
+                println!(
+                    "{}  {}  attempts={}  next={}  {}",
+                    delivery.id,
+                    delivery.event_type,
+                    delivery.attempts,
+                    delivery.next_attempt_at,
+                    delivery.last_error.unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+        Command::DeadLetters => {
+            for delivery in queue.dead_letters()? {
+                println!(
+                    "{}  {}  attempts={}  failed_at={}  {}",
+                    delivery.id,
+                    delivery.event_type,
+                    delivery.attempts,
+                    delivery.next_attempt_at,
+                    delivery.last_error.unwrap_or_default()
+                );
+            }
+            Ok(())
+        }
+        Command::Replay { id } => {
+            if queue.replay(&id, now_ms())? {
+                println!("Delivery {id} queued again");
+            } else {
+                println!("No dead letter {id}");
+            }
+            Ok(())
+        }
+    }
+}
+
+// Suivre les logs des transactions confirmées qui mentionnent le programme
+fn run(dispatcher: &mut Dispatcher<HttpTransport>, resolver: &RpcResolver, ws_url: &str) -> Result<()> {
+    loop {
+        let (_subscription, receiver) = match PubsubClient::logs_subscribe(
+            ws_url,
+            RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        ) {
+            Ok(subscription) => subscription,
+            Err(error) => {
+                eprintln!("Subscription to {ws_url} failed: {error}");
+                thread::sleep(RESUBSCRIBE_DELAY);
+                continue;
+            }
+        };
+        println!("Subscribed to program logs on {ws_url}");
+        
+        loop {
+            match receiver.recv_timeout(TICK) {
+                Ok(response) => {
+                    // Les événements d'une transaction échouée ne sont pas retenus: son état a été annulé
+                    let Ok(signature) = Signature::from_str(&response.value.signature) else {
+                        continue;
+                    };
+                    if response.value.err.is_some() {
+                        continue;
+                    }
+                    
+                    let parsed = parse_logs(&response.value.logs);
+                    for (event_index, log_event) in parsed.events.into_iter().enumerate() {
+                        let decoded = DecodedEvent {
+                            slot: response.context.slot,
+                            block_time: None,
+                            signature,
+                            instruction_index: log_event.instruction_index,
+                            source: EventSource::Log,
+                            event: log_event.event,
+                        };
+                        if let Some(notification) = notification(&decoded, event_index, resolver) {
+                            dispatcher.enqueue(&notification, now_ms())?;
+                        }
+                    }
+                }
+                Err(error) if error.is_timeout() => {}
+                Err(_) => {
+                    eprintln!("Subscription closed, reconnecting");
+                    break;
+                }
+            }
+            
+            let report = dispatcher.process_due(now_ms())?;
+            if report != Default::default() {
+                println!(
+                    "Delivered {}, retrying {}, dead-lettered {}",
+                    report.delivered, report.retried, report.dead
+                );
+            }
+        }
+        
+        thread::sleep(RESUBSCRIBE_DELAY);
+    }
+}
+
+// Date courante en millisecondes Unix
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+// URL websocket d'un RPC, comme la CLI Solana: même hôte, schéma ws(s) et port + 1 s'il est explicite
+fn websocket_url(http_url: &str) -> String {
+    let (scheme, rest) = match http_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", http_url),
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) => match port.parse::<u16>() {
+            Ok(port) => format!("{name}:{}", port.saturating_add(1)),
+            Err(_) => host.to_string(),
+        },
+        None => host.to_string(),
+    };
+    
+    if path.is_empty() {
+        format!("{scheme}://{host}")
+    } else {
+        format!("{scheme}://{host}/{path}")
+    }
+}
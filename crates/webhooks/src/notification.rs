@@ -0,0 +1,173 @@
+//! Conversion des événements décodés en notifications.
+
+use anchor_lang::prelude::Pubkey;
+use serde_json::{json, Value};
+use solana_nft_marketplace_events::{DecodedEvent, MarketplaceEvent};
+
+/// Listing tel que lu on-chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListingInfo {
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub collection: Option<Pubkey>,
+}
+
+/// Informations complémentaires nécessaires au filtrage (absentes de certains événements).
+pub trait Resolver {
+    // Listing (None s'il a été fermé)
+    fn listing(&self, address: &Pubkey) -> Option<ListingInfo>;
+    
+    // Détenteur actuel d'un NFT
+    fn mint_owner(&self, mint: &Pubkey) -> Option<Pubkey>;
+}
+
+/// Événement à notifier, avec les critères de filtrage des abonnements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub id: String,                 // Identifiant stable: signature et position de l'événement
+    pub event_type: &'static str,   // Nom de l'événement du programme
+    pub signature: String,
+    pub slot: u64,
+    pub sellers: Vec<Pubkey>,       // Wallets vendeurs concernés
+    pub mints: Vec<Pubkey>,         // NFTs concernés
+    pub collection: Option<Pubkey>, // Collection concernée, si connue
+    pub data: Value,                // Contenu de l'événement envoyé dans le webhook
+}
+
+// Construire la notification d'un événement (None pour les événements d'administration)
+pub fn notification(decoded: &DecodedEvent, event_index: usize, resolver: &impl Resolver) -> Option<Notification> {
+    let mut sellers = Vec::new();
+    let mut mints = Vec::new();
+    let mut collection = None;
+    
+    let data = match &decoded.event {
+        MarketplaceEvent::NFTListed(event) => {
+            sellers.push(event.seller);
+            mints.push(event.nft_mint);
+            collection = resolver.listing(&event.listing).and_then(|listing| listing.collection);
+            json!({
+                "listing": event.listing.to_string(),
+                "seller": event.seller.to_string(),
+                "nft_mint": event.nft_mint.to_string(),
+                "price": event.price,
+                "quantity": event.quantity,
+            })
+        }
+        MarketplaceEvent::NFTListingUpdated(event) => {
+            sellers.push(event.seller);
+            mints.push(event.nft_mint);
+            collection = resolver.listing(&event.listing).and_then(|listing| listing.collection);
+            json!({
+                "listing": event.listing.to_string(),
+                "seller": event.seller.to_string(),
+                "nft_mint": event.nft_mint.to_string(),
+                "price": event.price,
+                "quantity": event.quantity,
+            })
+        }
+        MarketplaceEvent::NFTSold(event) => {
+            // L'événement ne contient ni le vendeur ni le mint: ils sont lus dans le listing
+            let listing = resolver.listing(&event.listing);
+            if let Some(listing) = &listing {
+                sellers.push(listing.seller);
+                mints.push(listing.nft_mint);
+                collection = listing.collection;
+            }
+            json!({
+                "listing": event.listing.to_string(),
+                "seller": listing.as_ref().map(|listing| listing.seller.to_string()),
+                "nft_mint": listing.as_ref().map(|listing| listing.nft_mint.to_string()),
+                "buyer": event.buyer.to_string(),
+                "price": event.price,
+                "quantity": event.quantity,
+                "marketplace_fee": event.marketplace_fee,
+                "referrer": event.referrer.map(|referrer| referrer.to_string()),
+                "referral_fee": event.referral_fee,
+            })
+        }
+        MarketplaceEvent::NFTListingCanceled(event) => {
+            sellers.push(event.seller);
+            mints.push(event.nft_mint);
+            json!({
+                "listing": event.listing.to_string(),
+                "seller": event.seller.to_string(),
+                "nft_mint": event.nft_mint.to_string(),
+            })
+        }
+        MarketplaceEvent::ListingForceDelisted(event) => {
+            sellers.push(event.seller);
+            mints.push(event.nft_mint);
+            json!({
+                "listing": event.listing.to_string(),
+                "seller": event.seller.to_string(),
+                "nft_mint": event.nft_mint.to_string(),
+                "moderator": event.moderator.to_string(),
+                "reason_code": event.reason_code,
+                "delegated": event.delegated,
+            })
+        }
+        MarketplaceEvent::BundleSold(event) => {
+            sellers.push(event.seller);
+            mints.extend(&event.nft_mints);
+            json!({
+                "bundle": event.bundle.to_string(),
+                "seller": event.seller.to_string(),
+                "buyer": event.buyer.to_string(),
+                "nft_mints": pubkeys(&event.nft_mints),
+                "price": event.price,
+            })
+        }
+        MarketplaceEvent::SwapCreated(event) => {
+            // Une offre concerne les détenteurs des NFTs demandés
+            sellers.extend(event.requested_mints.iter().filter_map(|mint| resolver.mint_owner(mint)));
+            mints.extend(&event.requested_mints);
+            mints.extend(&event.offered_mints);
+            collection = event.requested_collection;
+            json!({
+                "swap": event.swap.to_string(),
+                "maker": event.maker.to_string(),
+                "offered_mints": pubkeys(&event.offered_mints),
+                "offered_lamports": event.offered_lamports,
+                "requested_mints": pubkeys(&event.requested_mints),
+                "requested_collection": event.requested_collection.map(|collection| collection.to_string()),
+                "requested_count": event.requested_count,
+            })
+        }
+        MarketplaceEvent::SwapAccepted(event) => {
+            sellers.extend([event.maker, event.taker]);
+            mints.extend(&event.offered_mints);
+            mints.extend(&event.received_mints);
+            json!({
+                "swap": event.swap.to_string(),
+                "maker": event.maker.to_string(),
+                "taker": event.taker.to_string(),
+                "offered_mints": pubkeys(&event.offered_mints),
+                "offered_lamports": event.offered_lamports,
+                "received_mints": pubkeys(&event.received_mints),
+            })
+        }
+        MarketplaceEvent::SwapCanceled(event) => {
+            sellers.push(event.maker);
+            json!({
+                "swap": event.swap.to_string(),
+                "maker": event.maker.to_string(),
+            })
+        }
+        _ => return None,
+    };
+    
+    Some(Notification {
+        id: format!("{}:{}", decoded.signature, event_index),
+        event_type: decoded.event.name(),
+        signature: decoded.signature.to_string(),
+        slot: decoded.slot,
+        sellers,
+        mints,
+        collection,
+        data,
+    })
+}
+
+fn pubkeys(keys: &[Pubkey]) -> Vec<String> {
+    keys.iter().map(Pubkey::to_string).collect()
+}
@@ -0,0 +1,181 @@
+//! File des envois en attente et envois abandonnés (dead letters).
+
+use solana_nft_marketplace_indexer::{Database, Row};
+
+use crate::WebhookError;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts BIGINT NOT NULL,
+    next_attempt_at BIGINT NOT NULL,
+    last_error TEXT,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS webhook_deliveries_due ON webhook_deliveries (next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS webhook_dead_letters (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts BIGINT NOT NULL,
+    last_error TEXT,
+    created_at BIGINT NOT NULL,
+    failed_at BIGINT NOT NULL
+);
+";
+
+const DELIVERY_COLUMNS: &str = "id, subscription_id, event_type, payload, attempts, next_attempt_at, last_error, created_at";
+
+/// Envoi d'une notification à un abonnement. Les dates sont en millisecondes Unix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    pub id: String,
+    pub subscription_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+}
+
+impl Delivery {
+    fn from_row(row: &Row) -> Self {
+        Delivery {
+            id: row.text(0),
+            subscription_id: row.text(1),
+            event_type: row.text(2),
+            payload: row.text(3),
+            attempts: row.int(4) as u32,
+            next_attempt_at: row.int(5),
+            last_error: row.opt_text(6),
+            created_at: row.int(7),
+        }
+    }
+}
+
+/// Base des envois (SQLite ou Postgres, comme l'indexeur).
+pub struct DeliveryQueue {
+    db: Database,
+}
+
+impl DeliveryQueue {
+    pub fn open(url: &str) -> Result<Self, WebhookError> {
+        let mut db = Database::connect(url)?;
+        db.batch(SCHEMA)?;
+        Ok(DeliveryQueue { db })
+    }
+    
+    // Ajouter un envoi (ignoré s'il est déjà en file)
+    pub fn enqueue(&mut self, delivery: &Delivery) -> Result<bool, WebhookError> {
+        let inserted = self.db.execute(
+            &format!(
+                "INSERT INTO webhook_deliveries ({DELIVERY_COLUMNS})
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (id) DO NOTHING"
+            ),
+            &[
+                delivery.id.as_str().into(),
+                delivery.subscription_id.as_str().into(),
+                delivery.event_type.as_str().into(),
+                delivery.payload.as_str().into(),
+                i64::from(delivery.attempts).into(),
+                delivery.next_attempt_at.into(),
+                delivery.last_error.clone().into(),
+                delivery.created_at.into(),
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+    
+    // Envois à tenter à la date `now`, du plus ancien au plus récent
+    pub fn due(&mut self, now: i64, limit: u32) -> Result<Vec<Delivery>, WebhookError> {
+        let rows = self.db.query(
+            &format!(
+                "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries
+                 WHERE next_attempt_at <= ?1
+                 ORDER BY next_attempt_at, created_at, id
+                 LIMIT ?2"
+            ),
+            &[now.into(), i64::from(limit).into()],
+        )?;
+        Ok(rows.iter().map(Delivery::from_row).collect())
+    }
+    
+    // Tous les envois en attente
+    pub fn pending(&mut self) -> Result<Vec<Delivery>, WebhookError> {
+        let rows = self.db.query(
+            &format!("SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries ORDER BY created_at, id"),
+            &[],
+        )?;
+        Ok(rows.iter().map(Delivery::from_row).collect())
+    }
+    
+    // Retirer un envoi réussi
+    pub fn delivered(&mut self, id: &str) -> Result<(), WebhookError> {
+        self.db.execute("DELETE FROM webhook_deliveries WHERE id = ?1", &[id.into()])?;
+        Ok(())
+    }
+    
+    // Reprogrammer un envoi échoué
+    pub fn reschedule(&mut self, id: &str, attempts: u32, next_attempt_at: i64, error: &str) -> Result<(), WebhookError> {
+        self.db.execute(
+            "UPDATE webhook_deliveries SET attempts = ?1, next_attempt_at = ?2, last_error = ?3 WHERE id = ?4",
+            &[i64::from(attempts).into(), next_attempt_at.into(), error.into(), id.into()],
+        )?;
+        Ok(())
+    }
+    
+    // Abandonner un envoi: il est déplacé dans les dead letters
+    pub fn dead_letter(&mut self, id: &str, attempts: u32, error: &str, now: i64) -> Result<(), WebhookError> {
+        let moved = self.db.transaction(|db| {
+            db.execute(
+                "INSERT INTO webhook_dead_letters
+                    (id, subscription_id, event_type, payload, attempts, last_error, created_at, failed_at)
+                 SELECT id, subscription_id, event_type, payload, ?1, ?2, created_at, ?3
+                 FROM webhook_deliveries WHERE id = ?4
+                 ON CONFLICT (id) DO UPDATE SET
+                    attempts = excluded.attempts,
+                    last_error = excluded.last_error,
+                    failed_at = excluded.failed_at",
+                &[i64::from(attempts).into(), error.into(), now.into(), id.into()],
+            )?;
+            db.execute("DELETE FROM webhook_deliveries WHERE id = ?1", &[id.into()])?;
+            Ok(())
+        });
+        Ok(moved?)
+    }
+    
+    // Envois abandonnés, du plus récent au plus ancien
+    pub fn dead_letters(&mut self) -> Result<Vec<Delivery>, WebhookError> {
+        let rows = self.db.query(
+            "SELECT id, subscription_id, event_type, payload, attempts, failed_at, last_error, created_at
+             FROM webhook_dead_letters ORDER BY failed_at DESC, id",
+            &[],
+        )?;
+        Ok(rows.iter().map(Delivery::from_row).collect())
+    }
+    
+    // Remettre un envoi abandonné en file pour un nouvel essai immédiat
+    pub fn replay(&mut self, id: &str, now: i64) -> Result<bool, WebhookError> {
+        let replayed = self.db.transaction(|db| {
+            let inserted = db.execute(
+                &format!(
+                    "INSERT INTO webhook_deliveries ({DELIVERY_COLUMNS})
+                     SELECT id, subscription_id, event_type, payload, 0, ?1, last_error, created_at
+                     FROM webhook_dead_letters WHERE id = ?2
+                     ON CONFLICT (id) DO NOTHING"
+                ),
+                &[now.into(), id.into()],
+            )?;
+            db.execute("DELETE FROM webhook_dead_letters WHERE id = ?1", &[id.into()])?;
+            Ok(inserted > 0)
+        });
+        Ok(replayed?)
+    }
+}
@@ -0,0 +1,49 @@
+//! Lecture on-chain des informations absentes des événements.
+
+use anchor_lang::prelude::Pubkey;
+use solana_client::rpc_client::RpcClient;
+use solana_nft_marketplace::NFTListing;
+use solana_nft_marketplace_sdk::accounts::fetch_optional;
+
+use crate::notification::{ListingInfo, Resolver};
+
+// Position du propriétaire dans un compte de jetons SPL (après le mint)
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+
+/// Résolution par RPC.
+pub struct RpcResolver {
+    rpc: RpcClient,
+}
+
+impl RpcResolver {
+    pub fn new(rpc: RpcClient) -> Self {
+        RpcResolver { rpc }
+    }
+}
+
+impl Resolver for RpcResolver {
+    fn listing(&self, address: &Pubkey) -> Option<ListingInfo> {
+        let listing = fetch_optional::<NFTListing>(&self.rpc, address).ok().flatten()?;
+        Some(ListingInfo {
+            seller: listing.seller,
+            nft_mint: listing.nft_mint,
+            collection: listing.collection,
+        })
+    }
+    
+    fn mint_owner(&self, mint: &Pubkey) -> Option<Pubkey> {
+        // Le détenteur d'un NFT est le propriétaire du compte de jetons qui en détient une unité
+        let holder = self
+            .rpc
+            .get_token_largest_accounts(mint)
+            .ok()?
+            .into_iter()
+            .find(|balance| balance.amount.amount != "0")?;
+        
+        let account = self.rpc.get_account(&holder.address.parse().ok()?).ok()?;
+        let owner = account
+            .data
+            .get(TOKEN_ACCOUNT_OWNER_OFFSET..TOKEN_ACCOUNT_OWNER_OFFSET + 32)?;
+        Pubkey::try_from(owner).ok()
+    }
+}
@@ -0,0 +1,48 @@
+//! Signature des envois (HMAC-SHA256).
+//!
+//! L'en-tête `X-Marketplace-Signature` vaut `t=<horodatage>,v1=<hmac>`, où `hmac` est le
+//! HMAC-SHA256 hexadécimal de `<horodatage>.<corps>` avec le secret de l'abonnement.
+//! Le destinataire recalcule la signature et rejette les horodatages trop anciens.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const SIGNATURE_HEADER: &str = "X-Marketplace-Signature";
+pub const EVENT_HEADER: &str = "X-Marketplace-Event";
+pub const DELIVERY_HEADER: &str = "X-Marketplace-Delivery";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> HmacSha256 {
+    // HMAC accepte des clés de toute longueur
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("hmac key");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+// Valeur de l'en-tête de signature d'un envoi (horodatage en secondes Unix)
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let signature = hex::encode(mac(secret, timestamp, body).finalize().into_bytes());
+    format!("t={timestamp},v1={signature}")
+}
+
+// Vérifier l'en-tête de signature reçu (comparaison en temps constant)
+// `tolerance` est l'écart maximal en secondes entre l'horodatage signé et `now`
+pub fn verify(secret: &str, header: &str, body: &[u8], now: i64, tolerance: i64) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return false;
+    };
+    (now - timestamp).abs() <= tolerance && mac(secret, timestamp, body).verify_slice(&signature).is_ok()
+}
@@ -0,0 +1,62 @@
+//! Abonnements aux notifications.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use serde::Deserialize;
+
+use crate::notification::Notification;
+use crate::WebhookError;
+
+/// Abonnement: les notifications qui correspondent à tous les filtres renseignés sont envoyées à `url`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    pub secret: String,                    // Clé de signature HMAC-SHA256 des envois
+    #[serde(default, with = "optional_pubkey")]
+    pub seller: Option<Pubkey>,            // Wallet vendeur concerné (vente, listing, offre reçue)
+    #[serde(default, with = "optional_pubkey")]
+    pub mint: Option<Pubkey>,              // NFT concerné
+    #[serde(default, with = "optional_pubkey")]
+    pub collection: Option<Pubkey>,        // Collection vérifiée concernée
+    #[serde(default)]
+    pub events: Vec<String>,               // Types d'événements (noms du programme), vide = tous
+}
+
+impl Subscription {
+    // Indiquer si une notification correspond aux filtres de l'abonnement
+    pub fn matches(&self, notification: &Notification) -> bool {
+        (self.events.is_empty() || self.events.iter().any(|event| event == notification.event_type))
+            && self.seller.is_none_or(|seller| notification.sellers.contains(&seller))
+            && self.mint.is_none_or(|mint| notification.mints.contains(&mint))
+            && self.collection.is_none_or(|collection| notification.collection == Some(collection))
+    }
+}
+
+// Charger les abonnements depuis un fichier JSON (tableau d'abonnements)
+pub fn load_subscriptions(path: &Path) -> Result<Vec<Subscription>, WebhookError> {
+    let contents = std::fs::read_to_string(path)?;
+    let subscriptions: Vec<Subscription> = serde_json::from_str(&contents)?;
+    
+    for (index, subscription) in subscriptions.iter().enumerate() {
+        if subscriptions[..index].iter().any(|other| other.id == subscription.id) {
+            return Err(WebhookError::DuplicateSubscription(subscription.id.clone()));
+        }
+    }
+    Ok(subscriptions)
+}
+
+// Adresses optionnelles écrites en base58 dans le fichier d'abonnements
+mod optional_pubkey {
+    use std::str::FromStr;
+    
+    use anchor_lang::prelude::Pubkey;
+    use serde::{de::Error, Deserialize, Deserializer};
+    
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pubkey>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| Pubkey::from_str(&value).map_err(D::Error::custom))
+            .transpose()
+    }
+}
@@ -0,0 +1,44 @@
+//! Envoi HTTP des webhooks.
+
+use std::time::Duration;
+
+/// Envoi d'une requête POST. Une erreur décrit l'échec (réseau ou statut HTTP non 2xx).
+pub trait Transport {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &[u8]) -> Result<(), String>;
+}
+
+/// Envoi par HTTP(S).
+pub struct HttpTransport {
+    client: reqwest::blocking::Client,
+}
+
+impl HttpTransport {
+    pub fn new(timeout: Duration) -> Result<Self, reqwest::Error> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("marketplace-webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(HttpTransport { client })
+    }
+}
+
+impl Transport for HttpTransport {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &[u8]) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        
+        let response = request.send().map_err(|error| error.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {status}"))
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Envois vers un serveur HTTP local qui enregistre les requêtes reçues.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use solana_nft_marketplace::NFTSold;
+use solana_nft_marketplace_events::{DecodedEvent, EventSource, MarketplaceEvent};
+use solana_nft_marketplace_webhooks::signature::{verify, SIGNATURE_HEADER};
+use solana_nft_marketplace_webhooks::{
+    notification, DeliveryQueue, Dispatcher, HttpTransport, ListingInfo, Resolver, RetryPolicy, Subscription,
+};
+use solana_sdk::signature::Signature;
+
+// Requête reçue par le serveur local
+struct Received {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Serveur HTTP local: répond avec les statuts programmés (200 ensuite) et garde les requêtes
+struct StandIn {
+    url: String,
+    statuses: Arc<Mutex<VecDeque<u16>>>,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl StandIn {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let statuses = Arc::new(Mutex::new(VecDeque::new()));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        
+        let (responses, requests) = (Arc::clone(&statuses), Arc::clone(&received));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((name, value)) => headers.push((name.to_string(), value.to_string())),
+                        None => break,
+                    }
+                }
+                let length = headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, value)| value.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.lock().unwrap().push(Received { headers, body });
+                
+                let status = responses.lock().unwrap().pop_front().unwrap_or(200);
+                write!(stream, "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            }
+        });
+        
+        StandIn { url, statuses, received }
+    }
+    
+    fn fail_next(&self, count: usize) {
+        self.statuses.lock().unwrap().extend(std::iter::repeat_n(500, count));
+    }
+}
+
+// Listing connu du résolveur de test
+struct FixedResolver(ListingInfo);
+
+impl Resolver for FixedResolver {
+    fn listing(&self, _address: &Pubkey) -> Option<ListingInfo> {
+        Some(self.0.clone())
+    }
+    
+    fn mint_owner(&self, _mint: &Pubkey) -> Option<Pubkey> {
+        None
+    }
+}
+
+fn subscription(id: &str, url: &str, seller: Option<Pubkey>, mint: Option<Pubkey>) -> Subscription {
+    Subscription {
+        id: id.to_string(),
+        url: url.to_string(),
+        secret: format!("secret-{id}"),
+        seller,
+        mint,
+        collection: None,
+        events: vec!["NFTSold".to_string()],
+    }
+}
+
+fn sale(resolver: &FixedResolver) -> solana_nft_marketplace_webhooks::Notification {
+    let decoded = DecodedEvent {
+        slot: 42,
+        block_time: None,
+        signature: Signature::from([7; 64]),
+        instruction_index: 0,
+        source: EventSource::Log,
+        event: MarketplaceEvent::NFTSold(NFTSold {
+            listing: Pubkey::new_unique(),
+            buyer: Pubkey::new_unique(),
+            price: 1_000,
+            quantity: 1,
+            marketplace_fee: 25,
+            referrer: None,
+            referral_fee: 0,
+        }),
+    };
+    notification(&decoded, 0, resolver).unwrap()
+}
+
+fn dispatcher(subscriptions: Vec<Subscription>, policy: RetryPolicy) -> Dispatcher<HttpTransport> {
+    let queue = DeliveryQueue::open("sqlite::memory:").unwrap();
+    let transport = HttpTransport::new(Duration::from_secs(5)).unwrap();
+    Dispatcher::new(subscriptions, queue, transport, policy)
+}
+
+#[test]
+fn delivers_signed_payload_to_matching_subscriptions() {
+    let server = StandIn::start();
+    let resolver = FixedResolver(ListingInfo {
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        collection: None,
+    });
+    let mut dispatcher = dispatcher(
+        vec![
+            subscription("seller", &server.url, Some(resolver.0.seller), None),
+            subscription("other-mint", &server.url, None, Some(Pubkey::new_unique())),
+        ],
+        RetryPolicy::default(),
+    );
+    
+    let now = 1_700_000_000_000;
+    assert_eq!(dispatcher.enqueue(&sale(&resolver), now).unwrap(), 1);
+    let report = dispatcher.process_due(now).unwrap();
+    assert_eq!(report.delivered, 1);
+    assert!(dispatcher.queue().pending().unwrap().is_empty());
+    
+    let received = server.received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    let request = &received[0];
+    let header = request.header(SIGNATURE_HEADER).unwrap();
+    assert!(verify("secret-seller", header, &request.body, now / 1_000, 300));
+    assert!(!verify("secret-other-mint", header, &request.body, now / 1_000, 300));
+    
+    let payload: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(payload["type"], "NFTSold");
+    assert_eq!(payload["subscription"], "seller");
+    assert_eq!(payload["data"]["seller"], resolver.0.seller.to_string());
+    assert_eq!(payload["data"]["price"], 1_000);
+}
+
+#[test]
+fn retries_with_backoff_then_dead_letters() {
+    let server = StandIn::start();
+    server.fail_next(3);
+    let resolver = FixedResolver(ListingInfo {
+        seller: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        collection: None,
+    });
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        base_delay_ms: 1_000,
+        max_delay_ms: 60_000,
+    };
+    let mut dispatcher = dispatcher(vec![subscription("seller", &server.url, None, None)], policy);
+    
+    let now = 1_700_000_000_000;
+    dispatcher.enqueue(&sale(&resolver), now).unwrap();
+    
+    // Premier échec: reprise après 1 s, puis 2 s
+    assert_eq!(dispatcher.process_due(now).unwrap().retried, 1);
+    assert_eq!(dispatcher.process_due(now + 999).unwrap(), Default::default());
+    assert_eq!(dispatcher.process_due(now + 1_000).unwrap().retried, 1);
+    assert_eq!(dispatcher.process_due(now + 2_999).unwrap(), Default::default());
+    
+    // Troisième échec: abandon
+    assert_eq!(dispatcher.process_due(now + 3_000).unwrap().dead, 1);
+    assert!(dispatcher.queue().pending().unwrap().is_empty());
+    let dead = dispatcher.queue().dead_letters().unwrap();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].attempts, 3);
+    assert_eq!(dead[0].last_error.as_deref(), Some("HTTP 500 Internal Server Error"));
+    
+    // Rejouer l'envoi abandonné: le serveur répond désormais 200
+    assert!(dispatcher.queue().replay(&dead[0].id, now + 10_000).unwrap());
+    assert_eq!(dispatcher.process_due(now + 10_000).unwrap().delivered, 1);
+    assert!(dispatcher.queue().dead_letters().unwrap().is_empty());
+    assert_eq!(server.received.lock().unwrap().len(), 4);
+}
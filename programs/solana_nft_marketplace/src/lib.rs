@@ -1,9 +1,54 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use anchor_spl::token::{self, Token, TokenAccount};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::metadata::{mpl_token_metadata, MetadataAccount};
+use anchor_lang::solana_program::program_option::COption;
 
 declare_id!("4hVp7QQKuowuf1SgPVXcD5YkTrHHiDRPbn4V9HKvYwrT");
 
+// Logs de diagnostic du programme, compilés seulement avec la feature `program-logs`.
+// Sans elle, les arguments ne sont ni formatés ni évalués à l'exécution: seuls les événements
+// (emit!) sont émis, ce qui économise les unités de calcul des builds de production.
+macro_rules! program_log {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "program-logs") {
+            msg!($($arg)*);
+        }
+    };
+}
+
+// Nombre maximum de NFTs dans un bundle
+pub const MAX_BUNDLE_ITEMS: usize = 5;
+
+// Nombre de comptes par élément dans un sweep (buy_many)
+pub const SWEEP_ACCOUNTS_PER_ITEM: usize = 10;
+
+// Nombre maximum de NFTs de chaque côté d'un échange
+pub const MAX_SWAP_ITEMS: usize = 4;
+
+// Nombre maximum de destinataires dans la répartition des recettes d'un listing
+pub const MAX_PAYOUT_SPLITS: usize = 5;
+
+// Nombre maximum de paliers dans la grille de frais
+pub const MAX_FEE_TIERS: usize = 5;
+
+// Nombre maximum de modérateurs de la marketplace
+pub const MAX_MODERATORS: usize = 5;
+
+// Version actuelle du format des comptes Marketplace et NFTListing
+pub const MARKETPLACE_VERSION: u8 = 1;
+pub const LISTING_VERSION: u8 = 1;
+
+// Délai minimum (2 jours) entre la mise en file d'un changement d'administration et son exécution
+pub const CHANGE_TIMELOCK_DELAY: i64 = 2 * 24 * 60 * 60;
+
+// Durée d'une fenêtre de volume des vendeurs (30 jours); le volume glissant couvre deux fenêtres
+pub const FEE_VOLUME_WINDOW: i64 = 30 * 24 * 60 * 60;
+
+// Nombre de prix plancher (asks les plus bas) conservés par collection
+pub const MAX_FLOOR_ASKS: usize = 10;
+
 // Module principal du programme
 #[program]
 pub mod solana_nft_marketplace {
@@ -14,17 +59,24 @@ pub mod solana_nft_marketplace {
         ctx: Context<InitializeMarketplace>,
         marketplace_fee: u16,  // Frais en points de base (1% = 100)
     ) -> Result<()> {
-        msg!("Initializing marketplace with fee: {}", marketplace_fee);
+        program_log!("Initializing marketplace with fee: {}", marketplace_fee);
+        
+        validate_fee_bps(marketplace_fee)?;
         
         // Accès au compte de marketplace via ctx.accounts
         let marketplace = &mut ctx.accounts.marketplace;
         
         // Initialisation des données du compte
+        marketplace.version = MARKETPLACE_VERSION;
         marketplace.authority = ctx.accounts.authority.key();
+        marketplace.treasury = ctx.accounts.authority.key();
         marketplace.fee = marketplace_fee;
         marketplace.bump = ctx.bumps.marketplace;
+        marketplace.referral_fee_bps = 0;
+        marketplace.curated = false;
+        marketplace.moderators = Vec::new();
         
-        msg!("Marketplace initialized with authority: {}, fee: {}, bump: {}", 
+        program_log!("Marketplace initialized with authority: {}, fee: {}, bump: {}", 
              marketplace.authority, marketplace.fee, marketplace.bump);
         
         // Émission d'un événement (équivalent à emit en Solidity)
@@ -40,11 +92,13 @@ pub mod solana_nft_marketplace {
     // Mettre un NFT en vente (équivalent à list/createListing en Solidity)
     pub fn list_nft(
         ctx: Context<ListNFT>,
-        price: u64,
+        price: u64,     // Prix unitaire en lamports
+        quantity: u64,  // Nombre d'unités mises en vente (1 pour un NFT, plus pour une édition / SFT)
+        payout_splits: Vec<PayoutSplit>,  // Répartition des recettes (vide = tout au vendeur)
     ) -> Result<()> {
-        msg!("Listing NFT for price: {} lamports", price);
-        msg!("NFT Mint: {}", ctx.accounts.nft_mint.key());
-        msg!("Seller: {}", ctx.accounts.seller.key());
+        program_log!("Listing {} unit(s) for price: {} lamports each", quantity, price);
+        program_log!("NFT Mint: {}", ctx.accounts.nft_mint.key());
+        program_log!("Seller: {}", ctx.accounts.seller.key());
         
         // Accès aux comptes via ctx.accounts
         let listing = &mut ctx.accounts.listing;
@@ -54,28 +108,79 @@ pub mod solana_nft_marketplace {
             &mut &ctx.accounts.nft_token_account.data.borrow()[..]
         )?;
         
-        msg!("NFT Token Account amount: {}", nft_token_account.amount);
+        program_log!("NFT Token Account amount: {}", nft_token_account.amount);
+        
+        // Vérification que le vendeur détient bien la quantité mise en vente
+        if quantity == 0 || nft_token_account.amount < quantity {
+            program_log!("Error: invalid quantity {}", quantity);
+            return err!(ErrorCode::InvalidQuantity);
+        }
+        
+        // Vérifier la répartition des recettes demandée par le vendeur
+        validate_payout_splits(&payout_splits)?;
+        
+        // Lire la collection vérifiée du NFT: ses métadonnées sont toujours lues pour que le vendeur
+        // ne puisse pas écarter les frais spécifiques de la collection
+        let collection = load_optional_collection(&ctx.accounts.nft_metadata, ctx.accounts.nft_mint.key())?;
+        
+        // En mode curé, seules les collections vérifiées et activées peuvent être listées
+        if ctx.accounts.marketplace.curated {
+            let allowed = match (&ctx.accounts.collection_config, collection) {
+                (Some(config), Some(collection)) => config.collection_mint == collection && config.enabled,
+                _ => false,
+            };
+            if !allowed {
+                program_log!("Error: NFT collection is not enabled on this marketplace");
+                return err!(ErrorCode::CollectionNotAllowed);
+            }
+        }
         
-        // Vérification que le compte token contient bien un NFT (amount = 1)
-        if nft_token_account.amount != 1 {
-            msg!("Error: NFT amount must be 1");
-            return err!(ErrorCode::InvalidNFTAmount);
+        // Créer l'enregistrement du vendeur lors de son premier listing
+        if ctx.accounts.seller_record.data_is_empty() {
+            let seller_key = ctx.accounts.seller.key();
+            let seeds: &[&[u8]] = &[b"seller_record", seller_key.as_ref(), &[ctx.bumps.seller_record]];
+            create_pda_account(
+                &ctx.accounts.seller.to_account_info(),
+                &ctx.accounts.seller_record.to_account_info(),
+                &ctx.accounts.system_program,
+                8 + SellerRecord::INIT_SPACE,
+                seeds,
+            )?;
+            
+            let record = SellerRecord {
+                seller: seller_key,
+                first_listed_at: Clock::get()?.unix_timestamp,
+                bump: ctx.bumps.seller_record,
+            };
+            record.try_serialize(&mut &mut ctx.accounts.seller_record.try_borrow_mut_data()?[..])?;
+            
+            update_marketplace_stats(&ctx.accounts.stats, |stats| {
+                stats.unique_sellers = stats.unique_sellers.saturating_add(1);
+            })?;
+            program_log!("New seller registered: {}", seller_key);
         }
+        update_marketplace_stats(&ctx.accounts.stats, |stats| {
+            stats.active_listings = stats.active_listings.saturating_add(1);
+        })?;
         
         // Initialisation des données du listing
+        listing.version = LISTING_VERSION;
         listing.seller = ctx.accounts.seller.key();
         listing.nft_mint = ctx.accounts.nft_mint.key();
         listing.price = price;
         listing.active = true;
         listing.bump = ctx.bumps.listing;
+        listing.quantity = quantity;
+        listing.payout_splits = payout_splits;
+        listing.collection = collection;
         
-        msg!("Listing created: Seller={}, NFT Mint={}, Price={}, Bump={}", 
-             listing.seller, listing.nft_mint, listing.price, listing.bump);
+        program_log!("Listing created: Seller={}, NFT Mint={}, Price={}, Quantity={}, Bump={}", 
+             listing.seller, listing.nft_mint, listing.price, listing.quantity, listing.bump);
         
-        msg!("Listing PDA address: {}", listing.key());
+        program_log!("Listing PDA address: {}", listing.key());
         
         // Approuver le PDA comme délégué pour le NFT
-        msg!("Approving PDA as delegate...");
+        program_log!("Approving PDA as delegate...");
         let cpi_accounts = token::Approve {
             to: ctx.accounts.nft_token_account.to_account_info(),
             delegate: listing.to_account_info(),
@@ -85,8 +190,14 @@ pub mod solana_nft_marketplace {
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         
-        token::approve(cpi_ctx, 1)?;
-        msg!("PDA delegate approval successful!");
+        token::approve(cpi_ctx, listing.quantity)?;
+        program_log!("PDA delegate approval successful!");
+        
+        // Ajouter l'annonce aux prix plancher de sa collection
+        let listing_key = listing.key();
+        update_collection_stats(ctx.accounts.collection_stats.as_deref(), listing.collection, |stats| {
+            insert_floor_ask(stats, listing_key, listing);
+        })?;
         
         // Émission d'un événement
         emit!(NFTListed {
@@ -94,6 +205,7 @@ pub mod solana_nft_marketplace {
             seller: listing.seller,
             nft_mint: listing.nft_mint,
             price: listing.price,
+            quantity: listing.quantity,
         });
         
         Ok(())
@@ -102,9 +214,10 @@ pub mod solana_nft_marketplace {
     // MISE À JOUR: Fonction de mise à jour d'un listing avec renouvellement de la délégation
     pub fn update_listing(
         ctx: Context<UpdateListing>,
-        price: u64,
+        price: u64,     // Nouveau prix unitaire en lamports
+        quantity: u64,  // Nouvelle quantité mise en vente
     ) -> Result<()> {
-        msg!("Updating listing price to: {} lamports", price);
+        program_log!("Updating listing to {} unit(s) at {} lamports each", quantity, price);
         let listing = &mut ctx.accounts.listing;
         
         // Désérialiser le compte TokenAccount
@@ -114,18 +227,18 @@ pub mod solana_nft_marketplace {
         
         // Vérifier que le vendeur est bien le propriétaire du listing
         if listing.seller != ctx.accounts.seller.key() {
-            msg!("Error: Seller mismatch");
+            program_log!("Error: Seller mismatch");
             return err!(ErrorCode::UnauthorizedAccess);
         }
         
-        // Vérifier que le vendeur possède encore le NFT
-        if nft_token_account.amount != 1 {
-            msg!("Error: NFT amount must be 1");
-            return err!(ErrorCode::InvalidNFTAmount);
+        // Vérifier que le vendeur possède encore la quantité mise en vente
+        if quantity == 0 || nft_token_account.amount < quantity {
+            program_log!("Error: invalid quantity {}", quantity);
+            return err!(ErrorCode::InvalidQuantity);
         }
         
         // CORRECTION: Ajouter la réapprobation du délégué pour éviter l'erreur de délégation
-        msg!("Renewing PDA delegate approval...");
+        program_log!("Renewing PDA delegate approval...");
         let cpi_accounts = token::Approve {
             to: ctx.accounts.nft_token_account.to_account_info(),
             delegate: listing.to_account_info(),
@@ -135,14 +248,32 @@ pub mod solana_nft_marketplace {
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         
-        token::approve(cpi_ctx, 1)?;
-        msg!("PDA delegate approval renewed successfully!");
+        token::approve(cpi_ctx, quantity)?;
+        program_log!("PDA delegate approval renewed successfully!");
+        
+        // Un listing réactivé redevient une annonce active
+        let was_active = listing.active;
+        if !was_active {
+            update_marketplace_stats(&ctx.accounts.stats, |stats| {
+                stats.active_listings = stats.active_listings.saturating_add(1);
+            })?;
+        }
         
-        // Mise à jour du prix et activation du listing
+        // Mise à jour du prix, de la quantité et activation du listing
         listing.price = price;
+        listing.quantity = quantity;
         listing.active = true;
         
-        msg!("Listing updated: Price={}, Active={}", listing.price, listing.active);
+        // Un ask existant est retiré puis réinséré à son nouveau prix (une hausse peut le faire sortir des prix plancher)
+        let listing_key = listing.key();
+        update_collection_stats(ctx.accounts.collection_stats.as_deref(), listing.collection, |stats| {
+            if was_active {
+                remove_floor_ask(stats, listing_key, listing);
+            }
+            insert_floor_ask(stats, listing_key, listing);
+        })?;
+        
+        program_log!("Listing updated: Price={}, Quantity={}, Active={}", listing.price, listing.quantity, listing.active);
         
         // Émission d'un événement
         emit!(NFTListingUpdated {
@@ -150,74 +281,155 @@ pub mod solana_nft_marketplace {
             seller: listing.seller,
             nft_mint: listing.nft_mint,
             price: listing.price,
+            quantity: listing.quantity,
         });
         
         Ok(())
     }
 
     // Acheter un NFT (équivalent à buy/purchaseListing en Solidity)
-    pub fn buy_nft(ctx: Context<BuyNFT>) -> Result<()> {
-        msg!("Buy NFT instruction started");
+    // remaining_accounts: destinataires de la répartition des recettes, dans l'ordre du listing
+    pub fn buy_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyNFT<'info>>,
+        quantity: u64,  // Nombre d'unités achetées (achat partiel possible)
+    ) -> Result<()> {
+        program_log!("Buy NFT instruction started");
         
         // Vérifier que la liste est active
         if !ctx.accounts.listing.active {
-            msg!("Error: Listing is not active");
+            program_log!("Error: Listing is not active");
             return err!(ErrorCode::ListingNotActive);
         }
         
+        // Vérifier que la quantité demandée est disponible
+        if quantity == 0 || quantity > ctx.accounts.listing.quantity {
+            program_log!("Error: invalid quantity {}, {} remaining", quantity, ctx.accounts.listing.quantity);
+            return err!(ErrorCode::InvalidQuantity);
+        }
+        
         // Récupérer les informations nécessaires avant d'emprunter de façon mutable
-        let price = ctx.accounts.listing.price;
+        let price = ctx.accounts.listing.price
+            .checked_mul(quantity)
+            .ok_or(ErrorCode::MathOverflow)?;
         let nft_mint = ctx.accounts.listing.nft_mint;
         let seller_key = ctx.accounts.listing.seller;
         let listing_bump = ctx.accounts.listing.bump;
-        let marketplace_fee = ctx.accounts.marketplace.fee;
+        let base_fee = ctx.accounts.marketplace.fee;
+        let referral_fee_bps = ctx.accounts.marketplace.referral_fee_bps;
         
-        msg!("Buy NFT data: Price={}, NFT Mint={}", price, nft_mint);
-        msg!("Seller: {}, Buyer: {}", seller_key, ctx.accounts.buyer.key());
+        program_log!("Buy NFT data: Price={}, Quantity={}, NFT Mint={}", price, quantity, nft_mint);
+        program_log!("Seller: {}, Buyer: {}", seller_key, ctx.accounts.buyer.key());
         
-        // Calculer les frais de la marketplace
-        let fee_amount = (price as u128)
-            .checked_mul(marketplace_fee as u128)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap() as u64;
+        // Le compte du parrain et son compte de gains doivent être fournis ensemble
+        let referrer_key = match (&ctx.accounts.referrer, &ctx.accounts.referrer_earnings) {
+            (Some(referrer), Some(earnings)) => {
+                if earnings.referrer != referrer.key() {
+                    program_log!("Error: referrer earnings account mismatch");
+                    return err!(ErrorCode::InvalidReferrer);
+                }
+                // Ni l'acheteur ni le vendeur ne peuvent toucher la part de parrainage
+                if referrer.key() == ctx.accounts.buyer.key() || referrer.key() == seller_key {
+                    program_log!("Error: referrer cannot be the buyer or the seller");
+                    return err!(ErrorCode::SelfReferral);
+                }
+                Some(referrer.key())
+            }
+            (None, None) => None,
+            _ => {
+                program_log!("Error: referrer and referrer earnings accounts must be provided together");
+                return err!(ErrorCode::InvalidReferrer);
+            }
+        };
         
-        let seller_amount = price.checked_sub(fee_amount).unwrap();
+        // Déterminer le taux de frais effectif: grille de frais, remise membre et frais de la collection
+        let now = Clock::get()?.unix_timestamp;
+        let fee_schedule = load_fee_schedule(&ctx.accounts.fee_schedule)?;
+        let collection_config = load_collection_config(
+            ctx.accounts.collection_config.as_deref(),
+            ctx.accounts.listing.collection,
+        )?;
+        let SaleFee {
+            fee_bps: marketplace_fee,
+            fee_tier,
+            holder_discount,
+            trailing_volume,
+        } = sale_fee(
+            base_fee,
+            fee_schedule.as_ref(),
+            &ctx.accounts.seller_volume,
+            ctx.accounts.seller_membership_token.as_ref(),
+            collection_config.as_ref(),
+            now,
+        )?;
         
-        msg!("Fee amount: {}, Seller amount: {}", fee_amount, seller_amount);
+        program_log!("Effective fee: {} bps (tier {:?}, holder discount: {})", marketplace_fee, fee_tier, holder_discount);
         
-        // Transférer SOL au vendeur (équivalent à transfer en Solidity)
-        // Différence: Solana utilise CPI (Cross-Program Invocation)
-        msg!("Transferring SOL to seller...");
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.seller_wallet.to_account_info(),
-            },
-        );
+        // Calculer les frais de la marketplace et la part du parrain
+        let referral_fee_bps = referrer_key.map(|_| referral_fee_bps);
+        let FeeBreakdown {
+            fee_amount,
+            referral_amount,
+            treasury_amount: authority_fee_amount,
+            seller_amount,
+        } = calculate_fee_breakdown(price, marketplace_fee, referral_fee_bps)?;
         
-        anchor_lang::system_program::transfer(cpi_context, seller_amount)?;
-        msg!("SOL transfer to seller successful");
+        program_log!("Fee amount: {}, Referral amount: {}, Seller amount: {}", fee_amount, referral_amount, seller_amount);
+        
+        // Transférer SOL au vendeur ou aux destinataires de la répartition
+        // Différence: Solana utilise CPI (Cross-Program Invocation)
+        program_log!("Transferring SOL to seller...");
+        pay_seller_proceeds(
+            &ctx.accounts.system_program,
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.seller_wallet,
+            &ctx.accounts.listing.payout_splits,
+            ctx.remaining_accounts,
+            seller_amount,
+        )?;
+        program_log!("SOL transfer to seller successful");
         
         // Transférer les frais à l'autorité de la marketplace
-        if fee_amount > 0 {
-            msg!("Transferring fees to marketplace authority...");
+        if authority_fee_amount > 0 {
+            program_log!("Transferring fees to marketplace treasury...");
             let fee_cpi_context = CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
                 anchor_lang::system_program::Transfer {
                     from: ctx.accounts.buyer.to_account_info(),
-                    to: ctx.accounts.marketplace_authority.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
                 },
             );
             
-            anchor_lang::system_program::transfer(fee_cpi_context, fee_amount)?;
-            msg!("Fee transfer successful");
+            anchor_lang::system_program::transfer(fee_cpi_context, authority_fee_amount)?;
+            program_log!("Fee transfer successful");
+        }
+        
+        // Verser la part des frais revenant au parrain et mettre à jour ses gains
+        if let (Some(referrer), Some(earnings)) = (&ctx.accounts.referrer, &mut ctx.accounts.referrer_earnings) {
+            if referral_amount > 0 {
+                program_log!("Transferring referral fee to referrer...");
+                let referral_cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: referrer.to_account_info(),
+                    },
+                );
+                
+                anchor_lang::system_program::transfer(referral_cpi_context, referral_amount)?;
+                program_log!("Referral fee transfer successful");
+            }
+            
+            earnings.total_earned = earnings.total_earned
+                .checked_add(referral_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            earnings.referred_sales = earnings.referred_sales
+                .checked_add(1)
+                .ok_or(ErrorCode::MathOverflow)?;
         }
         
         // Transférer le NFT du vendeur à l'acheteur en utilisant la délégation
         // Créer le signer PDA pour l'autorité de transfert
-        msg!("Preparing NFT transfer...");
+        program_log!("Preparing NFT transfer...");
         let seeds = &[
             b"listing",
             nft_mint.as_ref(),
@@ -226,9 +438,9 @@ pub mod solana_nft_marketplace {
         ];
         let signer = &[&seeds[..]];
         
-        msg!("NFT transfer: using PDA as authority with bump: {}", listing_bump);
-        msg!("Seller token account: {}", ctx.accounts.seller_token_account.key());
-        msg!("Buyer token account: {}", ctx.accounts.buyer_token_account.key());
+        program_log!("NFT transfer: using PDA as authority with bump: {}", listing_bump);
+        program_log!("Seller token account: {}", ctx.accounts.seller_token_account.key());
+        program_log!("Buyer token account: {}", ctx.accounts.buyer_token_account.key());
         
         let transfer_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
@@ -241,41 +453,86 @@ pub mod solana_nft_marketplace {
         );
         
         // Transfert avec signature du PDA qui a la délégation
-        msg!("Executing NFT transfer...");
-        token::transfer(transfer_ctx, 1)?;
-        msg!("NFT transfer successful!");
+        program_log!("Executing NFT transfer...");
+        token::transfer(transfer_ctx, quantity)?;
+        program_log!("NFT transfer successful!");
         
         // Maintenant, emprunter de façon mutable pour mettre à jour l'état
         let listing = &mut ctx.accounts.listing;
-        // Désactiver la liste
-        listing.active = false;
+        // Décrémenter la quantité restante et désactiver la liste si tout est vendu
+        listing.quantity -= quantity;
+        listing.active = listing.quantity > 0;
+        
+        program_log!("Listing quantity remaining: {}, Active={}", listing.quantity, listing.active);
+        
+        // Mettre à jour les statistiques globales
+        let active = listing.active;
+        update_marketplace_stats(&ctx.accounts.stats, |stats| {
+            record_sale(stats, quantity, price, authority_fee_amount);
+            if !active {
+                stats.active_listings = stats.active_listings.saturating_sub(1);
+            }
+        })?;
         
-        msg!("Listing marked as inactive");
+        // Mettre à jour les profils de trading de l'acheteur et du vendeur
+        update_trader_profile(&ctx.accounts.buyer_profile, |profile| {
+            record_profile_purchase(profile, quantity, price, now);
+        })?;
+        update_trader_profile(&ctx.accounts.seller_profile, |profile| {
+            record_profile_sale(profile, quantity, price, now);
+        })?;
+        
+        // Mettre à jour les statistiques de la collection
+        let (listing_key, unit_price, active) = (listing.key(), listing.price, listing.active);
+        update_collection_stats(ctx.accounts.collection_stats.as_deref(), listing.collection, |stats| {
+            record_collection_sale(stats, quantity, price, unit_price);
+            if !active {
+                remove_floor_ask(stats, listing_key, listing);
+            }
+        })?;
+        
+        // Ajouter la vente au volume glissant du vendeur
+        record_seller_volume(&ctx.accounts.seller_volume, price, now)?;
+        
+        if fee_schedule.is_some() {
+            emit!(FeeTierApplied {
+                listing: listing.key(),
+                seller: seller_key,
+                trailing_volume,
+                fee_tier,
+                holder_discount,
+                fee_bps: marketplace_fee,
+            });
+        }
         
         // Émission d'un événement
         emit!(NFTSold {
             listing: listing.key(),
             buyer: ctx.accounts.buyer.key(),
             price,
+            quantity,
+            marketplace_fee: authority_fee_amount,
+            referrer: referrer_key,
+            referral_fee: referral_amount,
         });
         
-        msg!("Buy NFT instruction completed successfully");
+        program_log!("Buy NFT instruction completed successfully");
         Ok(())
     }
 
     // Annuler une mise en vente (équivalent à cancel/cancelListing en Solidity)
     pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
-        msg!("Cancel listing instruction started");
+        program_log!("Cancel listing instruction started");
         let listing = &mut ctx.accounts.listing;
         
         // Vérifier que la personne qui annule est bien le vendeur
         if listing.seller != ctx.accounts.seller.key() {
-            msg!("Error: Unauthorized access - not the seller");
+            program_log!("Error: Unauthorized access - not the seller");
             return err!(ErrorCode::UnauthorizedAccess);
         }
         
         // Révoquer la délégation
-        msg!("Revoking token delegation...");
+        program_log!("Revoking token delegation...");
         let cpi_accounts = token::Revoke {
             source: ctx.accounts.nft_token_account.to_account_info(),
             authority: ctx.accounts.seller.to_account_info(),
@@ -285,11 +542,29 @@ pub mod solana_nft_marketplace {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         
         token::revoke(cpi_ctx)?;
-        msg!("Token delegation revoked successfully");
+        program_log!("Token delegation revoked successfully");
+        
+        // Un listing déjà inactif (vendu ou annulé) n'a plus qu'une délégation à révoquer
+        if !listing.active {
+            program_log!("Listing already inactive");
+            return Ok(());
+        }
         
         // Désactiver la liste
+        update_marketplace_stats(&ctx.accounts.stats, |stats| {
+            stats.active_listings = stats.active_listings.saturating_sub(1);
+        })?;
         listing.active = false;
-        msg!("Listing marked as inactive");
+        program_log!("Listing marked as inactive");
+        
+        let listing_key = listing.key();
+        update_collection_stats(ctx.accounts.collection_stats.as_deref(), listing.collection, |stats| {
+            remove_floor_ask(stats, listing_key, listing);
+        })?;
+        
+        update_trader_profile(&ctx.accounts.seller_profile, |profile| {
+            profile.cancelled_listings = profile.cancelled_listings.saturating_add(1);
+        })?;
         
         // Émission d'un événement
         emit!(NFTListingCanceled {
@@ -298,236 +573,3833 @@ pub mod solana_nft_marketplace {
             nft_mint: listing.nft_mint,
         });
         
-        msg!("Cancel listing instruction completed successfully");
+        program_log!("Cancel listing instruction completed successfully");
         Ok(())
     }
-}
-
-// Structure de compte pour la marketplace 
-// (équivalent à un struct en Solidity, mais stocké dans un compte séparé)
-#[account]
-pub struct Marketplace {
-    pub authority: Pubkey,    // Propriétaire/admin de la marketplace
-    pub fee: u16,             // Frais de commission en points de base (100 = 1%)
-    pub bump: u8,             // Utilisé pour la création de PDA
-}
 
-// Structure de compte pour un listing NFT
-#[account]
-pub struct NFTListing {
-    pub seller: Pubkey,       // Adresse du vendeur
-    pub nft_mint: Pubkey,     // Addresse du mint du NFT
-    pub price: u64,           // Prix en lamports (1 SOL = 10^9 lamports)
-    pub active: bool,         // État de la mise en vente
-    pub bump: u8,             // Utilisé pour la création de PDA
-}
+    // Mettre en vente un lot (bundle) de NFTs pour un prix unique
+    // remaining_accounts: triplets (nft_mint, nft_token_account, mint_blocklist) pour chaque NFT du lot,
+    // suivis de (nft_metadata, collection_config) en mode curé
+    pub fn list_bundle<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListBundle<'info>>,
+        bundle_id: u64,
+        price: u64,
+    ) -> Result<()> {
+        program_log!("Listing bundle {} for price: {} lamports", bundle_id, price);
+        
+        let remaining = ctx.remaining_accounts;
+        let curated = ctx.accounts.marketplace.curated;
+        let accounts_per_item = if curated { 5 } else { 3 };
+        
+        // Vérifier le nombre de comptes fournis (3 par NFT, 5 en mode curé)
+        if remaining.is_empty() || !remaining.chunks_exact(accounts_per_item).remainder().is_empty() {
+            program_log!("Error: remaining accounts must be grouped by {} per NFT", accounts_per_item);
+            return err!(ErrorCode::InvalidBundleAccounts);
+        }
+        
+        let item_count = remaining.len() / accounts_per_item;
+        if item_count > MAX_BUNDLE_ITEMS {
+            program_log!("Error: bundle cannot contain more than {} NFTs", MAX_BUNDLE_ITEMS);
+            return err!(ErrorCode::BundleTooLarge);
+        }
+        
+        let seller_key = ctx.accounts.seller.key();
+        let bundle_info = ctx.accounts.bundle.to_account_info();
+        let mut nft_mints: Vec<Pubkey> = Vec::with_capacity(item_count);
+        
+        for item in remaining.chunks(accounts_per_item) {
+            let nft_mint = &item[0];
+            let nft_token_account_info = &item[1];
+            
+            // Le mint doit appartenir au token program
+            if *nft_mint.owner != token::ID {
+                program_log!("Error: invalid mint account {}", nft_mint.key());
+                return err!(ErrorCode::InvalidBundleAccounts);
+            }
+            
+            // Un même NFT ne peut pas apparaître deux fois dans le lot
+            if nft_mints.contains(&nft_mint.key()) {
+                program_log!("Error: duplicate NFT mint {}", nft_mint.key());
+                return err!(ErrorCode::DuplicateBundleItem);
+            }
+            
+            // Mêmes règles que list_nft: mint non bloqué et collection activée en mode curé
+            require_not_blocked(&item[2], &nft_mint.key())?;
+            if curated {
+                require_collection_enabled(&item[3], &item[4], nft_mint.key())?;
+            }
+            
+            let nft_token_account = load_token_account(nft_token_account_info)?;
+            if nft_token_account.mint != nft_mint.key() || nft_token_account.owner != seller_key {
+                program_log!("Error: token account mismatch for mint {}", nft_mint.key());
+                return err!(ErrorCode::InvalidBundleAccounts);
+            }
+            
+            // Chaque élément du lot doit être un NFT (amount = 1)
+            if nft_token_account.amount != 1 {
+                program_log!("Error: NFT amount must be 1");
+                return err!(ErrorCode::InvalidNFTAmount);
+            }
+            
+            // Approuver le PDA du bundle comme délégué pour ce NFT
+            let cpi_accounts = token::Approve {
+                to: nft_token_account_info.clone(),
+                delegate: bundle_info.clone(),
+                authority: ctx.accounts.seller.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::approve(cpi_ctx, 1)?;
+            
+            nft_mints.push(nft_mint.key());
+        }
+        
+        program_log!("Bundle PDA delegate approval successful for {} NFTs", item_count);
+        
+        // Initialisation des données du bundle
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.seller = seller_key;
+        bundle.bundle_id = bundle_id;
+        bundle.nft_mints = nft_mints;
+        bundle.price = price;
+        bundle.active = true;
+        bundle.bump = ctx.bumps.bundle;
+        
+        program_log!("Bundle created: Seller={}, Items={}, Price={}, Bump={}", 
+             bundle.seller, bundle.nft_mints.len(), bundle.price, bundle.bump);
+        
+        // Émission d'un événement
+        emit!(BundleListed {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            nft_mints: bundle.nft_mints.clone(),
+            price: bundle.price,
+        });
+        
+        Ok(())
+    }
 
-// Structure pour l'instruction InitializeMarketplace
-// Définit tous les comptes nécessaires pour l'instruction
-// (équivalent aux paramètres d'une fonction Solidity)
-#[derive(Accounts)]
-pub struct InitializeMarketplace<'info> {
-    // Définit le compte marketplace comme un PDA (adresse dérivée du programme)
-    #[account(
-        init,                             // Créer un nouveau compte
-        payer = authority,                // Payé par l'autorité
-        space = 8 + 32 + 2 + 1,           // Taille: discriminator + Pubkey + u16 + u8
-        seeds = [b"marketplace"],         // Seeds pour générer le PDA
-        bump                              // Génère et stocke le bump
-    )]
-    pub marketplace: Account<'info, Marketplace>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,         // Doit signer la transaction
-    
-    // Programmes nécessaires à l'instruction
-    pub system_program: Program<'info, System>,
-}
+    // Mettre à jour le prix d'un bundle avec renouvellement des délégations
+    // remaining_accounts: nft_token_account pour chaque NFT, dans l'ordre du bundle
+    pub fn update_bundle<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateBundle<'info>>,
+        price: u64,
+    ) -> Result<()> {
+        program_log!("Updating bundle price to: {} lamports", price);
+        
+        let remaining = ctx.remaining_accounts;
+        let bundle_info = ctx.accounts.bundle.to_account_info();
+        let seller_key = ctx.accounts.seller.key();
+        
+        if remaining.len() != ctx.accounts.bundle.nft_mints.len() {
+            program_log!("Error: expected {} token accounts", ctx.accounts.bundle.nft_mints.len());
+            return err!(ErrorCode::InvalidBundleAccounts);
+        }
+        
+        for (nft_mint, nft_token_account_info) in ctx.accounts.bundle.nft_mints.iter().zip(remaining.iter()) {
+            let nft_token_account = load_token_account(nft_token_account_info)?;
+            if nft_token_account.mint != *nft_mint || nft_token_account.owner != seller_key {
+                program_log!("Error: token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidBundleAccounts);
+            }
+            
+            // Vérifier que le vendeur possède encore le NFT
+            if nft_token_account.amount != 1 {
+                program_log!("Error: NFT amount must be 1");
+                return err!(ErrorCode::InvalidNFTAmount);
+            }
+            
+            // Renouveler la délégation au PDA du bundle
+            let cpi_accounts = token::Approve {
+                to: nft_token_account_info.clone(),
+                delegate: bundle_info.clone(),
+                authority: ctx.accounts.seller.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::approve(cpi_ctx, 1)?;
+        }
+        
+        program_log!("Bundle PDA delegate approval renewed successfully!");
+        
+        // Mise à jour du prix et activation du bundle
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.price = price;
+        bundle.active = true;
+        
+        program_log!("Bundle updated: Price={}, Active={}", bundle.price, bundle.active);
+        
+        // Émission d'un événement
+        emit!(BundleUpdated {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            price: bundle.price,
+        });
+        
+        Ok(())
+    }
 
-// Structure pour l'instruction ListNFT
-#[derive(Accounts)]
-pub struct ListNFT<'info> {
-    // Nouveau compte de listing comme PDA
-    #[account(
-        init,
-        payer = seller,
-        space = 8 + 32 + 32 + 8 + 1 + 1,  // Taille pour NFTListing
-        seeds = [
-            b"listing", 
-            nft_mint.key().as_ref(), 
-            seller.key().as_ref()
-        ],
-        bump
-    )]
-    pub listing: Account<'info, NFTListing>,
-    
-    // Compte marketplace existant
-    #[account(
-        seeds = [b"marketplace"],
-        bump = marketplace.bump
-    )]
-    pub marketplace: Account<'info, Marketplace>,
-    
-    // Vendeur qui doit signer
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    
-    // Mint du NFT
-    /// CHECK: Vérifié par le token program
-    #[account(owner = token::ID)]
-    pub nft_mint: AccountInfo<'info>,
-    
-    // Compte de token du vendeur contenant le NFT
-    /// CHECK: Vérifié avec les contraintes et dans la logique
-    #[account(
-        mut, // Ajout de mut car nous avons besoin de modifier le compte lors de l'approbation
-        owner = token::ID,
-        constraint = token::TokenAccount::try_deserialize(&mut &nft_token_account.data.borrow()[..])?.mint == nft_mint.key(),
-        constraint = token::TokenAccount::try_deserialize(&mut &nft_token_account.data.borrow()[..])?.owner == seller.key()
-    )]
-    pub nft_token_account: AccountInfo<'info>,
-    
-    // Programmes nécessaires
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+    // Acheter tous les NFTs d'un bundle en une seule instruction
+    // remaining_accounts: triplets (seller_token_account, buyer_token_account, mint_blocklist) dans l'ordre du bundle
+    pub fn buy_bundle<'info>(ctx: Context<'_, '_, '_, 'info, BuyBundle<'info>>) -> Result<()> {
+        program_log!("Buy bundle instruction started");
+        
+        // Récupérer les informations nécessaires avant d'emprunter de façon mutable
+        let price = ctx.accounts.bundle.price;
+        let seller_key = ctx.accounts.bundle.seller;
+        let bundle_key = ctx.accounts.bundle.key();
+        let bundle_id = ctx.accounts.bundle.bundle_id;
+        let bundle_bump = ctx.accounts.bundle.bump;
+        let nft_mints = ctx.accounts.bundle.nft_mints.clone();
+        let buyer_key = ctx.accounts.buyer.key();
+        let marketplace_fee = ctx.accounts.marketplace.fee;
+        
+        let remaining = ctx.remaining_accounts;
+        if remaining.len() != nft_mints.len() * 3 {
+            program_log!("Error: expected {} remaining accounts", nft_mints.len() * 3);
+            return err!(ErrorCode::InvalidBundleAccounts);
+        }
+        
+        // Vérifier que chaque NFT est encore livrable avant tout transfert
+        for (nft_mint, item) in nft_mints.iter().zip(remaining.chunks(3)) {
+            require_not_blocked(&item[2], nft_mint)?;
+            
+            let seller_token_account = load_token_account(&item[0])?;
+            if seller_token_account.mint != *nft_mint
+                || seller_token_account.owner != seller_key
+                || seller_token_account.amount != 1
+                || seller_token_account.delegate != COption::Some(bundle_key)
+                || seller_token_account.delegated_amount < 1
+            {
+                program_log!("Error: bundle item {} is no longer deliverable", nft_mint);
+                return err!(ErrorCode::BundleItemUnavailable);
+            }
+            
+            let buyer_token_account = load_token_account(&item[1])?;
+            if buyer_token_account.mint != *nft_mint || buyer_token_account.owner != buyer_key {
+                program_log!("Error: buyer token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidBundleAccounts);
+            }
+        }
+        
+        // Taux de frais de la vente: un bundle n'a pas de collection, seules la grille de frais
+        // et la remise membre s'appliquent
+        let now = Clock::get()?.unix_timestamp;
+        let fee_schedule = load_fee_schedule(&ctx.accounts.fee_schedule)?;
+        let sale = sale_fee(
+            marketplace_fee,
+            fee_schedule.as_ref(),
+            &ctx.accounts.seller_volume,
+            ctx.accounts.seller_membership_token.as_ref(),
+            None,
+            now,
+        )?;
+        let (fee_amount, seller_amount) = calculate_marketplace_fee(price, sale.fee_bps)?;
+        
+        program_log!("Fee amount: {}, Seller amount: {}", fee_amount, seller_amount);
+        
+        // Transférer SOL au vendeur
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.seller_wallet.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_context, seller_amount)?;
+        
+        // Transférer les frais à l'autorité de la marketplace
+        if fee_amount > 0 {
+            let fee_cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(fee_cpi_context, fee_amount)?;
+        }
+        
+        // Transférer chaque NFT avec la signature du PDA du bundle
+        let bundle_id_bytes = bundle_id.to_le_bytes();
+        let seeds = &[
+            b"bundle",
+            seller_key.as_ref(),
+            bundle_id_bytes.as_ref(),
+            &[bundle_bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        for item in remaining.chunks(3) {
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: item[0].clone(),
+                    to: item[1].clone(),
+                    authority: ctx.accounts.bundle.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(transfer_ctx, 1)?;
+        }
+        
+        program_log!("Bundle NFT transfers successful!");
+        
+        // Mettre à jour les statistiques globales et les profils de trading
+        let quantity = nft_mints.len() as u64;
+        update_marketplace_stats(&ctx.accounts.stats, |stats| record_sale(stats, quantity, price, fee_amount))?;
+        update_trader_profile(&ctx.accounts.buyer_profile, |profile| {
+            record_profile_purchase(profile, quantity, price, now);
+        })?;
+        update_trader_profile(&ctx.accounts.seller_profile, |profile| {
+            record_profile_sale(profile, quantity, price, now);
+        })?;
+        record_seller_volume(&ctx.accounts.seller_volume, price, now)?;
+        
+        if fee_schedule.is_some() {
+            emit!(FeeTierApplied {
+                listing: bundle_key,
+                seller: seller_key,
+                trailing_volume: sale.trailing_volume,
+                fee_tier: sale.fee_tier,
+                holder_discount: sale.holder_discount,
+                fee_bps: sale.fee_bps,
+            });
+        }
+        
+        // Désactiver le bundle
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.active = false;
+        
+        // Émission d'un événement
+        emit!(BundleSold {
+            bundle: bundle.key(),
+            buyer: buyer_key,
+            seller: seller_key,
+            nft_mints,
+            price,
+        });
+        
+        program_log!("Buy bundle instruction completed successfully");
+        Ok(())
+    }
 
-// Structure pour l'instruction UpdateListing
-#[derive(Accounts)]
-pub struct UpdateListing<'info> {
-    // Compte de listing existant
-    #[account(
-        mut,
-        seeds = [
-            b"listing", 
-            listing.nft_mint.as_ref(), 
-            seller.key().as_ref()
-        ],
-        bump = listing.bump,
+    // Annuler la mise en vente d'un bundle
+    // remaining_accounts: nft_token_account pour chaque NFT, dans l'ordre du bundle
+    pub fn cancel_bundle<'info>(ctx: Context<'_, '_, '_, 'info, CancelBundle<'info>>) -> Result<()> {
+        program_log!("Cancel bundle instruction started");
+        
+        let remaining = ctx.remaining_accounts;
+        let seller_key = ctx.accounts.seller.key();
+        
+        if remaining.len() != ctx.accounts.bundle.nft_mints.len() {
+            program_log!("Error: expected {} token accounts", ctx.accounts.bundle.nft_mints.len());
+            return err!(ErrorCode::InvalidBundleAccounts);
+        }
+        
+        // Révoquer la délégation sur chaque NFT du lot
+        for (nft_mint, nft_token_account_info) in ctx.accounts.bundle.nft_mints.iter().zip(remaining.iter()) {
+            let nft_token_account = load_token_account(nft_token_account_info)?;
+            if nft_token_account.mint != *nft_mint || nft_token_account.owner != seller_key {
+                program_log!("Error: token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidBundleAccounts);
+            }
+            
+            let cpi_accounts = token::Revoke {
+                source: nft_token_account_info.clone(),
+                authority: ctx.accounts.seller.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::revoke(cpi_ctx)?;
+        }
+        
+        program_log!("Bundle token delegations revoked successfully");
+        
+        // Désactiver le bundle
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.active = false;
+        
+        // Émission d'un événement
+        emit!(BundleCanceled {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+        });
+        
+        program_log!("Cancel bundle instruction completed successfully");
+        Ok(())
+    }
+
+    // Acheter plusieurs NFTs listés en une seule instruction (sweep)
+    // Chaque listing est acheté pour toute sa quantité restante
+    // remaining_accounts: groupes (listing, seller_wallet, seller_token_account, buyer_token_account, mint_blocklist,
+    // collection_stats, seller_profile, seller_volume, collection_config, seller_membership_token), chacun suivi des
+    // destinataires de la répartition des recettes du listing s'il en a une (collection_stats et collection_config
+    // peuvent être n'importe quel compte si le listing n'a pas de collection, seller_membership_token si le vendeur
+    // n'a pas enregistré de NFT de membre)
+    pub fn buy_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyMany<'info>>,
+        max_total_price: u64,   // Dépense maximale totale en lamports
+        skip_unfillable: bool,  // Ignorer les listings non exécutables au lieu d'échouer
+    ) -> Result<()> {
+        program_log!("Buy many instruction started: max total {} lamports", max_total_price);
+        
+        let remaining = ctx.remaining_accounts;
+        if remaining.is_empty() {
+            program_log!("Error: no listing provided");
+            return err!(ErrorCode::InvalidSweepAccounts);
+        }
+        
+        let buyer_key = ctx.accounts.buyer.key();
+        let marketplace_fee = ctx.accounts.marketplace.fee;
+        let now = Clock::get()?.unix_timestamp;
+        let fee_schedule = load_fee_schedule(&ctx.accounts.fee_schedule)?;
+        let mut items_requested: u32 = 0;
+        let mut items_filled: u32 = 0;
+        let mut total_price: u64 = 0;
+        let mut total_fees: u64 = 0;
+        let mut cursor = 0;
+        
+        while cursor < remaining.len() {
+            if remaining.len() - cursor < SWEEP_ACCOUNTS_PER_ITEM {
+                program_log!("Error: remaining accounts must be groups of {}", SWEEP_ACCOUNTS_PER_ITEM);
+                return err!(ErrorCode::InvalidSweepAccounts);
+            }
+            
+            let listing_info = &remaining[cursor];
+            let seller_wallet = &remaining[cursor + 1];
+            let seller_token_account = &remaining[cursor + 2];
+            let buyer_token_account = &remaining[cursor + 3];
+            let mint_blocklist = &remaining[cursor + 4];
+            let collection_stats = &remaining[cursor + 5];
+            let seller_profile = &remaining[cursor + 6];
+            let seller_volume = &remaining[cursor + 7];
+            let collection_config = &remaining[cursor + 8];
+            let seller_membership_token = &remaining[cursor + 9];
+            
+            let mut listing = Account::<NFTListing>::try_from(listing_info)?;
+            
+            if seller_profile.key() != trader_profile_address(&listing.seller)
+                || seller_volume.key() != seller_volume_address(&listing.seller)
+            {
+                program_log!("Error: invalid seller accounts for listing {}", listing_info.key());
+                return err!(ErrorCode::InvalidSweepAccounts);
+            }
+            
+            // Les destinataires de la répartition suivent les comptes de l'élément
+            let item_end = cursor + SWEEP_ACCOUNTS_PER_ITEM + listing.payout_splits.len();
+            if item_end > remaining.len() {
+                program_log!("Error: missing payout recipients for listing {}", listing_info.key());
+                return err!(ErrorCode::InvalidSweepAccounts);
+            }
+            let payout_recipients = &remaining[cursor + SWEEP_ACCOUNTS_PER_ITEM..item_end];
+            cursor = item_end;
+            items_requested += 1;
+            
+            // Vérifier que l'élément peut être acheté avant tout transfert
+            let fillable = check_sweep_item(
+                &listing,
+                seller_wallet,
+                seller_token_account,
+                buyer_token_account,
+                mint_blocklist,
+                buyer_key,
+            ).and_then(|_| {
+                let item_price = listing.price.checked_mul(listing.quantity).ok_or(ErrorCode::MathOverflow)?;
+                let new_total = total_price.checked_add(item_price).ok_or(ErrorCode::MathOverflow)?;
+                if new_total > max_total_price {
+                    program_log!("Error: max total price exceeded");
+                    return err!(ErrorCode::MaxSpendExceeded);
+                }
+                Ok(())
+            });
+            
+            if let Err(error) = fillable {
+                if skip_unfillable {
+                    program_log!("Skipping unfillable listing {}", listing_info.key());
+                    continue;
+                }
+                return Err(error);
+            }
+            
+            let quantity = listing.quantity;
+            let price = listing.price.checked_mul(quantity).ok_or(ErrorCode::MathOverflow)?;
+            let collection_config = load_collection_config(Some(collection_config), listing.collection)?;
+            let sale = sale_fee(
+                marketplace_fee,
+                fee_schedule.as_ref(),
+                seller_volume,
+                Some(seller_membership_token),
+                collection_config.as_ref(),
+                now,
+            )?;
+            let (fee_amount, seller_amount) = calculate_marketplace_fee(price, sale.fee_bps)?;
+            
+            // Transférer SOL au vendeur ou aux destinataires de la répartition
+            pay_seller_proceeds(
+                &ctx.accounts.system_program,
+                &ctx.accounts.buyer.to_account_info(),
+                seller_wallet,
+                &listing.payout_splits,
+                payout_recipients,
+                seller_amount,
+            )?;
+            
+            // Transférer les frais à l'autorité de la marketplace
+            if fee_amount > 0 {
+                let fee_cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(fee_cpi_context, fee_amount)?;
+            }
+            
+            // Transférer le NFT avec la signature du PDA du listing
+            let seeds = &[
+                b"listing",
+                listing.nft_mint.as_ref(),
+                listing.seller.as_ref(),
+                &[listing.bump],
+            ];
+            let signer = &[&seeds[..]];
+            
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: seller_token_account.clone(),
+                    to: buyer_token_account.clone(),
+                    authority: listing_info.clone(),
+                },
+                signer,
+            );
+            token::transfer(transfer_ctx, quantity)?;
+            
+            // Désactiver le listing
+            listing.quantity = 0;
+            listing.active = false;
+            
+            update_marketplace_stats(&ctx.accounts.stats, |stats| {
+                record_sale(stats, quantity, price, fee_amount);
+                stats.active_listings = stats.active_listings.saturating_sub(1);
+            })?;
+            
+            let unit_price = listing.price;
+            update_collection_stats(Some(collection_stats), listing.collection, |stats| {
+                record_collection_sale(stats, quantity, price, unit_price);
+                remove_floor_ask(stats, listing_info.key(), &mut listing);
+            })?;
+            
+            // Persister l'état du listing avant l'élément suivant
+            listing.exit(&crate::ID)?;
+            
+            update_trader_profile(seller_profile, |profile| {
+                record_profile_sale(profile, quantity, price, now);
+            })?;
+            record_seller_volume(seller_volume, price, now)?;
+            update_trader_profile(&ctx.accounts.buyer_profile, |profile| {
+                record_profile_purchase(profile, quantity, price, now);
+            })?;
+            
+            if fee_schedule.is_some() {
+                emit!(FeeTierApplied {
+                    listing: listing_info.key(),
+                    seller: listing.seller,
+                    trailing_volume: sale.trailing_volume,
+                    fee_tier: sale.fee_tier,
+                    holder_discount: sale.holder_discount,
+                    fee_bps: sale.fee_bps,
+                });
+            }
+            
+            emit!(NFTSold {
+                listing: listing_info.key(),
+                buyer: buyer_key,
+                price,
+                quantity,
+                marketplace_fee: fee_amount,
+                referrer: None,
+                referral_fee: 0,
+            });
+            
+            items_filled += 1;
+            total_price = total_price.checked_add(price).ok_or(ErrorCode::MathOverflow)?;
+            total_fees = total_fees.checked_add(fee_amount).ok_or(ErrorCode::MathOverflow)?;
+        }
+        
+        program_log!("Sweep filled {}/{} listings for {} lamports", items_filled, items_requested, total_price);
+        
+        // Émission d'un événement récapitulatif
+        emit!(SweepCompleted {
+            buyer: buyer_key,
+            items_requested,
+            items_filled,
+            total_price,
+            total_fees,
+        });
+        
+        program_log!("Buy many instruction completed successfully");
+        Ok(())
+    }
+
+    // Créer une offre d'échange NFT contre NFT: le créateur dépose ses NFTs (et éventuellement des SOL)
+    // remaining_accounts: triplets (nft_mint, maker_token_account, escrow_token_account) pour chaque NFT offert
+    pub fn create_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateSwap<'info>>,
+        swap_id: u64,
+        offered_lamports: u64,              // SOL ajoutés à l'offre (0 si aucun)
+        requested_mints: Vec<Pubkey>,       // Mints précis demandés en échange
+        requested_collection: Option<Pubkey>, // Ou: n'importe quel NFT de cette collection vérifiée
+        requested_count: u8,                // Nombre de NFTs de la collection demandés
+    ) -> Result<()> {
+        program_log!("Creating swap offer {}", swap_id);
+        
+        let remaining = ctx.remaining_accounts;
+        if remaining.is_empty() || !remaining.chunks_exact(3).remainder().is_empty() {
+            program_log!("Error: remaining accounts must be (mint, maker token account, escrow token account) triplets");
+            return err!(ErrorCode::InvalidSwapAccounts);
+        }
+        
+        let item_count = remaining.len() / 3;
+        if item_count > MAX_SWAP_ITEMS || requested_mints.len() > MAX_SWAP_ITEMS {
+            program_log!("Error: a swap cannot contain more than {} NFTs per side", MAX_SWAP_ITEMS);
+            return err!(ErrorCode::SwapTooLarge);
+        }
+        
+        // La demande porte soit sur des mints précis, soit sur une collection
+        let valid_request = match requested_collection {
+            Some(_) => requested_mints.is_empty() && requested_count > 0 && (requested_count as usize) <= MAX_SWAP_ITEMS,
+            None => !requested_mints.is_empty() && requested_count == 0,
+        };
+        if !valid_request {
+            program_log!("Error: request must be either specific mints or a collection with a count");
+            return err!(ErrorCode::InvalidSwapRequest);
+        }
+        
+        // Un même mint ne peut pas être demandé deux fois
+        for (index, mint) in requested_mints.iter().enumerate() {
+            if requested_mints[..index].contains(mint) {
+                program_log!("Error: duplicate requested mint {}", mint);
+                return err!(ErrorCode::DuplicateSwapItem);
+            }
+        }
+        
+        let maker_key = ctx.accounts.maker.key();
+        let swap_key = ctx.accounts.swap.key();
+        let mut offered_mints: Vec<Pubkey> = Vec::with_capacity(item_count);
+        
+        for item in remaining.chunks_exact(3) {
+            let nft_mint = &item[0];
+            let maker_token_account_info = &item[1];
+            let escrow_token_account = &item[2];
+            
+            if *nft_mint.owner != token::ID {
+                program_log!("Error: invalid mint account {}", nft_mint.key());
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            if offered_mints.contains(&nft_mint.key()) {
+                program_log!("Error: duplicate NFT mint {}", nft_mint.key());
+                return err!(ErrorCode::DuplicateSwapItem);
+            }
+            
+            let maker_token_account = load_token_account(maker_token_account_info)?;
+            if maker_token_account.mint != nft_mint.key() || maker_token_account.owner != maker_key {
+                program_log!("Error: token account mismatch for mint {}", nft_mint.key());
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            if maker_token_account.amount != 1 {
+                program_log!("Error: NFT amount must be 1");
+                return err!(ErrorCode::InvalidNFTAmount);
+            }
+            
+            // Le compte d'escrow est l'ATA du PDA de l'offre pour ce mint
+            if escrow_token_account.key() != get_associated_token_address(&swap_key, &nft_mint.key()) {
+                program_log!("Error: invalid escrow account for mint {}", nft_mint.key());
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            // Créer le compte d'escrow (sans échec s'il a déjà été créé par un tiers)
+            let create_ctx = CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.maker.to_account_info(),
+                    associated_token: escrow_token_account.clone(),
+                    authority: ctx.accounts.swap.to_account_info(),
+                    mint: nft_mint.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            );
+            associated_token::create_idempotent(create_ctx)?;
+            
+            // Déposer le NFT dans l'escrow
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: maker_token_account_info.clone(),
+                    to: escrow_token_account.clone(),
+                    authority: ctx.accounts.maker.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, 1)?;
+            
+            offered_mints.push(nft_mint.key());
+        }
+        
+        program_log!("{} NFTs deposited in escrow", item_count);
+        
+        // Déposer les SOL offerts sur le PDA de l'offre
+        if offered_lamports > 0 {
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.maker.to_account_info(),
+                    to: ctx.accounts.swap.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, offered_lamports)?;
+            program_log!("{} lamports deposited in escrow", offered_lamports);
+        }
+        
+        // Initialisation des données de l'offre
+        let swap = &mut ctx.accounts.swap;
+        swap.maker = maker_key;
+        swap.swap_id = swap_id;
+        swap.offered_mints = offered_mints;
+        swap.offered_lamports = offered_lamports;
+        swap.requested_mints = requested_mints;
+        swap.requested_collection = requested_collection;
+        swap.requested_count = requested_count;
+        swap.bump = ctx.bumps.swap;
+        
+        // Émission d'un événement
+        emit!(SwapCreated {
+            swap: swap.key(),
+            maker: swap.maker,
+            offered_mints: swap.offered_mints.clone(),
+            offered_lamports: swap.offered_lamports,
+            requested_mints: swap.requested_mints.clone(),
+            requested_collection: swap.requested_collection,
+            requested_count: swap.requested_count,
+        });
+        
+        Ok(())
+    }
+
+    // Accepter une offre d'échange: les deux parties de l'échange sont exécutées de façon atomique
+    // remaining_accounts:
+    //   - triplets (escrow_token_account, taker_token_account, mint_blocklist) pour chaque NFT offert,
+    //     dans l'ordre de l'offre
+    //   - puis pour chaque NFT demandé: (taker_token_account, maker_token_account, mint_blocklist),
+    //     suivis du compte de métadonnées Metaplex si l'offre porte sur une collection
+    pub fn accept_swap<'info>(ctx: Context<'_, '_, '_, 'info, AcceptSwap<'info>>) -> Result<()> {
+        program_log!("Accept swap instruction started");
+        
+        let maker_key = ctx.accounts.swap.maker;
+        let taker_key = ctx.accounts.taker.key();
+        let swap_key = ctx.accounts.swap.key();
+        let swap_id = ctx.accounts.swap.swap_id;
+        let swap_bump = ctx.accounts.swap.bump;
+        let offered_mints = ctx.accounts.swap.offered_mints.clone();
+        let offered_lamports = ctx.accounts.swap.offered_lamports;
+        let requested_mints = ctx.accounts.swap.requested_mints.clone();
+        let requested_collection = ctx.accounts.swap.requested_collection;
+        
+        // Nombre de comptes par NFT demandé (métadonnées en plus pour une collection)
+        let (requested_len, accounts_per_request) = match requested_collection {
+            Some(_) => (ctx.accounts.swap.requested_count as usize, 4),
+            None => (requested_mints.len(), 3),
+        };
+        
+        let remaining = ctx.remaining_accounts;
+        let offered_accounts_len = offered_mints.len() * 3;
+        if remaining.len() != offered_accounts_len + requested_len * accounts_per_request {
+            program_log!("Error: unexpected number of remaining accounts");
+            return err!(ErrorCode::InvalidSwapAccounts);
+        }
+        let (offered_accounts, requested_accounts) = remaining.split_at(offered_accounts_len);
+        
+        // Partie du preneur: transférer les NFTs demandés au créateur de l'offre
+        let mut received_mints: Vec<Pubkey> = Vec::with_capacity(requested_len);
+        for (index, item) in requested_accounts.chunks_exact(accounts_per_request).enumerate() {
+            let taker_token_account = load_token_account(&item[0])?;
+            let maker_token_account = load_token_account(&item[1])?;
+            let nft_mint = taker_token_account.mint;
+            
+            require_not_blocked(&item[2], &nft_mint)?;
+            
+            match requested_collection {
+                Some(collection) => {
+                    let nft_collection = load_verified_collection(&item[3], nft_mint)?;
+                    if nft_collection != Some(collection) {
+                        program_log!("Error: NFT {} is not part of the requested collection", nft_mint);
+                        return err!(ErrorCode::SwapItemMismatch);
+                    }
+                }
+                None => {
+                    if nft_mint != requested_mints[index] {
+                        program_log!("Error: expected mint {}", requested_mints[index]);
+                        return err!(ErrorCode::SwapItemMismatch);
+                    }
+                }
+            }
+            
+            if received_mints.contains(&nft_mint) {
+                program_log!("Error: duplicate NFT mint {}", nft_mint);
+                return err!(ErrorCode::DuplicateSwapItem);
+            }
+            
+            if taker_token_account.owner != taker_key || taker_token_account.amount != 1 {
+                program_log!("Error: taker does not hold NFT {}", nft_mint);
+                return err!(ErrorCode::InvalidNFTAmount);
+            }
+            
+            if maker_token_account.mint != nft_mint || maker_token_account.owner != maker_key {
+                program_log!("Error: maker token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: item[0].clone(),
+                    to: item[1].clone(),
+                    authority: ctx.accounts.taker.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, 1)?;
+            
+            received_mints.push(nft_mint);
+        }
+        
+        program_log!("{} requested NFTs transferred to maker", received_mints.len());
+        
+        // Partie du créateur: libérer les NFTs en escrow vers le preneur
+        let swap_id_bytes = swap_id.to_le_bytes();
+        let seeds = &[
+            b"swap",
+            maker_key.as_ref(),
+            swap_id_bytes.as_ref(),
+            &[swap_bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        for (nft_mint, item) in offered_mints.iter().zip(offered_accounts.chunks_exact(3)) {
+            if item[0].key() != get_associated_token_address(&swap_key, nft_mint) {
+                program_log!("Error: invalid escrow account for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            require_not_blocked(&item[2], nft_mint)?;
+            
+            let taker_token_account = load_token_account(&item[1])?;
+            if taker_token_account.mint != *nft_mint || taker_token_account.owner != taker_key {
+                program_log!("Error: taker token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            release_swap_escrow(
+                &ctx.accounts.token_program,
+                &ctx.accounts.swap.to_account_info(),
+                &item[0],
+                &item[1],
+                &ctx.accounts.maker,
+                signer,
+            )?;
+        }
+        
+        // Verser les SOL offerts au preneur (le reste du compte revient au créateur à la fermeture)
+        if offered_lamports > 0 {
+            let swap_info = ctx.accounts.swap.to_account_info();
+            **swap_info.try_borrow_mut_lamports()? -= offered_lamports;
+            **ctx.accounts.taker.to_account_info().try_borrow_mut_lamports()? += offered_lamports;
+            program_log!("{} lamports transferred to taker", offered_lamports);
+            
+            // Les SOL reçus par le preneur comptent dans son volume de vente
+            record_seller_volume(&ctx.accounts.taker_volume, offered_lamports, Clock::get()?.unix_timestamp)?;
+        }
+        
+        // Émission d'un événement
+        emit!(SwapAccepted {
+            swap: swap_key,
+            maker: maker_key,
+            taker: taker_key,
+            offered_mints,
+            offered_lamports,
+            received_mints,
+        });
+        
+        program_log!("Accept swap instruction completed successfully");
+        Ok(())
+    }
+
+    // Annuler une offre d'échange et restituer les actifs déposés au créateur
+    // remaining_accounts: paires (escrow_token_account, maker_token_account) pour chaque NFT offert
+    pub fn cancel_swap<'info>(ctx: Context<'_, '_, '_, 'info, CancelSwap<'info>>) -> Result<()> {
+        program_log!("Cancel swap instruction started");
+        
+        let maker_key = ctx.accounts.maker.key();
+        let swap_key = ctx.accounts.swap.key();
+        let swap_id = ctx.accounts.swap.swap_id;
+        let swap_bump = ctx.accounts.swap.bump;
+        let offered_mints = ctx.accounts.swap.offered_mints.clone();
+        
+        let remaining = ctx.remaining_accounts;
+        if remaining.len() != offered_mints.len() * 2 {
+            program_log!("Error: expected {} token accounts", offered_mints.len() * 2);
+            return err!(ErrorCode::InvalidSwapAccounts);
+        }
+        
+        let swap_id_bytes = swap_id.to_le_bytes();
+        let seeds = &[
+            b"swap",
+            maker_key.as_ref(),
+            swap_id_bytes.as_ref(),
+            &[swap_bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        for (nft_mint, item) in offered_mints.iter().zip(remaining.chunks_exact(2)) {
+            if item[0].key() != get_associated_token_address(&swap_key, nft_mint) {
+                program_log!("Error: invalid escrow account for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            let maker_token_account = load_token_account(&item[1])?;
+            if maker_token_account.mint != *nft_mint || maker_token_account.owner != maker_key {
+                program_log!("Error: maker token account mismatch for mint {}", nft_mint);
+                return err!(ErrorCode::InvalidSwapAccounts);
+            }
+            
+            release_swap_escrow(
+                &ctx.accounts.token_program,
+                &ctx.accounts.swap.to_account_info(),
+                &item[0],
+                &item[1],
+                &ctx.accounts.maker.to_account_info(),
+                signer,
+            )?;
+        }
+        
+        program_log!("Escrowed NFTs returned to maker");
+        
+        // Les SOL déposés reviennent au créateur avec la fermeture du compte
+        emit!(SwapCanceled {
+            swap: swap_key,
+            maker: maker_key,
+        });
+        
+        program_log!("Cancel swap instruction completed successfully");
+        Ok(())
+    }
+
+    // Enregistrer un parrain (frontend partenaire) et créer son compte de gains
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let earnings = &mut ctx.accounts.referrer_earnings;
+        earnings.referrer = ctx.accounts.referrer.key();
+        earnings.total_earned = 0;
+        earnings.referred_sales = 0;
+        earnings.bump = ctx.bumps.referrer_earnings;
+        
+        program_log!("Referrer registered: {}", earnings.referrer);
+        
+        emit!(ReferrerRegistered {
+            referrer: earnings.referrer,
+            referrer_earnings: earnings.key(),
+        });
+        
+        Ok(())
+    }
+
+    // Créer la grille de frais de la marketplace, vide: les frais de base s'appliquent tant que
+    // ses paliers n'ont pas été définis par un changement MarketplaceChange::FeeSchedule
+    pub fn initialize_fee_schedule(ctx: Context<InitializeFeeSchedule>) -> Result<()> {
+        let fee_schedule = &mut ctx.accounts.fee_schedule;
+        fee_schedule.tiers = Vec::new();
+        fee_schedule.membership_collection = None;
+        fee_schedule.holder_discount_bps = 0;
+        fee_schedule.bump = ctx.bumps.fee_schedule;
+        
+        program_log!("Fee schedule initialized");
+        
+        emit!(FeeScheduleUpdated {
+            fee_schedule: fee_schedule.key(),
+            tiers: fee_schedule.tiers.clone(),
+            membership_collection: fee_schedule.membership_collection,
+            holder_discount_bps: fee_schedule.holder_discount_bps,
+        });
+        
+        Ok(())
+    }
+
+    // Créer le compte de volume glissant d'un vendeur (payé par n'importe qui)
+    pub fn initialize_seller_volume(ctx: Context<InitializeSellerVolume>) -> Result<()> {
+        let seller_volume = &mut ctx.accounts.seller_volume;
+        seller_volume.seller = ctx.accounts.seller.key();
+        seller_volume.window_start = Clock::get()?.unix_timestamp;
+        seller_volume.current_volume = 0;
+        seller_volume.previous_volume = 0;
+        seller_volume.membership_token = None;
+        seller_volume.membership_collection = None;
+        seller_volume.bump = ctx.bumps.seller_volume;
+        
+        program_log!("Seller volume account created for {}", seller_volume.seller);
+        
+        Ok(())
+    }
+
+    // Enregistrer le NFT de membre du vendeur: la remise des membres ne dépend plus des comptes choisis par l'acheteur
+    pub fn register_membership(ctx: Context<RegisterMembership>) -> Result<()> {
+        let seller = ctx.accounts.seller.key();
+        let collection = verify_membership_proof(
+            &ctx.accounts.fee_schedule,
+            seller,
+            &ctx.accounts.membership_token,
+            &ctx.accounts.membership_metadata,
+        )?;
+        
+        let seller_volume = &mut ctx.accounts.seller_volume;
+        seller_volume.membership_token = Some(ctx.accounts.membership_token.key());
+        seller_volume.membership_collection = Some(collection);
+        
+        program_log!("Membership token {} registered for {}", ctx.accounts.membership_token.key(), seller);
+        
+        emit!(MembershipRegistered {
+            seller,
+            membership_token: ctx.accounts.membership_token.key(),
+            collection,
+        });
+        
+        Ok(())
+    }
+
+    // Activer ou désactiver le mode curé de la marketplace
+    pub fn set_curated_mode(ctx: Context<SetCuratedMode>, curated: bool) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        marketplace.curated = curated;
+        
+        program_log!("Marketplace curated mode: {}", curated);
+        
+        emit!(CuratedModeUpdated {
+            marketplace: marketplace.key(),
+            curated,
+        });
+        
+        Ok(())
+    }
+
+    // Créer la configuration d'une collection partenaire (frais spécifiques définis par un
+    // changement MarketplaceChange::CollectionFeeOverride)
+    pub fn initialize_collection_config(
+        ctx: Context<InitializeCollectionConfig>,
+        enabled: bool,
+        royalty_policy: RoyaltyPolicy,
+    ) -> Result<()> {
+        let collection_config = &mut ctx.accounts.collection_config;
+        collection_config.collection_mint = ctx.accounts.collection_mint.key();
+        collection_config.enabled = enabled;
+        collection_config.fee_override = None;
+        collection_config.royalty_policy = royalty_policy;
+        collection_config.bump = ctx.bumps.collection_config;
+        
+        program_log!("Collection config created for {}", collection_config.collection_mint);
+        
+        emit!(CollectionConfigUpdated {
+            collection_config: collection_config.key(),
+            collection_mint: collection_config.collection_mint,
+            enabled,
+            fee_override: None,
+            royalty_policy,
+        });
+        
+        Ok(())
+    }
+
+    // Mettre à jour la configuration d'une collection partenaire (ses frais spécifiques ne changent
+    // qu'au travers d'un changement MarketplaceChange::CollectionFeeOverride)
+    pub fn update_collection_config(
+        ctx: Context<UpdateCollectionConfig>,
+        enabled: bool,
+        royalty_policy: RoyaltyPolicy,
+    ) -> Result<()> {
+        let collection_config = &mut ctx.accounts.collection_config;
+        collection_config.enabled = enabled;
+        collection_config.royalty_policy = royalty_policy;
+        
+        program_log!("Collection config updated for {}", collection_config.collection_mint);
+        
+        emit!(CollectionConfigUpdated {
+            collection_config: collection_config.key(),
+            collection_mint: collection_config.collection_mint,
+            enabled,
+            fee_override: collection_config.fee_override,
+            royalty_policy,
+        });
+        
+        Ok(())
+    }
+
+    // Retirer de force un listing signalé (NFT volé ou contrefait) et rembourser le loyer au vendeur
+    // Le token program n'autorise que le propriétaire à révoquer une délégation: la fermeture du
+    // listing, seul délégué, la rend inutilisable (aucune instruction ne signe pour un listing fermé)
+    // et l'enregistrement du retrait empêche de le recréer; les tokens restent chez le vendeur
+    pub fn force_delist(ctx: Context<ForceDelist>, reason_code: u16) -> Result<()> {
+        program_log!("Force delist instruction started");
+        
+        let listing = &mut ctx.accounts.listing;
+        
+        // Quantité encore déléguée au listing (nulle si le vendeur a révoqué la délégation)
+        let seller_token_account = load_token_account(&ctx.accounts.seller_token_account)?;
+        let delegated = if seller_token_account.delegate == COption::Some(listing.key()) {
+            seller_token_account.delegated_amount.min(seller_token_account.amount)
+        } else {
+            0
+        };
+        
+        // Enregistrer le retrait: le vendeur ne peut plus lister ce mint sans l'approbation d'un modérateur
+        let record = &mut ctx.accounts.delist_record;
+        record.nft_mint = listing.nft_mint;
+        record.seller = listing.seller;
+        record.moderator = ctx.accounts.moderator.key();
+        record.reason_code = reason_code;
+        record.delegated = delegated;
+        record.delisted_at = Clock::get()?.unix_timestamp;
+        record.bump = ctx.bumps.delist_record;
+        
+        if listing.active {
+            update_marketplace_stats(&ctx.accounts.stats, |stats| {
+                stats.active_listings = stats.active_listings.saturating_sub(1);
+            })?;
+            
+            let listing_key = listing.key();
+            update_collection_stats(ctx.accounts.collection_stats.as_deref(), listing.collection, |stats| {
+                remove_floor_ask(stats, listing_key, listing);
+            })?;
+        }
+        
+        program_log!("Listing {} removed by moderator {} (reason {})", 
+             listing.key(), ctx.accounts.moderator.key(), reason_code);
+        
+        emit!(ListingForceDelisted {
+            listing: listing.key(),
+            seller: listing.seller,
+            nft_mint: listing.nft_mint,
+            moderator: ctx.accounts.moderator.key(),
+            reason_code,
+            delegated,
+        });
+        
+        Ok(())
+    }
+
+    // Approuver la remise en vente d'un mint retiré de force à un vendeur (autorité ou modérateur)
+    // Le loyer de l'enregistrement revient au signataire
+    pub fn approve_relist(ctx: Context<ApproveRelist>) -> Result<()> {
+        let record = &ctx.accounts.delist_record;
+        
+        program_log!("Relisting of mint {} by seller {} approved", record.nft_mint, record.seller);
+        
+        emit!(RelistApproved {
+            nft_mint: record.nft_mint,
+            seller: record.seller,
+            moderator: ctx.accounts.moderator.key(),
+        });
+        
+        Ok(())
+    }
+
+    // Bloquer un mint signalé comme volé (autorité ou modérateur)
+    pub fn block_mint(ctx: Context<BlockMint>, reason: u16) -> Result<()> {
+        let entry = &mut ctx.accounts.mint_blocklist;
+        entry.nft_mint = ctx.accounts.nft_mint.key();
+        entry.reason = reason;
+        entry.blocked_by = ctx.accounts.moderator.key();
+        entry.blocked_at = Clock::get()?.unix_timestamp;
+        entry.bump = ctx.bumps.mint_blocklist;
+        
+        program_log!("Mint {} blocked (reason {})", entry.nft_mint, reason);
+        
+        emit!(MintBlocked {
+            nft_mint: entry.nft_mint,
+            moderator: entry.blocked_by,
+            reason,
+        });
+        
+        Ok(())
+    }
+
+    // Débloquer un mint (autorité ou modérateur), le loyer revient au signataire
+    pub fn unblock_mint(ctx: Context<UnblockMint>, reason: u16) -> Result<()> {
+        let entry = &ctx.accounts.mint_blocklist;
+        
+        program_log!("Mint {} unblocked (reason {})", entry.nft_mint, reason);
+        
+        emit!(MintUnblocked {
+            nft_mint: entry.nft_mint,
+            moderator: ctx.accounts.moderator.key(),
+            reason,
+        });
+        
+        Ok(())
+    }
+
+    // Mettre en file un changement sensible de la marketplace (frais, autorité, trésorerie, modérateurs)
+    // Le changement ne peut être exécuté qu'après CHANGE_TIMELOCK_DELAY
+    pub fn queue_change(
+        ctx: Context<QueueChange>,
+        change_id: u64,
+        change: MarketplaceChange,
+    ) -> Result<()> {
+        validate_marketplace_change(&ctx.accounts.marketplace, &change)?;
+        
+        let now = Clock::get()?.unix_timestamp;
+        let eta = now.checked_add(CHANGE_TIMELOCK_DELAY).ok_or(ErrorCode::MathOverflow)?;
+        
+        let pending = &mut ctx.accounts.pending_change;
+        pending.change_id = change_id;
+        pending.change = change.clone();
+        pending.queued_at = now;
+        pending.eta = eta;
+        pending.bump = ctx.bumps.pending_change;
+        
+        program_log!("Change {} queued, executable after {}", change_id, eta);
+        
+        emit!(ChangeQueued {
+            marketplace: ctx.accounts.marketplace.key(),
+            change_id,
+            change,
+            eta,
+        });
+        
+        Ok(())
+    }
+
+    // Exécuter un changement en file une fois le délai écoulé
+    pub fn execute_change(ctx: Context<ExecuteChange>) -> Result<()> {
+        let pending = &ctx.accounts.pending_change;
+        let now = Clock::get()?.unix_timestamp;
+        
+        if now < pending.eta {
+            program_log!("Error: change {} executable after {}, now {}", pending.change_id, pending.eta, now);
+            return err!(ErrorCode::TimelockNotElapsed);
+        }
+        
+        let change_id = pending.change_id;
+        let change = pending.change.clone();
+        let marketplace = &mut ctx.accounts.marketplace;
+        
+        // Revalider: l'état de la marketplace a pu changer depuis la mise en file
+        validate_marketplace_change(marketplace, &change)?;
+        
+        match change.clone() {
+            MarketplaceChange::Fee { fee } => {
+                marketplace.fee = fee;
+                program_log!("Marketplace fee set to {} bps", fee);
+            }
+            MarketplaceChange::Authority { authority } => {
+                marketplace.authority = authority;
+                program_log!("Marketplace authority set to {}", authority);
+            }
+            MarketplaceChange::Treasury { treasury } => {
+                marketplace.treasury = treasury;
+                program_log!("Marketplace treasury set to {}", treasury);
+            }
+            MarketplaceChange::AddModerator { moderator } => {
+                marketplace.moderators.push(moderator);
+                program_log!("Moderator added: {}", moderator);
+                
+                emit!(ModeratorAdded {
+                    marketplace: marketplace.key(),
+                    moderator,
+                });
+            }
+            MarketplaceChange::RemoveModerator { moderator } => {
+                marketplace.moderators.retain(|key| *key != moderator);
+                program_log!("Moderator removed: {}", moderator);
+                
+                emit!(ModeratorRemoved {
+                    marketplace: marketplace.key(),
+                    moderator,
+                });
+            }
+            MarketplaceChange::ReferralFee { referral_fee_bps } => {
+                marketplace.referral_fee_bps = referral_fee_bps;
+                program_log!("Referral fee set to {} bps of the marketplace fee", referral_fee_bps);
+                
+                emit!(ReferralFeeUpdated {
+                    marketplace: marketplace.key(),
+                    referral_fee_bps,
+                });
+            }
+            MarketplaceChange::FeeSchedule { tiers, membership_collection, holder_discount_bps } => {
+                let Some(fee_schedule) = ctx.accounts.fee_schedule.as_mut() else {
+                    program_log!("Error: fee schedule account is required");
+                    return err!(ErrorCode::InvalidFeeSchedule);
+                };
+                fee_schedule.tiers = tiers;
+                fee_schedule.membership_collection = membership_collection;
+                fee_schedule.holder_discount_bps = holder_discount_bps;
+                program_log!("Fee schedule updated with {} tiers", fee_schedule.tiers.len());
+                
+                emit!(FeeScheduleUpdated {
+                    fee_schedule: fee_schedule.key(),
+                    tiers: fee_schedule.tiers.clone(),
+                    membership_collection: fee_schedule.membership_collection,
+                    holder_discount_bps: fee_schedule.holder_discount_bps,
+                });
+            }
+            MarketplaceChange::CollectionFeeOverride { collection, fee_override } => {
+                let collection_config = match ctx.accounts.collection_config.as_mut() {
+                    Some(config) if config.collection_mint == collection => config,
+                    _ => {
+                        program_log!("Error: collection config of {} is required", collection);
+                        return err!(ErrorCode::InvalidCollectionConfig);
+                    }
+                };
+                collection_config.fee_override = fee_override;
+                program_log!("Fee override of collection {} set to {:?}", collection, fee_override);
+                
+                emit!(CollectionConfigUpdated {
+                    collection_config: collection_config.key(),
+                    collection_mint: collection,
+                    enabled: collection_config.enabled,
+                    fee_override,
+                    royalty_policy: collection_config.royalty_policy,
+                });
+            }
+        }
+        
+        emit!(ChangeExecuted {
+            marketplace: marketplace.key(),
+            change_id,
+            change,
+        });
+        
+        Ok(())
+    }
+
+    // Annuler un changement en file avant son exécution
+    pub fn cancel_change(ctx: Context<CancelChange>) -> Result<()> {
+        let pending = &ctx.accounts.pending_change;
+        
+        program_log!("Change {} canceled", pending.change_id);
+        
+        emit!(ChangeCanceled {
+            marketplace: ctx.accounts.marketplace.key(),
+            change_id: pending.change_id,
+            change: pending.change.clone(),
+        });
+        
+        Ok(())
+    }
+
+    // Migrer un compte marketplace de l'ancien format (sans version) vers le format actuel
+    pub fn migrate_marketplace(ctx: Context<MigrateMarketplace>) -> Result<()> {
+        let marketplace_info = ctx.accounts.marketplace.to_account_info();
+        let legacy: LegacyMarketplace = load_legacy_account(
+            &marketplace_info,
+            &Marketplace::DISCRIMINATOR,
+            8 + Marketplace::INIT_SPACE,
+        )?;
+        
+        if legacy.authority != ctx.accounts.authority.key() {
+            program_log!("Error: only the marketplace authority can migrate the marketplace");
+            return err!(ErrorCode::UnauthorizedAccess);
+        }
+        
+        let marketplace = Marketplace {
+            version: MARKETPLACE_VERSION,
+            authority: legacy.authority,
+            fee: legacy.fee,
+            bump: legacy.bump,
+            referral_fee_bps: 0,
+            curated: false,
+            moderators: Vec::new(),
+            treasury: legacy.authority,
+            reserved: [0; 64],
+        };
+        
+        realloc_account(
+            &marketplace_info,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            8 + Marketplace::INIT_SPACE,
+        )?;
+        marketplace.try_serialize(&mut &mut marketplace_info.try_borrow_mut_data()?[..])?;
+        
+        program_log!("Marketplace migrated to version {}", MARKETPLACE_VERSION);
+        
+        emit!(AccountMigrated {
+            account: marketplace_info.key(),
+            version: MARKETPLACE_VERSION,
+        });
+        
+        Ok(())
+    }
+
+    // Migrer un listing de l'ancien format (sans version) vers le format actuel
+    // N'importe qui peut payer la migration: le contenu du listing est conservé à l'identique
+    pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+        let listing_info = ctx.accounts.listing.to_account_info();
+        let legacy: LegacyNFTListing = load_legacy_account(
+            &listing_info,
+            &NFTListing::DISCRIMINATOR,
+            8 + NFTListing::INIT_SPACE,
+        )?;
+        
+        // Vérifier que le compte est bien le PDA du listing
+        let expected = Pubkey::create_program_address(
+            &[b"listing", legacy.nft_mint.as_ref(), legacy.seller.as_ref(), &[legacy.bump]],
+            &crate::ID,
+        ).map_err(|_| ErrorCode::InvalidAccountVersion)?;
+        if expected != listing_info.key() {
+            program_log!("Error: account is not a listing PDA");
+            return err!(ErrorCode::InvalidAccountVersion);
+        }
+        
+        // L'ancien format ne vendait qu'un seul NFT, sans répartition ni collection
+        let listing = NFTListing {
+            version: LISTING_VERSION,
+            seller: legacy.seller,
+            nft_mint: legacy.nft_mint,
+            price: legacy.price,
+            active: legacy.active,
+            bump: legacy.bump,
+            quantity: 1,
+            payout_splits: Vec::new(),
+            collection: None,
+            stats_tracked: false,
+            reserved: [0; 63],
+        };
+        
+        realloc_account(
+            &listing_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + NFTListing::INIT_SPACE,
+        )?;
+        listing.try_serialize(&mut &mut listing_info.try_borrow_mut_data()?[..])?;
+        
+        program_log!("Listing migrated to version {}", LISTING_VERSION);
+        
+        emit!(AccountMigrated {
+            account: listing_info.key(),
+            version: LISTING_VERSION,
+        });
+        
+        Ok(())
+    }
+
+    // Créer le compte de statistiques globales de la marketplace
+    pub fn initialize_stats(ctx: Context<InitializeStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.stats;
+        stats.total_sales = 0;
+        stats.total_volume = 0;
+        stats.total_fees = 0;
+        stats.active_listings = 0;
+        stats.unique_sellers = 0;
+        stats.bump = ctx.bumps.stats;
+        
+        program_log!("Marketplace stats initialized");
+        
+        Ok(())
+    }
+
+    // Créer le compte de statistiques d'une collection (sans permission, payé par l'appelant)
+    pub fn initialize_collection_stats(
+        ctx: Context<InitializeCollectionStats>,
+        collection: Pubkey,  // Mint de la collection Metaplex vérifiée
+    ) -> Result<()> {
+        let stats = &mut ctx.accounts.collection_stats;
+        stats.collection = collection;
+        stats.sales_count = 0;
+        stats.volume = 0;
+        stats.last_sale_price = 0;
+        stats.floor_asks = Vec::new();
+        stats.untracked_asks = 0;
+        stats.bump = ctx.bumps.collection_stats;
+        
+        program_log!("Collection stats initialized for {}", collection);
+        
+        Ok(())
+    }
+
+    // Recalculer les prix plancher d'une collection à partir de ses asks non suivis
+    // remaining_accounts: listings actifs de la collection absents des prix plancher (tous les untracked_asks)
+    // Les listings créés avant les statistiques de la collection peuvent aussi être ajoutés ici (en écriture)
+    pub fn refresh_collection_floor<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefreshCollectionFloor<'info>>,
+    ) -> Result<()> {
+        let stats = &mut ctx.accounts.collection_stats;
+        
+        let mut asks = stats.floor_asks.clone();
+        let mut counted = 0u64;
+        for listing_info in ctx.remaining_accounts.iter() {
+            let mut listing = Account::<NFTListing>::try_from(listing_info)?;
+            if !listing.active || listing.collection != Some(stats.collection) {
+                program_log!("Error: listing {} is not an active listing of the collection", listing_info.key());
+                return err!(ErrorCode::InvalidCollectionStats);
+            }
+            if asks.iter().any(|ask| ask.listing == listing_info.key()) {
+                program_log!("Error: listing {} is already tracked or duplicated", listing_info.key());
+                return err!(ErrorCode::InvalidCollectionStats);
+            }
+            
+            // Un listing créé avant les statistiques est désormais compté
+            if listing.stats_tracked {
+                counted += 1;
+            } else {
+                listing.stats_tracked = true;
+                listing.exit(&crate::ID)?;
+            }
+            
+            asks.push(FloorAsk { listing: listing_info.key(), price: listing.price });
+        }
+        
+        // Sans la totalité des asks non suivis, le plus bas d'entre eux pourrait manquer
+        if counted < stats.untracked_asks {
+            program_log!("Error: {} untracked asks expected, {} provided", stats.untracked_asks, counted);
+            return err!(ErrorCode::InvalidCollectionStats);
+        }
+        
+        asks.sort_by_key(|ask| ask.price);
+        let total = asks.len();
+        asks.truncate(MAX_FLOOR_ASKS);
+        stats.untracked_asks = (total - asks.len()) as u64;
+        stats.floor_asks = asks;
+        
+        program_log!("Collection {} floor: {:?}", stats.collection, stats.floor_asks.first().map(|ask| ask.price));
+        
+        Ok(())
+    }
+
+    // Créer le profil de trading d'un portefeuille (les échanges ne sont enregistrés qu'une fois le profil créé)
+    pub fn initialize_trader_profile(ctx: Context<InitializeTraderProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.wallet = ctx.accounts.wallet.key();
+        profile.items_bought = 0;
+        profile.items_sold = 0;
+        profile.volume_bought = 0;
+        profile.volume_sold = 0;
+        profile.first_trade_at = 0;
+        profile.last_trade_at = 0;
+        profile.cancelled_listings = 0;
+        profile.bump = ctx.bumps.profile;
+        
+        program_log!("Trader profile created for {}", profile.wallet);
+        
+        Ok(())
+    }
+
+    // Retourner le profil de trading d'un portefeuille (lisible par d'autres programmes via CPI)
+    pub fn get_trader_profile(ctx: Context<GetTraderProfile>) -> Result<TraderProfile> {
+        Ok((*ctx.accounts.profile).clone())
+    }
+}
+
+// Calculer les frais de la marketplace et la part du vendeur pour un prix donné
+pub fn calculate_marketplace_fee(price: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let fee_amount = (price as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::MathOverflow)? as u64;
+    
+    let seller_amount = price.checked_sub(fee_amount).ok_or(ErrorCode::MathOverflow)?;
+    
+    Ok((fee_amount, seller_amount))
+}
+
+// Calculer la part des frais de la marketplace reversée à un parrain
+pub fn calculate_referral_fee(fee_amount: u64, referral_fee_bps: u16) -> Result<u64> {
+    let referral_amount = (fee_amount as u128)
+        .checked_mul(referral_fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::MathOverflow)? as u64;
+    
+    Ok(referral_amount)
+}
+
+// Répartition du prix d'un achat entre la trésorerie, le parrain et le vendeur
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    pub fee_amount: u64,      // Frais totaux de la marketplace
+    pub referral_amount: u64, // Part des frais reversée au parrain
+    pub treasury_amount: u64, // Part des frais versée à la trésorerie
+    pub seller_amount: u64,   // Montant versé au vendeur (ou à sa répartition)
+}
+
+// Calculer la répartition d'un achat; referral_fee_bps = None si aucun parrain
+pub fn calculate_fee_breakdown(price: u64, fee_bps: u16, referral_fee_bps: Option<u16>) -> Result<FeeBreakdown> {
+    let (fee_amount, seller_amount) = calculate_marketplace_fee(price, fee_bps)?;
+    let referral_amount = match referral_fee_bps {
+        Some(bps) => calculate_referral_fee(fee_amount, bps)?,
+        None => 0,
+    };
+    
+    Ok(FeeBreakdown {
+        fee_amount,
+        referral_amount,
+        treasury_amount: fee_amount - referral_amount,
+        seller_amount,
+    })
+}
+
+// Choisir le taux de frais d'un achat: grille de frais (palier et remise membre),
+// remplacée par les frais spécifiques de la collection si sa configuration est active
+pub fn select_fee_bps(
+    base_fee: u16,
+    fee_schedule: Option<&FeeSchedule>,
+    trailing_volume: u64,
+    is_holder: bool,
+    collection_config: Option<&CollectionConfig>,
+) -> (u16, Option<u8>) {
+    let collection_fee = collection_config.and_then(|config| config.fee_override.filter(|_| config.enabled));
+    if let Some(fee_bps) = collection_fee {
+        return (fee_bps, None);
+    }
+    
+    match fee_schedule {
+        Some(schedule) => calculate_effective_fee_bps(schedule, base_fee, trailing_volume, is_holder),
+        None => (base_fee, None),
+    }
+}
+
+// Vérifier une répartition des recettes: au plus MAX_PAYOUT_SPLITS destinataires distincts totalisant 10000 bps
+pub fn validate_payout_splits(payout_splits: &[PayoutSplit]) -> Result<()> {
+    if payout_splits.is_empty() {
+        return Ok(());
+    }
+    
+    if payout_splits.len() > MAX_PAYOUT_SPLITS {
+        program_log!("Error: at most {} payout recipients allowed", MAX_PAYOUT_SPLITS);
+        return err!(ErrorCode::InvalidPayoutSplits);
+    }
+    
+    let mut total_bps: u32 = 0;
+    for (index, split) in payout_splits.iter().enumerate() {
+        if split.bps == 0 || payout_splits[..index].iter().any(|other| other.recipient == split.recipient) {
+            program_log!("Error: invalid payout split for {}", split.recipient);
+            return err!(ErrorCode::InvalidPayoutSplits);
+        }
+        total_bps += split.bps as u32;
+    }
+    
+    if total_bps != 10000 {
+        program_log!("Error: payout splits must sum to 10000 bps, got {}", total_bps);
+        return err!(ErrorCode::InvalidPayoutSplits);
+    }
+    
+    Ok(())
+}
+
+// Calculer le montant versé à chaque destinataire; le dernier reçoit le reste de l'arrondi
+pub fn calculate_payout_amounts(amount: u64, payout_splits: &[PayoutSplit]) -> Result<Vec<u64>> {
+    let mut amounts = Vec::with_capacity(payout_splits.len());
+    let mut distributed: u64 = 0;
+    
+    for (index, split) in payout_splits.iter().enumerate() {
+        let share = if index == payout_splits.len() - 1 {
+            amount.checked_sub(distributed).ok_or(ErrorCode::MathOverflow)?
+        } else {
+            (amount as u128)
+                .checked_mul(split.bps as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_div(10000)
+                .ok_or(ErrorCode::MathOverflow)? as u64
+        };
+        distributed = distributed.checked_add(share).ok_or(ErrorCode::MathOverflow)?;
+        amounts.push(share);
+    }
+    
+    Ok(amounts)
+}
+
+// Verser la part du vendeur, soit directement, soit selon la répartition du listing
+fn pay_seller_proceeds<'info>(
+    system_program: &Program<'info, System>,
+    buyer: &AccountInfo<'info>,
+    seller_wallet: &AccountInfo<'info>,
+    payout_splits: &[PayoutSplit],
+    recipients: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if payout_splits.is_empty() {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: buyer.clone(),
+                to: seller_wallet.clone(),
+            },
+        );
+        return anchor_lang::system_program::transfer(cpi_context, amount);
+    }
+    
+    if recipients.len() != payout_splits.len() {
+        program_log!("Error: expected {} payout recipients", payout_splits.len());
+        return err!(ErrorCode::InvalidPayoutRecipients);
+    }
+    
+    let amounts = calculate_payout_amounts(amount, payout_splits)?;
+    for ((split, recipient), share) in payout_splits.iter().zip(recipients.iter()).zip(amounts) {
+        if recipient.key() != split.recipient {
+            program_log!("Error: expected payout recipient {}", split.recipient);
+            return err!(ErrorCode::InvalidPayoutRecipients);
+        }
+        
+        if share > 0 {
+            let cpi_context = CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: buyer.clone(),
+                    to: recipient.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, share)?;
+        }
+    }
+    
+    Ok(())
+}
+
+// Vérifier une grille de frais: paliers triés par volume minimal strictement croissant
+fn validate_fee_schedule(tiers: &[FeeTier], holder_discount_bps: u16) -> Result<()> {
+    if tiers.len() > MAX_FEE_TIERS {
+        program_log!("Error: at most {} fee tiers allowed", MAX_FEE_TIERS);
+        return err!(ErrorCode::InvalidFeeSchedule);
+    }
+    
+    for (index, tier) in tiers.iter().enumerate() {
+        // Le taux d'un palier a les mêmes bornes que les frais de la marketplace
+        validate_fee_bps(tier.fee_bps)?;
+        
+        if index > 0 && tier.min_volume <= tiers[index - 1].min_volume {
+            program_log!("Error: invalid fee tier {}", index);
+            return err!(ErrorCode::InvalidFeeSchedule);
+        }
+    }
+    
+    validate_fee_bps(holder_discount_bps)
+}
+
+// Vérifier un taux en points de base (frais, palier, réduction, part des parrains)
+fn validate_fee_bps(fee_bps: u16) -> Result<()> {
+    if fee_bps > 10000 {
+        program_log!("Error: {} bps exceeds 10000 bps", fee_bps);
+        return err!(ErrorCode::InvalidFeeBps);
+    }
+    
+    Ok(())
+}
+
+// Calculer le taux de frais effectif et l'indice du palier appliqué
+pub fn calculate_effective_fee_bps(
+    schedule: &FeeSchedule,
+    base_fee: u16,
+    trailing_volume: u64,
+    is_holder: bool,
+) -> (u16, Option<u8>) {
+    // Le palier retenu est celui au volume minimal le plus élevé atteint par le vendeur
+    let tier = schedule
+        .tiers
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, tier)| trailing_volume >= tier.min_volume);
+    
+    let (fee_bps, fee_tier) = match tier {
+        Some((index, tier)) => (tier.fee_bps, Some(index as u8)),
+        None => (base_fee, None),
+    };
+    
+    if is_holder {
+        (fee_bps.saturating_sub(schedule.holder_discount_bps), fee_tier)
+    } else {
+        (fee_bps, fee_tier)
+    }
+}
+
+// Passer à la fenêtre de volume suivante si la fenêtre courante est écoulée
+pub fn roll_seller_volume(volume: &mut SellerVolume, now: i64) {
+    let elapsed = now.saturating_sub(volume.window_start);
+    if elapsed >= 2 * FEE_VOLUME_WINDOW {
+        volume.previous_volume = 0;
+        volume.current_volume = 0;
+        volume.window_start = now;
+    } else if elapsed >= FEE_VOLUME_WINDOW {
+        volume.previous_volume = volume.current_volume;
+        volume.current_volume = 0;
+        volume.window_start += FEE_VOLUME_WINDOW;
+    }
+}
+
+// Lire la grille de frais si elle a été initialisée
+fn load_fee_schedule(account: &AccountInfo) -> Result<Option<FeeSchedule>> {
+    if account.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(FeeSchedule::try_deserialize(&mut &account.data.borrow()[..])?))
+}
+
+// Lire le volume glissant d'un vendeur si son compte a été créé
+fn load_seller_volume(account: &AccountInfo) -> Result<Option<SellerVolume>> {
+    if account.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(SellerVolume::try_deserialize(&mut &account.data.borrow()[..])?))
+}
+
+// Vérifier qu'un vendeur détient un NFT de la collection de membres et retourner cette collection
+fn verify_membership_proof(
+    schedule: &FeeSchedule,
+    seller: Pubkey,
+    token_info: &AccountInfo,
+    metadata_info: &AccountInfo,
+) -> Result<Pubkey> {
+    let Some(collection) = schedule.membership_collection else {
+        program_log!("Error: the fee schedule has no membership collection");
+        return err!(ErrorCode::InvalidMembershipProof);
+    };
+    
+    let membership_token = load_token_account(token_info)?;
+    if membership_token.owner != seller || membership_token.amount == 0 {
+        program_log!("Error: seller does not hold the membership NFT");
+        return err!(ErrorCode::InvalidMembershipProof);
+    }
+    
+    if load_verified_collection(metadata_info, membership_token.mint)? != Some(collection) {
+        program_log!("Error: NFT {} is not part of the membership collection", membership_token.mint);
+        return err!(ErrorCode::InvalidMembershipProof);
+    }
+    
+    Ok(collection)
+}
+
+// Vérifier si le vendeur détient encore le NFT de membre qu'il a enregistré
+// Le compte de token enregistré est obligatoire: l'acheteur ne peut pas écarter la remise
+fn is_membership_holder(
+    schedule: &FeeSchedule,
+    seller_volume: Option<&SellerVolume>,
+    membership_token: Option<&AccountInfo>,
+) -> Result<bool> {
+    let Some(volume) = seller_volume.filter(|volume| volume.membership_token.is_some()) else {
+        return Ok(false);
+    };
+    
+    let Some(token_info) = membership_token.filter(|token| Some(token.key()) == volume.membership_token) else {
+        program_log!("Error: the registered membership token account must be provided");
+        return err!(ErrorCode::InvalidMembershipProof);
+    };
+    
+    // Collection de membres modifiée depuis l'enregistrement, ou compte de token fermé: pas de remise
+    if schedule.membership_collection != volume.membership_collection || *token_info.owner != token::ID {
+        return Ok(false);
+    }
+    
+    let token_account = load_token_account(token_info)?;
+    Ok(token_account.owner == volume.seller && token_account.amount > 0)
+}
+
+// Taux de frais appliqué à une vente
+struct SaleFee {
+    fee_bps: u16,
+    fee_tier: Option<u8>,
+    holder_discount: bool,
+    trailing_volume: u64,
+}
+
+// Déterminer le taux de frais d'une vente, quel que soit le chemin d'achat: grille de frais
+// (volume glissant du vendeur et remise membre), remplacée par les frais spécifiques de la collection
+fn sale_fee(
+    base_fee: u16,
+    fee_schedule: Option<&FeeSchedule>,
+    seller_volume: &AccountInfo,
+    membership_token: Option<&AccountInfo>,
+    collection_config: Option<&CollectionConfig>,
+    now: i64,
+) -> Result<SaleFee> {
+    let mut volume = load_seller_volume(seller_volume)?;
+    if let Some(volume) = volume.as_mut() {
+        roll_seller_volume(volume, now);
+    }
+    let trailing_volume = volume
+        .as_ref()
+        .map_or(0, |volume| volume.previous_volume.saturating_add(volume.current_volume));
+    
+    let holder_discount = match fee_schedule {
+        Some(schedule) => is_membership_holder(schedule, volume.as_ref(), membership_token)?,
+        None => false,
+    };
+    
+    let (fee_bps, fee_tier) = select_fee_bps(base_fee, fee_schedule, trailing_volume, holder_discount, collection_config);
+    Ok(SaleFee {
+        fee_bps,
+        fee_tier,
+        holder_discount,
+        trailing_volume,
+    })
+}
+
+// Lire la configuration de la collection d'un listing: elle doit être fournie à son adresse dès que
+// le listing a une collection (un compte non initialisé signifie l'absence de frais spécifiques)
+fn load_collection_config(account: Option<&AccountInfo>, collection: Option<Pubkey>) -> Result<Option<CollectionConfig>> {
+    let Some(collection) = collection else {
+        return Ok(None);
+    };
+    
+    let (expected, _) = Pubkey::find_program_address(&[b"collection", collection.as_ref()], &crate::ID);
+    let Some(account) = account.filter(|account| account.key() == expected) else {
+        program_log!("Error: the config of collection {} must be provided", collection);
+        return err!(ErrorCode::InvalidCollectionConfig);
+    };
+    
+    if account.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(CollectionConfig::try_deserialize(&mut &account.data.borrow()[..])?))
+}
+
+// Ajouter une vente au volume glissant d'un vendeur, si son compte de volume existe
+fn record_seller_volume(account: &AccountInfo, amount: u64, now: i64) -> Result<()> {
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    
+    let mut volume = SellerVolume::try_deserialize(&mut &account.data.borrow()[..])?;
+    roll_seller_volume(&mut volume, now);
+    volume.current_volume = volume.current_volume.saturating_add(amount);
+    volume.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    
+    Ok(())
+}
+
+// Vérifier si une adresse peut modérer la marketplace (autorité ou modérateur)
+pub fn is_moderator(marketplace: &Marketplace, key: &Pubkey) -> bool {
+    marketplace.authority == *key || marketplace.moderators.contains(key)
+}
+
+// Vérifier qu'un mint n'est pas bloqué: son entrée de liste de blocage ne doit pas exister
+fn require_not_blocked(mint_blocklist: &AccountInfo, nft_mint: &Pubkey) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[b"blocklist", nft_mint.as_ref()], &crate::ID);
+    if mint_blocklist.key() != expected {
+        program_log!("Error: invalid blocklist account for mint {}", nft_mint);
+        return err!(ErrorCode::InvalidBlocklistAccount);
+    }
+    
+    if !mint_blocklist.data_is_empty() {
+        program_log!("Error: mint {} is blocked", nft_mint);
+        return err!(ErrorCode::MintBlocked);
+    }
+    
+    Ok(())
+}
+
+// Désérialiser un compte de token SPL passé via remaining_accounts
+fn load_token_account(account: &AccountInfo) -> Result<TokenAccount> {
+    if *account.owner != token::ID {
+        program_log!("Error: account {} is not owned by the token program", account.key());
+        return err!(ErrorCode::InvalidTokenAccount);
+    }
+    TokenAccount::try_deserialize(&mut &account.data.borrow()[..])
+}
+
+// Vérifier le mint et le propriétaire d'un compte de token avec une seule désérialisation
+fn is_token_account_for(account: &AccountInfo, mint: &Pubkey, owner: &Pubkey) -> Result<bool> {
+    let token_account = TokenAccount::try_deserialize(&mut &account.data.borrow()[..])?;
+    Ok(token_account.mint == *mint && token_account.owner == *owner)
+}
+
+// Vérifier qu'un élément d'un sweep peut être acheté par l'acheteur
+fn check_sweep_item(
+    listing: &Account<NFTListing>,
+    seller_wallet: &AccountInfo,
+    seller_token_account: &AccountInfo,
+    buyer_token_account: &AccountInfo,
+    mint_blocklist: &AccountInfo,
+    buyer: Pubkey,
+) -> Result<()> {
+    if !listing.active {
+        program_log!("Error: Listing is not active");
+        return err!(ErrorCode::ListingNotActive);
+    }
+    
+    require_not_blocked(mint_blocklist, &listing.nft_mint)?;
+    
+    if seller_wallet.key() != listing.seller {
+        program_log!("Error: seller wallet mismatch");
+        return err!(ErrorCode::InvalidSweepAccounts);
+    }
+    
+    let seller_token = load_token_account(seller_token_account)?;
+    if seller_token.mint != listing.nft_mint
+        || seller_token.owner != listing.seller
+        || seller_token.amount < listing.quantity
+        || seller_token.delegate != COption::Some(listing.key())
+        || seller_token.delegated_amount < listing.quantity
+    {
+        program_log!("Error: listed NFT {} is no longer deliverable", listing.nft_mint);
+        return err!(ErrorCode::ListingUnfillable);
+    }
+    
+    let buyer_token = load_token_account(buyer_token_account)?;
+    if buyer_token.mint != listing.nft_mint || buyer_token.owner != buyer {
+        program_log!("Error: buyer token account mismatch for mint {}", listing.nft_mint);
+        return err!(ErrorCode::InvalidSweepAccounts);
+    }
+    
+    Ok(())
+}
+
+// Lire la collection vérifiée d'un NFT à partir de son compte de métadonnées Metaplex
+fn load_verified_collection(metadata_info: &AccountInfo, nft_mint: Pubkey) -> Result<Option<Pubkey>> {
+    if *metadata_info.owner != mpl_token_metadata::ID {
+        program_log!("Error: account {} is not a Metaplex metadata account", metadata_info.key());
+        return err!(ErrorCode::InvalidMetadataAccount);
+    }
+    
+    let metadata = MetadataAccount::try_deserialize(&mut &metadata_info.data.borrow()[..])?;
+    if metadata.mint != nft_mint {
+        program_log!("Error: metadata does not belong to mint {}", nft_mint);
+        return err!(ErrorCode::InvalidMetadataAccount);
+    }
+    
+    Ok(metadata
+        .collection
+        .as_ref()
+        .filter(|collection| collection.verified)
+        .map(|collection| collection.key))
+}
+
+// Lire la collection vérifiée d'un NFT à partir du compte de métadonnées à son adresse Metaplex
+// (None si le NFT n'a pas de métadonnées)
+fn load_optional_collection(metadata_info: &AccountInfo, nft_mint: Pubkey) -> Result<Option<Pubkey>> {
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), nft_mint.as_ref()],
+        &mpl_token_metadata::ID,
+    );
+    if metadata_info.key() != expected {
+        program_log!("Error: invalid metadata account for mint {}", nft_mint);
+        return err!(ErrorCode::InvalidMetadataAccount);
+    }
+    
+    if metadata_info.data_is_empty() {
+        return Ok(None);
+    }
+    load_verified_collection(metadata_info, nft_mint)
+}
+
+// Vérifier en mode curé que la collection vérifiée d'un NFT est activée par sa configuration
+fn require_collection_enabled(
+    metadata_info: &AccountInfo,
+    collection_config_info: &AccountInfo,
+    nft_mint: Pubkey,
+) -> Result<()> {
+    let allowed = match load_verified_collection(metadata_info, nft_mint)? {
+        Some(collection) => {
+            let (expected, _) = Pubkey::find_program_address(&[b"collection", collection.as_ref()], &crate::ID);
+            collection_config_info.key() == expected
+                && *collection_config_info.owner == crate::ID
+                && CollectionConfig::try_deserialize(&mut &collection_config_info.data.borrow()[..])?.enabled
+        }
+        None => false,
+    };
+    if !allowed {
+        program_log!("Error: collection of NFT {} is not enabled on this marketplace", nft_mint);
+        return err!(ErrorCode::CollectionNotAllowed);
+    }
+    
+    Ok(())
+}
+
+// Transférer un NFT hors de l'escrow d'une offre d'échange puis fermer le compte d'escrow
+fn release_swap_escrow<'info>(
+    token_program: &Program<'info, Token>,
+    swap: &AccountInfo<'info>,
+    escrow_token_account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    rent_receiver: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let transfer_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::Transfer {
+            from: escrow_token_account.clone(),
+            to: destination.clone(),
+            authority: swap.clone(),
+        },
+        signer,
+    );
+    token::transfer(transfer_ctx, 1)?;
+    
+    // Restituer le loyer du compte d'escrow au créateur de l'offre
+    let close_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: escrow_token_account.clone(),
+            destination: rent_receiver.clone(),
+            authority: swap.clone(),
+        },
+        signer,
+    );
+    token::close_account(close_ctx)
+}
+
+// Vérifier qu'un changement d'administration est applicable à l'état actuel de la marketplace
+pub fn validate_marketplace_change(marketplace: &Marketplace, change: &MarketplaceChange) -> Result<()> {
+    match change {
+        MarketplaceChange::Fee { fee } => validate_fee_bps(*fee)?,
+        MarketplaceChange::Authority { .. } | MarketplaceChange::Treasury { .. } => {}
+        MarketplaceChange::AddModerator { moderator } => {
+            if marketplace.moderators.contains(moderator) {
+                program_log!("Error: {} is already a moderator", moderator);
+                return err!(ErrorCode::ModeratorAlreadyExists);
+            }
+            
+            if marketplace.moderators.len() >= MAX_MODERATORS {
+                program_log!("Error: at most {} moderators allowed", MAX_MODERATORS);
+                return err!(ErrorCode::TooManyModerators);
+            }
+        }
+        MarketplaceChange::RemoveModerator { moderator } => {
+            if !marketplace.moderators.contains(moderator) {
+                program_log!("Error: {} is not a moderator", moderator);
+                return err!(ErrorCode::ModeratorNotFound);
+            }
+        }
+        MarketplaceChange::ReferralFee { referral_fee_bps } => validate_fee_bps(*referral_fee_bps)?,
+        MarketplaceChange::FeeSchedule { tiers, holder_discount_bps, .. } => {
+            validate_fee_schedule(tiers, *holder_discount_bps)?;
+        }
+        MarketplaceChange::CollectionFeeOverride { fee_override, .. } => {
+            if let Some(fee) = fee_override {
+                validate_fee_bps(*fee)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+// Lire un compte du programme encore à l'ancien format (sans champ de version)
+// Un compte déjà à la taille du format actuel est considéré comme migré
+fn load_legacy_account<T: AnchorDeserialize>(
+    account: &AccountInfo,
+    discriminator: &[u8; 8],
+    current_len: usize,
+) -> Result<T> {
+    if account.owner != &crate::ID {
+        program_log!("Error: account is not owned by the marketplace program");
+        return err!(ErrorCode::InvalidAccountVersion);
+    }
+    
+    let data = account.try_borrow_data()?;
+    if data.len() < 8 || data[..8] != discriminator[..] {
+        program_log!("Error: unexpected account discriminator");
+        return err!(ErrorCode::InvalidAccountVersion);
+    }
+    
+    if data.len() >= current_len {
+        program_log!("Error: account is already at the current layout");
+        return err!(ErrorCode::AccountAlreadyMigrated);
+    }
+    
+    let mut legacy_data: &[u8] = &data[8..];
+    T::deserialize(&mut legacy_data).map_err(|_| error!(ErrorCode::InvalidAccountVersion))
+}
+
+// Agrandir un compte du programme et compléter son loyer depuis le payeur
+fn realloc_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let missing_lamports = required_lamports.saturating_sub(account.lamports());
+    
+    if missing_lamports > 0 {
+        let transfer_ctx = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, missing_lamports)?;
+    }
+    
+    account.realloc(new_len, true)?;
+    
+    Ok(())
+}
+
+// Mettre à jour les statistiques globales si leur compte a été créé (sinon elles sont ignorées)
+fn update_marketplace_stats(account: &AccountInfo, update: impl FnOnce(&mut MarketplaceStats)) -> Result<()> {
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    
+    let mut stats = MarketplaceStats::try_deserialize(&mut &account.data.borrow()[..])?;
+    update(&mut stats);
+    stats.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    
+    Ok(())
+}
+
+// Ajouter une vente aux statistiques globales (compteurs saturants pour ne jamais bloquer un achat)
+pub fn record_sale(stats: &mut MarketplaceStats, quantity: u64, price: u64, fee: u64) {
+    stats.total_sales = stats.total_sales.saturating_add(quantity);
+    stats.total_volume = stats.total_volume.saturating_add(price);
+    stats.total_fees = stats.total_fees.saturating_add(fee);
+}
+
+// Créer un compte PDA appartenant au programme (équivalent manuel de `init`)
+// Gère le cas où le compte a déjà reçu des lamports avant sa création
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(space);
+    let signer = &[signer_seeds];
+    
+    if account.lamports() == 0 {
+        let create_ctx = CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+            signer,
+        );
+        return anchor_lang::system_program::create_account(create_ctx, required_lamports, space as u64, &crate::ID);
+    }
+    
+    let missing_lamports = required_lamports.saturating_sub(account.lamports());
+    if missing_lamports > 0 {
+        let transfer_ctx = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, missing_lamports)?;
+    }
+    
+    let allocate_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Allocate {
+            account_to_allocate: account.clone(),
+        },
+        signer,
+    );
+    anchor_lang::system_program::allocate(allocate_ctx, space as u64)?;
+    
+    let assign_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        anchor_lang::system_program::Assign {
+            account_to_assign: account.clone(),
+        },
+        signer,
+    );
+    anchor_lang::system_program::assign(assign_ctx, &crate::ID)
+}
+
+// Ajouter une vente aux statistiques d'une collection
+pub fn record_collection_sale(stats: &mut CollectionStats, quantity: u64, price: u64, unit_price: u64) {
+    stats.sales_count = stats.sales_count.saturating_add(quantity);
+    stats.volume = stats.volume.saturating_add(price);
+    stats.last_sale_price = unit_price;
+}
+
+// Ajouter un ask actif aux prix plancher d'une collection et le marquer comme compté
+// floor_asks contient toujours les asks les plus bas: un ask plus cher que le dernier suivi n'y entre
+// que si aucun ask n'est hors de l'ensemble, sinon il est seulement compté dans untracked_asks
+pub fn insert_floor_ask(stats: &mut CollectionStats, listing_key: Pubkey, listing: &mut NFTListing) {
+    listing.stats_tracked = true;
+    let price = listing.price;
+    let index = stats.floor_asks.partition_point(|ask| ask.price <= price);
+    let fits = index < stats.floor_asks.len() || stats.untracked_asks == 0;
+    if !fits || index >= MAX_FLOOR_ASKS {
+        stats.untracked_asks = stats.untracked_asks.saturating_add(1);
+        return;
+    }
+    
+    stats.floor_asks.insert(index, FloorAsk { listing: listing_key, price });
+    if stats.floor_asks.len() > MAX_FLOOR_ASKS {
+        stats.floor_asks.truncate(MAX_FLOOR_ASKS);
+        stats.untracked_asks = stats.untracked_asks.saturating_add(1);
+    }
+}
+
+// Retirer un ask actif (vendu, annulé, retiré ou repricé) des prix plancher d'une collection
+// Un listing créé avant les statistiques de sa collection n'y a jamais été compté: rien n'est retiré
+pub fn remove_floor_ask(stats: &mut CollectionStats, listing_key: Pubkey, listing: &mut NFTListing) {
+    if !listing.stats_tracked {
+        return;
+    }
+    listing.stats_tracked = false;
+    
+    let count = stats.floor_asks.len();
+    stats.floor_asks.retain(|ask| ask.listing != listing_key);
+    if stats.floor_asks.len() == count {
+        stats.untracked_asks = stats.untracked_asks.saturating_sub(1);
+    }
+    
+    if stats.floor_asks.is_empty() && stats.untracked_asks > 0 {
+        program_log!("Collection {} floor unknown: {} untracked asks must be refreshed", stats.collection, stats.untracked_asks);
+    }
+}
+
+// Appliquer une mise à jour aux statistiques de la collection d'un listing
+// Le compte doit être le PDA de la collection; s'il n'a pas encore été créé, rien n'est enregistré
+fn update_collection_stats(
+    account: Option<&AccountInfo>,
+    collection: Option<Pubkey>,
+    update: impl FnOnce(&mut CollectionStats),
+) -> Result<()> {
+    let Some(collection) = collection else {
+        return Ok(());
+    };
+    
+    let (expected, _) = Pubkey::find_program_address(&[b"collection_stats", collection.as_ref()], &crate::ID);
+    let Some(account) = account.filter(|account| account.key() == expected) else {
+        program_log!("Error: collection stats account for {} is missing or invalid", collection);
+        return err!(ErrorCode::InvalidCollectionStats);
+    };
+    
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    
+    let mut stats = CollectionStats::try_deserialize(&mut &account.data.borrow()[..])?;
+    update(&mut stats);
+    stats.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    
+    Ok(())
+}
+
+// Adresse du profil de trading d'un portefeuille
+pub fn trader_profile_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", wallet.as_ref()], &crate::ID).0
+}
+
+// Adresse du compte de volume glissant d'un vendeur
+pub fn seller_volume_address(seller: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"seller_volume", seller.as_ref()], &crate::ID).0
+}
+
+// Enregistrer un achat dans le profil de l'acheteur
+pub fn record_profile_purchase(profile: &mut TraderProfile, quantity: u64, price: u64, now: i64) {
+    profile.items_bought = profile.items_bought.saturating_add(quantity);
+    profile.volume_bought = profile.volume_bought.saturating_add(price);
+    record_profile_trade_time(profile, now);
+}
+
+// Enregistrer une vente dans le profil du vendeur
+pub fn record_profile_sale(profile: &mut TraderProfile, quantity: u64, price: u64, now: i64) {
+    profile.items_sold = profile.items_sold.saturating_add(quantity);
+    profile.volume_sold = profile.volume_sold.saturating_add(price);
+    record_profile_trade_time(profile, now);
+}
+
+fn record_profile_trade_time(profile: &mut TraderProfile, now: i64) {
+    if profile.first_trade_at == 0 {
+        profile.first_trade_at = now;
+    }
+    profile.last_trade_at = now;
+}
+
+// Appliquer une mise à jour au profil de trading d'un portefeuille s'il a été créé
+fn update_trader_profile(account: &AccountInfo, update: impl FnOnce(&mut TraderProfile)) -> Result<()> {
+    if account.data_is_empty() {
+        return Ok(());
+    }
+    
+    let mut profile = TraderProfile::try_deserialize(&mut &account.data.borrow()[..])?;
+    update(&mut profile);
+    profile.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    
+    Ok(())
+}
+
+// Structure de compte pour la marketplace 
+// (équivalent à un struct en Solidity, mais stocké dans un compte séparé)
+#[account]
+#[derive(InitSpace)]
+pub struct Marketplace {
+    pub version: u8,          // Version du format du compte (MARKETPLACE_VERSION)
+    pub authority: Pubkey,    // Propriétaire/admin de la marketplace
+    pub fee: u16,             // Frais de commission en points de base (100 = 1%)
+    pub bump: u8,             // Utilisé pour la création de PDA
+    pub referral_fee_bps: u16, // Part des frais reversée aux parrains, en points de base des frais
+    pub curated: bool,        // Mode curé: seules les collections activées peuvent être listées
+    #[max_len(MAX_MODERATORS)]
+    pub moderators: Vec<Pubkey>, // Modérateurs autorisés à retirer des listings (MAX_MODERATORS au maximum)
+    pub treasury: Pubkey,     // Compte qui reçoit les frais de la marketplace
+    pub reserved: [u8; 64],   // Espace réservé pour les champs futurs
+}
+
+// Structure de compte pour un listing NFT
+#[account]
+#[derive(InitSpace)]
+pub struct NFTListing {
+    pub version: u8,          // Version du format du compte (LISTING_VERSION)
+    pub seller: Pubkey,       // Adresse du vendeur
+    pub nft_mint: Pubkey,     // Addresse du mint du NFT
+    pub price: u64,           // Prix en lamports (1 SOL = 10^9 lamports)
+    pub active: bool,         // État de la mise en vente
+    pub bump: u8,             // Utilisé pour la création de PDA
+    pub quantity: u64,        // Quantité restante à vendre (prix unitaire = price)
+    #[max_len(MAX_PAYOUT_SPLITS)]
+    pub payout_splits: Vec<PayoutSplit>, // Répartition des recettes (vide = tout au vendeur)
+    pub collection: Option<Pubkey>,      // Collection Metaplex vérifiée du NFT, si connue
+    pub stats_tracked: bool,  // Ask compté dans les statistiques de sa collection (floor_asks ou untracked_asks)
+    pub reserved: [u8; 63],   // Espace réservé pour les champs futurs
+}
+
+// Part des recettes d'une vente versée à un destinataire
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct PayoutSplit {
+    pub recipient: Pubkey,    // Destinataire de la part
+    pub bps: u16,             // Part en points de base (total = 10000)
+}
+
+// Structure de compte pour un lot (bundle) de NFTs vendus ensemble
+#[account]
+#[derive(InitSpace)]
+pub struct BundleListing {
+    pub seller: Pubkey,         // Adresse du vendeur
+    pub bundle_id: u64,         // Identifiant du bundle choisi par le vendeur
+    #[max_len(MAX_BUNDLE_ITEMS)]
+    pub nft_mints: Vec<Pubkey>, // Mints des NFTs du lot (MAX_BUNDLE_ITEMS au maximum)
+    pub price: u64,             // Prix du lot complet en lamports
+    pub active: bool,           // État de la mise en vente
+    pub bump: u8,               // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour une offre d'échange NFT contre NFT
+#[account]
+#[derive(InitSpace)]
+pub struct SwapOffer {
+    pub maker: Pubkey,                      // Créateur de l'offre
+    pub swap_id: u64,                       // Identifiant de l'offre choisi par le créateur
+    #[max_len(MAX_SWAP_ITEMS)]
+    pub offered_mints: Vec<Pubkey>,         // NFTs déposés en escrow par le créateur
+    pub offered_lamports: u64,              // SOL déposés en escrow par le créateur
+    #[max_len(MAX_SWAP_ITEMS)]
+    pub requested_mints: Vec<Pubkey>,       // Mints précis demandés en échange
+    pub requested_collection: Option<Pubkey>, // Collection vérifiée demandée (à la place des mints)
+    pub requested_count: u8,                // Nombre de NFTs de la collection demandés
+    pub bump: u8,                           // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour les gains d'un parrain (reporting)
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerEarnings {
+    pub referrer: Pubkey,       // Adresse du parrain
+    pub total_earned: u64,      // Total des frais reversés en lamports
+    pub referred_sales: u64,    // Nombre de ventes apportées
+    pub bump: u8,               // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour la grille de frais de la marketplace
+#[account]
+#[derive(InitSpace)]
+pub struct FeeSchedule {
+    #[max_len(MAX_FEE_TIERS)]
+    pub tiers: Vec<FeeTier>,                  // Paliers triés par volume minimal croissant
+    pub membership_collection: Option<Pubkey>, // Collection des NFTs de membre donnant droit à une réduction
+    pub holder_discount_bps: u16,             // Réduction du taux (en bps) pour les détenteurs
+    pub bump: u8,                             // Utilisé pour la création de PDA
+}
+
+// Palier de frais appliqué à partir d'un volume glissant du vendeur
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct FeeTier {
+    pub min_volume: u64,      // Volume glissant minimal en lamports
+    pub fee_bps: u16,         // Taux de frais du palier en points de base
+}
+
+// Structure de compte pour le volume glissant d'un vendeur
+#[account]
+#[derive(InitSpace)]
+pub struct SellerVolume {
+    pub seller: Pubkey,       // Adresse du vendeur
+    pub window_start: i64,    // Début de la fenêtre de volume courante
+    pub current_volume: u64,  // Volume vendu sur la fenêtre courante
+    pub previous_volume: u64, // Volume vendu sur la fenêtre précédente
+    pub membership_token: Option<Pubkey>,      // Compte de token du NFT de membre enregistré par le vendeur
+    pub membership_collection: Option<Pubkey>, // Collection de membres lors de l'enregistrement
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour la configuration d'une collection partenaire
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionConfig {
+    pub collection_mint: Pubkey,      // Mint de la collection Metaplex
+    pub enabled: bool,                // Collection autorisée en mode curé
+    pub fee_override: Option<u16>,    // Frais spécifiques en points de base (remplace la grille)
+    pub royalty_policy: RoyaltyPolicy, // Politique de royalties annoncée pour la collection
+    pub bump: u8,                     // Utilisé pour la création de PDA
+}
+
+// Politique de royalties d'une collection
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum RoyaltyPolicy {
+    Optional,     // Royalties laissées au choix de l'acheteur
+    Enforced,     // Royalties obligatoires
+}
+
+// Structure de compte pour un mint bloqué (actif signalé volé)
+#[account]
+#[derive(InitSpace)]
+pub struct MintBlocklistEntry {
+    pub nft_mint: Pubkey,     // Mint bloqué
+    pub reason: u16,          // Code de raison du blocage
+    pub blocked_by: Pubkey,   // Autorité ou modérateur ayant bloqué le mint
+    pub blocked_at: i64,      // Date du blocage
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour le listing d'un vendeur retiré de force, en attente d'approbation pour être remis en vente
+#[account]
+#[derive(InitSpace)]
+pub struct DelistRecord {
+    pub nft_mint: Pubkey,     // Mint retiré
+    pub seller: Pubkey,       // Vendeur du listing retiré
+    pub moderator: Pubkey,    // Autorité ou modérateur ayant retiré le listing
+    pub reason_code: u16,     // Code de raison du retrait
+    pub delegated: u64,       // Quantité encore déléguée au listing lors du retrait
+    pub delisted_at: i64,     // Date du retrait
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Changement sensible de la marketplace soumis au délai de CHANGE_TIMELOCK_DELAY
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub enum MarketplaceChange {
+    Fee { fee: u16 },                    // Nouveaux frais en points de base
+    Authority { authority: Pubkey },     // Nouvelle autorité
+    Treasury { treasury: Pubkey },       // Nouvelle trésorerie
+    AddModerator { moderator: Pubkey },  // Modérateur à ajouter
+    RemoveModerator { moderator: Pubkey }, // Modérateur à retirer
+    ReferralFee { referral_fee_bps: u16 }, // Part des frais reversée aux parrains (5000 = 50% des frais)
+    FeeSchedule {                        // Nouvelle grille de frais
+        #[max_len(MAX_FEE_TIERS)]
+        tiers: Vec<FeeTier>,
+        membership_collection: Option<Pubkey>,
+        holder_discount_bps: u16,
+    },
+    CollectionFeeOverride {              // Frais spécifiques d'une collection (None = grille)
+        collection: Pubkey,
+        fee_override: Option<u16>,
+    },
+}
+
+// Structure de compte pour un changement en file d'attente
+#[account]
+#[derive(InitSpace)]
+pub struct PendingChange {
+    pub change_id: u64,       // Identifiant choisi par l'autorité
+    pub change: MarketplaceChange, // Changement à appliquer
+    pub queued_at: i64,       // Date de mise en file
+    pub eta: i64,             // Date à partir de laquelle le changement peut être exécuté
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Ancien format du compte marketplace (avant l'ajout du champ de version)
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyMarketplace {
+    pub authority: Pubkey,
+    pub fee: u16,
+    pub bump: u8,
+}
+
+// Ancien format du compte de listing (avant l'ajout du champ de version)
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyNFTListing {
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,
+    pub active: bool,
+    pub bump: u8,
+}
+
+// Structure de compte pour les statistiques globales de la marketplace
+#[account]
+#[derive(InitSpace)]
+pub struct MarketplaceStats {
+    pub total_sales: u64,     // Nombre total de NFTs vendus
+    pub total_volume: u64,    // Volume total des ventes en lamports
+    pub total_fees: u64,      // Frais perçus par la trésorerie en lamports
+    pub active_listings: u64, // Nombre de listings actifs
+    pub unique_sellers: u64,  // Nombre de vendeurs distincts ayant listé au moins une fois
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Structure de compte marquant un vendeur déjà compté dans les statistiques
+#[account]
+#[derive(InitSpace)]
+pub struct SellerRecord {
+    pub seller: Pubkey,       // Adresse du vendeur
+    pub first_listed_at: i64, // Date de son premier listing
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Structure de compte pour les statistiques d'une collection
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionStats {
+    pub collection: Pubkey,   // Mint de la collection Metaplex vérifiée
+    pub sales_count: u64,     // Nombre de NFTs vendus
+    pub volume: u64,          // Volume des ventes en lamports
+    pub last_sale_price: u64, // Prix unitaire de la dernière vente
+    #[max_len(MAX_FLOOR_ASKS)]
+    pub floor_asks: Vec<FloorAsk>, // Asks actifs les plus bas, triés par prix croissant (le premier est le plancher)
+    pub untracked_asks: u64,  // Asks actifs de la collection absents de floor_asks (tous plus chers que ceux suivis)
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Annonce active parmi les prix plancher d'une collection
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct FloorAsk {
+    pub listing: Pubkey,      // Listing concerné
+    pub price: u64,           // Prix unitaire en lamports
+}
+
+// Structure de compte pour le profil de trading d'un portefeuille (réputation portable)
+#[account]
+#[derive(InitSpace)]
+pub struct TraderProfile {
+    pub wallet: Pubkey,       // Portefeuille concerné
+    pub items_bought: u64,    // Nombre de NFTs achetés
+    pub items_sold: u64,      // Nombre de NFTs vendus
+    pub volume_bought: u64,   // Volume acheté en lamports
+    pub volume_sold: u64,     // Volume vendu en lamports
+    pub first_trade_at: i64,  // Date du premier échange (0 si aucun)
+    pub last_trade_at: i64,   // Date du dernier échange (0 si aucun)
+    pub cancelled_listings: u64, // Nombre de listings annulés par le vendeur
+    pub bump: u8,             // Utilisé pour la création de PDA
+}
+
+// Structure pour l'instruction InitializeMarketplace
+// Définit tous les comptes nécessaires pour l'instruction
+// (équivalent aux paramètres d'une fonction Solidity)
+#[derive(Accounts)]
+pub struct InitializeMarketplace<'info> {
+    // Définit le compte marketplace comme un PDA (adresse dérivée du programme)
+    #[account(
+        init,                             // Créer un nouveau compte
+        payer = authority,                // Payé par l'autorité
+        space = 8 + Marketplace::INIT_SPACE,  // Taille: discriminator + Marketplace
+        seeds = [b"marketplace"],         // Seeds pour générer le PDA
+        bump                              // Génère et stocke le bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,         // Doit signer la transaction
+    
+    // Programmes nécessaires à l'instruction
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction ListNFT
+#[derive(Accounts)]
+pub struct ListNFT<'info> {
+    // Nouveau compte de listing comme PDA
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + NFTListing::INIT_SPACE,  // Taille pour NFTListing
+        seeds = [
+            b"listing", 
+            nft_mint.key().as_ref(), 
+            seller.key().as_ref()
+        ],
+        bump
+    )]
+    pub listing: Account<'info, NFTListing>,
+    
+    // Compte marketplace existant
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Vendeur qui doit signer
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    // Mint du NFT
+    /// CHECK: Vérifié par le token program
+    #[account(owner = token::ID)]
+    pub nft_mint: AccountInfo<'info>,
+    
+    // Compte de token du vendeur contenant le NFT
+    /// CHECK: Vérifié avec les contraintes et dans la logique
+    #[account(
+        mut, // Ajout de mut car nous avons besoin de modifier le compte lors de l'approbation
+        owner = token::ID,
+        constraint = is_token_account_for(&nft_token_account, &nft_mint.key(), &seller.key())?
+    )]
+    pub nft_token_account: AccountInfo<'info>,
+    
+    // Compte de métadonnées Metaplex du NFT (peut ne pas exister: le listing n'a pas de collection)
+    /// CHECK: Adresse et contenu vérifiés dans la logique
+    pub nft_metadata: UncheckedAccount<'info>,
+    
+    // Configuration de la collection du NFT (obligatoire en mode curé)
+    #[account(
+        seeds = [b"collection", collection_config.collection_mint.as_ref()],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Option<Account<'info, CollectionConfig>>,
+    
+    // Entrée de la liste de blocage pour ce mint (ne doit pas exister)
+    /// CHECK: Adresse vérifiée par les seeds, doit être vide
+    #[account(
+        seeds = [b"blocklist", nft_mint.key().as_ref()],
+        bump,
+        constraint = mint_blocklist.data_is_empty() @ ErrorCode::MintBlocked
+    )]
+    pub mint_blocklist: UncheckedAccount<'info>,
+    
+    // Enregistrement d'un retrait de force de ce mint (ne doit pas exister)
+    /// CHECK: Adresse vérifiée par les seeds, doit être vide
+    #[account(
+        seeds = [b"delisted", nft_mint.key().as_ref(), seller.key().as_ref()],
+        bump,
+        constraint = delist_record.data_is_empty() @ ErrorCode::RelistNotApproved
+    )]
+    pub delist_record: UncheckedAccount<'info>,
+    
+    // Statistiques de la collection du listing (obligatoire si le listing a une collection)
+    /// CHECK: Adresse et contenu vérifiés dans l'instruction
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    // Enregistrement du vendeur, créé lors de son premier listing
+    /// CHECK: Adresse vérifiée par les seeds, créé dans l'instruction s'il n'existe pas
+    #[account(
+        mut,
+        seeds = [b"seller_record", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_record: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction UpdateListing
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    // Compte de listing existant
+    #[account(
+        mut,
+        seeds = [
+            b"listing", 
+            listing.nft_mint.as_ref(), 
+            seller.key().as_ref()
+        ],
+        bump = listing.bump,
         constraint = listing.seller == seller.key() @ ErrorCode::UnauthorizedAccess
     )]
     pub listing: Account<'info, NFTListing>,
     
-    // Vendeur qui doit signer
-    #[account(mut)]
-    pub seller: Signer<'info>,
+    // Vendeur qui doit signer
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    // Compte de token du vendeur contenant le NFT
+    /// CHECK: Vérifié avec les contraintes et dans la logique
+    #[account(
+        mut, // CORRECTION: Ajout de mut pour permettre la réapprobation
+        owner = token::ID,
+        constraint = is_token_account_for(&nft_token_account, &listing.nft_mint, &seller.key())?
+    )]
+    pub nft_token_account: AccountInfo<'info>,
+    
+    // Statistiques de la collection du listing (obligatoire si le listing a une collection)
+    /// CHECK: Adresse et contenu vérifiés dans l'instruction
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction BuyNFT
+#[derive(Accounts)]
+pub struct BuyNFT<'info> {
+    // Compte marketplace
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Compte de listing qui doit être actif
+    #[account(
+        mut,
+        seeds = [
+            b"listing", 
+            listing.nft_mint.as_ref(), 
+            listing.seller.as_ref()
+        ],
+        bump = listing.bump,
+        constraint = listing.active @ ErrorCode::ListingNotActive
+    )]
+    pub listing: Account<'info, NFTListing>,
+    
+    // Acheteur qui doit signer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    // Vendeur qui reçoit le paiement
+    /// CHECK: Nous vérifions juste que c'est le vendeur
+    #[account(
+        mut,
+        constraint = seller_wallet.key() == listing.seller
+    )]
+    pub seller_wallet: AccountInfo<'info>,
+    
+    // Trésorerie de la marketplace qui reçoit les frais
+    /// CHECK: Compte qui recevra les frais
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    // Compte de token du vendeur contenant le NFT
+    /// CHECK: Vérifié avec les contraintes
+    #[account(
+        mut,
+        owner = token::ID,
+        constraint = is_token_account_for(&seller_token_account, &listing.nft_mint, &listing.seller)?
+    )]
+    pub seller_token_account: AccountInfo<'info>,
+    
+    // Compte de token de l'acheteur qui recevra le NFT
+    /// CHECK: Vérifié avec les contraintes
+    #[account(
+        mut,
+        owner = token::ID,
+        constraint = is_token_account_for(&buyer_token_account, &listing.nft_mint, &buyer.key())?
+    )]
+    pub buyer_token_account: AccountInfo<'info>,
+    
+    // Parrain optionnel qui reçoit une part des frais de la marketplace
+    /// CHECK: Vérifié dans la logique avec le compte de gains du parrain
+    #[account(mut)]
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    // Compte de gains du parrain (obligatoire si un parrain est fourni)
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer_earnings.referrer.as_ref()],
+        bump = referrer_earnings.bump
+    )]
+    pub referrer_earnings: Option<Account<'info, ReferrerEarnings>>,
+    
+    // Grille de frais de la marketplace (peut ne pas encore exister: frais fixes dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
+    // Volume glissant du vendeur (peut ne pas exister: volume nul dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"seller_volume", listing.seller.as_ref()],
+        bump
+    )]
+    pub seller_volume: UncheckedAccount<'info>,
+    
+    // Compte de token du NFT de membre enregistré par le vendeur (obligatoire s'il en a enregistré un)
+    /// CHECK: Comparé au compte enregistré dans le volume du vendeur
+    pub seller_membership_token: Option<AccountInfo<'info>>,
+    
+    // Configuration de la collection du listing (obligatoire si le listing a une collection,
+    // peut ne pas être initialisée: pas de frais spécifiques dans ce cas)
+    /// CHECK: Adresse vérifiée dans la logique, désérialisé s'il existe
+    pub collection_config: Option<UncheckedAccount<'info>>,
+    
+    // Entrée de la liste de blocage pour ce mint (ne doit pas exister)
+    /// CHECK: Adresse vérifiée par les seeds, doit être vide
+    #[account(
+        seeds = [b"blocklist", listing.nft_mint.as_ref()],
+        bump,
+        constraint = mint_blocklist.data_is_empty() @ ErrorCode::MintBlocked
+    )]
+    pub mint_blocklist: UncheckedAccount<'info>,
+    
+    // Statistiques de la collection du listing (obligatoire si le listing a une collection)
+    /// CHECK: Adresse et contenu vérifiés dans l'instruction
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    
+    // Profil de trading de l'acheteur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_profile: UncheckedAccount<'info>,
+    
+    // Profil de trading du vendeur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", listing.seller.as_ref()],
+        bump
+    )]
+    pub seller_profile: UncheckedAccount<'info>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction CancelListing
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"listing", 
+            listing.nft_mint.as_ref(), 
+            seller.key().as_ref()
+        ],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, NFTListing>,
+    
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ ErrorCode::UnauthorizedAccess
+    )]
+    pub seller: Signer<'info>,
+    
+    /// CHECK: Vérifié avec les contraintes
+    #[account(
+        mut,
+        owner = token::ID
+    )]
+    pub nft_token_account: AccountInfo<'info>,
+    
+    // Statistiques de la collection du listing (obligatoire si le listing a une collection)
+    /// CHECK: Adresse et contenu vérifiés dans l'instruction
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    
+    // Profil de trading du vendeur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_profile: UncheckedAccount<'info>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction ListBundle
+#[derive(Accounts)]
+#[instruction(bundle_id: u64)]
+pub struct ListBundle<'info> {
+    // Nouveau compte de bundle comme PDA
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + BundleListing::INIT_SPACE,  // Taille pour BundleListing
+        seeds = [
+            b"bundle",
+            seller.key().as_ref(),
+            bundle_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    
+    // Compte marketplace existant
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Vendeur qui doit signer
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction UpdateBundle
+#[derive(Accounts)]
+pub struct UpdateBundle<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"bundle",
+            seller.key().as_ref(),
+            bundle.bundle_id.to_le_bytes().as_ref()
+        ],
+        bump = bundle.bump,
+        constraint = bundle.seller == seller.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction BuyBundle
+#[derive(Accounts)]
+pub struct BuyBundle<'info> {
+    // Compte marketplace
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Compte de bundle qui doit être actif
+    #[account(
+        mut,
+        seeds = [
+            b"bundle",
+            bundle.seller.as_ref(),
+            bundle.bundle_id.to_le_bytes().as_ref()
+        ],
+        bump = bundle.bump,
+        constraint = bundle.active @ ErrorCode::ListingNotActive
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    
+    // Acheteur qui doit signer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    // Vendeur qui reçoit le paiement
+    /// CHECK: Nous vérifions juste que c'est le vendeur
+    #[account(
+        mut,
+        constraint = seller_wallet.key() == bundle.seller
+    )]
+    pub seller_wallet: AccountInfo<'info>,
+    
+    // Trésorerie de la marketplace qui reçoit les frais
+    /// CHECK: Compte qui recevra les frais
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    // Profil de trading de l'acheteur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_profile: UncheckedAccount<'info>,
+    
+    // Profil de trading du vendeur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", bundle.seller.as_ref()],
+        bump
+    )]
+    pub seller_profile: UncheckedAccount<'info>,
+    
+    // Volume glissant du vendeur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"seller_volume", bundle.seller.as_ref()],
+        bump
+    )]
+    pub seller_volume: UncheckedAccount<'info>,
+    
+    // Grille de frais de la marketplace (peut ne pas encore exister: frais fixes dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
+    // Compte de token du NFT de membre enregistré par le vendeur (obligatoire s'il en a enregistré un)
+    /// CHECK: Comparé au compte enregistré dans le volume du vendeur
+    pub seller_membership_token: Option<AccountInfo<'info>>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction CancelBundle
+#[derive(Accounts)]
+pub struct CancelBundle<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"bundle",
+            seller.key().as_ref(),
+            bundle.bundle_id.to_le_bytes().as_ref()
+        ],
+        bump = bundle.bump,
+        constraint = bundle.active @ ErrorCode::ListingNotActive
+    )]
+    pub bundle: Account<'info, BundleListing>,
+    
+    #[account(
+        mut,
+        constraint = seller.key() == bundle.seller @ ErrorCode::UnauthorizedAccess
+    )]
+    pub seller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction BuyMany
+#[derive(Accounts)]
+pub struct BuyMany<'info> {
+    // Compte marketplace
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Acheteur qui doit signer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    // Trésorerie de la marketplace qui reçoit les frais
+    /// CHECK: Compte qui recevra les frais
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    // Profil de trading de l'acheteur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"profile", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_profile: UncheckedAccount<'info>,
+    
+    // Grille de frais de la marketplace (peut ne pas encore exister: frais fixes dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump
+    )]
+    pub fee_schedule: UncheckedAccount<'info>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction CreateSwap
+#[derive(Accounts)]
+#[instruction(swap_id: u64)]
+pub struct CreateSwap<'info> {
+    // Nouveau compte d'offre d'échange comme PDA
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + SwapOffer::INIT_SPACE,  // Taille pour SwapOffer
+        seeds = [
+            b"swap",
+            maker.key().as_ref(),
+            swap_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub swap: Account<'info, SwapOffer>,
+    
+    // Créateur de l'offre qui doit signer
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction AcceptSwap
+#[derive(Accounts)]
+pub struct AcceptSwap<'info> {
+    // Offre d'échange, fermée au profit du créateur une fois acceptée
+    #[account(
+        mut,
+        seeds = [
+            b"swap",
+            swap.maker.as_ref(),
+            swap.swap_id.to_le_bytes().as_ref()
+        ],
+        bump = swap.bump,
+        close = maker
+    )]
+    pub swap: Account<'info, SwapOffer>,
+    
+    // Créateur de l'offre qui reçoit le loyer des comptes fermés
+    /// CHECK: Nous vérifions juste que c'est le créateur de l'offre
+    #[account(
+        mut,
+        constraint = maker.key() == swap.maker @ ErrorCode::UnauthorizedAccess
+    )]
+    pub maker: AccountInfo<'info>,
+    
+    // Preneur qui doit signer
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    
+    // Volume glissant du preneur (peut ne pas exister: rien n'est enregistré dans ce cas)
+    /// CHECK: Adresse vérifiée par les seeds, désérialisé dans la logique s'il existe
+    #[account(
+        mut,
+        seeds = [b"seller_volume", taker.key().as_ref()],
+        bump
+    )]
+    pub taker_volume: UncheckedAccount<'info>,
+    
+    // Programmes nécessaires
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction CancelSwap
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"swap",
+            maker.key().as_ref(),
+            swap.swap_id.to_le_bytes().as_ref()
+        ],
+        bump = swap.bump,
+        close = maker
+    )]
+    pub swap: Account<'info, SwapOffer>,
+    
+    #[account(
+        mut,
+        constraint = maker.key() == swap.maker @ ErrorCode::UnauthorizedAccess
+    )]
+    pub maker: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction RegisterReferrer
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferrerEarnings::INIT_SPACE,  // Taille pour ReferrerEarnings
+        seeds = [b"referrer", referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_earnings: Account<'info, ReferrerEarnings>,
+    
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction InitializeFeeSchedule
+#[derive(Accounts)]
+pub struct InitializeFeeSchedule<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FeeSchedule::INIT_SPACE,  // Taille pour FeeSchedule
+        seeds = [b"fee_schedule"],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction InitializeSellerVolume
+#[derive(Accounts)]
+pub struct InitializeSellerVolume<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SellerVolume::INIT_SPACE,  // Taille pour SellerVolume
+        seeds = [b"seller_volume", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_volume: Account<'info, SellerVolume>,
+    
+    /// CHECK: Vendeur pour lequel le compte de volume est créé
+    pub seller: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction RegisterMembership
+#[derive(Accounts)]
+pub struct RegisterMembership<'info> {
+    #[account(
+        mut,
+        seeds = [b"seller_volume", seller.key().as_ref()],
+        bump = seller_volume.bump
+    )]
+    pub seller_volume: Account<'info, SellerVolume>,
+    
+    #[account(
+        seeds = [b"fee_schedule"],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    
+    pub seller: Signer<'info>,
+    
+    // Compte de token du vendeur contenant le NFT de membre
+    /// CHECK: Vérifié dans la logique avec les métadonnées
+    pub membership_token: AccountInfo<'info>,
+    
+    // Métadonnées Metaplex du NFT de membre
+    /// CHECK: Vérifié dans la logique
+    pub membership_metadata: AccountInfo<'info>,
+}
+
+// Structure pour l'instruction SetCuratedMode
+#[derive(Accounts)]
+pub struct SetCuratedMode<'info> {
+    #[account(
+        mut,
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    pub authority: Signer<'info>,
+}
+
+// Structure pour l'instruction InitializeCollectionConfig
+#[derive(Accounts)]
+pub struct InitializeCollectionConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + CollectionConfig::INIT_SPACE,  // Taille pour CollectionConfig
+        seeds = [b"collection", collection_mint.key().as_ref()],
+        bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    // Mint de la collection Metaplex
+    /// CHECK: Vérifié par le token program
+    #[account(owner = token::ID)]
+    pub collection_mint: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction UpdateCollectionConfig
+#[derive(Accounts)]
+pub struct UpdateCollectionConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection", collection_config.collection_mint.as_ref()],
+        bump = collection_config.bump
+    )]
+    pub collection_config: Account<'info, CollectionConfig>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    pub authority: Signer<'info>,
+}
+
+// Structure pour l'instruction ForceDelist
+#[derive(Accounts)]
+pub struct ForceDelist<'info> {
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Listing retiré, fermé au profit du vendeur
+    #[account(
+        mut,
+        seeds = [
+            b"listing",
+            listing.nft_mint.as_ref(),
+            listing.seller.as_ref()
+        ],
+        bump = listing.bump,
+        close = seller_wallet
+    )]
+    pub listing: Account<'info, NFTListing>,
+    
+    // Vendeur qui récupère le loyer du listing
+    /// CHECK: Nous vérifions juste que c'est le vendeur
+    #[account(
+        mut,
+        constraint = seller_wallet.key() == listing.seller
+    )]
+    pub seller_wallet: AccountInfo<'info>,
+    
+    // Modérateur (ou autorité) qui doit signer et paie l'enregistrement du retrait
+    #[account(
+        mut,
+        constraint = is_moderator(&marketplace, &moderator.key()) @ ErrorCode::UnauthorizedAccess
+    )]
+    pub moderator: Signer<'info>,
+    
+    // Enregistrement du retrait, qui bloque la remise en vente jusqu'à son approbation
+    #[account(
+        init,
+        payer = moderator,
+        space = 8 + DelistRecord::INIT_SPACE,
+        seeds = [b"delisted", listing.nft_mint.as_ref(), listing.seller.as_ref()],
+        bump
+    )]
+    pub delist_record: Account<'info, DelistRecord>,
+    
+    // Compte de token du vendeur, pour relever la quantité encore déléguée au listing
+    /// CHECK: Vérifié avec les contraintes
+    #[account(
+        owner = token::ID,
+        constraint = is_token_account_for(&seller_token_account, &listing.nft_mint, &listing.seller)?
+    )]
+    pub seller_token_account: AccountInfo<'info>,
+    
+    // Statistiques de la collection du listing (obligatoire si le listing a une collection)
+    /// CHECK: Adresse et contenu vérifiés dans l'instruction
+    #[account(mut)]
+    pub collection_stats: Option<UncheckedAccount<'info>>,
+    
+    // Statistiques globales de la marketplace (ignorées tant qu'elles n'ont pas été créées)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction ApproveRelist
+#[derive(Accounts)]
+pub struct ApproveRelist<'info> {
+    // Enregistrement du retrait, fermé au profit du signataire
+    #[account(
+        mut,
+        seeds = [b"delisted", delist_record.nft_mint.as_ref(), delist_record.seller.as_ref()],
+        bump = delist_record.bump,
+        close = moderator
+    )]
+    pub delist_record: Account<'info, DelistRecord>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    // Modérateur (ou autorité) qui doit signer
+    #[account(
+        mut,
+        constraint = is_moderator(&marketplace, &moderator.key()) @ ErrorCode::UnauthorizedAccess
+    )]
+    pub moderator: Signer<'info>,
+}
+
+// Structure pour l'instruction BlockMint
+#[derive(Accounts)]
+pub struct BlockMint<'info> {
+    #[account(
+        init,
+        payer = moderator,
+        space = 8 + MintBlocklistEntry::INIT_SPACE,  // Taille pour MintBlocklistEntry
+        seeds = [b"blocklist", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_blocklist: Account<'info, MintBlocklistEntry>,
+    
+    // Mint du NFT à bloquer
+    /// CHECK: Vérifié par le token program
+    #[account(owner = token::ID)]
+    pub nft_mint: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump
+    )]
+    pub marketplace: Account<'info, Marketplace>,
     
-    // Compte de token du vendeur contenant le NFT
-    /// CHECK: Vérifié avec les contraintes et dans la logique
+    // Modérateur (ou autorité) qui doit signer
     #[account(
-        mut, // CORRECTION: Ajout de mut pour permettre la réapprobation
-        owner = token::ID,
-        constraint = token::TokenAccount::try_deserialize(&mut &nft_token_account.data.borrow()[..])?.mint == listing.nft_mint,
-        constraint = token::TokenAccount::try_deserialize(&mut &nft_token_account.data.borrow()[..])?.owner == seller.key()
+        mut,
+        constraint = is_moderator(&marketplace, &moderator.key()) @ ErrorCode::UnauthorizedAccess
     )]
-    pub nft_token_account: AccountInfo<'info>,
+    pub moderator: Signer<'info>,
     
-    // Programmes nécessaires
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
-// Structure pour l'instruction BuyNFT
+// Structure pour l'instruction UnblockMint
 #[derive(Accounts)]
-pub struct BuyNFT<'info> {
-    // Compte marketplace
+pub struct UnblockMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"blocklist", mint_blocklist.nft_mint.as_ref()],
+        bump = mint_blocklist.bump,
+        close = moderator
+    )]
+    pub mint_blocklist: Account<'info, MintBlocklistEntry>,
+    
     #[account(
         seeds = [b"marketplace"],
         bump = marketplace.bump
     )]
     pub marketplace: Account<'info, Marketplace>,
     
-    // Compte de listing qui doit être actif
+    // Modérateur (ou autorité) qui doit signer
     #[account(
         mut,
-        seeds = [
-            b"listing", 
-            listing.nft_mint.as_ref(), 
-            listing.seller.as_ref()
-        ],
-        bump = listing.bump,
-        constraint = listing.active @ ErrorCode::ListingNotActive
+        constraint = is_moderator(&marketplace, &moderator.key()) @ ErrorCode::UnauthorizedAccess
     )]
-    pub listing: Account<'info, NFTListing>,
+    pub moderator: Signer<'info>,
+}
+
+// Structure pour l'instruction QueueChange
+#[derive(Accounts)]
+#[instruction(change_id: u64)]
+pub struct QueueChange<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingChange::INIT_SPACE,  // Taille pour PendingChange
+        seeds = [b"change", change_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingChange>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
     
-    // Acheteur qui doit signer
     #[account(mut)]
-    pub buyer: Signer<'info>,
+    pub authority: Signer<'info>,
     
-    // Vendeur qui reçoit le paiement
-    /// CHECK: Nous vérifions juste que c'est le vendeur
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction ExecuteChange
+#[derive(Accounts)]
+pub struct ExecuteChange<'info> {
     #[account(
         mut,
-        constraint = seller_wallet.key() == listing.seller
+        seeds = [b"change", pending_change.change_id.to_le_bytes().as_ref()],
+        bump = pending_change.bump,
+        close = authority
     )]
-    pub seller_wallet: AccountInfo<'info>,
+    pub pending_change: Account<'info, PendingChange>,
     
-    // Autorité de la marketplace qui reçoit les frais
-    /// CHECK: Compte qui recevra les frais
     #[account(
         mut,
-        constraint = marketplace_authority.key() == marketplace.authority
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
     )]
-    pub marketplace_authority: AccountInfo<'info>,
+    pub marketplace: Account<'info, Marketplace>,
     
-    // Compte de token du vendeur contenant le NFT
-    /// CHECK: Vérifié avec les contraintes
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    // Grille de frais (obligatoire pour un changement FeeSchedule)
     #[account(
         mut,
-        owner = token::ID,
-        constraint = token::TokenAccount::try_deserialize(&mut &seller_token_account.data.borrow()[..])?.mint == listing.nft_mint,
-        constraint = token::TokenAccount::try_deserialize(&mut &seller_token_account.data.borrow()[..])?.owner == listing.seller
+        seeds = [b"fee_schedule"],
+        bump = fee_schedule.bump
     )]
-    pub seller_token_account: AccountInfo<'info>,
+    pub fee_schedule: Option<Account<'info, FeeSchedule>>,
     
-    // Compte de token de l'acheteur qui recevra le NFT
-    /// CHECK: Vérifié avec les contraintes
+    // Configuration de la collection (obligatoire pour un changement CollectionFeeOverride)
     #[account(
         mut,
-        owner = token::ID,
-        constraint = token::TokenAccount::try_deserialize(&mut &buyer_token_account.data.borrow()[..])?.mint == listing.nft_mint,
-        constraint = token::TokenAccount::try_deserialize(&mut &buyer_token_account.data.borrow()[..])?.owner == buyer.key()
+        seeds = [b"collection", collection_config.collection_mint.as_ref()],
+        bump = collection_config.bump
     )]
-    pub buyer_token_account: AccountInfo<'info>,
-    
-    // Programmes nécessaires
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
+    pub collection_config: Option<Account<'info, CollectionConfig>>,
 }
 
-// Structure pour l'instruction CancelListing
+// Structure pour l'instruction CancelChange
 #[derive(Accounts)]
-pub struct CancelListing<'info> {
+pub struct CancelChange<'info> {
     #[account(
         mut,
-        seeds = [
-            b"listing", 
-            listing.nft_mint.as_ref(), 
-            seller.key().as_ref()
-        ],
-        bump = listing.bump,
-        constraint = listing.active @ ErrorCode::ListingNotActive
+        seeds = [b"change", pending_change.change_id.to_le_bytes().as_ref()],
+        bump = pending_change.bump,
+        close = authority
     )]
-    pub listing: Account<'info, NFTListing>,
+    pub pending_change: Account<'info, PendingChange>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
     
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+// Structure pour l'instruction MigrateMarketplace
+#[derive(Accounts)]
+pub struct MigrateMarketplace<'info> {
+    // Compte marketplace à l'ancien format (ne peut pas être désérialisé comme Marketplace)
+    /// CHECK: Adresse vérifiée par les seeds, contenu vérifié dans l'instruction
     #[account(
         mut,
-        constraint = seller.key() == listing.seller @ ErrorCode::UnauthorizedAccess
+        seeds = [b"marketplace"],
+        bump
     )]
-    pub seller: Signer<'info>,
+    pub marketplace: UncheckedAccount<'info>,
     
-    /// CHECK: Vérifié avec les contraintes
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction MigrateListing
+#[derive(Accounts)]
+pub struct MigrateListing<'info> {
+    // Listing à l'ancien format (ne peut pas être désérialisé comme NFTListing)
+    /// CHECK: Propriétaire, discriminateur et adresse vérifiés dans l'instruction
+    #[account(mut)]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction InitializeStats
+#[derive(Accounts)]
+pub struct InitializeStats<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarketplaceStats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, MarketplaceStats>,
+    
+    #[account(
+        seeds = [b"marketplace"],
+        bump = marketplace.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub marketplace: Account<'info, Marketplace>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction InitializeCollectionStats
+#[derive(Accounts)]
+#[instruction(collection: Pubkey)]
+pub struct InitializeCollectionStats<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CollectionStats::INIT_SPACE,
+        seeds = [b"collection_stats", collection.as_ref()],
+        bump
+    )]
+    pub collection_stats: Account<'info, CollectionStats>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Structure pour l'instruction RefreshCollectionFloor
+#[derive(Accounts)]
+pub struct RefreshCollectionFloor<'info> {
     #[account(
         mut,
-        owner = token::ID
+        seeds = [b"collection_stats", collection_stats.collection.as_ref()],
+        bump = collection_stats.bump
     )]
-    pub nft_token_account: AccountInfo<'info>,
+    pub collection_stats: Account<'info, CollectionStats>,
+}
+
+// Structure pour l'instruction InitializeTraderProfile
+#[derive(Accounts)]
+pub struct InitializeTraderProfile<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TraderProfile::INIT_SPACE,
+        seeds = [b"profile", wallet.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, TraderProfile>,
+    
+    /// CHECK: Portefeuille pour lequel le profil est créé
+    pub wallet: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
+// Structure pour l'instruction GetTraderProfile
+#[derive(Accounts)]
+pub struct GetTraderProfile<'info> {
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, TraderProfile>,
+}
+
 // Codes d'erreur personnalisés (équivalent à require/revert en Solidity)
 #[error_code]
 pub enum ErrorCode {
@@ -537,10 +4409,81 @@ pub enum ErrorCode {
     ListingNotActive,
     #[msg("Accès non autorisé")]
     UnauthorizedAccess,
+    #[msg("Dépassement de capacité dans le calcul")]
+    MathOverflow,
+    #[msg("Compte de token invalide")]
+    InvalidTokenAccount,
+    #[msg("Comptes fournis pour le bundle invalides")]
+    InvalidBundleAccounts,
+    #[msg("Le bundle contient trop de NFTs")]
+    BundleTooLarge,
+    #[msg("Le même NFT apparaît plusieurs fois dans le bundle")]
+    DuplicateBundleItem,
+    #[msg("Un NFT du bundle n'est plus disponible")]
+    BundleItemUnavailable,
+    #[msg("Comptes fournis pour le sweep invalides")]
+    InvalidSweepAccounts,
+    #[msg("Le listing ne peut pas être exécuté")]
+    ListingUnfillable,
+    #[msg("Le montant maximal de dépense est dépassé")]
+    MaxSpendExceeded,
+    #[msg("Comptes fournis pour l'échange invalides")]
+    InvalidSwapAccounts,
+    #[msg("L'échange contient trop de NFTs")]
+    SwapTooLarge,
+    #[msg("La demande de l'échange est invalide")]
+    InvalidSwapRequest,
+    #[msg("Le même NFT apparaît plusieurs fois dans l'échange")]
+    DuplicateSwapItem,
+    #[msg("Le NFT fourni ne correspond pas à la demande de l'échange")]
+    SwapItemMismatch,
+    #[msg("Compte de métadonnées Metaplex invalide")]
+    InvalidMetadataAccount,
+    #[msg("Quantité invalide")]
+    InvalidQuantity,
+    #[msg("Comptes du parrain invalides")]
+    InvalidReferrer,
+    #[msg("Les points de base ne peuvent pas dépasser 10000")]
+    InvalidFeeBps,
+    #[msg("Répartition des recettes invalide")]
+    InvalidPayoutSplits,
+    #[msg("Destinataires de la répartition des recettes invalides")]
+    InvalidPayoutRecipients,
+    #[msg("Grille de frais invalide")]
+    InvalidFeeSchedule,
+    #[msg("Preuve de détention du NFT de membre invalide")]
+    InvalidMembershipProof,
+    #[msg("La collection du NFT n'est pas autorisée sur la marketplace")]
+    CollectionNotAllowed,
+    #[msg("La configuration de collection ne correspond pas au listing")]
+    InvalidCollectionConfig,
+    #[msg("Cette adresse est déjà modératrice")]
+    ModeratorAlreadyExists,
+    #[msg("Cette adresse n'est pas modératrice")]
+    ModeratorNotFound,
+    #[msg("Nombre maximum de modérateurs atteint")]
+    TooManyModerators,
+    #[msg("Ce NFT est bloqué et ne peut pas être échangé")]
+    MintBlocked,
+    #[msg("Compte de liste de blocage invalide")]
+    InvalidBlocklistAccount,
+    #[msg("Le délai du changement n'est pas encore écoulé")]
+    TimelockNotElapsed,
+    #[msg("Le compte n'est pas à un format migrable")]
+    InvalidAccountVersion,
+    #[msg("Le compte est déjà au format actuel")]
+    AccountAlreadyMigrated,
+    #[msg("Compte de statistiques de collection invalide")]
+    InvalidCollectionStats,
+    #[msg("Le parrain ne peut pas être l'acheteur ou le vendeur")]
+    SelfReferral,
+    #[msg("Le NFT a été retiré par un modérateur: sa remise en vente doit être approuvée")]
+    RelistNotApproved,
 }
 
 // Définition des événements (équivalent aux events en Solidity)
 #[event]
+#[derive(Clone, Debug)]
 pub struct MarketplaceCreated {
     pub marketplace: Pubkey,
     pub authority: Pubkey,
@@ -548,21 +4491,29 @@ pub struct MarketplaceCreated {
 }
 
 #[event]
+#[derive(Clone, Debug)]
 pub struct NFTListed {
     pub listing: Pubkey,
     pub seller: Pubkey,
     pub nft_mint: Pubkey,
     pub price: u64,
+    pub quantity: u64,
 }
 
 #[event]
+#[derive(Clone, Debug)]
 pub struct NFTSold {
     pub listing: Pubkey,
     pub buyer: Pubkey,
     pub price: u64,
+    pub quantity: u64,
+    pub marketplace_fee: u64,     // Part des frais versée à l'autorité de la marketplace
+    pub referrer: Option<Pubkey>, // Parrain ayant apporté la vente, le cas échéant
+    pub referral_fee: u64,        // Part des frais versée au parrain
 }
 
 #[event]
+#[derive(Clone, Debug)]
 pub struct NFTListingCanceled {
     pub listing: Pubkey,
     pub seller: Pubkey,
@@ -570,9 +4521,225 @@ pub struct NFTListingCanceled {
 }
 
 #[event]
+#[derive(Clone, Debug)]
 pub struct NFTListingUpdated {
     pub listing: Pubkey,
     pub seller: Pubkey,
     pub nft_mint: Pubkey,
     pub price: u64,
+    pub quantity: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct BundleListed {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mints: Vec<Pubkey>,
+    pub price: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct BundleUpdated {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct BundleSold {
+    pub bundle: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mints: Vec<Pubkey>,
+    pub price: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct BundleCanceled {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct SweepCompleted {
+    pub buyer: Pubkey,
+    pub items_requested: u32,
+    pub items_filled: u32,
+    pub total_price: u64,
+    pub total_fees: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct SwapCreated {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub offered_mints: Vec<Pubkey>,
+    pub offered_lamports: u64,
+    pub requested_mints: Vec<Pubkey>,
+    pub requested_collection: Option<Pubkey>,
+    pub requested_count: u8,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct SwapAccepted {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub offered_mints: Vec<Pubkey>,
+    pub offered_lamports: u64,
+    pub received_mints: Vec<Pubkey>,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct SwapCanceled {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ReferralFeeUpdated {
+    pub marketplace: Pubkey,
+    pub referral_fee_bps: u16,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ReferrerRegistered {
+    pub referrer: Pubkey,
+    pub referrer_earnings: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct MembershipRegistered {
+    pub seller: Pubkey,
+    pub membership_token: Pubkey,
+    pub collection: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct FeeScheduleUpdated {
+    pub fee_schedule: Pubkey,
+    pub tiers: Vec<FeeTier>,
+    pub membership_collection: Option<Pubkey>,
+    pub holder_discount_bps: u16,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct FeeTierApplied {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub trailing_volume: u64,
+    pub fee_tier: Option<u8>,   // Indice du palier appliqué (None = frais de base)
+    pub holder_discount: bool,  // Réduction membre appliquée
+    pub fee_bps: u16,           // Taux effectif en points de base
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct CuratedModeUpdated {
+    pub marketplace: Pubkey,
+    pub curated: bool,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct CollectionConfigUpdated {
+    pub collection_config: Pubkey,
+    pub collection_mint: Pubkey,
+    pub enabled: bool,
+    pub fee_override: Option<u16>,
+    pub royalty_policy: RoyaltyPolicy,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ModeratorAdded {
+    pub marketplace: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ModeratorRemoved {
+    pub marketplace: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ListingForceDelisted {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub moderator: Pubkey,
+    pub reason_code: u16,
+    pub delegated: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct RelistApproved {
+    pub nft_mint: Pubkey,
+    pub seller: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct MintBlocked {
+    pub nft_mint: Pubkey,
+    pub moderator: Pubkey,
+    pub reason: u16,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct MintUnblocked {
+    pub nft_mint: Pubkey,
+    pub moderator: Pubkey,
+    pub reason: u16,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ChangeQueued {
+    pub marketplace: Pubkey,
+    pub change_id: u64,
+    pub change: MarketplaceChange,
+    pub eta: i64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ChangeExecuted {
+    pub marketplace: Pubkey,
+    pub change_id: u64,
+    pub change: MarketplaceChange,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ChangeCanceled {
+    pub marketplace: Pubkey,
+    pub change_id: u64,
+    pub change: MarketplaceChange,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
 }
\ No newline at end of file
@@ -0,0 +1,205 @@
+//! Instructions portant sur plusieurs NFTs (bundles, sweeps et échanges) et leurs remaining_accounts.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_nft_marketplace::{accounts, instruction};
+use solana_nft_marketplace_sdk::pda;
+
+use super::{program_instruction, with_remaining, Env};
+
+pub fn bundle_address(seller: &Pubkey, bundle_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"bundle", seller.as_ref(), bundle_id.to_le_bytes().as_ref()],
+        &solana_nft_marketplace::ID,
+    )
+    .0
+}
+
+pub fn swap_address(maker: &Pubkey, swap_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"swap", maker.as_ref(), swap_id.to_le_bytes().as_ref()],
+        &solana_nft_marketplace::ID,
+    )
+    .0
+}
+
+// Triplets (mint, compte de token, liste de blocage) d'un bundle
+pub fn bundle_items(seller: &Pubkey, mints: &[Pubkey]) -> Vec<AccountMeta> {
+    mints
+        .iter()
+        .flat_map(|mint| {
+            [
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(get_associated_token_address(seller, mint), false),
+                AccountMeta::new_readonly(pda::mint_blocklist_address(mint).0, false),
+            ]
+        })
+        .collect()
+}
+
+// Comptes d'un bundle en mode curé: chaque triplet est suivi des métadonnées et de la configuration de collection
+pub fn curated_bundle_items(seller: &Pubkey, collection: &Pubkey, mints: &[Pubkey]) -> Vec<AccountMeta> {
+    bundle_items(seller, mints)
+        .chunks(3)
+        .zip(mints)
+        .flat_map(|(item, mint)| {
+            let mut item = item.to_vec();
+            item.push(AccountMeta::new_readonly(pda::metadata_address(mint).0, false));
+            item.push(AccountMeta::new_readonly(pda::collection_config_address(collection).0, false));
+            item
+        })
+        .collect()
+}
+
+pub fn list_bundle(seller: &Pubkey, bundle_id: u64, price: u64, items: &[AccountMeta]) -> Instruction {
+    let instruction = program_instruction(
+        accounts::ListBundle {
+            bundle: bundle_address(seller, bundle_id),
+            marketplace: pda::marketplace_address().0,
+            seller: *seller,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ListBundle { bundle_id, price },
+    );
+    with_remaining(instruction, items)
+}
+
+pub fn buy_bundle(env: &Env, buyer: &Pubkey, seller: &Pubkey, bundle_id: u64, items: &[AccountMeta]) -> Instruction {
+    let instruction = program_instruction(
+        accounts::BuyBundle {
+            marketplace: pda::marketplace_address().0,
+            bundle: bundle_address(seller, bundle_id),
+            buyer: *buyer,
+            seller_wallet: *seller,
+            treasury: env.marketplace().treasury,
+            buyer_profile: pda::trader_profile_address(buyer).0,
+            seller_profile: pda::trader_profile_address(seller).0,
+            seller_volume: pda::seller_volume_address(seller).0,
+            fee_schedule: pda::fee_schedule_address().0,
+            seller_membership_token: None,
+            stats: pda::stats_address().0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BuyBundle {},
+    );
+    with_remaining(instruction, items)
+}
+
+// Triplets (compte du vendeur, compte de l'acheteur, liste de blocage) d'un achat de bundle
+pub fn bundle_purchase_items(seller: &Pubkey, buyer: &Pubkey, mints: &[Pubkey]) -> Vec<AccountMeta> {
+    mints
+        .iter()
+        .flat_map(|mint| {
+            [
+                AccountMeta::new(get_associated_token_address(seller, mint), false),
+                AccountMeta::new(get_associated_token_address(buyer, mint), false),
+                AccountMeta::new_readonly(pda::mint_blocklist_address(mint).0, false),
+            ]
+        })
+        .collect()
+}
+
+pub fn buy_many(env: &Env, buyer: &Pubkey, max_total_price: u64, skip_unfillable: bool, items: &[AccountMeta]) -> Instruction {
+    let instruction = program_instruction(
+        accounts::BuyMany {
+            marketplace: pda::marketplace_address().0,
+            buyer: *buyer,
+            treasury: env.marketplace().treasury,
+            buyer_profile: pda::trader_profile_address(buyer).0,
+            fee_schedule: pda::fee_schedule_address().0,
+            stats: pda::stats_address().0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BuyMany {
+            max_total_price,
+            skip_unfillable,
+        },
+    );
+    with_remaining(instruction, items)
+}
+
+// Groupe de comptes d'un listing sans répartition pour buy_many (vendeur sans NFT de membre enregistré)
+pub fn sweep_item(env: &Env, buyer: &Pubkey, listing: &Pubkey) -> Vec<AccountMeta> {
+    let state = env.listing(listing);
+    let (collection_stats, collection_config) = match state.collection {
+        Some(collection) => (
+            pda::collection_stats_address(&collection).0,
+            pda::collection_config_address(&collection).0,
+        ),
+        None => (Pubkey::new_unique(), Pubkey::new_unique()),
+    };
+    vec![
+        AccountMeta::new(*listing, false),
+        AccountMeta::new(state.seller, false),
+        AccountMeta::new(get_associated_token_address(&state.seller, &state.nft_mint), false),
+        AccountMeta::new(get_associated_token_address(buyer, &state.nft_mint), false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&state.nft_mint).0, false),
+        AccountMeta::new(collection_stats, false),
+        AccountMeta::new(pda::trader_profile_address(&state.seller).0, false),
+        AccountMeta::new(pda::seller_volume_address(&state.seller).0, false),
+        AccountMeta::new_readonly(collection_config, false),
+        AccountMeta::new_readonly(Pubkey::new_unique(), false),
+    ]
+}
+
+pub fn create_swap(
+    maker: &Pubkey,
+    swap_id: u64,
+    requested_mints: Vec<Pubkey>,
+    requested_collection: Option<Pubkey>,
+    requested_count: u8,
+    items: &[AccountMeta],
+) -> Instruction {
+    let instruction = program_instruction(
+        accounts::CreateSwap {
+            swap: swap_address(maker, swap_id),
+            maker: *maker,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateSwap {
+            swap_id,
+            offered_lamports: 0,
+            requested_mints,
+            requested_collection,
+            requested_count,
+        },
+    );
+    with_remaining(instruction, items)
+}
+
+// Triplets (mint, compte du créateur, escrow) des NFTs offerts
+pub fn swap_items(maker: &Pubkey, swap_id: u64, mints: &[Pubkey]) -> Vec<AccountMeta> {
+    let swap = swap_address(maker, swap_id);
+    mints
+        .iter()
+        .flat_map(|mint| {
+            [
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(get_associated_token_address(maker, mint), false),
+                AccountMeta::new(get_associated_token_address(&swap, mint), false),
+            ]
+        })
+        .collect()
+}
+
+pub fn accept_swap(maker: &Pubkey, taker: &Pubkey, swap_id: u64, items: &[AccountMeta]) -> Instruction {
+    let instruction = program_instruction(
+        accounts::AcceptSwap {
+            swap: swap_address(maker, swap_id),
+            maker: *maker,
+            taker: *taker,
+            taker_volume: pda::seller_volume_address(taker).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::AcceptSwap {},
+    );
+    with_remaining(instruction, items)
+}
@@ -0,0 +1,501 @@
+//! Outils communs aux tests du programme: runtime natif et comptes de départ.
+
+#![allow(dead_code)]
+
+pub mod batch;
+pub mod runtime;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+use solana_nft_marketplace::{
+    accounts, instruction, FeeTier, Marketplace, MarketplaceChange, MarketplaceStats, NFTListing, PendingChange,
+    RoyaltyPolicy,
+};
+use solana_nft_marketplace_sdk::{instructions, pda};
+
+pub use runtime::{AccountState, InstructionError, TestRuntime, TransactionFailure, TransactionOutcome};
+
+pub type TransactionResult = std::result::Result<TransactionOutcome, TransactionFailure>;
+
+// Solde initial des portefeuilles de test
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+// Frais de la marketplace utilisés par défaut (2,5 %)
+pub const DEFAULT_FEE_BPS: u16 = 250;
+
+// Construire une instruction du programme à partir de ses comptes Anchor et de ses données
+pub fn program_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: solana_nft_marketplace::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Ajouter des remaining_accounts à une instruction
+pub fn with_remaining(mut instruction: Instruction, remaining: &[AccountMeta]) -> Instruction {
+    instruction.accounts.extend_from_slice(remaining);
+    instruction
+}
+
+// Retirer la signature d'un compte de l'instruction
+pub fn without_signer(mut instruction: Instruction, signer: &Pubkey) -> Instruction {
+    for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == *signer) {
+        meta.is_signer = false;
+    }
+    instruction
+}
+
+// Remplacer un compte de l'instruction par un autre (mêmes droits)
+pub fn replace_account(mut instruction: Instruction, from: &Pubkey, to: Pubkey) -> Instruction {
+    for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == *from) {
+        meta.pubkey = to;
+    }
+    instruction
+}
+
+// Vérifier qu'une transaction a échoué avec le code d'erreur attendu
+#[track_caller]
+pub fn assert_error(result: TransactionResult, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Ok(outcome) => panic!("expected error {code}, transaction succeeded: {:#?}", outcome.logs),
+        Err(failure) => assert_eq!(
+            failure.custom_code(),
+            Some(code),
+            "unexpected error {:?}, logs: {:#?}",
+            failure.error,
+            failure.logs
+        ),
+    }
+}
+
+// Vérifier qu'une transaction a échoué avec une erreur du runtime ou d'un programme natif
+#[track_caller]
+pub fn assert_program_error(result: TransactionResult, error: ProgramError) {
+    match result {
+        Ok(outcome) => panic!("expected {error:?}, transaction succeeded: {:#?}", outcome.logs),
+        Err(failure) => assert_eq!(
+            failure.error,
+            InstructionError::Program(error),
+            "unexpected error, logs: {:#?}",
+            failure.logs
+        ),
+    }
+}
+
+/// Marketplace initialisée dans un runtime de test.
+pub struct Env {
+    pub runtime: TestRuntime,
+    pub authority: Pubkey,
+}
+
+impl Env {
+    // Marketplace aux frais par défaut, avec ses statistiques
+    pub fn new() -> Self {
+        Self::with_fee(DEFAULT_FEE_BPS)
+    }
+    
+    pub fn with_fee(fee_bps: u16) -> Self {
+        let mut runtime = TestRuntime::new();
+        let authority = Pubkey::new_unique();
+        runtime.airdrop(&authority, WALLET_LAMPORTS);
+        
+        let mut env = Env { runtime, authority };
+        env.send(&[
+            instructions::initialize_marketplace(authority, fee_bps),
+            instructions::initialize_stats(authority),
+        ])
+        .expect("marketplace initialization failed");
+        env
+    }
+    
+    pub fn send(&mut self, instructions: &[Instruction]) -> TransactionResult {
+        self.runtime.process(instructions)
+    }
+    
+    pub fn send_one(&mut self, instruction: Instruction) -> TransactionResult {
+        self.runtime.process(&[instruction])
+    }
+    
+    // Portefeuille alimenté en SOL
+    pub fn wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.runtime.airdrop(&wallet, WALLET_LAMPORTS);
+        wallet
+    }
+    
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.runtime.lamports(address)
+    }
+    
+    pub fn exists(&self, address: &Pubkey) -> bool {
+        self.runtime.account(address).is_some()
+    }
+    
+    // Décoder un compte Anchor du programme
+    pub fn get<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.runtime.account(address).unwrap_or_else(|| panic!("account {address} not found"));
+        T::try_deserialize(&mut &account.data[..]).expect("undecodable account")
+    }
+    
+    pub fn marketplace(&self) -> Marketplace {
+        self.get(&pda::marketplace_address().0)
+    }
+    
+    pub fn stats(&self) -> MarketplaceStats {
+        self.get(&pda::stats_address().0)
+    }
+    
+    pub fn listing(&self, address: &Pubkey) -> NFTListing {
+        self.get(address)
+    }
+    
+    // Créer un mint SPL (0 décimale) au nom de l'autorité de la marketplace
+    pub fn mint(&mut self, supply: u64) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let state = spl_token::state::Mint {
+            mint_authority: COption::Some(self.authority),
+            supply,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        state.pack_into_slice(&mut data);
+        self.set_rent_exempt(mint, data, spl_token::ID);
+        mint
+    }
+    
+    // Créer le compte de token associé d'un portefeuille avec un solde donné
+    pub fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let address = get_associated_token_address(owner, mint);
+        let state = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        state.pack_into_slice(&mut data);
+        self.set_rent_exempt(address, data, spl_token::ID);
+        address
+    }
+    
+    // Créer un NFT détenu par un portefeuille (retourne son mint)
+    pub fn nft(&mut self, owner: &Pubkey) -> Pubkey {
+        self.edition(owner, 1)
+    }
+    
+    // Créer une édition (SFT) de plusieurs unités détenues par un portefeuille
+    pub fn edition(&mut self, owner: &Pubkey, supply: u64) -> Pubkey {
+        let mint = self.mint(supply);
+        self.token_account(owner, &mint, supply);
+        mint
+    }
+    
+    pub fn token(&self, address: &Pubkey) -> spl_token::state::Account {
+        let account = self.runtime.account(address).unwrap_or_else(|| panic!("token account {address} not found"));
+        spl_token::state::Account::unpack(&account.data).expect("undecodable token account")
+    }
+    
+    // Solde de token d'un portefeuille pour un mint (0 si son compte associé n'existe pas)
+    pub fn balance(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        let address = get_associated_token_address(owner, mint);
+        self.runtime.account(&address).map_or(0, |_| self.token(&address).amount)
+    }
+    
+    // Créer les métadonnées Metaplex d'un mint, avec une collection éventuelle (vérifiée ou non)
+    pub fn metadata(&mut self, mint: &Pubkey, collection: Option<(Pubkey, bool)>) -> Pubkey {
+        let address = pda::metadata_address(mint).0;
+        let metadata = mpl_token_metadata::accounts::Metadata {
+            key: mpl_token_metadata::types::Key::MetadataV1,
+            update_authority: self.authority,
+            mint: *mint,
+            name: "Test NFT".to_string(),
+            symbol: "TEST".to_string(),
+            uri: "https://example.com/nft.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: None,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(mpl_token_metadata::types::TokenStandard::NonFungible),
+            collection: collection.map(|(key, verified)| mpl_token_metadata::types::Collection { verified, key }),
+            uses: None,
+            collection_details: None,
+            programmable_config: None,
+        };
+        let data = metadata.try_to_vec().expect("metadata serialization failed");
+        self.set_rent_exempt(address, data, mpl_token_metadata::ID);
+        address
+    }
+    
+    // NFT d'une collection vérifiée, avec ses métadonnées
+    pub fn collection_nft(&mut self, owner: &Pubkey, collection: &Pubkey) -> Pubkey {
+        let mint = self.nft(owner);
+        self.metadata(&mint, Some((*collection, true)));
+        mint
+    }
+    
+    fn set_rent_exempt(&mut self, address: Pubkey, data: Vec<u8>, owner: Pubkey) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.runtime.set_account(address, AccountState::new(lamports, data, owner));
+    }
+    
+    // Mettre en vente une quantité d'un mint; le listing doit réussir
+    pub fn list(&mut self, seller: &Pubkey, mint: &Pubkey, price: u64, quantity: u64) -> Pubkey {
+        let mut list = instructions::ListNft::new(*seller, *mint, price);
+        list.quantity = quantity;
+        self.send_one(list.instruction()).expect("listing failed");
+        pda::listing_address(mint, seller).0
+    }
+    
+    // Instruction d'achat d'un listing existant
+    pub fn buy_instruction(&self, buyer: &Pubkey, listing: &Pubkey, quantity: u64) -> Instruction {
+        let mut buy = instructions::BuyNft::new(*buyer, *listing, &self.listing(listing), &self.marketplace());
+        buy.quantity = quantity;
+        buy.instruction()
+    }
+    
+    // Acheter un listing (le compte de token de l'acheteur est créé s'il n'existe pas)
+    pub fn buy(&mut self, buyer: &Pubkey, listing: &Pubkey, quantity: u64) -> TransactionResult {
+        let mint = self.listing(listing).nft_mint;
+        if !self.exists(&get_associated_token_address(buyer, &mint)) {
+            self.token_account(buyer, &mint, 0);
+        }
+        let instruction = self.buy_instruction(buyer, listing, quantity);
+        self.send_one(instruction)
+    }
+    
+    pub fn cancel(&mut self, listing: &Pubkey) -> TransactionResult {
+        let instruction = instructions::CancelListing::new(*listing, &self.listing(listing)).instruction();
+        self.send_one(instruction)
+    }
+    
+    pub fn update(&mut self, listing: &Pubkey, price: u64, quantity: u64) -> TransactionResult {
+        let instruction = instructions::UpdateListing::new(*listing, &self.listing(listing), price, quantity).instruction();
+        self.send_one(instruction)
+    }
+}
+
+// Instructions d'administration et comptes optionnels
+impl Env {
+    pub fn change_address(change_id: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"change", change_id.to_le_bytes().as_ref()], &solana_nft_marketplace::ID).0
+    }
+    
+    pub fn queue_change(&self, change_id: u64, change: MarketplaceChange) -> Instruction {
+        program_instruction(
+            accounts::QueueChange {
+                pending_change: Self::change_address(change_id),
+                marketplace: pda::marketplace_address().0,
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::QueueChange { change_id, change },
+        )
+    }
+    
+    // Les comptes optionnels sont déduits du changement en file
+    pub fn execute_change(&self, change_id: u64) -> Instruction {
+        let pending_change = Self::change_address(change_id);
+        let change = self.exists(&pending_change).then(|| self.get::<PendingChange>(&pending_change).change);
+        program_instruction(
+            accounts::ExecuteChange {
+                pending_change,
+                marketplace: pda::marketplace_address().0,
+                authority: self.authority,
+                fee_schedule: matches!(change, Some(MarketplaceChange::FeeSchedule { .. }))
+                    .then(|| pda::fee_schedule_address().0),
+                collection_config: match change {
+                    Some(MarketplaceChange::CollectionFeeOverride { collection, .. }) => {
+                        Some(pda::collection_config_address(&collection).0)
+                    }
+                    _ => None,
+                },
+            },
+            instruction::ExecuteChange {},
+        )
+    }
+    
+    // Appliquer un changement en file après l'écoulement du délai
+    pub fn apply_change(&mut self, change_id: u64, change: MarketplaceChange) {
+        let queue = self.queue_change(change_id, change);
+        self.send_one(queue).expect("queue_change failed");
+        self.runtime.advance_clock(solana_nft_marketplace::CHANGE_TIMELOCK_DELAY);
+        let execute = self.execute_change(change_id);
+        self.send_one(execute).expect("execute_change failed");
+    }
+    
+    pub fn register_referrer(&self, referrer: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::RegisterReferrer {
+                referrer_earnings: pda::referrer_earnings_address(referrer).0,
+                referrer: *referrer,
+                system_program: system_program::ID,
+            },
+            instruction::RegisterReferrer {},
+        )
+    }
+    
+    pub fn initialize_fee_schedule(&self) -> Instruction {
+        program_instruction(
+            accounts::InitializeFeeSchedule {
+                fee_schedule: pda::fee_schedule_address().0,
+                marketplace: pda::marketplace_address().0,
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeFeeSchedule {},
+        )
+    }
+    
+    // Créer la grille de frais puis y appliquer les paliers après l'écoulement du délai
+    // (changement u64::MAX, pour ne pas entrer en conflit avec les identifiants des tests)
+    pub fn set_fee_schedule(
+        &mut self,
+        tiers: Vec<FeeTier>,
+        membership_collection: Option<Pubkey>,
+        holder_discount_bps: u16,
+    ) {
+        let initialize = self.initialize_fee_schedule();
+        self.send_one(initialize).expect("initialize_fee_schedule failed");
+        self.apply_change(u64::MAX, MarketplaceChange::FeeSchedule { tiers, membership_collection, holder_discount_bps });
+    }
+    
+    pub fn initialize_seller_volume(&self, seller: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::InitializeSellerVolume {
+                seller_volume: pda::seller_volume_address(seller).0,
+                seller: *seller,
+                payer: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeSellerVolume {},
+        )
+    }
+    
+    // Enregistrer le NFT de membre détenu par le vendeur dans son compte de volume
+    pub fn register_membership(&self, seller: &Pubkey, membership_mint: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::RegisterMembership {
+                seller_volume: pda::seller_volume_address(seller).0,
+                fee_schedule: pda::fee_schedule_address().0,
+                seller: *seller,
+                membership_token: get_associated_token_address(seller, membership_mint),
+                membership_metadata: pda::metadata_address(membership_mint).0,
+            },
+            instruction::RegisterMembership {},
+        )
+    }
+    
+    pub fn set_curated_mode(&self, curated: bool) -> Instruction {
+        program_instruction(
+            accounts::SetCuratedMode {
+                marketplace: pda::marketplace_address().0,
+                authority: self.authority,
+            },
+            instruction::SetCuratedMode { curated },
+        )
+    }
+    
+    pub fn initialize_collection_config(&self, collection: &Pubkey, enabled: bool) -> Instruction {
+        program_instruction(
+            accounts::InitializeCollectionConfig {
+                collection_config: pda::collection_config_address(collection).0,
+                collection_mint: *collection,
+                marketplace: pda::marketplace_address().0,
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeCollectionConfig {
+                enabled,
+                royalty_policy: RoyaltyPolicy::Optional,
+            },
+        )
+    }
+    
+    pub fn initialize_collection_stats(&self, collection: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::InitializeCollectionStats {
+                collection_stats: pda::collection_stats_address(collection).0,
+                payer: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeCollectionStats { collection: *collection },
+        )
+    }
+    
+    pub fn initialize_trader_profile(&self, wallet: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::InitializeTraderProfile {
+                profile: pda::trader_profile_address(wallet).0,
+                wallet: *wallet,
+                payer: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeTraderProfile {},
+        )
+    }
+    
+    pub fn block_mint(&self, moderator: &Pubkey, nft_mint: &Pubkey, reason: u16) -> Instruction {
+        program_instruction(
+            accounts::BlockMint {
+                mint_blocklist: pda::mint_blocklist_address(nft_mint).0,
+                nft_mint: *nft_mint,
+                marketplace: pda::marketplace_address().0,
+                moderator: *moderator,
+                system_program: system_program::ID,
+            },
+            instruction::BlockMint { reason },
+        )
+    }
+    
+    pub fn force_delist(&self, moderator: &Pubkey, listing: &Pubkey, reason_code: u16) -> Instruction {
+        let listing_state = self.listing(listing);
+        program_instruction(
+            accounts::ForceDelist {
+                marketplace: pda::marketplace_address().0,
+                listing: *listing,
+                seller_wallet: listing_state.seller,
+                moderator: *moderator,
+                delist_record: pda::delist_record_address(&listing_state.nft_mint, &listing_state.seller).0,
+                seller_token_account: get_associated_token_address(&listing_state.seller, &listing_state.nft_mint),
+                collection_stats: listing_state
+                    .collection
+                    .map(|collection| pda::collection_stats_address(&collection).0),
+                stats: pda::stats_address().0,
+                system_program: system_program::ID,
+            },
+            instruction::ForceDelist { reason_code },
+        )
+    }
+    
+    pub fn approve_relist(&self, moderator: &Pubkey, nft_mint: &Pubkey, seller: &Pubkey) -> Instruction {
+        program_instruction(
+            accounts::ApproveRelist {
+                delist_record: pda::delist_record_address(nft_mint, seller).0,
+                marketplace: pda::marketplace_address().0,
+                moderator: *moderator,
+            },
+            instruction::ApproveRelist {},
+        )
+    }
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,520 @@
+//! Exécution native des instructions, à la manière de `processor!` de `solana-program-test`.
+//!
+//! Le programme est appelé directement via `entry` sur un tampon sérialisé au format du loader
+//! BPF (ce qui permet `realloc` et `assign`), et les syscalls sont remplacés par des stubs qui
+//! fournissent l'horloge, le loyer, les logs, les données de retour et les CPIs vers les
+//! programmes System, SPL Token et Associated Token (exécutés nativement eux aussi).
+//!
+//! Vérifications reproduites du runtime: signatures et droits d'écriture des CPIs (y compris les
+//! signatures de PDA), conservation des lamports par instruction, comptes en lecture seule
+//! inchangés, atomicité des transactions. Les unités de calcul ne sont pas mesurées.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::program_utils::limited_deserialize;
+use anchor_lang::solana_program::system_instruction::{SystemError, SystemInstruction};
+use anchor_lang::solana_program::{bpf_loader, system_program};
+use anchor_lang::{Discriminator, Event};
+
+// Loader des programmes natifs (propriétaire du System program)
+const NATIVE_LOADER_ID: &str = "NativeLoader1111111111111111111111111111111";
+
+// Taille maximale des données d'une instruction système
+const SYSTEM_INSTRUCTION_LIMIT: u64 = 1232;
+
+/// État d'un compte entre deux transactions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl AccountState {
+    pub fn new(lamports: u64, data: Vec<u8>, owner: Pubkey) -> Self {
+        AccountState {
+            lamports,
+            data,
+            owner,
+            executable: false,
+        }
+    }
+    
+    fn program(loader: Pubkey) -> Self {
+        AccountState {
+            lamports: 1,
+            data: Vec::new(),
+            owner: loader,
+            executable: true,
+        }
+    }
+}
+
+impl Default for AccountState {
+    fn default() -> Self {
+        AccountState::new(0, Vec::new(), system_program::ID)
+    }
+}
+
+/// Erreur d'une instruction, telle que le runtime la rapporterait.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InstructionError {
+    Program(ProgramError),           // Erreur renvoyée par le programme (ou un programme appelé par CPI)
+    UnbalancedInstruction,           // La somme des lamports a changé
+    ReadonlyAccountModified(Pubkey), // Un compte en lecture seule a été modifié
+}
+
+/// Échec d'une transaction: toutes ses instructions sont annulées.
+#[derive(Clone, Debug)]
+pub struct TransactionFailure {
+    pub instruction_index: usize,
+    pub error: InstructionError,
+    pub logs: Vec<String>,
+}
+
+impl TransactionFailure {
+    /// Code d'erreur personnalisé (erreurs du programme et d'Anchor), s'il y en a un.
+    pub fn custom_code(&self) -> Option<u32> {
+        match self.error {
+            InstructionError::Program(ProgramError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+/// Résultat d'une transaction réussie.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionOutcome {
+    pub logs: Vec<String>,
+    pub events: Vec<Vec<u8>>,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl TransactionOutcome {
+    /// Événements Anchor d'un type donné, dans l'ordre d'émission.
+    pub fn events<E: Event + Discriminator>(&self) -> Vec<E> {
+        self.events
+            .iter()
+            .filter(|data| data.len() >= 8 && data[..8] == E::DISCRIMINATOR)
+            .map(|data| E::deserialize(&mut &data[8..]).expect("undecodable event"))
+            .collect()
+    }
+}
+
+// Contexte d'exécution du thread de test (les stubs de syscalls sont globaux)
+#[derive(Default)]
+struct InvokeContext {
+    clock: Clock,
+    programs: Vec<Pubkey>,
+    logs: Vec<String>,
+    events: Vec<Vec<u8>>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<InvokeContext> = RefCell::new(InvokeContext::default());
+}
+
+fn with_context<R>(f: impl FnOnce(&mut InvokeContext) -> R) -> R {
+    CONTEXT.with(|context| f(&mut context.borrow_mut()))
+}
+
+struct NativeStubs;
+
+impl SyscallStubs for NativeStubs {
+    fn sol_log(&self, message: &str) {
+        with_context(|context| context.logs.push(format!("Program log: {message}")));
+    }
+    
+    fn sol_log_compute_units(&self) {}
+    
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        invoke(instruction, account_infos, signers_seeds)
+    }
+    
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = with_context(|context| context.clock.clone());
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+    
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+    
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        with_context(|context| context.return_data.clone())
+    }
+    
+    fn sol_set_return_data(&self, data: &[u8]) {
+        with_context(|context| {
+            let program_id = *context.programs.last().expect("no program is executing");
+            context.return_data = (!data.is_empty()).then(|| (program_id, data.to_vec()));
+        });
+    }
+    
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        with_context(|context| {
+            context.logs.push(format!("Program data: {} field(s)", fields.len()));
+            context.events.extend(fields.iter().map(|field| field.to_vec()));
+        });
+    }
+    
+    fn sol_get_stack_height(&self) -> u64 {
+        with_context(|context| context.programs.len() as u64)
+    }
+}
+
+// Installer les stubs une seule fois pour tout le processus de test
+fn install_stubs() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(NativeStubs));
+    });
+}
+
+// CPI: vérifier les droits transmis puis exécuter le programme appelé sur les mêmes comptes
+fn invoke(instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let caller = with_context(|context| *context.programs.last().expect("no program is executing"));
+    let pda_signers = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+    
+    let mut accounts = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let info = account_infos
+            .iter()
+            .find(|info| *info.key == meta.pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if meta.is_signer && !info.is_signer && !pda_signers.contains(&meta.pubkey) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if meta.is_writable && !info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        let mut info = info.clone();
+        info.is_signer = meta.is_signer;
+        info.is_writable = meta.is_writable;
+        accounts.push(info);
+    }
+    
+    with_context(|context| {
+        context.programs.push(instruction.program_id);
+        context.return_data = None;
+    });
+    let result = process_native(&instruction.program_id, &accounts, &instruction.data);
+    with_context(|context| context.programs.pop());
+    result
+}
+
+// Programmes natifs appelables par CPI
+fn process_native(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if *program_id == system_program::ID {
+        process_system_instruction(accounts, data)
+    } else if *program_id == spl_token::ID {
+        spl_token::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == spl_associated_token_account::ID {
+        spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+// Sous-ensemble du System program utilisé par Anchor, le programme et l'Associated Token program
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        limited_deserialize(data, SYSTEM_INSTRUCTION_LIMIT).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let account = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+    
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let (from, to) = (account(0)?, account(1)?);
+            if to.lamports() > 0 || !to.data_is_empty() || *to.owner != system_program::ID {
+                return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+            }
+            transfer(from, to, lamports)?;
+            allocate(to, space)?;
+            assign(to, &owner)
+        }
+        SystemInstruction::Transfer { lamports } => transfer(account(0)?, account(1)?, lamports),
+        SystemInstruction::Allocate { space } => allocate(account(0)?, space),
+        SystemInstruction::Assign { owner } => assign(account(0)?, &owner),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !from.is_writable || !to.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *from.owner != system_program::ID || !from.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from.lamports() < lamports {
+        return Err(ProgramError::Custom(SystemError::ResultWithNegativeLamports as u32));
+    }
+    
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+    }
+    account.realloc(space as usize, true)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if *account.owner == *owner {
+        return Ok(());
+    }
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+// Sérialiser les comptes d'une instruction au format du loader BPF (sans doublons)
+fn serialize(program_id: &Pubkey, keys: &[(Pubkey, bool, bool)], states: &[AccountState], positions: &[usize], data: &[u8]) -> Vec<u64> {
+    let mut buffer: Vec<u8> = Vec::new();
+    buffer.extend_from_slice(&(positions.len() as u64).to_le_bytes());
+    
+    let mut serialized = vec![false; keys.len()];
+    for (meta_index, &position) in positions.iter().enumerate() {
+        if serialized[position] {
+            let first = positions.iter().position(|&other| other == position).unwrap();
+            debug_assert!(first < meta_index);
+            buffer.push(first as u8);
+            buffer.extend_from_slice(&[0; 7]);
+            continue;
+        }
+        serialized[position] = true;
+        
+        let (key, is_signer, is_writable) = &keys[position];
+        let state = &states[position];
+        buffer.push(u8::MAX);
+        buffer.push(*is_signer as u8);
+        buffer.push(*is_writable as u8);
+        buffer.push(state.executable as u8);
+        buffer.extend_from_slice(&[0; 4]);
+        buffer.extend_from_slice(key.as_ref());
+        buffer.extend_from_slice(state.owner.as_ref());
+        buffer.extend_from_slice(&state.lamports.to_le_bytes());
+        buffer.extend_from_slice(&(state.data.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(&state.data);
+        buffer.resize(buffer.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        buffer.resize(buffer.len().next_multiple_of(8), 0);
+        buffer.extend_from_slice(&u64::MAX.to_le_bytes());
+    }
+    
+    buffer.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    buffer.extend_from_slice(data);
+    buffer.extend_from_slice(program_id.as_ref());
+    
+    // Tampon aligné sur 8 octets, comme la région d'entrée de la VM
+    let mut aligned = vec![0u64; buffer.len().div_ceil(8)];
+    unsafe {
+        std::ptr::copy_nonoverlapping(buffer.as_ptr(), aligned.as_mut_ptr() as *mut u8, buffer.len());
+    }
+    aligned
+}
+
+/// Banque de comptes en mémoire qui exécute des transactions sur le programme.
+pub struct TestRuntime {
+    accounts: HashMap<Pubkey, AccountState>,
+    clock: Clock,
+}
+
+impl Default for TestRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestRuntime {
+    pub fn new() -> Self {
+        install_stubs();
+        
+        let mut accounts = HashMap::new();
+        accounts.insert(system_program::ID, AccountState::program(NATIVE_LOADER_ID.parse().unwrap()));
+        accounts.insert(spl_token::ID, AccountState::program(bpf_loader::ID));
+        accounts.insert(spl_associated_token_account::ID, AccountState::program(bpf_loader::ID));
+        accounts.insert(solana_nft_marketplace::ID, AccountState::program(bpf_loader::ID));
+        
+        TestRuntime {
+            accounts,
+            clock: Clock {
+                slot: 1,
+                epoch_start_timestamp: 1_700_000_000,
+                epoch: 0,
+                leader_schedule_epoch: 1,
+                unix_timestamp: 1_700_000_000,
+            },
+        }
+    }
+    
+    pub fn account(&self, address: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(address)
+    }
+    
+    pub fn set_account(&mut self, address: Pubkey, state: AccountState) {
+        self.accounts.insert(address, state);
+    }
+    
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.accounts.get(address).map_or(0, |state| state.lamports)
+    }
+    
+    /// Parcourir tous les comptes de la banque.
+    pub fn accounts(&self) -> impl Iterator<Item = (&Pubkey, &AccountState)> {
+        self.accounts.iter()
+    }
+    
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) {
+        self.accounts.entry(*address).or_default().lamports += lamports;
+    }
+    
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+    
+    /// Avancer l'horloge (et le slot) d'un nombre de secondes.
+    pub fn advance_clock(&mut self, seconds: i64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += 1;
+    }
+    
+    /// Placer l'horloge à une date donnée.
+    pub fn set_unix_timestamp(&mut self, unix_timestamp: i64) {
+        self.clock.unix_timestamp = unix_timestamp;
+        self.clock.slot += 1;
+    }
+    
+    /// Exécuter une transaction: toutes les instructions réussissent ou aucune n'est appliquée.
+    pub fn process(&mut self, instructions: &[Instruction]) -> std::result::Result<TransactionOutcome, TransactionFailure> {
+        let snapshot = self.accounts.clone();
+        let clock = self.clock.clone();
+        with_context(|context| {
+            *context = InvokeContext {
+                clock,
+                ..InvokeContext::default()
+            };
+        });
+        
+        for (instruction_index, instruction) in instructions.iter().enumerate() {
+            if let Err(error) = self.execute(instruction) {
+                self.accounts = snapshot;
+                let logs = with_context(|context| std::mem::take(&mut context.logs));
+                return Err(TransactionFailure {
+                    instruction_index,
+                    error,
+                    logs,
+                });
+            }
+        }
+        
+        // Les comptes sans lamports sont supprimés à la fin de la transaction
+        self.accounts.retain(|_, state| state.lamports > 0);
+        Ok(with_context(|context| TransactionOutcome {
+            logs: std::mem::take(&mut context.logs),
+            events: std::mem::take(&mut context.events),
+            return_data: context.return_data.take(),
+        }))
+    }
+    
+    // Exécuter une instruction de premier niveau et appliquer ses effets aux comptes
+    fn execute(&mut self, instruction: &Instruction) -> std::result::Result<(), InstructionError> {
+        // Comptes uniques de l'instruction, droits fusionnés pour les doublons
+        let mut keys: Vec<(Pubkey, bool, bool)> = Vec::new();
+        let mut positions = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            match keys.iter().position(|(key, _, _)| *key == meta.pubkey) {
+                Some(position) => {
+                    keys[position].1 |= meta.is_signer;
+                    keys[position].2 |= meta.is_writable;
+                    positions.push(position);
+                }
+                None => {
+                    keys.push((meta.pubkey, meta.is_signer, meta.is_writable));
+                    positions.push(keys.len() - 1);
+                }
+            }
+        }
+        let before: Vec<AccountState> = keys
+            .iter()
+            .map(|(key, _, _)| self.accounts.get(key).cloned().unwrap_or_default())
+            .collect();
+        
+        let program = self.accounts.get(&instruction.program_id);
+        if !program.is_some_and(|program| program.executable) {
+            return Err(InstructionError::Program(ProgramError::IncorrectProgramId));
+        }
+        
+        let mut buffer = serialize(&instruction.program_id, &keys, &before, &positions, &instruction.data);
+        let (program_id, infos, data) = unsafe { deserialize(buffer.as_mut_ptr() as *mut u8) };
+        
+        with_context(|context| context.programs.push(*program_id));
+        let result = if *program_id == solana_nft_marketplace::ID {
+            solana_nft_marketplace::entry(program_id, &infos, data)
+        } else {
+            process_native(program_id, &infos, data)
+        };
+        with_context(|context| context.programs.pop());
+        result.map_err(InstructionError::Program)?;
+        
+        // Relire les comptes depuis le tampon (première occurrence de chaque compte)
+        let mut after: Vec<AccountState> = Vec::with_capacity(keys.len());
+        for position in 0..keys.len() {
+            let meta_index = positions.iter().position(|&other| other == position).unwrap();
+            let info = &infos[meta_index];
+            after.push(AccountState {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            });
+        }
+        
+        let total_before: u128 = before.iter().map(|state| state.lamports as u128).sum();
+        let total_after: u128 = after.iter().map(|state| state.lamports as u128).sum();
+        if total_before != total_after {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+        
+        for ((key, _, is_writable), (old, new)) in keys.iter().zip(before.iter().zip(after.iter())) {
+            if !is_writable && old != new {
+                return Err(InstructionError::ReadonlyAccountModified(*key));
+            }
+        }
+        
+        for ((key, _, _), state) in keys.iter().zip(after) {
+            self.accounts.insert(*key, state);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,893 @@
+//! Chaque `ErrorCode` du programme est déclenché au moins une fois, ainsi que les erreurs Anchor
+//! attendues pour un signataire manquant ou un compte incorrect.
+
+mod common;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use anchor_spl::associated_token::get_associated_token_address;
+use common::batch::*;
+use common::*;
+use solana_nft_marketplace::{
+    accounts, instruction, ErrorCode, FeeTier, MarketplaceChange, PayoutSplit, CHANGE_TIMELOCK_DELAY, MAX_BUNDLE_ITEMS,
+    MAX_MODERATORS, MAX_SWAP_ITEMS,
+};
+use solana_nft_marketplace_sdk::{instructions, pda};
+
+const PRICE: u64 = 1_000_000_000;
+
+// Listing d'un NFT fraîchement créé: (vendeur, mint, listing)
+fn listed_nft(env: &mut Env, price: u64) -> (Pubkey, Pubkey, Pubkey) {
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, price, 1);
+    (seller, mint, listing)
+}
+
+#[test]
+fn invalid_nft_amount() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.mint(1);
+    env.token_account(&seller, &mint, 0);
+    
+    let list = list_bundle(&seller, 1, PRICE, &bundle_items(&seller, &[mint]));
+    assert_error(env.send_one(list), ErrorCode::InvalidNFTAmount);
+}
+
+#[test]
+fn listing_not_active() {
+    let mut env = Env::new();
+    let (_, _, listing) = listed_nft(&mut env, PRICE);
+    let buyer = env.wallet();
+    env.cancel(&listing).unwrap();
+    
+    assert_error(env.buy(&buyer, &listing, 1), ErrorCode::ListingNotActive);
+}
+
+#[test]
+fn unauthorized_access() {
+    let mut env = Env::new();
+    let intruder = env.wallet();
+    
+    let queue = env.queue_change(1, MarketplaceChange::ReferralFee { referral_fee_bps: 100 });
+    let queue = replace_account(queue, &env.authority.clone(), intruder);
+    assert_error(env.send_one(queue), ErrorCode::UnauthorizedAccess);
+    
+    // Un portefeuille qui n'est pas modérateur ne peut pas bloquer de mint
+    let mint = env.nft(&intruder);
+    let block = env.block_mint(&intruder, &mint, 1);
+    assert_error(env.send_one(block), ErrorCode::UnauthorizedAccess);
+}
+
+#[test]
+fn math_overflow() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 2);
+    let listing = env.list(&seller, &mint, u64::MAX, 2);
+    
+    assert_error(env.buy(&buyer, &listing, 2), ErrorCode::MathOverflow);
+}
+
+#[test]
+fn invalid_token_account() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.mint(1);
+    let not_a_token_account = env.wallet();
+    
+    let items = [
+        AccountMeta::new_readonly(mint, false),
+        AccountMeta::new(not_a_token_account, false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&mint).0, false),
+    ];
+    assert_error(env.send_one(list_bundle(&seller, 1, PRICE, &items)), ErrorCode::InvalidTokenAccount);
+}
+
+#[test]
+fn invalid_bundle_accounts() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    
+    let mut items = bundle_items(&seller, &[mint]);
+    items.pop();
+    assert_error(env.send_one(list_bundle(&seller, 1, PRICE, &items)), ErrorCode::InvalidBundleAccounts);
+}
+
+#[test]
+fn bundle_too_large() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mints: Vec<Pubkey> = (0..=MAX_BUNDLE_ITEMS).map(|_| env.nft(&seller)).collect();
+    
+    let list = list_bundle(&seller, 1, PRICE, &bundle_items(&seller, &mints));
+    assert_error(env.send_one(list), ErrorCode::BundleTooLarge);
+}
+
+#[test]
+fn duplicate_bundle_item() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    
+    let list = list_bundle(&seller, 1, PRICE, &bundle_items(&seller, &[mint, mint]));
+    assert_error(env.send_one(list), ErrorCode::DuplicateBundleItem);
+}
+
+#[test]
+fn bundle_item_unavailable() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let other = env.wallet();
+    let mints = [env.nft(&seller), env.nft(&seller)];
+    let list = list_bundle(&seller, 1, PRICE, &bundle_items(&seller, &mints));
+    env.send_one(list).unwrap();
+    for mint in &mints {
+        env.token_account(&buyer, mint, 0);
+    }
+    
+    // Le vendeur transfère un des NFTs après la mise en vente du bundle
+    let destination = env.token_account(&other, &mints[1], 0);
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &get_associated_token_address(&seller, &mints[1]),
+        &destination,
+        &seller,
+        &[],
+        1,
+    )
+    .unwrap();
+    env.send_one(transfer).unwrap();
+    
+    let buy = buy_bundle(&env, &buyer, &seller, 1, &bundle_purchase_items(&seller, &buyer, &mints));
+    assert_error(env.send_one(buy), ErrorCode::BundleItemUnavailable);
+    assert_eq!(env.balance(&buyer, &mints[0]), 0);
+}
+
+#[test]
+fn invalid_sweep_accounts() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE, false, &[])), ErrorCode::InvalidSweepAccounts);
+    
+    let (_, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    let mut items = sweep_item(&env, &buyer, &listing);
+    items.pop();
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE, false, &items)), ErrorCode::InvalidSweepAccounts);
+}
+
+#[test]
+fn listing_unfillable() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    
+    // Le vendeur révoque la délégation sans annuler le listing
+    let revoke = spl_token::instruction::revoke(&spl_token::ID, &get_associated_token_address(&seller, &mint), &seller, &[]).unwrap();
+    env.send_one(revoke).unwrap();
+    
+    let items = sweep_item(&env, &buyer, &listing);
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE, false, &items)), ErrorCode::ListingUnfillable);
+    
+    // En ignorant les listings non exécutables, le sweep réussit sans rien acheter
+    env.send_one(buy_many(&env, &buyer, PRICE, true, &items)).unwrap();
+    assert_eq!(env.balance(&buyer, &mint), 0);
+}
+
+#[test]
+fn max_spend_exceeded() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (_, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    
+    let items = sweep_item(&env, &buyer, &listing);
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE - 1, false, &items)), ErrorCode::MaxSpendExceeded);
+}
+
+#[test]
+fn invalid_swap_accounts() {
+    let mut env = Env::new();
+    let maker = env.wallet();
+    let wanted = env.mint(1);
+    
+    let create = create_swap(&maker, 1, vec![wanted], None, 0, &[]);
+    assert_error(env.send_one(create), ErrorCode::InvalidSwapAccounts);
+    
+    // Escrow qui n'est pas l'ATA du PDA de l'offre
+    let offered = env.nft(&maker);
+    let mut items = swap_items(&maker, 1, &[offered]);
+    items[2].pubkey = Pubkey::new_unique();
+    let create = create_swap(&maker, 1, vec![wanted], None, 0, &items);
+    assert_error(env.send_one(create), ErrorCode::InvalidSwapAccounts);
+}
+
+#[test]
+fn swap_too_large() {
+    let mut env = Env::new();
+    let maker = env.wallet();
+    let offered = env.nft(&maker);
+    let wanted: Vec<Pubkey> = (0..=MAX_SWAP_ITEMS).map(|_| Pubkey::new_unique()).collect();
+    
+    let create = create_swap(&maker, 1, wanted, None, 0, &swap_items(&maker, 1, &[offered]));
+    assert_error(env.send_one(create), ErrorCode::SwapTooLarge);
+}
+
+#[test]
+fn invalid_swap_request() {
+    let mut env = Env::new();
+    let maker = env.wallet();
+    let offered = env.nft(&maker);
+    let items = swap_items(&maker, 1, &[offered]);
+    
+    // Ni mints ni collection
+    assert_error(env.send_one(create_swap(&maker, 1, Vec::new(), None, 0, &items)), ErrorCode::InvalidSwapRequest);
+    // Collection demandée sans nombre de NFTs
+    let collection = Some(Pubkey::new_unique());
+    assert_error(env.send_one(create_swap(&maker, 1, Vec::new(), collection, 0, &items)), ErrorCode::InvalidSwapRequest);
+}
+
+#[test]
+fn duplicate_swap_item() {
+    let mut env = Env::new();
+    let maker = env.wallet();
+    let offered = env.nft(&maker);
+    
+    let create = create_swap(&maker, 1, vec![Pubkey::new_unique()], None, 0, &swap_items(&maker, 1, &[offered, offered]));
+    assert_error(env.send_one(create), ErrorCode::DuplicateSwapItem);
+    
+    // Un mint demandé deux fois
+    let wanted = Pubkey::new_unique();
+    let create = create_swap(&maker, 1, vec![wanted, wanted], None, 0, &swap_items(&maker, 1, &[offered]));
+    assert_error(env.send_one(create), ErrorCode::DuplicateSwapItem);
+    
+    // Un compte d'escrow créé à l'avance par un tiers ne bloque pas l'offre
+    env.token_account(&swap_address(&maker, 1), &offered, 0);
+    let create = create_swap(&maker, 1, vec![wanted], None, 0, &swap_items(&maker, 1, &[offered]));
+    env.send_one(create).unwrap();
+    assert_eq!(env.balance(&swap_address(&maker, 1), &offered), 1);
+}
+
+#[test]
+fn swap_item_mismatch() {
+    let mut env = Env::new();
+    let maker = env.wallet();
+    let taker = env.wallet();
+    let offered = env.nft(&maker);
+    let wanted = env.nft(&taker);
+    let other = env.nft(&taker);
+    
+    let create = create_swap(&maker, 1, vec![wanted], None, 0, &swap_items(&maker, 1, &[offered]));
+    env.send_one(create).unwrap();
+    let swap = swap_address(&maker, 1);
+    assert_eq!(env.balance(&swap, &offered), 1);
+    env.token_account(&taker, &offered, 0);
+    env.token_account(&maker, &other, 0);
+    
+    // Le preneur fournit un autre NFT que celui demandé
+    let items = [
+        AccountMeta::new(get_associated_token_address(&swap, &offered), false),
+        AccountMeta::new(get_associated_token_address(&taker, &offered), false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&offered).0, false),
+        AccountMeta::new(get_associated_token_address(&taker, &other), false),
+        AccountMeta::new(get_associated_token_address(&maker, &other), false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&other).0, false),
+    ];
+    assert_error(env.send_one(accept_swap(&maker, &taker, 1, &items)), ErrorCode::SwapItemMismatch);
+    
+    // Avec le NFT demandé, l'échange est exécuté et l'escrow fermé
+    env.token_account(&maker, &wanted, 0);
+    let mut items = items.to_vec();
+    items[3].pubkey = get_associated_token_address(&taker, &wanted);
+    items[4].pubkey = get_associated_token_address(&maker, &wanted);
+    items[5].pubkey = pda::mint_blocklist_address(&wanted).0;
+    env.send_one(accept_swap(&maker, &taker, 1, &items)).unwrap();
+    assert_eq!(env.balance(&taker, &offered), 1);
+    assert_eq!(env.balance(&maker, &wanted), 1);
+    assert!(!env.exists(&swap));
+    assert!(!env.exists(&get_associated_token_address(&swap, &offered)));
+}
+
+#[test]
+fn invalid_metadata_account() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    
+    // Métadonnées d'un autre mint
+    let list = instructions::ListNft::new(seller, mint, PRICE);
+    let other = env.nft(&seller);
+    let other_metadata = env.metadata(&other, None);
+    let instruction = replace_account(list.instruction(), &pda::metadata_address(&mint).0, other_metadata);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidMetadataAccount);
+    
+    // Compte à l'adresse des métadonnées qui n'est pas détenu par Metaplex
+    let metadata = pda::metadata_address(&mint).0;
+    env.runtime.set_account(metadata, AccountState::new(1_000_000, vec![1; 8], system_program::ID));
+    assert_error(env.send_one(list.instruction()), ErrorCode::InvalidMetadataAccount);
+}
+
+#[test]
+fn invalid_quantity() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 2);
+    
+    let mut list = instructions::ListNft::new(seller, mint, PRICE);
+    list.quantity = 3;
+    assert_error(env.send_one(list.instruction()), ErrorCode::InvalidQuantity);
+    list.quantity = 0;
+    assert_error(env.send_one(list.instruction()), ErrorCode::InvalidQuantity);
+    
+    let listing = env.list(&seller, &mint, PRICE, 2);
+    assert_error(env.buy(&buyer, &listing, 3), ErrorCode::InvalidQuantity);
+    assert_error(env.buy(&buyer, &listing, 0), ErrorCode::InvalidQuantity);
+    assert_error(env.update(&listing, PRICE, 3), ErrorCode::InvalidQuantity);
+}
+
+#[test]
+fn invalid_referrer() {
+    let mut env = Env::new();
+    let referrer = env.wallet();
+    let impostor = env.wallet();
+    let register = env.register_referrer(&referrer);
+    env.send_one(register).unwrap();
+    let buyer = env.wallet();
+    let (_, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    
+    // Le parrain ne correspond pas au compte de gains fourni
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    buy.referrer = Some(referrer);
+    let instruction = replace_account(buy.instruction(), &referrer, impostor);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidReferrer);
+    
+    // Parrain sans compte de gains
+    let earnings = pda::referrer_earnings_address(&referrer).0;
+    let instruction = replace_account(buy.instruction(), &earnings, solana_nft_marketplace::ID);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidReferrer);
+}
+
+#[test]
+fn self_referral() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    let register_buyer = env.register_referrer(&buyer);
+    let register_seller = env.register_referrer(&seller);
+    env.send(&[register_buyer, register_seller]).unwrap();
+    
+    // L'acheteur se désigne comme parrain
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    buy.referrer = Some(buyer);
+    assert_error(env.send_one(buy.instruction()), ErrorCode::SelfReferral);
+    
+    // Le vendeur désigné comme parrain
+    buy.referrer = Some(seller);
+    assert_error(env.send_one(buy.instruction()), ErrorCode::SelfReferral);
+}
+
+#[test]
+fn invalid_fee_bps() {
+    let mut env = Env::new();
+    let queue = env.queue_change(1, MarketplaceChange::ReferralFee { referral_fee_bps: 10_001 });
+    assert_error(env.send_one(queue), ErrorCode::InvalidFeeBps);
+    
+    // Les paliers de la grille ont les mêmes bornes que les frais de la marketplace
+    for (tiers, holder_discount_bps) in [(Vec::new(), 10_001), (vec![FeeTier { min_volume: 0, fee_bps: 10_001 }], 0)] {
+        let change = MarketplaceChange::FeeSchedule { tiers, membership_collection: None, holder_discount_bps };
+        assert_error(env.send_one(env.queue_change(1, change)), ErrorCode::InvalidFeeBps);
+    }
+    
+    let change = MarketplaceChange::CollectionFeeOverride { collection: env.mint(1), fee_override: Some(10_001) };
+    assert_error(env.send_one(env.queue_change(1, change)), ErrorCode::InvalidFeeBps);
+    
+    let queue = env.queue_change(1, MarketplaceChange::Fee { fee: 10_001 });
+    assert_error(env.send_one(queue), ErrorCode::InvalidFeeBps);
+    
+    let mut runtime = TestRuntime::new();
+    let authority = Pubkey::new_unique();
+    runtime.airdrop(&authority, WALLET_LAMPORTS);
+    let init = instructions::initialize_marketplace(authority, 10_001);
+    let result = runtime.process(&[init]);
+    assert_error(result, ErrorCode::InvalidFeeBps);
+}
+
+#[test]
+fn invalid_payout_splits() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    let recipient = Pubkey::new_unique();
+    
+    let cases = [
+        vec![PayoutSplit { recipient, bps: 9_999 }],
+        vec![PayoutSplit { recipient, bps: 5_000 }, PayoutSplit { recipient, bps: 5_000 }],
+        vec![PayoutSplit { recipient, bps: 0 }, PayoutSplit { recipient: seller, bps: 10_000 }],
+        (0..6).map(|_| PayoutSplit { recipient: Pubkey::new_unique(), bps: 1_000 }).collect(),
+    ];
+    for payout_splits in cases {
+        let mut list = instructions::ListNft::new(seller, mint, PRICE);
+        list.payout_splits = payout_splits;
+        assert_error(env.send_one(list.instruction()), ErrorCode::InvalidPayoutSplits);
+    }
+}
+
+#[test]
+fn invalid_payout_recipients() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let recipients = [env.wallet(), env.wallet()];
+    let mint = env.nft(&seller);
+    let mut list = instructions::ListNft::new(seller, mint, PRICE);
+    list.payout_splits = recipients.iter().map(|recipient| PayoutSplit { recipient: *recipient, bps: 5_000 }).collect();
+    env.send_one(list.instruction()).unwrap();
+    let listing = pda::listing_address(&mint, &seller).0;
+    env.token_account(&buyer, &mint, 0);
+    
+    // Destinataires manquants
+    let mut instruction = env.buy_instruction(&buyer, &listing, 1);
+    instruction.accounts.pop();
+    assert_error(env.send_one(instruction), ErrorCode::InvalidPayoutRecipients);
+    
+    // Destinataires dans le mauvais ordre
+    let mut instruction = env.buy_instruction(&buyer, &listing, 1);
+    let count = instruction.accounts.len();
+    instruction.accounts.swap(count - 1, count - 2);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidPayoutRecipients);
+}
+
+#[test]
+fn invalid_fee_schedule() {
+    let mut env = Env::new();
+    
+    let schedule = |tiers| MarketplaceChange::FeeSchedule { tiers, membership_collection: None, holder_discount_bps: 0 };
+    
+    let unsorted = vec![FeeTier { min_volume: 10, fee_bps: 200 }, FeeTier { min_volume: 10, fee_bps: 100 }];
+    assert_error(env.send_one(env.queue_change(1, schedule(unsorted))), ErrorCode::InvalidFeeSchedule);
+    
+    let too_many = (0..6).map(|index| FeeTier { min_volume: index, fee_bps: 100 }).collect();
+    assert_error(env.send_one(env.queue_change(1, schedule(too_many))), ErrorCode::InvalidFeeSchedule);
+    
+    // Changement de grille exécuté sans le compte de la grille
+    env.send_one(env.queue_change(1, schedule(Vec::new()))).unwrap();
+    env.runtime.advance_clock(CHANGE_TIMELOCK_DELAY);
+    let execute = program_instruction(
+        accounts::ExecuteChange {
+            pending_change: Env::change_address(1),
+            marketplace: pda::marketplace_address().0,
+            authority: env.authority,
+            fee_schedule: None,
+            collection_config: None,
+        },
+        instruction::ExecuteChange {},
+    );
+    assert_error(env.send_one(execute), ErrorCode::InvalidFeeSchedule);
+}
+
+#[test]
+fn invalid_membership_proof() {
+    let mut env = Env::new();
+    let membership_collection = env.mint(1);
+    env.set_fee_schedule(Vec::new(), Some(membership_collection), 100);
+    
+    let buyer = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    env.send_one(env.initialize_seller_volume(&seller)).unwrap();
+    
+    // NFT de membre détenu par un autre portefeuille
+    let holder = env.wallet();
+    let membership_mint = env.collection_nft(&holder, &membership_collection);
+    let register = replace_account(
+        env.register_membership(&seller, &membership_mint),
+        &get_associated_token_address(&seller, &membership_mint),
+        get_associated_token_address(&holder, &membership_mint),
+    );
+    assert_error(env.send_one(register), ErrorCode::InvalidMembershipProof);
+    
+    // NFT du vendeur hors de la collection de membres
+    let outsider = env.collection_nft(&seller, &Pubkey::new_unique());
+    assert_error(env.send_one(env.register_membership(&seller, &outsider)), ErrorCode::InvalidMembershipProof);
+    
+    // Collection non vérifiée
+    let unverified = env.nft(&seller);
+    env.metadata(&unverified, Some((membership_collection, false)));
+    assert_error(env.send_one(env.register_membership(&seller, &unverified)), ErrorCode::InvalidMembershipProof);
+    
+    // Une fois le NFT de membre enregistré, l'acheteur ne peut pas omettre ni remplacer son compte
+    let membership_mint = env.collection_nft(&seller, &membership_collection);
+    env.send_one(env.register_membership(&seller, &membership_mint)).unwrap();
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    assert_error(env.send_one(buy.instruction()), ErrorCode::InvalidMembershipProof);
+    buy.membership_token = Some(get_associated_token_address(&seller, &outsider));
+    assert_error(env.send_one(buy.instruction()), ErrorCode::InvalidMembershipProof);
+}
+
+#[test]
+fn collection_not_allowed() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let curated = env.set_curated_mode(true);
+    let config = env.initialize_collection_config(&collection, false);
+    env.send(&[curated, config]).unwrap();
+    let seller = env.wallet();
+    let mint = env.collection_nft(&seller, &collection);
+    
+    // Sans configuration de collection
+    let mut list = instructions::ListNft::new(seller, mint, PRICE);
+    assert_error(env.send_one(list.instruction()), ErrorCode::CollectionNotAllowed);
+    
+    // Collection désactivée
+    list.collection = Some(collection);
+    list.with_collection_config = true;
+    assert_error(env.send_one(list.instruction()), ErrorCode::CollectionNotAllowed);
+    
+    // Un bundle est soumis aux mêmes règles, pour chacun de ses NFTs
+    let items = curated_bundle_items(&seller, &collection, &[mint]);
+    assert_error(env.send_one(list_bundle(&seller, 1, PRICE, &items)), ErrorCode::CollectionNotAllowed);
+    
+    let other_collection = env.mint(1);
+    let enabled = env.initialize_collection_config(&other_collection, true);
+    env.send_one(enabled).unwrap();
+    let allowed = env.collection_nft(&seller, &other_collection);
+    let items = curated_bundle_items(&seller, &other_collection, &[allowed]);
+    env.send_one(list_bundle(&seller, 1, PRICE, &items)).unwrap();
+    
+    // Le format non curé (sans métadonnées) est refusé en mode curé
+    let items = bundle_items(&seller, &[mint]);
+    assert_error(env.send_one(list_bundle(&seller, 2, PRICE, &items)), ErrorCode::InvalidBundleAccounts);
+}
+
+#[test]
+fn invalid_collection_config() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let other_collection = env.mint(1);
+    let config = env.initialize_collection_config(&other_collection, true);
+    env.send_one(config).unwrap();
+    env.apply_change(1, MarketplaceChange::CollectionFeeOverride { collection: other_collection, fee_override: Some(0) });
+    
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.collection_nft(&seller, &collection);
+    let mut list = instructions::ListNft::new(seller, mint, PRICE);
+    list.collection = Some(collection);
+    env.send_one(list.instruction()).unwrap();
+    let listing = pda::listing_address(&mint, &seller).0;
+    env.token_account(&buyer, &mint, 0);
+    
+    // Configuration (sans frais) d'une autre collection
+    let buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    let config = pda::collection_config_address(&collection).0;
+    let instruction = replace_account(buy.instruction(), &config, pda::collection_config_address(&other_collection).0);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidCollectionConfig);
+    
+    // Configuration omise par l'acheteur
+    let instruction = replace_account(buy.instruction(), &config, solana_nft_marketplace::ID);
+    assert_error(env.send_one(instruction), ErrorCode::InvalidCollectionConfig);
+    
+    // Sweep avec la configuration d'une autre collection
+    let mut items = sweep_item(&env, &buyer, &listing);
+    items[8].pubkey = pda::collection_config_address(&other_collection).0;
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE, false, &items)), ErrorCode::InvalidCollectionConfig);
+    
+    // Frais spécifiques appliqués à la configuration d'une autre collection
+    let change = MarketplaceChange::CollectionFeeOverride { collection, fee_override: Some(0) };
+    env.send_one(env.queue_change(2, change)).unwrap();
+    env.runtime.advance_clock(CHANGE_TIMELOCK_DELAY);
+    let execute = replace_account(env.execute_change(2), &config, pda::collection_config_address(&other_collection).0);
+    assert_error(env.send_one(execute), ErrorCode::InvalidCollectionConfig);
+}
+
+#[test]
+fn moderator_already_exists_and_not_found() {
+    let mut env = Env::new();
+    let moderator = env.wallet();
+    env.apply_change(1, MarketplaceChange::AddModerator { moderator });
+    
+    let queue = env.queue_change(2, MarketplaceChange::AddModerator { moderator });
+    assert_error(env.send_one(queue), ErrorCode::ModeratorAlreadyExists);
+    
+    let queue = env.queue_change(2, MarketplaceChange::RemoveModerator { moderator: Pubkey::new_unique() });
+    assert_error(env.send_one(queue), ErrorCode::ModeratorNotFound);
+    
+    // Revalidation à l'exécution: le modérateur a été retiré entre-temps
+    let queue = env.queue_change(3, MarketplaceChange::RemoveModerator { moderator });
+    env.send_one(queue).unwrap();
+    env.apply_change(4, MarketplaceChange::RemoveModerator { moderator });
+    let execute = env.execute_change(3);
+    assert_error(env.send_one(execute), ErrorCode::ModeratorNotFound);
+}
+
+#[test]
+fn too_many_moderators() {
+    let mut env = Env::new();
+    for change_id in 0..MAX_MODERATORS as u64 {
+        env.apply_change(change_id, MarketplaceChange::AddModerator { moderator: Pubkey::new_unique() });
+    }
+    assert_eq!(env.marketplace().moderators.len(), MAX_MODERATORS);
+    
+    let queue = env.queue_change(99, MarketplaceChange::AddModerator { moderator: Pubkey::new_unique() });
+    assert_error(env.send_one(queue), ErrorCode::TooManyModerators);
+}
+
+#[test]
+fn mint_blocked() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    let block = env.block_mint(&env.authority.clone(), &mint, 1);
+    env.send_one(block).unwrap();
+    
+    assert_error(env.buy(&buyer, &listing, 1), ErrorCode::MintBlocked);
+    
+    let second = env.nft(&seller);
+    let block = env.block_mint(&env.authority.clone(), &second, 1);
+    env.send_one(block).unwrap();
+    let list = instructions::ListNft::new(seller, second, PRICE);
+    assert_error(env.send_one(list.instruction()), ErrorCode::MintBlocked);
+    
+    // Un mint bloqué ne peut pas non plus entrer dans un bundle
+    let third = env.nft(&seller);
+    let list = list_bundle(&seller, 1, PRICE, &bundle_items(&seller, &[third, second]));
+    assert_error(env.send_one(list), ErrorCode::MintBlocked);
+}
+
+#[test]
+fn invalid_blocklist_account() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (_, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    
+    let mut items = sweep_item(&env, &buyer, &listing);
+    items[4].pubkey = Pubkey::new_unique();
+    assert_error(env.send_one(buy_many(&env, &buyer, PRICE, false, &items)), ErrorCode::InvalidBlocklistAccount);
+}
+
+#[test]
+fn timelock_not_elapsed() {
+    let mut env = Env::new();
+    let queue = env.queue_change(1, MarketplaceChange::Fee { fee: 100 });
+    env.send_one(queue).unwrap();
+    
+    assert_error(env.send_one(env.execute_change(1)), ErrorCode::TimelockNotElapsed);
+}
+
+#[test]
+fn invalid_account_version() {
+    let mut env = Env::new();
+    let payer = env.wallet();
+    let wallet = env.wallet();
+    
+    let migrate = program_instruction(
+        accounts::MigrateListing {
+            listing: wallet,
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateListing {},
+    );
+    assert_error(env.send_one(migrate), ErrorCode::InvalidAccountVersion);
+}
+
+#[test]
+fn account_already_migrated() {
+    let mut env = Env::new();
+    let migrate = program_instruction(
+        accounts::MigrateMarketplace {
+            marketplace: pda::marketplace_address().0,
+            authority: env.authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateMarketplace {},
+    );
+    assert_error(env.send_one(migrate), ErrorCode::AccountAlreadyMigrated);
+}
+
+#[test]
+fn invalid_collection_stats() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let init = env.initialize_collection_stats(&collection);
+    env.send_one(init).unwrap();
+    let (_, _, listing) = listed_nft(&mut env, PRICE);
+    
+    // Listing hors de la collection
+    let refresh = program_instruction(
+        accounts::RefreshCollectionFloor {
+            collection_stats: pda::collection_stats_address(&collection).0,
+        },
+        instruction::RefreshCollectionFloor {},
+    );
+    let refresh = with_remaining(refresh, &[AccountMeta::new_readonly(listing, false)]);
+    assert_error(env.send_one(refresh), ErrorCode::InvalidCollectionStats);
+    
+    // Listing d'une collection sans le compte de statistiques de sa collection
+    let seller = env.wallet();
+    let mint = env.collection_nft(&seller, &collection);
+    let mut list = instructions::ListNft::new(seller, mint, PRICE);
+    list.collection = Some(collection);
+    let instruction = replace_account(
+        list.instruction(),
+        &pda::collection_stats_address(&collection).0,
+        pda::collection_stats_address(&Pubkey::new_unique()).0,
+    );
+    assert_error(env.send_one(instruction), ErrorCode::InvalidCollectionStats);
+}
+
+#[test]
+fn relist_not_approved() {
+    let mut env = Env::new();
+    let moderator = env.authority;
+    
+    // Le vendeur ne peut pas remettre en vente juste après un retrait de force
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.send_one(env.force_delist(&moderator, &listing, 2)).unwrap();
+    let list = instructions::ListNft::new(seller, mint, PRICE);
+    assert_error(env.send_one(list.instruction()), ErrorCode::RelistNotApproved);
+    
+    // Y compris lorsqu'il avait révoqué la délégation avant le retrait
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    let seller_token_account = get_associated_token_address(&seller, &mint);
+    let revoke = spl_token::instruction::revoke(&spl_token::ID, &seller_token_account, &seller, &[]).unwrap();
+    env.send_one(revoke).unwrap();
+    env.send_one(env.force_delist(&moderator, &listing, 2)).unwrap();
+    assert_eq!(env.balance(&seller, &mint), 1);
+    let list = instructions::ListNft::new(seller, mint, PRICE);
+    assert_error(env.send_one(list.instruction()), ErrorCode::RelistNotApproved);
+}
+
+// Signataires manquants: Anchor refuse l'instruction avant toute logique
+
+#[test]
+fn missing_signatures_are_rejected() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    
+    let buy = without_signer(env.buy_instruction(&buyer, &listing, 1), &buyer);
+    assert_error(env.send_one(buy), AnchorErrorCode::AccountNotSigner);
+    
+    let cancel = instructions::CancelListing::new(listing, &env.listing(&listing)).instruction();
+    assert_error(env.send_one(without_signer(cancel, &seller)), AnchorErrorCode::AccountNotSigner);
+    
+    let second = env.nft(&seller);
+    let list = instructions::ListNft::new(seller, second, PRICE).instruction();
+    assert_error(env.send_one(without_signer(list, &seller)), AnchorErrorCode::AccountNotSigner);
+    
+    let authority = env.authority;
+    let queue = env.queue_change(1, MarketplaceChange::ReferralFee { referral_fee_bps: 1 });
+    assert_error(env.send_one(without_signer(queue, &authority)), AnchorErrorCode::AccountNotSigner);
+}
+
+#[test]
+fn another_signer_cannot_manage_the_listing() {
+    let mut env = Env::new();
+    let intruder = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&intruder, &mint, 1);
+    
+    // Le listing est dérivé du vendeur: un autre signataire ne retrouve pas ses seeds
+    let cancel = instructions::CancelListing::new(listing, &env.listing(&listing)).instruction();
+    let cancel = replace_account(cancel, &seller, intruder);
+    assert_error(env.send_one(cancel), AnchorErrorCode::ConstraintSeeds);
+    
+    let update = instructions::UpdateListing::new(listing, &env.listing(&listing), 1, 1).instruction();
+    let update = replace_account(update, &seller, intruder);
+    assert_error(env.send_one(update), AnchorErrorCode::ConstraintSeeds);
+    
+    // Transfert direct par le délégué sans la signature du PDA
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &get_associated_token_address(&seller, &mint),
+        &get_associated_token_address(&intruder, &mint),
+        &listing,
+        &[],
+        1,
+    )
+    .unwrap();
+    let transfer = without_signer(transfer, &listing);
+    assert_program_error(env.send_one(transfer), ProgramError::MissingRequiredSignature);
+}
+
+// Comptes incorrects
+
+#[test]
+fn wrong_accounts_in_buy_are_rejected() {
+    let mut env = Env::new();
+    let buyer = env.wallet();
+    let stranger = env.wallet();
+    let (seller, mint, listing) = listed_nft(&mut env, PRICE);
+    env.token_account(&buyer, &mint, 0);
+    env.token_account(&stranger, &mint, 0);
+    let buy = env.buy_instruction(&buyer, &listing, 1);
+    let treasury = env.marketplace().treasury;
+    
+    // Paiement détourné vers un autre portefeuille que le vendeur
+    let instruction = replace_account(buy.clone(), &seller, stranger);
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintRaw);
+    
+    // Frais détournés
+    let instruction = replace_account(buy.clone(), &treasury, stranger);
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintRaw);
+    
+    // NFT livré sur le compte d'un autre portefeuille
+    let instruction = replace_account(
+        buy.clone(),
+        &get_associated_token_address(&buyer, &mint),
+        get_associated_token_address(&stranger, &mint),
+    );
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintRaw);
+    
+    // Compte de volume d'un autre vendeur
+    let instruction = replace_account(
+        buy.clone(),
+        &pda::seller_volume_address(&seller).0,
+        pda::seller_volume_address(&stranger).0,
+    );
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintSeeds);
+    
+    // Un portefeuille à la place du listing
+    let instruction = replace_account(buy.clone(), &listing, stranger);
+    assert_error(env.send_one(instruction), AnchorErrorCode::AccountOwnedByWrongProgram);
+    
+    // Un autre compte du programme à la place du listing
+    let instruction = replace_account(buy.clone(), &listing, pda::stats_address().0);
+    assert_error(env.send_one(instruction), AnchorErrorCode::AccountDiscriminatorMismatch);
+    
+    // Un autre programme à la place du token program
+    let instruction = replace_account(buy.clone(), &anchor_spl::token::ID, anchor_spl::associated_token::ID);
+    assert_error(env.send_one(instruction), AnchorErrorCode::InvalidProgramId);
+    
+    // Rien n'a bougé
+    assert!(env.listing(&listing).active);
+    assert_eq!(env.balance(&seller, &mint), 1);
+    env.send_one(buy).unwrap();
+}
+
+#[test]
+fn wrong_accounts_in_list_are_rejected() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let other = env.wallet();
+    let mint = env.nft(&seller);
+    let list = instructions::ListNft::new(seller, mint, PRICE).instruction();
+    
+    // Mint qui n'appartient pas au token program
+    let fake_mint = env.wallet();
+    let instruction = replace_account(list.clone(), &mint, fake_mint);
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintSeeds);
+    let instruction = replace_account(
+        list.clone(),
+        &pda::listing_address(&mint, &seller).0,
+        pda::listing_address(&fake_mint, &seller).0,
+    );
+    let instruction = replace_account(instruction, &pda::mint_blocklist_address(&mint).0, pda::mint_blocklist_address(&fake_mint).0);
+    let instruction = replace_account(instruction, &mint, fake_mint);
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintOwner);
+    
+    // Compte de token d'un autre portefeuille
+    env.token_account(&other, &mint, 1);
+    let instruction = replace_account(
+        list.clone(),
+        &get_associated_token_address(&seller, &mint),
+        get_associated_token_address(&other, &mint),
+    );
+    assert_error(env.send_one(instruction), AnchorErrorCode::ConstraintRaw);
+    
+    env.send_one(list).unwrap();
+}
@@ -0,0 +1,525 @@
+//! Calcul et versement des frais: répartition exacte en lamports, arrondis, parrainage,
+//! répartition des recettes, grille de frais (volume glissant et remise membre) et frais de collection.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::get_associated_token_address;
+use common::batch::*;
+use common::*;
+use solana_nft_marketplace::{
+    calculate_fee_breakdown, calculate_marketplace_fee, calculate_payout_amounts, instruction, CollectionConfig,
+    ErrorCode, FeeSchedule, FeeTier, FeeTierApplied, MarketplaceChange, NFTSold, PayoutSplit, ReferrerEarnings,
+    SellerVolume, CHANGE_TIMELOCK_DELAY, FEE_VOLUME_WINDOW,
+};
+use solana_nft_marketplace_sdk::fees::{quote_buy, BuyQuoteInputs};
+use solana_nft_marketplace_sdk::{instructions, pda};
+
+// Soldes de la trésorerie, du vendeur et de l'acheteur
+fn balances(env: &Env, seller: &Pubkey, buyer: &Pubkey) -> (u64, u64, u64) {
+    let treasury = env.marketplace().treasury;
+    (env.lamports(&treasury), env.lamports(seller), env.lamports(buyer))
+}
+
+// Vendre un NFT au prix donné et retourner les variations (trésorerie, vendeur, acheteur)
+fn sell_at(env: &mut Env, price: u64) -> (u64, u64, u64) {
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, price, 1);
+    
+    let before = balances(env, &seller, &buyer);
+    env.buy(&buyer, &listing, 1).unwrap();
+    let after = balances(env, &seller, &buyer);
+    (after.0 - before.0, after.1 - before.1, before.2 - after.2)
+}
+
+#[test]
+fn fee_is_split_exactly_between_treasury_and_seller() {
+    let mut env = Env::new();
+    let (treasury, seller, buyer) = sell_at(&mut env, 1_000_000_000);
+    
+    assert_eq!(treasury, 25_000_000);
+    assert_eq!(seller, 975_000_000);
+    assert_eq!(buyer, 1_000_000_000);
+}
+
+#[test]
+fn fee_rounds_down_in_favor_of_the_seller() {
+    let mut env = Env::new();
+    
+    // 399 * 250 / 10000 = 9,975 -> 9
+    let (treasury, seller, buyer) = sell_at(&mut env, 399);
+    assert_eq!((treasury, seller, buyer), (9, 390, 399));
+    
+    // Prix trop bas pour générer des frais
+    let (treasury, seller, buyer) = sell_at(&mut env, 39);
+    assert_eq!((treasury, seller, buyer), (0, 39, 39));
+}
+
+#[test]
+fn zero_and_full_fee_marketplaces() {
+    let mut env = Env::with_fee(0);
+    assert_eq!(sell_at(&mut env, 1_000), (0, 1_000, 1_000));
+    
+    let mut env = Env::with_fee(10_000);
+    assert_eq!(sell_at(&mut env, 1_000), (1_000, 0, 1_000));
+}
+
+#[test]
+fn quantity_purchase_charges_the_unit_price_times_quantity() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 3);
+    let listing = env.list(&seller, &mint, 333, 3);
+    
+    let before = balances(&env, &seller, &buyer);
+    let outcome = env.buy(&buyer, &listing, 3).unwrap();
+    let after = balances(&env, &seller, &buyer);
+    
+    // 999 * 250 / 10000 = 24,975 -> 24
+    assert_eq!(after.0 - before.0, 24);
+    assert_eq!(after.1 - before.1, 975);
+    assert_eq!(before.2 - after.2, 999);
+    assert_eq!(outcome.events::<NFTSold>()[0].marketplace_fee, 24);
+}
+
+#[test]
+fn referrer_receives_its_share_of_the_fee() {
+    let mut env = Env::new();
+    let referrer = env.wallet();
+    env.apply_change(1, MarketplaceChange::ReferralFee { referral_fee_bps: 4_000 });
+    let register = env.register_referrer(&referrer);
+    env.send(&[register]).unwrap();
+    
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, 1_000_003, 1);
+    env.token_account(&buyer, &mint, 0);
+    
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    buy.referrer = Some(referrer);
+    let before = balances(&env, &seller, &buyer);
+    let referrer_before = env.lamports(&referrer);
+    let outcome = env.send_one(buy.instruction()).unwrap();
+    let after = balances(&env, &seller, &buyer);
+    
+    // Frais: 1_000_003 * 250 / 10000 = 25_000; parrain: 25_000 * 40 % = 10_000
+    assert_eq!(env.lamports(&referrer) - referrer_before, 10_000);
+    assert_eq!(after.0 - before.0, 15_000);
+    assert_eq!(after.1 - before.1, 975_003);
+    assert_eq!(before.2 - after.2, 1_000_003);
+    
+    let earnings: ReferrerEarnings = env.get(&pda::referrer_earnings_address(&referrer).0);
+    assert_eq!(earnings.total_earned, 10_000);
+    assert_eq!(earnings.referred_sales, 1);
+    
+    let event = &outcome.events::<NFTSold>()[0];
+    assert_eq!(event.referrer, Some(referrer));
+    assert_eq!(event.referral_fee, 10_000);
+    assert_eq!(event.marketplace_fee, 15_000);
+    assert_eq!(env.stats().total_fees, 15_000);
+}
+
+#[test]
+fn payout_splits_give_the_rounding_remainder_to_the_last_recipient() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let recipients = [env.wallet(), env.wallet(), env.wallet()];
+    let mint = env.nft(&seller);
+    
+    let mut list = instructions::ListNft::new(seller, mint, 1_001);
+    list.payout_splits = vec![
+        PayoutSplit { recipient: recipients[0], bps: 3_333 },
+        PayoutSplit { recipient: recipients[1], bps: 3_333 },
+        PayoutSplit { recipient: recipients[2], bps: 3_334 },
+    ];
+    env.send_one(list.instruction()).unwrap();
+    let listing = pda::listing_address(&mint, &seller).0;
+    
+    let before: Vec<u64> = recipients.iter().map(|recipient| env.lamports(recipient)).collect();
+    let seller_before = env.lamports(&seller);
+    env.buy(&buyer, &listing, 1).unwrap();
+    
+    // Part vendeur: 1_001 - 25 = 976; 976 * 3333 / 10000 = 325 (x2), reste 326
+    let received: Vec<u64> = recipients
+        .iter()
+        .zip(before)
+        .map(|(recipient, before)| env.lamports(recipient) - before)
+        .collect();
+    assert_eq!(received, vec![325, 325, 326]);
+    assert_eq!(env.lamports(&seller), seller_before);
+}
+
+// Grille de frais: 2,5 % de base, 2 % dès 10 SOL et 1 % dès 100 SOL de volume glissant
+fn tiered_env() -> Env {
+    let mut env = Env::new();
+    env.set_fee_schedule(
+        vec![
+            FeeTier { min_volume: 10_000_000_000, fee_bps: 200 },
+            FeeTier { min_volume: 100_000_000_000, fee_bps: 100 },
+        ],
+        None,
+        0,
+    );
+    env
+}
+
+// Vendre un NFT du vendeur donné et retourner (frais de trésorerie, événement de palier)
+fn sale_by(env: &mut Env, seller: &Pubkey, price: u64) -> (u64, FeeTierApplied) {
+    let buyer = env.wallet();
+    let mint = env.nft(seller);
+    let listing = env.list(seller, &mint, price, 1);
+    let treasury = env.marketplace().treasury;
+    
+    let before = env.lamports(&treasury);
+    let outcome = env.buy(&buyer, &listing, 1).unwrap();
+    (env.lamports(&treasury) - before, outcome.events::<FeeTierApplied>().remove(0))
+}
+
+#[test]
+fn fee_tier_follows_the_trailing_seller_volume() {
+    let mut env = tiered_env();
+    let seller = env.wallet();
+    env.runtime.airdrop(&seller, 1_000_000_000_000);
+    let init = env.initialize_seller_volume(&seller);
+    env.send_one(init).unwrap();
+    
+    // Aucun volume: frais de base
+    let (fee, applied) = sale_by(&mut env, &seller, 10_000_000_000);
+    assert_eq!(fee, 250_000_000);
+    assert_eq!((applied.fee_tier, applied.fee_bps, applied.trailing_volume), (None, 250, 0));
+    
+    // 10 SOL vendus: premier palier
+    let (fee, applied) = sale_by(&mut env, &seller, 10_000_000_000);
+    assert_eq!(fee, 200_000_000);
+    assert_eq!((applied.fee_tier, applied.fee_bps, applied.trailing_volume), (Some(0), 200, 10_000_000_000));
+    
+    // Fenêtre suivante: le volume passé compte encore
+    env.runtime.advance_clock(FEE_VOLUME_WINDOW);
+    let (_, applied) = sale_by(&mut env, &seller, 100_000_000_000);
+    assert_eq!(applied.trailing_volume, 20_000_000_000);
+    assert_eq!(applied.fee_tier, Some(0));
+    
+    let volume: SellerVolume = env.get(&pda::seller_volume_address(&seller).0);
+    assert_eq!(volume.previous_volume, 20_000_000_000);
+    assert_eq!(volume.current_volume, 100_000_000_000);
+    
+    let (_, applied) = sale_by(&mut env, &seller, 1_000_000_000);
+    assert_eq!(applied.fee_tier, Some(1));
+    assert_eq!(applied.fee_bps, 100);
+    
+    // Deux fenêtres sans vente: le volume repart de zéro
+    env.runtime.advance_clock(2 * FEE_VOLUME_WINDOW);
+    let (_, applied) = sale_by(&mut env, &seller, 1_000_000_000);
+    assert_eq!((applied.fee_tier, applied.trailing_volume), (None, 0));
+}
+
+#[test]
+fn seller_without_volume_account_pays_the_base_fee() {
+    let mut env = tiered_env();
+    let seller = env.wallet();
+    
+    let (fee, applied) = sale_by(&mut env, &seller, 10_000_000_000);
+    assert_eq!(fee, 250_000_000);
+    let (fee, applied_again) = sale_by(&mut env, &seller, 10_000_000_000);
+    assert_eq!(fee, 250_000_000);
+    assert_eq!(applied.trailing_volume, 0);
+    assert_eq!(applied_again.trailing_volume, 0);
+    assert!(!env.exists(&pda::seller_volume_address(&seller).0));
+}
+
+#[test]
+fn every_settlement_path_adds_to_the_seller_volume() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    env.send_one(env.initialize_seller_volume(&seller)).unwrap();
+    
+    // Bundle vendu 3_000_000
+    let bundled = [env.nft(&seller), env.nft(&seller)];
+    env.send_one(list_bundle(&seller, 1, 3_000_000, &bundle_items(&seller, &bundled))).unwrap();
+    for mint in &bundled {
+        env.token_account(&buyer, mint, 0);
+    }
+    let buy = buy_bundle(&env, &buyer, &seller, 1, &bundle_purchase_items(&seller, &buyer, &bundled));
+    env.send_one(buy).unwrap();
+    
+    // Listing acheté dans un sweep pour 2_000_000
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, 2_000_000, 1);
+    env.token_account(&buyer, &mint, 0);
+    let sweep = buy_many(&env, &buyer, 2_000_000, false, &sweep_item(&env, &buyer, &listing));
+    env.send_one(sweep).unwrap();
+    
+    // Échange accepté par le vendeur contre un NFT et 1_000_000 lamports
+    let wanted = env.nft(&seller);
+    let offered = env.nft(&buyer);
+    let mut create = create_swap(&buyer, 1, vec![wanted], None, 0, &swap_items(&buyer, 1, &[offered]));
+    create.data = instruction::CreateSwap {
+        swap_id: 1,
+        offered_lamports: 1_000_000,
+        requested_mints: vec![wanted],
+        requested_collection: None,
+        requested_count: 0,
+    }
+    .data();
+    env.send_one(create).unwrap();
+    env.token_account(&seller, &offered, 0);
+    env.token_account(&buyer, &wanted, 0);
+    let swap = swap_address(&buyer, 1);
+    let items = [
+        AccountMeta::new(get_associated_token_address(&swap, &offered), false),
+        AccountMeta::new(get_associated_token_address(&seller, &offered), false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&offered).0, false),
+        AccountMeta::new(get_associated_token_address(&seller, &wanted), false),
+        AccountMeta::new(get_associated_token_address(&buyer, &wanted), false),
+        AccountMeta::new_readonly(pda::mint_blocklist_address(&wanted).0, false),
+    ];
+    env.send_one(accept_swap(&buyer, &seller, 1, &items)).unwrap();
+    
+    let volume: SellerVolume = env.get(&pda::seller_volume_address(&seller).0);
+    assert_eq!(volume.current_volume, 6_000_000);
+}
+
+#[test]
+fn membership_holder_gets_the_discount() {
+    let mut env = Env::new();
+    let membership_collection = env.mint(1);
+    env.set_fee_schedule(Vec::new(), Some(membership_collection), 150);
+    
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let membership_mint = env.collection_nft(&seller, &membership_collection);
+    let membership_token = anchor_spl::associated_token::get_associated_token_address(&seller, &membership_mint);
+    let init = env.initialize_seller_volume(&seller);
+    let register = env.register_membership(&seller, &membership_mint);
+    env.send(&[init, register]).unwrap();
+    let volume: SellerVolume = env.get(&pda::seller_volume_address(&seller).0);
+    assert_eq!(volume.membership_token, Some(membership_token));
+    
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, 1_000_000, 1);
+    env.token_account(&buyer, &mint, 0);
+    let treasury = env.marketplace().treasury;
+    
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    buy.membership_token = Some(membership_token);
+    let before = env.lamports(&treasury);
+    let outcome = env.send_one(buy.instruction()).unwrap();
+    
+    // 250 - 150 = 100 bps
+    assert_eq!(env.lamports(&treasury) - before, 10_000);
+    let applied = &outcome.events::<FeeTierApplied>()[0];
+    assert!(applied.holder_discount);
+    assert_eq!(applied.fee_bps, 100);
+    
+    // Le vendeur s'est séparé de son NFT de membre: plus de remise, sans bloquer les ventes
+    let other = env.wallet();
+    env.token_account(&seller, &membership_mint, 0);
+    env.token_account(&other, &membership_mint, 1);
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, 1_000_000, 1);
+    env.token_account(&buyer, &mint, 0);
+    let mut buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    buy.membership_token = Some(membership_token);
+    let outcome = env.send_one(buy.instruction()).unwrap();
+    let applied = &outcome.events::<FeeTierApplied>()[0];
+    assert!(!applied.holder_discount);
+    assert_eq!(applied.fee_bps, 250);
+}
+
+#[test]
+fn collection_fee_override_replaces_the_schedule() {
+    let mut env = tiered_env();
+    let collection = env.mint(1);
+    let config = env.initialize_collection_config(&collection, true);
+    env.send_one(config).unwrap();
+    env.apply_change(1, MarketplaceChange::CollectionFeeOverride { collection, fee_override: Some(50) });
+    
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.collection_nft(&seller, &collection);
+    let mut list = instructions::ListNft::new(seller, mint, 1_000_000);
+    list.collection = Some(collection);
+    env.send_one(list.instruction()).unwrap();
+    let listing = pda::listing_address(&mint, &seller).0;
+    assert_eq!(env.listing(&listing).collection, Some(collection));
+    env.token_account(&buyer, &mint, 0);
+    let treasury = env.marketplace().treasury;
+    
+    let buy = instructions::BuyNft::new(buyer, listing, &env.listing(&listing), &env.marketplace());
+    let before = env.lamports(&treasury);
+    let outcome = env.send_one(buy.instruction()).unwrap();
+    
+    assert_eq!(env.lamports(&treasury) - before, 5_000);
+    let applied = &outcome.events::<FeeTierApplied>()[0];
+    assert_eq!((applied.fee_tier, applied.fee_bps), (None, 50));
+    
+    // Le même listing acheté dans un sweep paie les mêmes frais
+    let mint = env.collection_nft(&seller, &collection);
+    let mut list = instructions::ListNft::new(seller, mint, 1_000_000);
+    list.collection = Some(collection);
+    env.send_one(list.instruction()).unwrap();
+    let listing = pda::listing_address(&mint, &seller).0;
+    env.token_account(&buyer, &mint, 0);
+    let before = env.lamports(&treasury);
+    let sweep = buy_many(&env, &buyer, 1_000_000, false, &sweep_item(&env, &buyer, &listing));
+    let outcome = env.send_one(sweep).unwrap();
+    
+    assert_eq!(env.lamports(&treasury) - before, 5_000);
+    assert_eq!(outcome.events::<FeeTierApplied>()[0].fee_bps, 50);
+}
+
+#[test]
+fn bundle_and_sweep_purchases_follow_the_fee_schedule() {
+    let mut env = tiered_env();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    env.runtime.airdrop(&buyer, 1_000_000_000_000);
+    env.send_one(env.initialize_seller_volume(&seller)).unwrap();
+    let treasury = env.marketplace().treasury;
+    
+    // Bundle de 10 SOL sans volume passé: frais de base
+    let bundled = [env.nft(&seller), env.nft(&seller)];
+    env.send_one(list_bundle(&seller, 1, 10_000_000_000, &bundle_items(&seller, &bundled))).unwrap();
+    for mint in &bundled {
+        env.token_account(&buyer, mint, 0);
+    }
+    let before = env.lamports(&treasury);
+    let buy = buy_bundle(&env, &buyer, &seller, 1, &bundle_purchase_items(&seller, &buyer, &bundled));
+    let outcome = env.send_one(buy).unwrap();
+    assert_eq!(env.lamports(&treasury) - before, 250_000_000);
+    assert_eq!(outcome.events::<FeeTierApplied>()[0].fee_tier, None);
+    
+    // Les 10 SOL du bundle comptent dans le volume: premier palier pour le sweep
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, 1_000_000_000, 1);
+    env.token_account(&buyer, &mint, 0);
+    let before = env.lamports(&treasury);
+    let sweep = buy_many(&env, &buyer, 1_000_000_000, false, &sweep_item(&env, &buyer, &listing));
+    let outcome = env.send_one(sweep).unwrap();
+    assert_eq!(env.lamports(&treasury) - before, 20_000_000);
+    let applied = &outcome.events::<FeeTierApplied>()[0];
+    assert_eq!((applied.fee_tier, applied.fee_bps, applied.trailing_volume), (Some(0), 200, 10_000_000_000));
+}
+
+#[test]
+fn fee_changes_apply_only_after_the_timelock() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let init_schedule = env.initialize_fee_schedule();
+    let init_config = env.initialize_collection_config(&collection, true);
+    env.send(&[init_schedule, init_config]).unwrap();
+    
+    let tiers = vec![FeeTier { min_volume: 0, fee_bps: 100 }];
+    let changes = [
+        MarketplaceChange::ReferralFee { referral_fee_bps: 2_000 },
+        MarketplaceChange::FeeSchedule { tiers: tiers.clone(), membership_collection: None, holder_discount_bps: 50 },
+        MarketplaceChange::CollectionFeeOverride { collection, fee_override: Some(25) },
+    ];
+    for (change_id, change) in changes.into_iter().enumerate() {
+        env.send_one(env.queue_change(change_id as u64, change)).unwrap();
+    }
+    
+    // Avant l'échéance, aucun des changements de frais ne peut être exécuté
+    env.runtime.advance_clock(CHANGE_TIMELOCK_DELAY - 1);
+    for change_id in 0..3 {
+        assert_error(env.send_one(env.execute_change(change_id)), ErrorCode::TimelockNotElapsed);
+    }
+    let schedule: FeeSchedule = env.get(&pda::fee_schedule_address().0);
+    let config: CollectionConfig = env.get(&pda::collection_config_address(&collection).0);
+    assert_eq!(env.marketplace().referral_fee_bps, 0);
+    assert!(schedule.tiers.is_empty());
+    assert_eq!(config.fee_override, None);
+    
+    env.runtime.advance_clock(1);
+    for change_id in 0..3 {
+        env.send_one(env.execute_change(change_id)).unwrap();
+    }
+    let schedule: FeeSchedule = env.get(&pda::fee_schedule_address().0);
+    let config: CollectionConfig = env.get(&pda::collection_config_address(&collection).0);
+    assert_eq!(env.marketplace().referral_fee_bps, 2_000);
+    assert_eq!((schedule.tiers, schedule.holder_discount_bps), (tiers, 50));
+    assert_eq!(config.fee_override, Some(25));
+}
+
+#[test]
+fn sdk_quote_matches_the_executed_purchase() {
+    let mut env = tiered_env();
+    let referrer = env.wallet();
+    env.apply_change(1, MarketplaceChange::ReferralFee { referral_fee_bps: 2_500 });
+    let register = env.register_referrer(&referrer);
+    env.send(&[register]).unwrap();
+    
+    let seller = env.wallet();
+    let init = env.initialize_seller_volume(&seller);
+    env.send_one(init).unwrap();
+    sale_by(&mut env, &seller, 12_000_000_000);
+    
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 7);
+    let listing = env.list(&seller, &mint, 123_456_789, 7);
+    env.token_account(&buyer, &mint, 0);
+    env.runtime.advance_clock(FEE_VOLUME_WINDOW + 5);
+    
+    let listing_state = env.listing(&listing);
+    let marketplace = env.marketplace();
+    let schedule: FeeSchedule = env.get(&pda::fee_schedule_address().0);
+    let volume: SellerVolume = env.get(&pda::seller_volume_address(&seller).0);
+    let quote = quote_buy(&BuyQuoteInputs {
+        marketplace: &marketplace,
+        listing: &listing_state,
+        quantity: 5,
+        fee_schedule: Some(&schedule),
+        seller_volume: Some(&volume),
+        seller_is_holder: false,
+        collection_config: None,
+        has_referrer: true,
+        now: env.runtime.clock().unix_timestamp,
+    })
+    .unwrap();
+    
+    let mut buy = instructions::BuyNft::new(buyer, listing, &listing_state, &marketplace);
+    buy.quantity = 5;
+    buy.referrer = Some(referrer);
+    let (treasury_before, seller_before, buyer_before) = balances(&env, &seller, &buyer);
+    let referrer_before = env.lamports(&referrer);
+    env.send_one(buy.instruction()).unwrap();
+    let (treasury_after, seller_after, buyer_after) = balances(&env, &seller, &buyer);
+    
+    assert_eq!(quote.fee_tier, Some(0));
+    assert_eq!(buyer_before - buyer_after, quote.total_price);
+    assert_eq!(treasury_after - treasury_before, quote.breakdown.treasury_amount);
+    assert_eq!(env.lamports(&referrer) - referrer_before, quote.breakdown.referral_amount);
+    assert_eq!(seller_after - seller_before, quote.breakdown.seller_amount);
+}
+
+#[test]
+fn fee_math_at_the_extremes() {
+    assert_eq!(calculate_marketplace_fee(u64::MAX, 10_000).unwrap(), (u64::MAX, 0));
+    assert_eq!(calculate_marketplace_fee(u64::MAX, 0).unwrap(), (0, u64::MAX));
+    assert_eq!(calculate_marketplace_fee(u64::MAX, 250).unwrap().0, (u64::MAX as u128 * 250 / 10_000) as u64);
+    assert_eq!(calculate_marketplace_fee(0, 250).unwrap(), (0, 0));
+    
+    // Des points de base supérieurs à 10000 rendraient les frais supérieurs au prix
+    assert!(calculate_marketplace_fee(10_000, 10_001).is_err());
+    
+    let breakdown = calculate_fee_breakdown(u64::MAX, 10_000, Some(10_000)).unwrap();
+    assert_eq!(breakdown.referral_amount, u64::MAX);
+    assert_eq!(breakdown.treasury_amount, 0);
+    assert_eq!(breakdown.seller_amount, 0);
+    
+    let splits = [
+        PayoutSplit { recipient: Pubkey::new_unique(), bps: 1 },
+        PayoutSplit { recipient: Pubkey::new_unique(), bps: 9_999 },
+    ];
+    let amounts = calculate_payout_amounts(u64::MAX, &splits).unwrap();
+    assert_eq!(amounts.iter().map(|amount| *amount as u128).sum::<u128>(), u64::MAX as u128);
+    assert_eq!(calculate_payout_amounts(9_999, &splits).unwrap(), vec![0, 9_999]);
+}
@@ -0,0 +1,728 @@
+//! Parcours principaux du programme: initialisation, listing, mise à jour, achat, annulation,
+//! administration différée et fonctionnalités dépendant de l'horloge.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use solana_nft_marketplace::{
+    accounts, instruction, AccountMigrated, ChangeExecuted, ChangeQueued, CollectionStats, DelistRecord,
+    LegacyMarketplace, LegacyNFTListing, MarketplaceChange, MarketplaceCreated, MintBlocklistEntry, NFTListed,
+    NFTListing, NFTListingCanceled, NFTListingUpdated, NFTSold, PendingChange, SellerRecord, TraderProfile,
+    CHANGE_TIMELOCK_DELAY, LISTING_VERSION, MARKETPLACE_VERSION, MAX_FLOOR_ASKS,
+};
+use solana_nft_marketplace_sdk::pda;
+
+const PRICE: u64 = 2_000_000_000;
+
+#[test]
+fn initialize_marketplace_sets_authority_treasury_and_fee() {
+    let mut runtime = TestRuntime::new();
+    let authority = Pubkey::new_unique();
+    runtime.airdrop(&authority, WALLET_LAMPORTS);
+    
+    let outcome = runtime
+        .process(&[solana_nft_marketplace_sdk::instructions::initialize_marketplace(authority, 300)])
+        .unwrap();
+    
+    let marketplace_address = pda::marketplace_address().0;
+    let account = runtime.account(&marketplace_address).unwrap();
+    assert_eq!(account.owner, solana_nft_marketplace::ID);
+    let marketplace = solana_nft_marketplace::Marketplace::try_deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(marketplace.version, MARKETPLACE_VERSION);
+    assert_eq!(marketplace.authority, authority);
+    assert_eq!(marketplace.treasury, authority);
+    assert_eq!(marketplace.fee, 300);
+    assert_eq!(marketplace.referral_fee_bps, 0);
+    assert!(!marketplace.curated);
+    assert!(marketplace.moderators.is_empty());
+    
+    let events = outcome.events::<MarketplaceCreated>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].marketplace, marketplace_address);
+    assert_eq!(events[0].fee, 300);
+}
+
+#[test]
+fn initialize_marketplace_twice_fails() {
+    let mut env = Env::new();
+    let instruction = solana_nft_marketplace_sdk::instructions::initialize_marketplace(env.authority, 100);
+    
+    let failure = env.send_one(instruction).unwrap_err();
+    assert_eq!(failure.custom_code(), Some(0)); // SystemError::AccountAlreadyInUse
+}
+
+#[test]
+fn list_nft_creates_listing_and_delegates_to_it() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    
+    let instruction = solana_nft_marketplace_sdk::instructions::ListNft::new(seller, mint, PRICE).instruction();
+    let outcome = env.send_one(instruction).unwrap();
+    let listing_address = pda::listing_address(&mint, &seller).0;
+    
+    let listing = env.listing(&listing_address);
+    assert_eq!(listing.version, LISTING_VERSION);
+    assert_eq!(listing.seller, seller);
+    assert_eq!(listing.nft_mint, mint);
+    assert_eq!(listing.price, PRICE);
+    assert_eq!(listing.quantity, 1);
+    assert!(listing.active);
+    assert!(listing.payout_splits.is_empty());
+    assert_eq!(listing.collection, None);
+    
+    // Le NFT reste chez le vendeur, le listing est délégué
+    let token = env.token(&get_associated_token_address(&seller, &mint));
+    assert_eq!(token.amount, 1);
+    assert_eq!(token.delegate, Some(listing_address).into());
+    assert_eq!(token.delegated_amount, 1);
+    
+    let stats = env.stats();
+    assert_eq!(stats.active_listings, 1);
+    assert_eq!(stats.unique_sellers, 1);
+    
+    let events = outcome.events::<NFTListed>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].listing, listing_address);
+    assert_eq!(events[0].price, PRICE);
+    assert_eq!(events[0].quantity, 1);
+}
+
+#[test]
+fn seller_record_keeps_first_listing_time() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let first_mint = env.nft(&seller);
+    let second_mint = env.nft(&seller);
+    
+    let first_listed_at = env.runtime.clock().unix_timestamp;
+    env.list(&seller, &first_mint, PRICE, 1);
+    env.runtime.advance_clock(3_600);
+    env.list(&seller, &second_mint, PRICE, 1);
+    
+    let record: SellerRecord = env.get(&pda::seller_record_address(&seller).0);
+    assert_eq!(record.seller, seller);
+    assert_eq!(record.first_listed_at, first_listed_at);
+    
+    let stats = env.stats();
+    assert_eq!(stats.active_listings, 2);
+    assert_eq!(stats.unique_sellers, 1);
+}
+
+#[test]
+fn update_listing_changes_price_and_renews_delegation() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.edition(&seller, 5);
+    let listing = env.list(&seller, &mint, PRICE, 2);
+    
+    let outcome = env.update(&listing, PRICE / 2, 5).unwrap();
+    
+    let state = env.listing(&listing);
+    assert_eq!(state.price, PRICE / 2);
+    assert_eq!(state.quantity, 5);
+    assert!(state.active);
+    assert_eq!(env.token(&get_associated_token_address(&seller, &mint)).delegated_amount, 5);
+    
+    let events = outcome.events::<NFTListingUpdated>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].price, PRICE / 2);
+    assert_eq!(events[0].quantity, 5);
+}
+
+#[test]
+fn update_listing_reactivates_a_canceled_listing() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    
+    env.cancel(&listing).unwrap();
+    assert_eq!(env.stats().active_listings, 0);
+    
+    env.update(&listing, PRICE, 1).unwrap();
+    assert!(env.listing(&listing).active);
+    assert_eq!(env.stats().active_listings, 1);
+    assert_eq!(env.token(&get_associated_token_address(&seller, &mint)).delegate, Some(listing).into());
+}
+
+#[test]
+fn buy_nft_transfers_token_and_payment() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    let treasury = env.marketplace().treasury;
+    
+    let seller_before = env.lamports(&seller);
+    let buyer_before = env.lamports(&buyer);
+    let treasury_before = env.lamports(&treasury);
+    
+    let outcome = env.buy(&buyer, &listing, 1).unwrap();
+    
+    let fee = PRICE * DEFAULT_FEE_BPS as u64 / 10_000;
+    assert_eq!(env.lamports(&buyer), buyer_before - PRICE);
+    assert_eq!(env.lamports(&seller), seller_before + PRICE - fee);
+    assert_eq!(env.lamports(&treasury), treasury_before + fee);
+    
+    assert_eq!(env.balance(&buyer, &mint), 1);
+    assert_eq!(env.balance(&seller, &mint), 0);
+    
+    let state = env.listing(&listing);
+    assert!(!state.active);
+    assert_eq!(state.quantity, 0);
+    
+    let stats = env.stats();
+    assert_eq!(stats.total_sales, 1);
+    assert_eq!(stats.total_volume, PRICE);
+    assert_eq!(stats.total_fees, fee);
+    assert_eq!(stats.active_listings, 0);
+    
+    let events = outcome.events::<NFTSold>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].listing, listing);
+    assert_eq!(events[0].buyer, buyer);
+    assert_eq!(events[0].price, PRICE);
+    assert_eq!(events[0].quantity, 1);
+    assert_eq!(events[0].marketplace_fee, fee);
+    assert_eq!(events[0].referrer, None);
+}
+
+#[test]
+fn partial_buys_of_an_edition_keep_the_listing_active() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 10);
+    let listing = env.list(&seller, &mint, PRICE, 4);
+    
+    env.buy(&buyer, &listing, 3).unwrap();
+    
+    let state = env.listing(&listing);
+    assert!(state.active);
+    assert_eq!(state.quantity, 1);
+    assert_eq!(env.balance(&buyer, &mint), 3);
+    let seller_token = env.token(&get_associated_token_address(&seller, &mint));
+    assert_eq!(seller_token.amount, 7);
+    assert_eq!(seller_token.delegated_amount, 1);
+    assert_eq!(env.stats().active_listings, 1);
+    
+    env.buy(&buyer, &listing, 1).unwrap();
+    assert!(!env.listing(&listing).active);
+    assert_eq!(env.balance(&buyer, &mint), 4);
+    assert_eq!(env.stats().total_sales, 4);
+    assert_eq!(env.stats().active_listings, 0);
+}
+
+#[test]
+fn cancel_listing_revokes_delegation() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    
+    let outcome = env.cancel(&listing).unwrap();
+    
+    assert!(!env.listing(&listing).active);
+    let token = env.token(&get_associated_token_address(&seller, &mint));
+    assert_eq!(token.amount, 1);
+    assert!(token.delegate.is_none());
+    assert_eq!(env.stats().active_listings, 0);
+    
+    let events = outcome.events::<NFTListingCanceled>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].listing, listing);
+    assert_eq!(events[0].nft_mint, mint);
+}
+
+#[test]
+fn inactive_listings_can_still_be_canceled() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.edition(&seller, 3);
+    let listing = env.list(&seller, &mint, PRICE, 2);
+    env.buy(&buyer, &listing, 2).unwrap();
+    assert!(!env.listing(&listing).active);
+    
+    // Annuler un listing vendu ou déjà annulé ne compte ni n'émet rien de plus
+    let outcome = env.cancel(&listing).unwrap();
+    assert!(outcome.events::<NFTListingCanceled>().is_empty());
+    assert!(!env.listing(&listing).active);
+    assert_eq!(env.stats().active_listings, 0);
+    assert!(env.token(&get_associated_token_address(&seller, &mint)).delegate.is_none());
+    
+    env.cancel(&listing).unwrap();
+    assert_eq!(env.balance(&seller, &mint), 1);
+}
+
+#[test]
+fn trading_works_before_the_stats_account_exists() {
+    let mut runtime = TestRuntime::new();
+    let authority = Pubkey::new_unique();
+    runtime.airdrop(&authority, WALLET_LAMPORTS);
+    let mut env = Env { runtime, authority };
+    env.send_one(solana_nft_marketplace_sdk::instructions::initialize_marketplace(authority, 300)).unwrap();
+    assert!(!env.exists(&pda::stats_address().0));
+    
+    // Listing, mise à jour, achat et annulation ignorent les statistiques absentes
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let sold = env.nft(&seller);
+    let listing = env.list(&seller, &sold, PRICE, 1);
+    env.update(&listing, PRICE / 2, 1).unwrap();
+    env.buy(&buyer, &listing, 1).unwrap();
+    assert_eq!(env.balance(&buyer, &sold), 1);
+    
+    let canceled = env.nft(&seller);
+    let listing = env.list(&seller, &canceled, PRICE, 1);
+    env.cancel(&listing).unwrap();
+    assert!(!env.exists(&pda::stats_address().0));
+    
+    // Une fois créées, les statistiques comptent les opérations suivantes
+    env.send_one(solana_nft_marketplace_sdk::instructions::initialize_stats(authority)).unwrap();
+    let mint = env.nft(&seller);
+    env.list(&seller, &mint, PRICE, 1);
+    assert_eq!(env.stats().active_listings, 1);
+}
+
+#[test]
+fn failed_instruction_reverts_the_whole_transaction() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    env.token_account(&buyer, &mint, 0);
+    let buyer_before = env.lamports(&buyer);
+    
+    // Le premier achat réussit, le second échoue: aucun des deux n'est appliqué
+    let buy = env.buy_instruction(&buyer, &listing, 1);
+    let failure = env.send(&[buy.clone(), buy]).unwrap_err();
+    assert_eq!(failure.instruction_index, 1);
+    
+    assert_eq!(env.lamports(&buyer), buyer_before);
+    assert_eq!(env.balance(&buyer, &mint), 0);
+    assert!(env.listing(&listing).active);
+}
+
+#[test]
+fn trader_profiles_record_trade_times_from_the_clock() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let init_seller = env.initialize_trader_profile(&seller);
+    let init_buyer = env.initialize_trader_profile(&buyer);
+    env.send(&[init_seller, init_buyer]).unwrap();
+    
+    let first_mint = env.nft(&seller);
+    let second_mint = env.nft(&seller);
+    let first = env.list(&seller, &first_mint, PRICE, 1);
+    let second = env.list(&seller, &second_mint, PRICE * 2, 1);
+    
+    let first_trade_at = env.runtime.clock().unix_timestamp;
+    env.buy(&buyer, &first, 1).unwrap();
+    env.runtime.advance_clock(86_400);
+    let last_trade_at = env.runtime.clock().unix_timestamp;
+    env.buy(&buyer, &second, 1).unwrap();
+    
+    let buyer_profile: TraderProfile = env.get(&pda::trader_profile_address(&buyer).0);
+    assert_eq!(buyer_profile.items_bought, 2);
+    assert_eq!(buyer_profile.volume_bought, PRICE * 3);
+    assert_eq!(buyer_profile.first_trade_at, first_trade_at);
+    assert_eq!(buyer_profile.last_trade_at, last_trade_at);
+    
+    let seller_profile: TraderProfile = env.get(&pda::trader_profile_address(&seller).0);
+    assert_eq!(seller_profile.items_sold, 2);
+    assert_eq!(seller_profile.volume_sold, PRICE * 3);
+    assert_eq!(seller_profile.first_trade_at, first_trade_at);
+    assert_eq!(seller_profile.last_trade_at, last_trade_at);
+    
+    // get_trader_profile renvoie le profil en données de retour
+    let get_profile = program_instruction(
+        accounts::GetTraderProfile {
+            profile: pda::trader_profile_address(&buyer).0,
+        },
+        instruction::GetTraderProfile {},
+    );
+    let outcome = env.send_one(get_profile).unwrap();
+    let (program_id, data) = outcome.return_data.unwrap();
+    assert_eq!(program_id, solana_nft_marketplace::ID);
+    let returned = TraderProfile::deserialize(&mut &data[..]).unwrap();
+    assert_eq!(returned.items_bought, 2);
+    assert_eq!(returned.last_trade_at, last_trade_at);
+}
+
+#[test]
+fn cancel_is_recorded_in_the_seller_profile() {
+    let mut env = Env::new();
+    let seller = env.wallet();
+    let init = env.initialize_trader_profile(&seller);
+    env.send_one(init).unwrap();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    
+    env.cancel(&listing).unwrap();
+    
+    let profile: TraderProfile = env.get(&pda::trader_profile_address(&seller).0);
+    assert_eq!(profile.cancelled_listings, 1);
+}
+
+#[test]
+fn queued_change_executes_once_the_timelock_elapsed() {
+    let mut env = Env::new();
+    let queue = env.queue_change(7, MarketplaceChange::Fee { fee: 500 });
+    let queued_at = env.runtime.clock().unix_timestamp;
+    let outcome = env.send_one(queue).unwrap();
+    
+    let pending_address = Env::change_address(7);
+    let pending: PendingChange = env.get(&pending_address);
+    assert_eq!(pending.queued_at, queued_at);
+    assert_eq!(pending.eta, queued_at + CHANGE_TIMELOCK_DELAY);
+    assert_eq!(outcome.events::<ChangeQueued>()[0].eta, pending.eta);
+    
+    // Une seconde avant l'échéance, l'exécution est refusée
+    env.runtime.advance_clock(CHANGE_TIMELOCK_DELAY - 1);
+    let execute = env.execute_change(7);
+    assert_error(env.send_one(execute.clone()), solana_nft_marketplace::ErrorCode::TimelockNotElapsed);
+    assert_eq!(env.marketplace().fee, DEFAULT_FEE_BPS);
+    
+    env.runtime.advance_clock(1);
+    let authority_before = env.lamports(&env.authority);
+    let pending_rent = env.lamports(&pending_address);
+    let outcome = env.send_one(execute).unwrap();
+    
+    assert_eq!(env.marketplace().fee, 500);
+    assert!(!env.exists(&pending_address));
+    assert_eq!(env.lamports(&env.authority), authority_before + pending_rent);
+    let events = outcome.events::<ChangeExecuted>();
+    assert_eq!(events[0].change_id, 7);
+    assert_eq!(events[0].change, MarketplaceChange::Fee { fee: 500 });
+}
+
+#[test]
+fn timelocked_treasury_change_redirects_fees() {
+    let mut env = Env::new();
+    let treasury = env.wallet();
+    env.apply_change(1, MarketplaceChange::Treasury { treasury });
+    assert_eq!(env.marketplace().treasury, treasury);
+    
+    let seller = env.wallet();
+    let buyer = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    let authority_before = env.lamports(&env.authority);
+    
+    env.buy(&buyer, &listing, 1).unwrap();
+    
+    assert_eq!(env.lamports(&treasury), WALLET_LAMPORTS + PRICE * DEFAULT_FEE_BPS as u64 / 10_000);
+    assert_eq!(env.lamports(&env.authority), authority_before);
+}
+
+#[test]
+fn moderator_can_block_mint_and_force_delist() {
+    let mut env = Env::new();
+    let moderator = env.wallet();
+    env.apply_change(1, MarketplaceChange::AddModerator { moderator });
+    assert_eq!(env.marketplace().moderators, vec![moderator]);
+    
+    let seller = env.wallet();
+    let mint = env.nft(&seller);
+    let listing = env.list(&seller, &mint, PRICE, 1);
+    
+    let blocked_at = env.runtime.clock().unix_timestamp;
+    let block = env.block_mint(&moderator, &mint, 3);
+    env.send_one(block).unwrap();
+    let entry: MintBlocklistEntry = env.get(&pda::mint_blocklist_address(&mint).0);
+    assert_eq!(entry.nft_mint, mint);
+    assert_eq!(entry.reason, 3);
+    assert_eq!(entry.blocked_by, moderator);
+    assert_eq!(entry.blocked_at, blocked_at);
+    
+    // Le listing est fermé et son loyer revient au vendeur
+    let seller_before = env.lamports(&seller);
+    let listing_rent = env.lamports(&listing);
+    let delisted_at = env.runtime.clock().unix_timestamp;
+    let delist = env.force_delist(&moderator, &listing, 3);
+    env.send_one(delist).unwrap();
+    assert!(!env.exists(&listing));
+    assert_eq!(env.lamports(&seller), seller_before + listing_rent);
+    assert_eq!(env.stats().active_listings, 0);
+    
+    // Le NFT reste chez le vendeur et le retrait est enregistré
+    let record_address = pda::delist_record_address(&mint, &seller).0;
+    assert_eq!(env.balance(&seller, &mint), 1);
+    let record: DelistRecord = env.get(&record_address);
+    assert_eq!(record.nft_mint, mint);
+    assert_eq!(record.seller, seller);
+    assert_eq!(record.moderator, moderator);
+    assert_eq!(record.reason_code, 3);
+    assert_eq!(record.delegated, 1);
+    assert_eq!(record.delisted_at, delisted_at);
+    
+    // L'approbation du modérateur permet au vendeur de lister à nouveau le NFT
+    let unblock = program_instruction(
+        accounts::UnblockMint {
+            mint_blocklist: pda::mint_blocklist_address(&mint).0,
+            marketplace: pda::marketplace_address().0,
+            moderator,
+        },
+        instruction::UnblockMint { reason: 3 },
+    );
+    env.send_one(unblock).unwrap();
+    let approve = env.approve_relist(&moderator, &mint, &seller);
+    env.send_one(approve).unwrap();
+    assert!(!env.exists(&record_address));
+    env.list(&seller, &mint, PRICE, 1);
+}
+
+#[test]
+fn force_delist_only_blocks_the_delisted_seller() {
+    let mut env = Env::new();
+    let moderator = env.authority;
+    let (first, second) = (env.wallet(), env.wallet());
+    let mint = env.edition(&first, 3);
+    env.token_account(&second, &mint, 2);
+    let first_listing = env.list(&first, &mint, PRICE, 3);
+    let second_listing = env.list(&second, &mint, PRICE, 2);
+    
+    // Le retrait du listing d'un vendeur laisse celui de l'autre détenteur de l'édition
+    env.send_one(env.force_delist(&moderator, &first_listing, 4)).unwrap();
+    assert!(!env.exists(&first_listing));
+    assert!(env.listing(&second_listing).active);
+    assert!(!env.exists(&pda::delist_record_address(&mint, &second).0));
+    
+    // Le second listing peut être retiré à son tour puis remis en vente sans toucher au premier retrait
+    env.send_one(env.force_delist(&moderator, &second_listing, 4)).unwrap();
+    env.send_one(env.approve_relist(&moderator, &mint, &second)).unwrap();
+    env.list(&second, &mint, PRICE, 2);
+    let record: DelistRecord = env.get(&pda::delist_record_address(&mint, &first).0);
+    assert_eq!((record.seller, record.delegated), (first, 3));
+}
+
+// Mettre en vente un NFT d'une collection dont les statistiques existent
+fn list_in_collection(env: &mut Env, collection: &Pubkey, price: u64) -> Pubkey {
+    let seller = env.wallet();
+    let mint = env.collection_nft(&seller, collection);
+    let mut list = solana_nft_marketplace_sdk::instructions::ListNft::new(seller, mint, price);
+    list.collection = Some(*collection);
+    env.send_one(list.instruction()).unwrap();
+    pda::listing_address(&mint, &seller).0
+}
+
+fn floor_prices(env: &Env, collection: &Pubkey) -> Vec<u64> {
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(collection).0);
+    stats.floor_asks.iter().map(|ask| ask.price).collect()
+}
+
+fn refresh_floor(collection: &Pubkey, listings: &[Pubkey]) -> Instruction {
+    let refresh = program_instruction(
+        accounts::RefreshCollectionFloor {
+            collection_stats: pda::collection_stats_address(collection).0,
+        },
+        instruction::RefreshCollectionFloor {},
+    );
+    // En écriture: les listings créés avant les statistiques y sont marqués comme comptés
+    let remaining: Vec<_> = listings.iter().map(|listing| AccountMeta::new(*listing, false)).collect();
+    with_remaining(refresh, &remaining)
+}
+
+#[test]
+fn collection_floor_follows_sales_cancellations_and_reprices() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let init = env.initialize_collection_stats(&collection);
+    env.send_one(init).unwrap();
+    
+    let cheapest = list_in_collection(&mut env, &collection, PRICE / 2);
+    let middle = list_in_collection(&mut env, &collection, PRICE);
+    let highest = list_in_collection(&mut env, &collection, 2 * PRICE);
+    assert_eq!(floor_prices(&env, &collection), vec![PRICE / 2, PRICE, 2 * PRICE]);
+    
+    // La vente de l'annonce la moins chère remonte le plancher à l'annonce suivante
+    let buyer = env.wallet();
+    env.buy(&buyer, &cheapest, 1).unwrap();
+    assert_eq!(floor_prices(&env, &collection), vec![PRICE, 2 * PRICE]);
+    
+    // Une hausse de prix du plancher le replace derrière les annonces moins chères
+    env.update(&middle, 3 * PRICE, 1).unwrap();
+    assert_eq!(floor_prices(&env, &collection), vec![2 * PRICE, 3 * PRICE]);
+    
+    // L'annulation du plancher le retire
+    env.cancel(&highest).unwrap();
+    assert_eq!(floor_prices(&env, &collection), vec![3 * PRICE]);
+    
+    // Le retrait par un modérateur aussi
+    let moderator = env.wallet();
+    env.apply_change(1, MarketplaceChange::AddModerator { moderator });
+    let delist = env.force_delist(&moderator, &middle, 1);
+    env.send_one(delist).unwrap();
+    assert!(floor_prices(&env, &collection).is_empty());
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 0);
+}
+
+#[test]
+fn collection_floor_stays_exact_beyond_the_tracked_asks() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let init = env.initialize_collection_stats(&collection);
+    env.send_one(init).unwrap();
+    
+    let count = MAX_FLOOR_ASKS as u64 + 2;
+    let listings: Vec<_> = (1..=count).map(|price| list_in_collection(&mut env, &collection, price)).collect();
+    assert_eq!(floor_prices(&env, &collection), (1..=MAX_FLOOR_ASKS as u64).collect::<Vec<_>>());
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 2);
+    
+    // Le plancher repricé au-dessus des asks non suivis n'est pas réinséré devant eux
+    env.update(&listings[0], 100, 1).unwrap();
+    assert_eq!(floor_prices(&env, &collection), (2..=MAX_FLOOR_ASKS as u64).collect::<Vec<_>>());
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 3);
+    
+    // Un nouvel ask plus cher que le dernier suivi reste hors de l'ensemble, un ask plus bas y entre
+    let expensive = list_in_collection(&mut env, &collection, 50);
+    list_in_collection(&mut env, &collection, 1);
+    assert_eq!(floor_prices(&env, &collection), (1..=MAX_FLOOR_ASKS as u64).collect::<Vec<_>>());
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 4);
+    
+    // Les annulations vident l'ensemble sans y faire entrer les asks non suivis
+    for listing in &listings[1..4] {
+        env.cancel(listing).unwrap();
+    }
+    assert_eq!(floor_prices(&env, &collection), vec![1, 5, 6, 7, 8, 9, 10]);
+    
+    // Le recalcul exige tous les asks non suivis, et uniquement eux
+    let untracked = [listings[0], listings[10], listings[11], expensive];
+    let partial = refresh_floor(&collection, &untracked[..3]);
+    assert_error(env.send_one(partial), solana_nft_marketplace::ErrorCode::InvalidCollectionStats);
+    let tracked = refresh_floor(&collection, &[listings[4], listings[10], listings[11], expensive]);
+    assert_error(env.send_one(tracked), solana_nft_marketplace::ErrorCode::InvalidCollectionStats);
+    
+    let refresh = refresh_floor(&collection, &untracked);
+    env.send_one(refresh).unwrap();
+    assert_eq!(floor_prices(&env, &collection), vec![1, 5, 6, 7, 8, 9, 10, 11, 12, 50]);
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 1);
+}
+
+#[test]
+fn listings_created_before_collection_stats_are_not_counted() {
+    let mut env = Env::new();
+    let collection = env.mint(1);
+    let early = list_in_collection(&mut env, &collection, 1);
+    let cheapest = list_in_collection(&mut env, &collection, 2);
+    assert!(!env.listing(&early).stats_tracked);
+    
+    let init = env.initialize_collection_stats(&collection);
+    env.send_one(init).unwrap();
+    let count = MAX_FLOOR_ASKS as u64 + 1;
+    let listings: Vec<_> = (1..=count).map(|price| list_in_collection(&mut env, &collection, 10 + price)).collect();
+    assert!(env.listing(&listings[0]).stats_tracked);
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 1);
+    
+    // L'annulation d'un listing jamais compté ne retire pas un ask non suivi
+    env.cancel(&early).unwrap();
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!(stats.untracked_asks, 1);
+    assert_eq!(floor_prices(&env, &collection), (11..=10 + MAX_FLOOR_ASKS as u64).collect::<Vec<_>>());
+    
+    // Un listing non compté ne remplace pas un ask non suivi lors du recalcul
+    let missing = refresh_floor(&collection, &[cheapest]);
+    assert_error(env.send_one(missing), solana_nft_marketplace::ErrorCode::InvalidCollectionStats);
+    
+    // Ajouté au recalcul, il est désormais compté et retiré à sa vente
+    let refresh = refresh_floor(&collection, &[cheapest, listings[MAX_FLOOR_ASKS]]);
+    env.send_one(refresh).unwrap();
+    assert!(env.listing(&cheapest).stats_tracked);
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!((stats.floor_asks[0].price, stats.untracked_asks), (2, 2));
+    
+    let buyer = env.wallet();
+    env.buy(&buyer, &cheapest, 1).unwrap();
+    let stats: CollectionStats = env.get(&pda::collection_stats_address(&collection).0);
+    assert_eq!((stats.floor_asks[0].price, stats.untracked_asks), (11, 2));
+}
+
+#[test]
+fn migrate_legacy_marketplace_and_listing() {
+    let mut env = Env::new();
+    let marketplace_address = pda::marketplace_address().0;
+    let (_, marketplace_bump) = pda::marketplace_address();
+    
+    // Remplacer la marketplace par un compte à l'ancien format (sans version)
+    let mut data = solana_nft_marketplace::Marketplace::DISCRIMINATOR.to_vec();
+    LegacyMarketplace {
+        authority: env.authority,
+        fee: 150,
+        bump: marketplace_bump,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    let rent = Rent::default().minimum_balance(data.len());
+    env.runtime.set_account(marketplace_address, AccountState::new(rent, data, solana_nft_marketplace::ID));
+    
+    let migrate = program_instruction(
+        accounts::MigrateMarketplace {
+            marketplace: marketplace_address,
+            authority: env.authority,
+            system_program: anchor_lang::solana_program::system_program::ID,
+        },
+        instruction::MigrateMarketplace {},
+    );
+    let outcome = env.send_one(migrate).unwrap();
+    
+    let marketplace = env.marketplace();
+    assert_eq!(marketplace.version, MARKETPLACE_VERSION);
+    assert_eq!(marketplace.authority, env.authority);
+    assert_eq!(marketplace.treasury, env.authority);
+    assert_eq!(marketplace.fee, 150);
+    assert_eq!(marketplace.bump, marketplace_bump);
+    let account = env.runtime.account(&marketplace_address).unwrap();
+    assert_eq!(account.lamports, Rent::default().minimum_balance(account.data.len()));
+    assert_eq!(outcome.events::<AccountMigrated>()[0].version, MARKETPLACE_VERSION);
+    
+    // Listing à l'ancien format, migré par un tiers
+    let seller = env.wallet();
+    let payer = env.wallet();
+    let mint = env.nft(&seller);
+    let (listing_address, listing_bump) = pda::listing_address(&mint, &seller);
+    let mut data = NFTListing::DISCRIMINATOR.to_vec();
+    LegacyNFTListing {
+        seller,
+        nft_mint: mint,
+        price: PRICE,
+        active: true,
+        bump: listing_bump,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    let rent = Rent::default().minimum_balance(data.len());
+    env.runtime.set_account(listing_address, AccountState::new(rent, data, solana_nft_marketplace::ID));
+    
+    let migrate = program_instruction(
+        accounts::MigrateListing {
+            listing: listing_address,
+            payer,
+            system_program: anchor_lang::solana_program::system_program::ID,
+        },
+        instruction::MigrateListing {},
+    );
+    env.send_one(migrate).unwrap();
+    
+    let listing = env.listing(&listing_address);
+    assert_eq!(listing.version, LISTING_VERSION);
+    assert_eq!(listing.seller, seller);
+    assert_eq!(listing.nft_mint, mint);
+    assert_eq!(listing.price, PRICE);
+    assert_eq!(listing.quantity, 1);
+    assert!(listing.active);
+}
@@ -0,0 +1,332 @@
+//! Séquences aléatoires de listings, mises à jour, achats et annulations entre plusieurs portefeuilles
+//! et mints, comparées à un modèle de la marketplace. Après chaque instruction, les invariants
+//! suivants sont vérifiés:
+//! - les lamports sont conservés, la trésorerie ne reçoit que les frais attendus;
+//! - chaque NFT est détenu par exactement un portefeuille (la supply d'une édition est répartie
+//!   entre les portefeuilles, sans unité perdue ni créée);
+//! - un listing inactif ou inexistant ne peut jamais être acheté;
+//! - un listing actif reste exécutable (délégation et solde suffisants);
+//! - les statistiques de la marketplace correspondent au modèle.
+//!
+//! Le nombre de séquences se règle avec `PROPTEST_CASES`.
+
+mod common;
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use proptest::prelude::*;
+use solana_nft_marketplace::{NFTListing, LISTING_VERSION};
+use solana_nft_marketplace_sdk::{instructions, pda};
+
+const USERS: usize = 4;
+
+// Supply de chaque mint et répartition initiale entre les portefeuilles
+const MINTS: [&[u64; USERS]; 3] = [&[1, 0, 0, 0], &[0, 1, 0, 0], &[0, 0, 3, 1]];
+
+const MAX_QUANTITY: u64 = 5;
+const MAX_PRICE: u64 = 100_000_000;
+
+#[derive(Clone, Debug)]
+enum Operation {
+    List { seller: usize, mint: usize, price: u64, quantity: u64 },
+    Update { seller: usize, mint: usize, price: u64, quantity: u64 },
+    Buy { buyer: usize, seller: usize, mint: usize, quantity: u64 },
+    Cancel { seller: usize, mint: usize },
+}
+
+fn price() -> impl Strategy<Value = u64> {
+    // Petits prix pour exercer les arrondis des frais, puis prix quelconques
+    prop_oneof![0..=1_000u64, 0..=MAX_PRICE]
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    let user = 0..USERS;
+    let mint = 0..MINTS.len();
+    let quantity = 0..=MAX_QUANTITY;
+    prop_oneof![
+        3 => (user.clone(), mint.clone(), price(), quantity.clone())
+            .prop_map(|(seller, mint, price, quantity)| Operation::List { seller, mint, price, quantity }),
+        2 => (user.clone(), mint.clone(), price(), quantity.clone())
+            .prop_map(|(seller, mint, price, quantity)| Operation::Update { seller, mint, price, quantity }),
+        4 => (user.clone(), user.clone(), mint.clone(), quantity)
+            .prop_map(|(buyer, seller, mint, quantity)| Operation::Buy { buyer, seller, mint, quantity }),
+        2 => (user, mint).prop_map(|(seller, mint)| Operation::Cancel { seller, mint }),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ModelListing {
+    price: u64,
+    quantity: u64,
+    active: bool,
+}
+
+// État attendu de la marketplace
+struct Model {
+    holdings: Vec<[u64; USERS]>,
+    listings: HashMap<(usize, usize), ModelListing>,
+    fee_bps: u16,
+    total_sales: u64,
+    total_volume: u64,
+    total_fees: u64,
+}
+
+impl Model {
+    // Appliquer une opération au modèle; retourne false si le programme doit la refuser
+    fn apply(&mut self, operation: &Operation) -> bool {
+        match *operation {
+            Operation::List { seller, mint, price, quantity } => {
+                if self.listings.contains_key(&(seller, mint)) || !self.holds(seller, mint, quantity) {
+                    return false;
+                }
+                self.listings.insert((seller, mint), ModelListing { price, quantity, active: true });
+                true
+            }
+            Operation::Update { seller, mint, price, quantity } => {
+                if !self.listings.contains_key(&(seller, mint)) || !self.holds(seller, mint, quantity) {
+                    return false;
+                }
+                self.listings.insert((seller, mint), ModelListing { price, quantity, active: true });
+                true
+            }
+            Operation::Buy { buyer, seller, mint, quantity } => {
+                let Some(listing) = self.listings.get_mut(&(seller, mint)) else {
+                    return false;
+                };
+                if !listing.active || quantity == 0 || quantity > listing.quantity {
+                    return false;
+                }
+                listing.quantity -= quantity;
+                listing.active = listing.quantity > 0;
+                
+                let total = listing.price * quantity;
+                self.holdings[mint][seller] -= quantity;
+                self.holdings[mint][buyer] += quantity;
+                self.total_sales += quantity;
+                self.total_volume += total;
+                self.total_fees += (total as u128 * self.fee_bps as u128 / 10_000) as u64;
+                true
+            }
+            // Un listing inactif peut encore être annulé (sans effet sur les statistiques)
+            Operation::Cancel { seller, mint } => match self.listings.get_mut(&(seller, mint)) {
+                Some(listing) => {
+                    listing.active = false;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+    
+    fn holds(&self, user: usize, mint: usize, quantity: u64) -> bool {
+        quantity > 0 && self.holdings[mint][user] >= quantity
+    }
+    
+    fn active_listings(&self) -> u64 {
+        self.listings.values().filter(|listing| listing.active).count() as u64
+    }
+}
+
+struct Harness {
+    env: Env,
+    users: Vec<Pubkey>,
+    mints: Vec<Pubkey>,
+    model: Model,
+    treasury: Pubkey,
+    initial_lamports: u64,
+    initial_treasury: u64,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let mut env = Env::new();
+        let users: Vec<Pubkey> = (0..USERS).map(|_| env.wallet()).collect();
+        let mut mints = Vec::new();
+        for distribution in MINTS {
+            let mint = env.mint(distribution.iter().sum());
+            // Tous les comptes associés existent dès le départ: les achats ne créent aucun compte
+            for (user, amount) in users.iter().zip(distribution.iter()) {
+                env.token_account(user, &mint, *amount);
+            }
+            mints.push(mint);
+        }
+        
+        let treasury = env.marketplace().treasury;
+        let model = Model {
+            holdings: MINTS.iter().map(|distribution| **distribution).collect(),
+            listings: HashMap::new(),
+            fee_bps: env.marketplace().fee,
+            total_sales: 0,
+            total_volume: 0,
+            total_fees: 0,
+        };
+        
+        let mut harness = Harness {
+            initial_treasury: env.lamports(&treasury),
+            env,
+            users,
+            mints,
+            model,
+            treasury,
+            initial_lamports: 0,
+        };
+        harness.initial_lamports = harness.tracked_lamports();
+        harness
+    }
+    
+    fn listing_address(&self, seller: usize, mint: usize) -> Pubkey {
+        pda::listing_address(&self.mints[mint], &self.users[seller]).0
+    }
+    
+    // État du listing sur la chaîne, ou un listing fictif si le compte n'existe pas
+    // (l'instruction est alors construite comme le ferait un client et doit échouer)
+    fn listing_state(&self, seller: usize, mint: usize) -> NFTListing {
+        let address = self.listing_address(seller, mint);
+        if self.env.exists(&address) {
+            return self.env.listing(&address);
+        }
+        NFTListing {
+            version: LISTING_VERSION,
+            seller: self.users[seller],
+            nft_mint: self.mints[mint],
+            price: 0,
+            active: false,
+            bump: 0,
+            quantity: 0,
+            payout_splits: Vec::new(),
+            collection: None,
+            stats_tracked: false,
+            reserved: [0; 63],
+        }
+    }
+    
+    fn instruction(&self, operation: &Operation) -> Instruction {
+        match *operation {
+            Operation::List { seller, mint, price, quantity } => {
+                let mut list = instructions::ListNft::new(self.users[seller], self.mints[mint], price);
+                list.quantity = quantity;
+                list.instruction()
+            }
+            Operation::Update { seller, mint, price, quantity } => {
+                let listing = self.listing_state(seller, mint);
+                instructions::UpdateListing::new(self.listing_address(seller, mint), &listing, price, quantity).instruction()
+            }
+            Operation::Buy { buyer, seller, mint, quantity } => {
+                let listing = self.listing_state(seller, mint);
+                let mut buy = instructions::BuyNft::new(
+                    self.users[buyer],
+                    self.listing_address(seller, mint),
+                    &listing,
+                    &self.env.marketplace(),
+                );
+                buy.quantity = quantity;
+                buy.instruction()
+            }
+            Operation::Cancel { seller, mint } => {
+                let listing = self.listing_state(seller, mint);
+                instructions::CancelListing::new(self.listing_address(seller, mint), &listing).instruction()
+            }
+        }
+    }
+    
+    fn step(&mut self, operation: &Operation) -> std::result::Result<(), TestCaseError> {
+        // Un listing inactif ou inexistant sur la chaîne ne doit jamais pouvoir être acheté
+        let buying_inactive = match *operation {
+            Operation::Buy { seller, mint, .. } => !self.listing_state(seller, mint).active,
+            _ => false,
+        };
+        
+        let instruction = self.instruction(operation);
+        let result = self.env.send_one(instruction);
+        let expected = self.model.apply(operation);
+        
+        if buying_inactive {
+            prop_assert!(result.is_err(), "inactive listing bought: {:?}", operation);
+        }
+        match &result {
+            Ok(outcome) => prop_assert!(expected, "{:?} succeeded, logs: {:#?}", operation, outcome.logs),
+            Err(failure) => prop_assert!(!expected, "{:?} failed: {:?}, logs: {:#?}", operation, failure.error, failure.logs),
+        }
+        self.check_invariants()
+    }
+    
+    // Lamports des portefeuilles, de la trésorerie et des comptes du programme (loyers des listings)
+    fn tracked_lamports(&self) -> u64 {
+        let wallets: u64 = self.users.iter().map(|user| self.env.lamports(user)).sum();
+        let program_accounts: u64 = self
+            .env
+            .runtime
+            .accounts()
+            .filter(|(_, account)| account.owner == solana_nft_marketplace::ID)
+            .map(|(_, account)| account.lamports)
+            .sum();
+        wallets + self.env.lamports(&self.treasury) + program_accounts
+    }
+    
+    fn check_invariants(&self) -> std::result::Result<(), TestCaseError> {
+        // Conservation des lamports: ce que perdent les portefeuilles va aux vendeurs, à la trésorerie
+        // ou aux loyers des comptes créés
+        prop_assert_eq!(self.tracked_lamports(), self.initial_lamports);
+        prop_assert_eq!(self.env.lamports(&self.treasury) - self.initial_treasury, self.model.total_fees);
+        
+        // Chaque unité de chaque mint est détenue par exactement un portefeuille
+        for (index, mint) in self.mints.iter().enumerate() {
+            let supply: u64 = MINTS[index].iter().sum();
+            let balances: Vec<u64> = self.users.iter().map(|user| self.env.balance(user, mint)).collect();
+            prop_assert_eq!(balances.iter().sum::<u64>(), supply);
+            if supply == 1 {
+                prop_assert_eq!(balances.iter().filter(|amount| **amount == 1).count(), 1);
+            }
+            prop_assert_eq!(&balances[..], &self.model.holdings[index][..]);
+        }
+        
+        // Les listings correspondent au modèle et un listing actif peut toujours être exécuté
+        for seller in 0..USERS {
+            for mint in 0..self.mints.len() {
+                let address = self.listing_address(seller, mint);
+                let expected = self.model.listings.get(&(seller, mint));
+                prop_assert_eq!(self.env.exists(&address), expected.is_some());
+                let Some(expected) = expected else {
+                    continue;
+                };
+                
+                let listing = self.env.listing(&address);
+                let actual = ModelListing {
+                    price: listing.price,
+                    quantity: listing.quantity,
+                    active: listing.active,
+                };
+                prop_assert_eq!(&actual, expected);
+                
+                if listing.active {
+                    let token = self.env.token(&get_associated_token_address(&self.users[seller], &self.mints[mint]));
+                    prop_assert_eq!(token.delegate, COption::Some(address));
+                    prop_assert!(token.delegated_amount >= listing.quantity);
+                    prop_assert!(token.amount >= listing.quantity);
+                }
+            }
+        }
+        
+        let stats = self.env.stats();
+        prop_assert_eq!(stats.active_listings, self.model.active_listings());
+        prop_assert_eq!(stats.total_sales, self.model.total_sales);
+        prop_assert_eq!(stats.total_volume, self.model.total_volume);
+        prop_assert_eq!(stats.total_fees, self.model.total_fees);
+        Ok(())
+    }
+}
+
+proptest! {
+    #[test]
+    fn random_sequences_preserve_invariants(operations in prop::collection::vec(operation(), 1..40)) {
+        let mut harness = Harness::new();
+        for operation in &operations {
+            harness.step(operation)?;
+        }
+    }
+}